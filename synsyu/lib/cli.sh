@@ -22,6 +22,11 @@ parse_cli() {
         CONFIG_PATH="$2"
         shift 2
         ;;
+      --profile)
+        SYNSYU_PROFILE="$2"
+        export SYNSYU_PROFILE
+        shift 2
+        ;;
       --manifest)
         SYN_MANIFEST_PATH="$2"
         shift 2
@@ -199,6 +204,11 @@ enforce_offline_mode() {
 # Allow common flags to appear after the command name.
 parse_post_command_flags() {
   local -a rest=()
+  # Only the arguments after the command name belong to it.
+  while [ $# -gt 0 ] && [ "$1" != "$COMMAND" ]; do
+    shift
+  done
+  [ $# -gt 0 ] && shift
   while [ $# -gt 0 ]; do
     case "$1" in
       --with-flatpak|-w-fp)
@@ -250,6 +260,11 @@ parse_post_command_flags() {
         CONFIG_PATH="$2"
         shift
         ;;
+      --profile)
+        SYNSYU_PROFILE="$2"
+        export SYNSYU_PROFILE
+        shift
+        ;;
       --manifest)
         SYN_MANIFEST_PATH="$2"
         shift
@@ -8,7 +8,8 @@
 #
 # Security / Safety Notes:
 #   Invokes package managers and helpers; expects caller to
-#   run as an unprivileged user with sudo for pacman actions.
+#   run as an unprivileged user; pacman actions go through the
+#   configured privilege tool (core.privilege_tool, default sudo).
 #------------------------------------------------------------
 # SSE Principles Observed:
 #   - Single-responsibility command functions
@@ -36,6 +37,19 @@ matches_filters() {
   return 0
 }
 
+#--- privileged
+# Runs a command through PRIVILEGE_TOOL (core.privilege_tool); direct when root
+# or when the tool is set to an empty string.
+privileged() {
+  if [ "$(id -u)" -eq 0 ] || [ -z "${PRIVILEGE_TOOL-sudo}" ]; then
+    "$@"
+    return $?
+  fi
+  local -a tool
+  read -r -a tool <<<"${PRIVILEGE_TOOL-sudo}"
+  "${tool[@]}" "$@"
+}
+
 #--- run_pacman_repo_batch
 run_pacman_repo_batch() {
   local config_path="${1:-}"
@@ -50,34 +64,14 @@ run_pacman_repo_batch() {
   if [ "$NO_CONFIRM" = "1" ]; then
     args+=(--noconfirm)
   fi
-  # Security: invokes pacman with sudo; limited to manifest-selected repo packages.
+  # Security: invokes pacman via the privilege tool; limited to manifest-selected repo packages.
   set +e
-  sudo pacman "${args[@]}" "${pkgs[@]}"
+  privileged pacman "${args[@]}" "${pkgs[@]}"
   local status=$?
   set -e
   return "$status"
 }
 
-#--- pacman_failure_retryable
-pacman_failure_retryable() {
-  local message="${1:-}"
-  local lower="${message,,}"
-
-  case "$lower" in
-    *"invalid or corrupted package"*|*"corrupted package"*|*"signature"*|*"pgp"*|*"gpgme"*|*"keyring"*|*"unknown trust"*|*"marginal trust"*|*"checksum"*|*"integrity"*|*"failed to commit transaction"*|*"conflicting files"*|*"exists in filesystem"*|*"could not satisfy dependencies"*|*"unresolvable package conflicts"*|*"database is locked"*|*"not enough free disk space"*|*"target not found"*)
-      return 1
-      ;;
-  esac
-
-  case "$lower" in
-    *"failed retrieving file"*|*"failed to retrieve some files"*|*"could not resolve host"*|*"connection timed out"*|*"operation too slow"*|*"the requested url returned error"*|*"failed to synchronize all databases"*|*"download library error"*|*"failed to retrieve"*|*"server returned error"*|*"connection refused"*|*"connection reset"*)
-      return 0
-      ;;
-  esac
-
-  return 1
-}
-
 #--- aur_helper_failure_retryable
 aur_helper_failure_retryable() {
   local message="${1:-}"
@@ -100,10 +94,26 @@ aur_helper_failure_retryable() {
 
 #--- run_aur_helper_update
 run_aur_helper_update() {
-  local helper="$1" pkg="$2"
+  local helper="$1" pkg="$2" version="${3:-}"
   local -a hargs=(-S)
   [ "$NO_CONFIRM" = "1" ] && hargs+=(--noconfirm)
 
+  # Measure build-directory usage through synsyu_core when available so
+  # later plans and space checks can estimate this package's build size.
+  local -a runner=()
+  local core_bin="$SYN_CORE_BIN"
+  if [ ! -x "$core_bin" ]; then
+    core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+  fi
+  if [ -n "$core_bin" ] && [ -x "$core_bin" ]; then
+    runner=("$core_bin" build-history record --package "$pkg" --helper "$helper")
+    [ -n "$version" ] && runner+=(--version "$version")
+    if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+      runner+=(--config "$CONFIG_PATH")
+    fi
+    runner+=(--)
+  fi
+
   local attempt_limit=1
   if [ "${ACQUISITION_AUR_HELPER_ENABLED:-1}" = "1" ]; then
     attempt_limit=$((ACQUISITION_AUR_HELPER_MAX_RETRIES + 1))
@@ -112,17 +122,20 @@ run_aur_helper_update() {
     attempt_limit=1
   fi
 
-  local attempt=1 status stderr_file stderr_text
+  local attempt=1 status stderr_file stderr_text started_ms duration_ms
+  local -a fields=("package=$pkg" "source=AUR")
   while [ "$attempt" -le "$attempt_limit" ]; do
     stderr_file="$(mktemp "${TMPDIR:-/tmp}/synsyu_aur_helper_XXXXXX")"
-    log_info "ACQUIRE" "AUR helper acquisition attempt $attempt/$attempt_limit for $pkg using $helper"
+    log_info "ACQUIRE" "AUR helper acquisition attempt $attempt/$attempt_limit for $pkg using $helper" "${fields[@]}"
+    started_ms="$(date +%s%3N)"
     set +e
-    "$helper" "${hargs[@]}" "$pkg" 2>"$stderr_file"
+    "${runner[@]}" "$helper" "${hargs[@]}" "$pkg" 2>"$stderr_file"
     status=$?
     set -e
+    duration_ms=$(($(date +%s%3N) - started_ms))
 
     if [ "$status" -eq 0 ]; then
-      log_info "ACQUIRE" "AUR helper acquisition succeeded for $pkg on attempt $attempt"
+      log_info "ACQUIRE" "AUR helper acquisition succeeded for $pkg on attempt $attempt" "${fields[@]}" "duration_ms=$duration_ms"
       rm -f "$stderr_file"
       return 0
     fi
@@ -132,10 +145,10 @@ run_aur_helper_update() {
       printf '%s\n' "$stderr_text" >&2
     fi
     rm -f "$stderr_file"
-    log_warn "ACQUIRE" "AUR helper acquisition failed for $pkg on attempt $attempt with exit $status: ${stderr_text:-no stderr captured}"
+    log_warn "ACQUIRE" "AUR helper acquisition failed for $pkg on attempt $attempt with exit $status: ${stderr_text:-no stderr captured}" "${fields[@]}" "duration_ms=$duration_ms"
 
     if ! aur_helper_failure_retryable "$stderr_text"; then
-      log_error "ACQUIRE" "AUR helper failure for $pkg is terminal for Syn-Syu retry policy"
+      log_error "ACQUIRE" "AUR helper failure for $pkg is terminal for Syn-Syu retry policy" "${fields[@]}"
       return "$status"
     fi
 
@@ -146,65 +159,10 @@ run_aur_helper_update() {
     fi
   done
 
-  log_error "ACQUIRE" "AUR helper retry budget exhausted for $pkg after $attempt_limit attempt(s)"
+  log_error "ACQUIRE" "AUR helper retry budget exhausted for $pkg after $attempt_limit attempt(s)" "${fields[@]}"
   return 1
 }
 
-#--- mirror_create_pacman_config
-mirror_create_pacman_config() {
-  local server="$1"
-  local tmp_dir="$2"
-  local temp_mirrorlist="$tmp_dir/mirrorlist"
-  local temp_config="$tmp_dir/pacman.conf"
-
-  printf 'Server = %s\n' "$server" >"$temp_mirrorlist"
-
-  MIRROR_PACMAN_CONF="$MIRROR_PACMAN_CONF" \
-  MIRRORLIST_PATH="$MIRRORLIST_PATH" \
-  TEMP_MIRRORLIST="$temp_mirrorlist" \
-  TEMP_PACMAN_CONF="$temp_config" \
-  python3 - <<'PY'
-import os
-import re
-import sys
-
-source = os.environ["MIRROR_PACMAN_CONF"]
-mirrorlist = os.environ["MIRRORLIST_PATH"]
-temp_mirrorlist = os.environ["TEMP_MIRRORLIST"]
-target = os.environ["TEMP_PACMAN_CONF"]
-
-try:
-    with open(source, "r", encoding="utf-8") as handle:
-        lines = handle.readlines()
-except OSError as err:
-    print(f"failed to read pacman config {source}: {err}", file=sys.stderr)
-    sys.exit(1)
-
-pattern = re.compile(r"^(\s*)Include\s*=\s*" + re.escape(mirrorlist) + r"\s*(#.*)?$")
-replaced = 0
-out = []
-for line in lines:
-    match = pattern.match(line)
-    if match:
-        indent = match.group(1) or ""
-        out.append(f"{indent}Include = {temp_mirrorlist}\n")
-        replaced += 1
-    else:
-        out.append(line)
-
-if replaced == 0:
-    print(f"no Include = {mirrorlist} line found in {source}", file=sys.stderr)
-    sys.exit(2)
-
-try:
-    with open(target, "w", encoding="utf-8") as handle:
-        handle.writelines(out)
-except OSError as err:
-    print(f"failed to write temporary pacman config {target}: {err}", file=sys.stderr)
-    sys.exit(1)
-PY
-}
-
 #--- run_repo_batch_default
 run_repo_batch_default() {
   local -a pkgs=("$@")
@@ -212,6 +170,8 @@ run_repo_batch_default() {
 }
 
 #--- run_repo_batch_with_mirrors
+# Delegates mirror failover to `synsyu_core acquire repo`, which builds one
+# temporary pacman config per attempt and classifies pacman failures.
 run_repo_batch_with_mirrors() {
   local -a pkgs=("$@")
   [ "${#pkgs[@]}" -gt 0 ] || return 0
@@ -222,75 +182,40 @@ run_repo_batch_with_mirrors() {
     return $?
   fi
 
-  local -a mirror_candidates=()
-  mapfile -t mirror_candidates < <(manifest_mirror_candidates_stream || true)
-  if [ "${#mirror_candidates[@]}" -eq 0 ]; then
-    log_warn "MIRROR" "No usable mirror candidates in manifest; using pacman default mirror configuration"
+  local core_bin="$SYN_CORE_BIN"
+  if [ ! -x "$core_bin" ]; then
+    core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+  fi
+  if [ -z "$core_bin" ] || [ ! -x "$core_bin" ]; then
+    log_warn "MIRROR" "synsyu_core not found; using pacman default mirror configuration"
     run_repo_batch_default "${pkgs[@]}"
     return $?
   fi
 
-  local attempt_limit=$((MIRRORS_MAX_FAILOVERS + 1))
-  if [ "$attempt_limit" -lt 1 ]; then
-    attempt_limit=1
+  local attempt_log="${LOG_DIR:-${TMPDIR:-/tmp}}/acquire-repo-${SESSION_STAMP:-$(date -u +%s)}.json"
+  local -a args=(acquire repo --manifest "$(manifest_resolved_path)" --attempt-log "$attempt_log")
+  if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+    args+=(--config "$CONFIG_PATH")
   fi
-  if [ "$attempt_limit" -gt "${#mirror_candidates[@]}" ]; then
-    attempt_limit="${#mirror_candidates[@]}"
+  if [ "$NO_CONFIRM" = "1" ]; then
+    args+=(--noconfirm)
+  fi
+  if [ "${OFFLINE:-0}" = "1" ]; then
+    args+=(--offline)
   fi
 
-  local attempt=1 server tmp_dir pacman_config status stderr_file stderr_text
-  while [ "$attempt" -le "$attempt_limit" ]; do
-    server="${mirror_candidates[$((attempt - 1))]}"
-    tmp_dir="$(mktemp -d "${TMPDIR:-/tmp}/synsyu_mirror_XXXXXX")"
-    pacman_config="$tmp_dir/pacman.conf"
-    stderr_file="$tmp_dir/pacman.stderr"
-
-    log_info "MIRROR" "Repo batch attempt $attempt/$attempt_limit using mirror: $server"
-    if ! mirror_create_pacman_config "$server" "$tmp_dir" 2>"$stderr_file"; then
-      stderr_text="$(tr '\n' ' ' <"$stderr_file" 2>/dev/null || true)"
-      log_warn "MIRROR" "Unable to prepare temporary pacman config: ${stderr_text:-unknown error}"
-      rm -rf "$tmp_dir"
-      run_repo_batch_default "${pkgs[@]}"
-      return $?
-    fi
-
-    local -a pacman_args=(--config "$pacman_config" -S)
-    if [ "$NO_CONFIRM" = "1" ]; then
-      pacman_args+=(--noconfirm)
-    fi
-    set +e
-    sudo pacman "${pacman_args[@]}" "${pkgs[@]}" 2>"$stderr_file"
-    status=$?
-    set -e
-
-    if [ "$status" -eq 0 ]; then
-      log_info "MIRROR" "Repo batch succeeded on mirror attempt $attempt"
-      rm -rf "$tmp_dir"
-      return 0
-    fi
-
-    stderr_text="$(tr '\n' ' ' <"$stderr_file" 2>/dev/null || true)"
-    if [ -s "$stderr_file" ]; then
-      printf '%s\n' "$stderr_text" >&2
-    fi
-    log_warn "MIRROR" "Repo batch failed on mirror attempt $attempt with pacman exit $status: ${stderr_text:-no stderr captured}"
-
-    if ! pacman_failure_retryable "$stderr_text"; then
-      log_error "MIRROR" "Pacman failure is not mirror-retryable; stopping mirror failover"
-      rm -rf "$tmp_dir"
-      return "$status"
-    fi
-
-    rm -rf "$tmp_dir"
-    attempt=$((attempt + 1))
-    if [ "$attempt" -le "$attempt_limit" ] && [ "${MIRRORS_RETRY_DELAY_SECONDS:-0}" -gt 0 ]; then
-      log_info "MIRROR" "Waiting ${MIRRORS_RETRY_DELAY_SECONDS}s before next mirror attempt"
-      sleep "$MIRRORS_RETRY_DELAY_SECONDS"
-    fi
-  done
+  local status
+  set +e
+  "$core_bin" "${args[@]}" -- "${pkgs[@]}"
+  status=$?
+  set -e
 
-  log_error "MIRROR" "Mirror retry budget exhausted after $attempt_limit attempt(s)"
-  return 1
+  if [ -f "$attempt_log" ]; then
+    local summary
+    summary="$(jq -r '"status=\(.status) attempts=\(.attempts | length)/\(.attempt_limit) source=\(.mirror_source)" + (if (.attempts | last | .failure_class) then " class=\(.attempts | last | .failure_class)" else "" end)' "$attempt_log" 2>/dev/null || true)"
+    log_info "MIRROR" "Repo acquisition ${summary:-finished}; attempt log: $attempt_log"
+  fi
+  return "$status"
 }
 
 #--- run_repo_batch
@@ -316,10 +241,27 @@ run_snapshot() {
     return 0
   fi
   log_info "SNAPSHOT" "Executing $phase snapshot command"
-  if ! bash -c "$cmd"; then
+  # The last non-empty output line is taken as the snapshot ID for the run
+  # journal (e.g. `snapper create --print-number`).
+  local output status snapshot_id
+  set +e
+  output="$(bash -c "$cmd")"
+  status=$?
+  set -e
+  [ -n "$output" ] && printf '%s\n' "$output"
+  snapshot_id="$(printf '%s\n' "$output" | awk 'NF { line = $0 } END { gsub(/^[ \t]+|[ \t]+$/, "", line); print line }')"
+  [[ "$snapshot_id" =~ ^[^[:space:]]{1,128}$ ]] || snapshot_id=""
+  journal_snapshot "$phase" "$status" "$snapshot_id"
+  # The snapshot command is configuration, not audit detail; record only the
+  # phase and, when the command printed one, the snapshot ID.
+  local audit_detail=""
+  [ -n "$snapshot_id" ] && audit_detail="snapshot $snapshot_id"
+  if [ "$status" -eq 0 ]; then
+    audit_event snapshot_taken success "$phase" "$audit_detail"
+  else
     log_error "SNAPSHOT" "Snapshot command for phase $phase failed"
+    audit_event snapshot_taken failure "$phase" "$audit_detail"
     if [ "$SNAPSHOT_REQUIRE_SUCCESS" = "1" ]; then
-      log_finalize
       exit 420
     fi
   fi
@@ -327,6 +269,11 @@ run_snapshot() {
 
 #--- dispatch_command
 dispatch_command() {
+  case "$COMMAND" in
+    sync|aur|repo|update|group|plan|clean|apps|flatpak|fwupd)
+      journal_begin "$COMMAND" "${COMMAND_ARGS[@]}"
+      ;;
+  esac
   case "$COMMAND" in
     core)
       cmd_core
@@ -366,7 +313,13 @@ dispatch_command() {
       cmd_clean
       ;;
     log)
-      cmd_log
+      cmd_log "${COMMAND_ARGS[@]}"
+      ;;
+    runs)
+      cmd_runs "${COMMAND_ARGS[@]}"
+      ;;
+    du)
+      cmd_du "${COMMAND_ARGS[@]}"
       ;;
     export)
       cmd_export "${COMMAND_ARGS[@]}"
@@ -461,6 +414,7 @@ cmd_core() {
 #--- cmd_sync
 cmd_sync() {
   FAILED_UPDATES=()
+  journal_phase manifest
   manifest_require
   if [ "${OFFLINE:-0}" = "1" ]; then
     log_info "OFFLINE" "Offline mode: skipping sync (repo/AUR/flatpak/fwupd disabled)"
@@ -470,8 +424,11 @@ cmd_sync() {
     return 0
   fi
   log_info "SYNC" "Commencing orchestrated upgrade"
+  journal_phase snapshot_pre
   run_snapshot "pre"
+  journal_phase disk_check
   check_disk_space
+  journal_phase updates
   local helper
   helper="$(select_helper || true)"
   if [ -n "$AUR_HELPER" ]; then
@@ -508,6 +465,7 @@ cmd_sync() {
         if [ "${#repo_batch[@]}" -ge "$BATCH_SIZE" ]; then
           if run_repo_batch "${repo_batch[@]}"; then
             processed=$((processed + ${#repo_batch[@]}))
+            record_applied_updates "${repo_batch[@]}"
           else
             local status=$?
             log_warn "UPDATE" "Failed repo batch: ${repo_batch[*]}"
@@ -521,7 +479,7 @@ cmd_sync() {
         ;;
       *)
         if ! execute_update "$pkg" "$source" "$target" "$helper"; then
-          log_warn "UPDATE" "Failed to update $pkg"
+          log_warn "UPDATE" "Failed to update $pkg" "package=$pkg" "source=$source"
           failed=$((failed + 1))
         else
           processed=$((processed + 1))
@@ -533,6 +491,7 @@ cmd_sync() {
   if [ "${#repo_batch[@]}" -gt 0 ] && [ "$DRY_RUN" = "0" ]; then
     if run_repo_batch "${repo_batch[@]}"; then
       processed=$((processed + ${#repo_batch[@]}))
+      record_applied_updates "${repo_batch[@]}"
     else
       local status=$?
       log_warn "UPDATE" "Failed repo batch: ${repo_batch[*]}"
@@ -543,6 +502,7 @@ cmd_sync() {
     fi
   fi
 
+  journal_phase applications
   if [ "$APPLICATIONS_FLATPAK" = "1" ]; then
     if ! run_flatpak_updates; then
       failed=$((failed + 1))
@@ -562,7 +522,10 @@ cmd_sync() {
   fi
   [ "$QUIET" = "1" ] || printf -- '-> Log stored at: %s\n' "$LOG_PATH"
   if [ "$DRY_RUN" = "0" ]; then
+    journal_phase verification
     check_pacnew
+    check_boot_integrity || true
+    journal_phase snapshot_post
     run_snapshot "post"
   fi
   print_failed_update_summary
@@ -608,6 +571,7 @@ cmd_apps() {
     fi
     return 0
   fi
+  journal_phase applications
   run_flatpak_updates || true
   run_fwupd_updates || true
   print_failed_update_summary
@@ -627,7 +591,7 @@ execute_update() {
         return 1
       fi
       if run_repo_batch "$pkg"; then
-        :
+        record_applied_updates "$pkg"
       else
         local status=$?
         record_failed_update "$pkg" "repo acquisition failed (exit $status)"
@@ -649,8 +613,8 @@ execute_update() {
         return 1
       fi
       # Security: helper executes as invoking user; it will escalate internally if needed.
-      if run_aur_helper_update "$helper" "$pkg"; then
-        :
+      if run_aur_helper_update "$helper" "$pkg" "$target"; then
+        record_applied_updates "$pkg"
       else
         local status=$?
         record_failed_update "$pkg" "$helper exited $status"
@@ -683,7 +647,9 @@ cmd_update() {
     return 0
   fi
   REBUILD_MANIFEST=1
+  journal_phase manifest
   manifest_require
+  journal_phase updates
   declare -A requested=()
   local pkg
   for pkg in "$@"; do
@@ -719,12 +685,16 @@ cmd_update() {
     fi
   done < <(manifest_packages_stream || true)
   log_info "SUMMARY" "Updates processed=$processed failed=$failed"
+  if [ "$DRY_RUN" = "0" ] && [ "$processed" -gt 0 ]; then
+    journal_phase verification
+    check_boot_integrity || true
+  fi
   print_failed_update_summary
 }
 
 #--- cmd_group
 cmd_group() {
-  local group="$1"
+  local group="${1:-}"
   if [ -z "$group" ]; then
     log_error "E202" "group command requires a group name"
     exit 202
@@ -777,7 +747,7 @@ PY
 
 #--- cmd_inspect
 cmd_inspect() {
-  local pkg="$1"
+  local pkg="${1:-}"
   if [ -z "$pkg" ]; then
     log_error "E301" "inspect requires a package name"
     exit 301
@@ -933,23 +903,41 @@ cmd_export() {
 #--- cmd_clean
 cmd_clean() {
   log_info "CLEAN" "Pruning cache and orphans"
-  if command -v paccache >/dev/null 2>&1; then
-    if ! sudo paccache -rk "$CLEAN_KEEP_VERSIONS"; then
+  journal_phase clean
+  local core_bin="$SYN_CORE_BIN"
+  if [ ! -x "$core_bin" ]; then
+    core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+  fi
+  local -a clean_args=(clean cache --keep "$CLEAN_KEEP_VERSIONS")
+  if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+    clean_args+=(--config "$CONFIG_PATH")
+  fi
+  if [ "${DRY_RUN:-0}" = "1" ]; then
+    clean_args+=(--dry-run)
+    clean_cache_dry_run "$core_bin" "${clean_args[@]}"
+  # synsyu_core elevates only the removal of root-owned cache files.
+  elif [ -n "$core_bin" ] && [ -x "$core_bin" ] && "$core_bin" "${clean_args[@]}"; then
+    log_info "CLEAN" "Package caches pruned by synsyu_core (keep $CLEAN_KEEP_VERSIONS)"
+  elif command -v paccache >/dev/null 2>&1; then
+    [ -n "$core_bin" ] && log_warn "CLEAN" "synsyu_core cache pruning failed; falling back to paccache"
+    if ! privileged paccache -rk "$CLEAN_KEEP_VERSIONS"; then
       log_warn "CLEAN" "paccache failed; falling back to pacman -Sc"
-      # Security: limited to cache pruning via sudo pacman -Sc, no package installs/removals.
-      sudo pacman -Sc --noconfirm || log_warn "CLEAN" "Failed to prune pacman cache"
+      # Security: limited to cache pruning via privileged pacman -Sc, no package installs/removals.
+      privileged pacman -Sc --noconfirm || log_warn "CLEAN" "Failed to prune pacman cache"
     fi
   else
     log_warn "CLEAN" "paccache not available; using pacman -Sc"
-    sudo pacman -Sc --noconfirm || log_warn "CLEAN" "Failed to prune pacman cache"
+    privileged pacman -Sc --noconfirm || log_warn "CLEAN" "Failed to prune pacman cache"
   fi
 
   if [ "$CLEAN_REMOVE_ORPHANS" = "1" ]; then
     local orphan_file="/tmp/syn-syu_orphans.txt"
-    sudo pacman -Qtdq >"$orphan_file" 2>/dev/null || true
+    privileged pacman -Qtdq >"$orphan_file" 2>/dev/null || true
     if [ -s "$orphan_file" ]; then
       mapfile -t _syn_syu_orphans <"$orphan_file"
-      if [ "${#_syn_syu_orphans[@]}" -gt 0 ] && sudo pacman -Rns --noconfirm "${_syn_syu_orphans[@]}"; then
+      if [ "${DRY_RUN:-0}" = "1" ]; then
+        log_info "CLEAN" "Dry run: would remove ${#_syn_syu_orphans[@]} orphaned package(s): ${_syn_syu_orphans[*]}"
+      elif [ "${#_syn_syu_orphans[@]}" -gt 0 ] && privileged pacman -Rns --noconfirm "${_syn_syu_orphans[@]}"; then
         log_info "CLEAN" "Removed orphaned packages"
       else
         log_warn "CLEAN" "Failed to remove orphaned packages"
@@ -963,13 +951,76 @@ cmd_clean() {
   fi
 
   if [ -d "$LOG_DIR" ]; then
-    find "$LOG_DIR" -maxdepth 1 -type f -name 'installer_*.log*' -mtime +30 -delete 2>/dev/null || true
+    if [ "${DRY_RUN:-0}" = "1" ]; then
+      local stale_logs
+      stale_logs="$(find "$LOG_DIR" -maxdepth 1 -type f -name 'installer_*.log*' -mtime +30 2>/dev/null | wc -l)"
+      [ "$stale_logs" -gt 0 ] && log_info "CLEAN" "Dry run: would remove $stale_logs installer log(s) older than 30 days"
+    else
+      find "$LOG_DIR" -maxdepth 1 -type f -name 'installer_*.log*' -mtime +30 -delete 2>/dev/null || true
+    fi
   fi
 }
 
+#--- clean_cache_dry_run
+# Usage: clean_cache_dry_run <core_bin> <clean args...>
+# Reports what cache pruning would remove without deleting anything; the
+# fallback only ever uses paccache's listing mode, never -r or pacman -Sc.
+clean_cache_dry_run() {
+  local core_bin="$1"
+  shift
+  if [ -n "$core_bin" ] && [ -x "$core_bin" ] && "$core_bin" "$@"; then
+    log_info "CLEAN" "Dry run: synsyu_core reported cache pruning (keep $CLEAN_KEEP_VERSIONS); nothing removed"
+  elif command -v paccache >/dev/null 2>&1; then
+    [ -n "$core_bin" ] && log_warn "CLEAN" "synsyu_core cache dry run failed; listing candidates with paccache -d"
+    paccache -dk "$CLEAN_KEEP_VERSIONS" || log_warn "CLEAN" "paccache dry run failed"
+    log_info "CLEAN" "Dry run: nothing removed"
+  else
+    log_warn "CLEAN" "Dry run: synsyu_core and paccache unavailable; skipping cache pruning preview"
+  fi
+}
+
+#--- cmd_du
+cmd_du() {
+  local core_bin="$SYN_CORE_BIN"
+  if [ ! -x "$core_bin" ]; then
+    core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+  fi
+  if [ -z "$core_bin" ] || [ ! -x "$core_bin" ]; then
+    log_error "E301" "synsyu_core binary not found at $SYN_CORE_BIN"
+    return 1
+  fi
+  local -a args=(du --manifest "$(manifest_resolved_path)")
+  if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+    args+=(--config "$CONFIG_PATH")
+  fi
+  [ "${JSON_OUTPUT:-0}" = "1" ] && args+=(--json)
+  # Remaining words (e.g. `syn-syu du 50`) set how many packages to list.
+  [ $# -gt 0 ] && args+=(--top "$1")
+  "$core_bin" "${args[@]}"
+}
+
 #--- cmd_log
 cmd_log() {
   local dir="${LOG_DIR:-$HOME/.local/share/syn-syu}"
+  # Filters after `--` (e.g. `syn-syu log -- --level error --source AUR
+  # --since 30d`) are handed to `synsyu_core logs query`.
+  if [ $# -gt 0 ]; then
+    local core_bin="$SYN_CORE_BIN"
+    if [ ! -x "$core_bin" ]; then
+      core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+    fi
+    if [ -z "$core_bin" ] || [ ! -x "$core_bin" ]; then
+      log_error "LOG" "synsyu_core not found; log queries need the core binary"
+      return 1
+    fi
+    local -a args=(logs query --dir "$dir")
+    if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+      args+=(--config "$CONFIG_PATH")
+    fi
+    [ "${JSON_OUTPUT:-0}" = "1" ] && args+=(--json)
+    "$core_bin" "${args[@]}" "$@"
+    return
+  fi
   if [ ! -d "$dir" ]; then
     printf 'No logs found in %s\n' "$dir"
     return 0
@@ -977,6 +1028,27 @@ cmd_log() {
   ls -1t "$dir"/*.log 2>/dev/null | head -n 10
 }
 
+#--- cmd_runs
+# Usage: cmd_runs [list|show RUN_ID|last] [ARGS...]
+cmd_runs() {
+  local core_bin="$SYN_CORE_BIN"
+  if [ ! -x "$core_bin" ]; then
+    core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+  fi
+  if [ -z "$core_bin" ] || [ ! -x "$core_bin" ]; then
+    log_error "RUNS" "synsyu_core not found; the run journal needs the core binary"
+    return 1
+  fi
+  local action="${1:-list}"
+  [ $# -gt 0 ] && shift
+  local -a args=(runs "$action")
+  if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+    args+=(--config "$CONFIG_PATH")
+  fi
+  [ "${JSON_OUTPUT:-0}" = "1" ] && args+=(--json)
+  "$core_bin" "${args[@]}" "$@"
+}
+
 #--- cmd_helpers
 cmd_helpers() {
   detect_helpers
@@ -1171,7 +1243,7 @@ cmd_acquisition() {
         pacman_repo: {
           implemented: true,
           strategy: "mirror_failover",
-          executor: "pacman via temporary pacman config",
+          executor: "synsyu_core acquire repo (temporary pacman config per attempt)",
           enabled: ($mirrors_enabled == 1),
           max_failovers: $mirror_failovers,
           max_attempts: $mirror_attempts,
@@ -1259,15 +1331,23 @@ Commands:
   inspect <pkg>     Show manifest detail for package
   check             Summarize manifest contents
   clean             Prune caches and remove orphans
-  log               List recent Syn-Syu log files
+  log [-- FILTERS]  List recent log files, or query entries (--level, --code,
+                    --package, --source, --since, --until, --contains)
+  runs [list|show ID|last]
+                    Show the run journal (one record per sync/update/clean run)
+  du [N]            Disk usage by package, source, Flatpak, caches, and logs
   export            Export package lists for replication
   help              Display this help message
   config            Open config.toml in \$EDITOR (creates from example if missing)
+  config get KEY    Print the effective value of a dotted key (e.g. space.mode)
+  config set KEY VALUE
+                    Change one key in config.toml, keeping comments; validated first
   groups-edit       Open groups.toml in \$EDITOR (creates if missing)
   version           Show version information
 
 Flags:
   --config <path>   Use alternate configuration file
+  --profile <name>  Apply the [profiles.<name>] overlay (or set SYNSYU_PROFILE)
   --manifest <path> Override manifest location
   --plan <path>     Override plan output location
   --rebuild         Force manifest rebuild before command
@@ -1286,7 +1366,7 @@ Flags:
   --confirm, -c     Ask for confirmation in helpers (drop --noconfirm)
   --noconfirm, -nc  Force non-interactive operations (default)
   --helper <name>   Force a specific AUR helper
-  --strict          Fail plan when any source reports errors
+  --strict          Validate configuration at startup; fail plan when any source reports errors
   --include <regex> Include only packages matching regex (repeatable)
   --exclude <regex> Exclude packages matching regex (repeatable)
   --min-free-gb <N> Override required free space buffer in gigabytes
@@ -1384,21 +1464,51 @@ print(os.path.expanduser(path))
 PY
 }
 
+#--- cmd_config_key
+# Usage: cmd_config_key get KEY | set KEY VALUE
+cmd_config_key() {
+  local core_bin
+  core_bin="$(config_core_bin)"
+  if [ -z "$core_bin" ]; then
+    log_error "CONFIG" "synsyu_core not found; config get/set need the core binary"
+    return 1
+  fi
+  local action="$1"
+  shift
+  local path="${CONFIG_PATH:-$DEFAULT_CONFIG_PATH}"
+  path="$(expand_path "$path")"
+  [ "$action" = "set" ] && ensure_config_seed "$path"
+  local -a args=(config "$action" "$@")
+  [ -f "$path" ] && args+=(--config "$path")
+  "$core_bin" "${args[@]}"
+}
+
 #--- cmd_config
 cmd_config() {
   if [ "${1:-}" = "--groups" ]; then
     cmd_groups_edit
     return
   fi
+  case "${1:-}" in
+    get|set)
+      cmd_config_key "$@"
+      return
+      ;;
+  esac
   local path="${CONFIG_PATH:-$DEFAULT_CONFIG_PATH}"
   path="$(expand_path "$path")"
   if ! require_tty_for_edit "Config path: $path"; then
     exit 1
   fi
   ensure_config_seed "$path"
-  local editor
+  local editor before after
   editor="$(resolve_editor)" || exit 1
+  before="$(sha256sum "$path" 2>/dev/null | cut -d' ' -f1)"
   "$editor" "$path"
+  after="$(sha256sum "$path" 2>/dev/null | cut -d' ' -f1)"
+  if [ "$before" != "$after" ]; then
+    audit_event config_changed success "$path" "edited with $editor"
+  fi
 }
 
 #--- cmd_groups_edit
@@ -4,12 +4,14 @@
 # Etiquette: Synavera Script Etiquette — Bash Profile v1.1.1
 #------------------------------------------------------------
 # Purpose:
-#   Shared utility functions for conversions and failure
-#   tracking used across Syn-Syu command modules.
+#   Shared utility functions for conversions, failure
+#   tracking, and audit events used across Syn-Syu command
+#   modules.
 #
 # Security / Safety Notes:
 #   Pure data transformations; no external commands beyond
-#   python3 for numeric conversions.
+#   python3 for numeric conversions and synsyu_core for
+#   audit delivery.
 #------------------------------------------------------------
 # SSE Principles Observed:
 #   - Modular utilities for reuse across command modules
@@ -67,6 +69,77 @@ record_failed_update() {
   local pkg="${1:-unknown}" reason="${2:-unspecified failure}"
   reason="${reason//$'\n'/ }"
   FAILED_UPDATES+=("$pkg|$reason")
+  audit_event package_failed failure "$pkg" "$reason"
+  journal_item "$pkg" failure "$reason"
+}
+
+#--- record_applied_updates
+record_applied_updates() {
+  local pkg
+  for pkg in "$@"; do
+    audit_event package_applied success "$pkg"
+    journal_item "$pkg" success
+  done
+}
+
+#--- audit_event
+# Usage: audit_event ACTION OUTCOME SUBJECT [DETAIL]
+# Hands the event to `synsyu_core audit record`, which fans it out to the
+# [audit] backends. Audit trouble is logged, never fatal.
+audit_event() {
+  [ "${AUDIT_ENABLED:-0}" = "1" ] || return 0
+  local action="$1" outcome="$2" subject="$3" detail="${4:-}"
+  local core_bin="$SYN_CORE_BIN"
+  if [ ! -x "$core_bin" ]; then
+    core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+  fi
+  if [ -z "$core_bin" ] || [ ! -x "$core_bin" ]; then
+    log_warn "AUDIT" "synsyu_core not found; audit event $action for $subject not recorded"
+    return 0
+  fi
+  local -a args=(audit record --action "$action" --outcome "$outcome" --subject "$subject")
+  [ -n "$detail" ] && args+=(--detail "$detail")
+  if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+    args+=(--config "$CONFIG_PATH")
+  fi
+  local output
+  if ! output="$("$core_bin" "${args[@]}" 2>&1)"; then
+    log_warn "AUDIT" "Audit event $action for $subject not delivered: ${output//$'\n'/ }"
+  fi
+  return 0
+}
+
+#--- signature_run
+# Usage: signature_run sign|verify PATH
+signature_run() {
+  local action="$1" path="$2"
+  local core_bin="$SYN_CORE_BIN"
+  if [ ! -x "$core_bin" ]; then
+    core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+  fi
+  if [ -z "$core_bin" ] || [ ! -x "$core_bin" ]; then
+    log_error "SIGN" "synsyu_core not found; cannot $action $path"
+    return 1
+  fi
+  local -a args=("$action")
+  if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+    args+=(--config "$CONFIG_PATH")
+  fi
+  local output
+  if ! output="$("$core_bin" "${args[@]}" "$path" 2>&1)"; then
+    log_error "SIGN" "${output//$'\n'/ }"
+    return 1
+  fi
+  log_debug "SIGN" "${output//$'\n'/ }"
+}
+
+#--- signature_refresh
+# Usage: signature_refresh PATH
+# Re-sign a manifest or plan the orchestrator changed after synsyu_core
+# signed it; a no-op unless [signing].enabled is set.
+signature_refresh() {
+  [ "${SIGNING_ENABLED:-0}" = "1" ] || return 0
+  signature_run sign "$1"
 }
 
 #--- print_failed_update_summary
@@ -89,6 +162,41 @@ print_failed_update_summary() {
   done
 }
 
+#--- check_boot_integrity
+check_boot_integrity() {
+  local core_bin="$SYN_CORE_BIN"
+  if [ ! -x "$core_bin" ]; then
+    core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+  fi
+  if [ -z "$core_bin" ] || [ ! -x "$core_bin" ]; then
+    log_warn "BOOT" "synsyu_core not found; skipping kernel/boot image verification"
+    return 0
+  fi
+  local -a args=(kernel verify)
+  if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+    args+=(--config "$CONFIG_PATH")
+  fi
+  local report status
+  set +e
+  report="$("$core_bin" "${args[@]}" 2>&1)"
+  status=$?
+  set -e
+  if [ "$status" -eq 0 ]; then
+    log_info "BOOT" "Installed kernels match their module trees and boot images"
+    return 0
+  fi
+  log_error "BOOT" "Kernel/boot image verification failed (exit $status)"
+  while IFS= read -r line; do
+    [ -z "$line" ] && continue
+    log_error "BOOT" "$line"
+  done <<<"$report"
+  # Always shown, even in quiet mode: rebooting now may leave the system unbootable.
+  printf '\n!! Kernel/boot image mismatch detected after the update:\n' >&2
+  printf '%s\n' "$report" | grep '^!!' >&2 || printf '%s\n' "$report" >&2
+  printf '\n' >&2
+  return 1
+}
+
 #--- check_pacnew
 check_pacnew() {
   if [ "${CLEAN_CHECK_PACNEW:-1}" != "1" ]; then
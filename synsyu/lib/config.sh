@@ -7,7 +7,8 @@
 #   Configuration loading and prerequisite checks.
 #
 # Security / Safety Notes:
-#   Reads user-owned config files; refuses to proceed if jq or
+#   Reads the layered configuration through synsyu_core, or the
+#   user-owned file directly; refuses to proceed if jq or
 #   python3 are missing.
 #------------------------------------------------------------
 # SSE Principles Observed:
@@ -27,30 +28,70 @@ ensure_prerequisites() {
   fi
 }
 
+#--- config_core_bin
+# Usage: config_core_bin
+# Prints the synsyu_core path, or nothing when it is not installed.
+config_core_bin() {
+  local core_bin="$SYN_CORE_BIN"
+  [ -x "$core_bin" ] || core_bin="$(command -v synsyu_core 2>/dev/null || true)"
+  [ -n "$core_bin" ] && [ -x "$core_bin" ] && printf '%s' "$core_bin"
+  return 0
+}
+
 #--- load_config
 load_config() {
   if [ -z "$CONFIG_PATH" ]; then
     CONFIG_PATH="$DEFAULT_CONFIG_PATH"
   fi
 
-  if [ ! -f "$CONFIG_PATH" ]; then
+  # The core merges /etc/syn-syu/config.toml, the user file, and SYNSYU_*
+  # variables; read the file directly only when it is unavailable.
+  local merged_json="" core_bin
+  core_bin="$(config_core_bin)"
+  if [ -n "$core_bin" ]; then
+    local -a core_args=(config --merged)
+    [ -f "$CONFIG_PATH" ] && core_args+=(--config "$CONFIG_PATH")
+    local stderr_file merge_error
+    stderr_file="$(mktemp "${TMPDIR:-/tmp}/synsyu_config_XXXXXX")"
+    if ! merged_json="$("$core_bin" "${core_args[@]}" 2>"$stderr_file")"; then
+      merged_json=""
+      merge_error="$(tr '\n' ' ' <"$stderr_file" 2>/dev/null || true)"
+      merge_error="${merge_error%"${merge_error##*[![:space:]]}"}"
+      if [ -n "${SYNSYU_PROFILE:-}" ]; then
+        # A selected profile only exists in the core's view; keep its error.
+        CONFIG_PROFILE_ERROR="${merge_error:-synsyu_core config --merged failed}"
+      else
+        # Logging is not up yet; check_config_merge reports it.
+        CONFIG_MERGE_ERROR="${merge_error:-no stderr captured}"
+      fi
+    fi
+    rm -f "$stderr_file"
+  elif [ -n "${SYNSYU_PROFILE:-}" ]; then
+    CONFIG_PROFILE_ERROR="synsyu_core not found; cannot apply profile $SYNSYU_PROFILE"
+  fi
+
+  if [ ! -f "$CONFIG_PATH" ] && { [ -z "$merged_json" ] || [ "$merged_json" = "{}" ]; }; then
     HELPER_PRIORITY=($(printf '%s\n' "${HELPER_CANDIDATES[@]}"))
     return
   fi
 
   local py_output
-  if ! py_output="$(CONFIG_PATH="$CONFIG_PATH" python3 - <<'PY'
+  if ! py_output="$(CONFIG_PATH="$CONFIG_PATH" MERGED_CONFIG="$merged_json" python3 - <<'PY'
 import json
 import os
 import sys
 import tomllib
 
+merged = os.environ.get("MERGED_CONFIG")
 config_path = os.environ.get("CONFIG_PATH")
-try:
-    with open(config_path, "rb") as handle:
-        data = tomllib.load(handle)
-except FileNotFoundError:
-    sys.exit(1)
+if merged:
+    data = json.loads(merged)
+else:
+    try:
+        with open(config_path, "rb") as handle:
+            data = tomllib.load(handle)
+    except FileNotFoundError:
+        sys.exit(1)
 
 core = data.get("core", {})
 helpers = data.get("helpers", {})
@@ -65,8 +106,12 @@ mirrors = data.get("mirrors", {})
 acquisition = data.get("acquisition", {})
 acquisition_aur_rpc = acquisition.get("aur_rpc", {})
 acquisition_aur_helper = acquisition.get("aur_helper", {})
+audit = data.get("audit", {})
+signing = data.get("signing", {})
 
 log_directory = logging.get("directory") or core.get("log_directory", "")
+if log_directory:
+    log_directory = os.path.expanduser(log_directory)
 
 def to_bytes(value):
     try:
@@ -78,16 +123,21 @@ def to_bytes(value):
     return int(round(number * 1024 * 1024 * 1024))
 
 settings = {
-    "manifest": core.get("manifest_path", ""),
+    "manifest": os.path.expanduser(core.get("manifest_path", "")),
     "helper_priority": helpers.get("priority", []),
     "helper_default": helpers_section.get("default", ""),
     "log_directory": log_directory,
     "log_level": logging.get("level", ""),
     "log_retention_days": logging.get("retention_days"),
     "log_retention_megabytes": logging.get("retention_megabytes"),
+    "log_format": logging.get("format", "text"),
+    "audit_enabled": bool(audit.get("backends")),
+    "signing_enabled": bool(signing.get("enabled", False)),
+    "signing_require": bool(signing.get("require", False)),
     "batch_size": core.get("batch_size", 10),
+    "privilege_tool": core.get("privilege_tool", "sudo"),
     "space_min_free_bytes": to_bytes(space.get("min_free_gb")),
-    "space_mode": space.get("mode", "warn"),
+    "space_mode": space.get("mode", space.get("policy", "warn")),
     "snapshots_enabled": snapshots.get("enabled", False),
     "snapshot_pre": snapshots.get("pre_command", ""),
     "snapshot_post": snapshots.get("post_command", ""),
@@ -122,7 +172,7 @@ PY
     py_output=""
   fi
 
-  local manifest_path helper_line helper_default log_dir batch_size log_level retention_days retention_mb
+  local manifest_path helper_line helper_default log_dir batch_size log_level retention_days retention_mb log_format
   local apps_flatpak apps_fwupd
   local mirrors_enabled mirrors_probe mirrors_mirrorlist mirrors_pacman_conf
   local mirrors_max_candidates mirrors_max_failovers mirrors_retry_delay mirrors_probe_timeout mirrors_max_sync_age
@@ -136,7 +186,24 @@ PY
     log_level="$(printf '%s' "$py_output" | jq -r '.log_level // empty')"
     retention_days="$(printf '%s' "$py_output" | jq -r '.log_retention_days // empty')"
     retention_mb="$(printf '%s' "$py_output" | jq -r '.log_retention_megabytes // empty')"
+    log_format="$(printf '%s' "$py_output" | jq -r '.log_format // "text"')"
+    if [ "$(printf '%s' "$py_output" | jq -r '.audit_enabled // false')" = "true" ]; then
+      AUDIT_ENABLED=1
+    else
+      AUDIT_ENABLED=0
+    fi
+    if [ "$(printf '%s' "$py_output" | jq -r '.signing_enabled // false')" = "true" ]; then
+      SIGNING_ENABLED=1
+    else
+      SIGNING_ENABLED=0
+    fi
+    if [ "$(printf '%s' "$py_output" | jq -r '.signing_require // false')" = "true" ]; then
+      SIGNING_REQUIRE=1
+    else
+      SIGNING_REQUIRE=0
+    fi
     batch_size="$(printf '%s' "$py_output" | jq -r '.batch_size // 10')"
+    PRIVILEGE_TOOL="$(printf '%s' "$py_output" | jq -r '.privilege_tool // "sudo"')"
     apps_flatpak="$(printf '%s' "$py_output" | jq -r '.apps_flatpak_enabled // false')"
     apps_fwupd="$(printf '%s' "$py_output" | jq -r '.apps_fwupd_enabled // false')"
     mirrors_enabled="$(printf '%s' "$py_output" | jq -r '.mirrors_enabled // true')"
@@ -207,6 +274,14 @@ PY
           ;;
       esac
     fi
+    case "${log_format,,}" in
+      json|jsonl) LOG_FORMAT="json" ;;
+      text|"") LOG_FORMAT="text" ;;
+      *)
+        printf 'Syn-Syu config: invalid logging.format "%s"; defaulting to text.\n' "$log_format" >&2
+        LOG_FORMAT="text"
+        ;;
+    esac
     if [[ "$retention_days" =~ ^[0-9]+$ ]]; then
       LOG_RETENTION_DAYS="$retention_days"
     fi
@@ -331,3 +406,53 @@ PY
     MIRRORS_MAX_CANDIDATES=1
   fi
 }
+
+#--- check_config_strict
+# Usage: check_config_strict
+# Under --strict, refuse to run when `synsyu_core config --check` reports
+# unknown keys, out-of-range values, or conflicting settings.
+check_config_strict() {
+  [ "${STRICT_MODE:-0}" = "1" ] || return 0
+  local core_bin
+  core_bin="$(config_core_bin)"
+  if [ -z "$core_bin" ]; then
+    log_warn "CONFIG" "synsyu_core not found; skipping strict configuration check"
+    return 0
+  fi
+  local -a args=(config --check)
+  [ -f "$CONFIG_PATH" ] && args+=(--config "$CONFIG_PATH")
+  local output line
+  if output="$("$core_bin" "${args[@]}" 2>&1)"; then
+    log_debug "CONFIG" "Strict configuration check passed"
+    return 0
+  fi
+  while IFS= read -r line; do
+    line="${line#"${line%%[![:space:]]*}"}"
+    line="${line#error: }"
+    [ -n "$line" ] && log_error "E104" "$line"
+  done <<<"$output"
+  exit 104
+}
+
+#--- check_config_merge
+# Usage: check_config_merge
+# Warn when synsyu_core could not merge the configuration layers, so a
+# broken /etc file or SYNSYU_* variable does not silently fall back to
+# reading the user file alone.
+check_config_merge() {
+  [ -n "$CONFIG_MERGE_ERROR" ] || return 0
+  log_warn "CONFIG" "synsyu_core config --merged failed; reading $CONFIG_PATH directly: $CONFIG_MERGE_ERROR"
+}
+
+#--- check_config_profile
+# Usage: check_config_profile
+# Stop when --profile/SYNSYU_PROFILE names a profile the core could not
+# apply, rather than running with the base configuration.
+check_config_profile() {
+  [ -n "${SYNSYU_PROFILE:-}" ] || return 0
+  if [ -n "$CONFIG_PROFILE_ERROR" ]; then
+    log_error "E105" "$CONFIG_PROFILE_ERROR"
+    exit 105
+  fi
+  log_info "CONFIG" "Using configuration profile $SYNSYU_PROFILE"
+}
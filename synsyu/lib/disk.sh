@@ -115,7 +115,6 @@ PY
 
   if [ "$available_bytes" -lt "$required_bytes" ]; then
     log_error "DISK" "Insufficient space: need $(format_bytes "$required_bytes") (download $(format_bytes "$download_bytes") + build $(format_bytes "$build_bytes") + install $(format_bytes "$install_bytes") + buffer $(format_bytes "$margin_bytes")), only $(format_bytes "$available_bytes") available on $available_path"
-    log_finalize
     exit 421
   fi
 
@@ -7,7 +7,8 @@
 #   Detect and rank available Arch User Repository helpers.
 #
 # Security / Safety Notes:
-#   Performs PATH lookups only; no commands are executed here.
+#   Performs PATH lookups; the only write is helpers.default via
+#   `synsyu_core config set`.
 #
 # Dependencies:
 #   command -v (POSIX shell builtin).
@@ -56,32 +57,18 @@ select_helper() {
 }
 
 #--- update_helper_default
+# Usage: update_helper_default HELPER
+# Writes helpers.default through `synsyu_core config set`, which validates the
+# value, keeps the file's comments, and records the config_changed audit event.
 update_helper_default() {
   local helper="$1"
   local cfg="${CONFIG_PATH:-$DEFAULT_CONFIG_PATH}"
-  local cfg_dir
-  cfg_dir="$(dirname "$cfg")"
-  mkdir -p "$cfg_dir"
-  if ! command -v python3 >/dev/null 2>&1; then
-    printf 'python3 required to update config\n' >&2
+  local core_bin
+  core_bin="$(config_core_bin)"
+  if [ -z "$core_bin" ]; then
+    printf 'synsyu_core required to update config\n' >&2
     return 1
   fi
-  python3 - "$helper" "$cfg" <<'PY' || return 1
-import sys, os, tomllib, tomli_w
-
-helper = sys.argv[1]
-cfg_path = sys.argv[2]
-data = {}
-if os.path.isfile(cfg_path):
-    with open(cfg_path, "rb") as handle:
-        try:
-            data = tomllib.load(handle)
-        except Exception:
-            data = {}
-helpers = data.get("helpers") or {}
-helpers["default"] = helper
-data["helpers"] = helpers
-with open(cfg_path, "wb") as handle:
-    tomli_w.dump(data, handle)
-PY
+  ensure_config_seed "$cfg"
+  "$core_bin" config set helpers.default "$helper" --config "$cfg" >/dev/null
 }
@@ -0,0 +1,122 @@
+#!/usr/bin/env bash
+#============================================================
+# Synavera Project: Syn-Syu
+# Module: synsyu/lib/journal.sh
+# Etiquette: Synavera Script Etiquette — Bash Profile v1.1.1
+#------------------------------------------------------------
+# Purpose:
+#   Record the run journal for orchestrated operations: run
+#   start, the manifest and plan acted on, phase durations, per-item
+#   outcomes, snapshot IDs, and the exit status, through
+#   `synsyu_core runs`.
+#
+# Security / Safety Notes:
+#   Journal writes are best-effort and never change a run's
+#   outcome; the first failure disables the journal for the
+#   rest of the run with a single warning.
+#------------------------------------------------------------
+# SSE Principles Observed:
+#   - One record per run, reconstructable after a crash
+#   - Explicit, side-effect-free helpers for predictability
+#============================================================
+
+JOURNAL_ACTIVE=0
+JOURNAL_PHASE=""
+JOURNAL_PHASE_START_MS=0
+
+#--- journal_call
+# Usage: journal_call SUBCOMMAND [ARGS...]
+journal_call() {
+  [ "$JOURNAL_ACTIVE" = "1" ] || return 0
+  local sub="$1"
+  shift
+  local -a args=(runs "$sub" --run-id "$RUN_ID")
+  if [ -n "${CONFIG_PATH:-}" ] && [ -f "$CONFIG_PATH" ]; then
+    args+=(--config "$CONFIG_PATH")
+  fi
+  local output
+  if ! output="$("$JOURNAL_CORE_BIN" "${args[@]}" "$@" 2>&1)"; then
+    JOURNAL_ACTIVE=0
+    log_warn "JOURNAL" "Run journal disabled for this run: ${output//$'\n'/ }"
+  fi
+  return 0
+}
+
+#--- journal_begin
+# Usage: journal_begin COMMAND [ARGS...]
+journal_begin() {
+  local command="$1"
+  shift
+  JOURNAL_CORE_BIN="$SYN_CORE_BIN"
+  if [ ! -x "$JOURNAL_CORE_BIN" ]; then
+    JOURNAL_CORE_BIN="$(command -v synsyu_core 2>/dev/null || true)"
+  fi
+  if [ -z "$JOURNAL_CORE_BIN" ] || [ ! -x "$JOURNAL_CORE_BIN" ]; then
+    log_debug "JOURNAL" "synsyu_core not found; run journal disabled"
+    return 0
+  fi
+  JOURNAL_ACTIVE=1
+  journal_call start --command "$command" --log "$LOG_PATH" -- "$@"
+  [ "$JOURNAL_ACTIVE" = "1" ] && log_info "JOURNAL" "Run $RUN_ID recorded in the run journal"
+  return 0
+}
+
+#--- journal_phase_close
+# Usage: journal_phase_close [STATUS]
+journal_phase_close() {
+  local status="${1:-0}"
+  [ -n "$JOURNAL_PHASE" ] || return 0
+  local duration_ms=$(($(date +%s%3N) - JOURNAL_PHASE_START_MS))
+  journal_call phase --name "$JOURNAL_PHASE" --duration-ms "$duration_ms" --status "$status"
+  JOURNAL_PHASE=""
+}
+
+#--- journal_phase
+# Usage: journal_phase NAME
+# Closes the open phase as successful and starts timing NAME.
+journal_phase() {
+  [ "$JOURNAL_ACTIVE" = "1" ] || return 0
+  journal_phase_close 0
+  JOURNAL_PHASE="$1"
+  JOURNAL_PHASE_START_MS="$(date +%s%3N)"
+}
+
+#--- journal_manifest
+# Usage: journal_manifest PATH
+journal_manifest() {
+  [ -f "${1:-}" ] || return 0
+  journal_call manifest --manifest "$1"
+}
+
+#--- journal_plan
+# Usage: journal_plan PATH
+journal_plan() {
+  [ -f "${1:-}" ] || return 0
+  journal_call plan --plan "$1"
+}
+
+#--- journal_item
+# Usage: journal_item NAME OUTCOME [DETAIL]
+journal_item() {
+  local -a args=(--name "$1" --outcome "$2")
+  [ -n "${3:-}" ] && args+=(--detail "$3")
+  journal_call item "${args[@]}"
+}
+
+#--- journal_snapshot
+# Usage: journal_snapshot PHASE STATUS [ID]
+journal_snapshot() {
+  local -a args=(--phase "$1" --status "$2")
+  [ -n "${3:-}" ] && args+=(--id "$3")
+  journal_call snapshot "${args[@]}"
+}
+
+#--- journal_end
+# Usage: journal_end STATUS
+journal_end() {
+  [ "$JOURNAL_ACTIVE" = "1" ] || return 0
+  local status="${1:-0}"
+  journal_phase_close "$status"
+  journal_call end --status "$status"
+  JOURNAL_ACTIVE=0
+}
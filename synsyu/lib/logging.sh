@@ -5,8 +5,8 @@
 #------------------------------------------------------------
 # Purpose:
 #   Provide structured logging utilities for Syn-Syu
-#   orchestrator, including append-only log files and hash
-#   generation for audit chaining.
+#   orchestrator, including append-only, hash-chained log
+#   files sealed with a SHA-256 sidecar at exit.
 #
 # Security / Safety Notes:
 #   Writes logs under user-owned directories only.
@@ -62,7 +62,7 @@ log_prune() {
 
   local retention_days="${LOG_RETENTION_DAYS:-0}"
   if [[ "$retention_days" =~ ^[0-9]+$ ]] && [ "$retention_days" -gt 0 ]; then
-    find "$dir" -maxdepth 1 -type f \( -name '*.log' -o -name '*.log.hash' \) -mtime +"$retention_days" -print0 \
+    find "$dir" -maxdepth 1 -type f \( -name '*.log' -o -name '*.log.gz' -o -name '*.log.hash' -o -name '*.log.gz.hash' \) -mtime +"$retention_days" -print0 \
       | xargs -0 -r rm -f --
   fi
 
@@ -85,7 +85,7 @@ logs = []
 try:
     with os.scandir(directory) as entries:
         for entry in entries:
-            if entry.is_file() and entry.name.endswith(".log"):
+            if entry.is_file() and entry.name.endswith((".log", ".log.gz")):
                 try:
                     stat = entry.stat()
                 except FileNotFoundError:
@@ -139,16 +139,77 @@ log_init() {
   touch "$LOG_PATH"
 }
 
+#--- log_format_line
+# Render one log line in LOG_FORMAT (text or json). Extra key=value arguments
+# become typed fields: run_id, package, source, duration_ms, prev.
+log_format_line() {
+  local timestamp="$1" level="$2" code="$3" message="$4"
+  shift 4
+  local -a fields=()
+  local pair key value
+  for pair in "$@"; do
+    key="${pair%%=*}"
+    value="${pair#*=}"
+    [ -n "$value" ] || continue
+    case "$key" in
+      run_id|package|source|prev) fields+=("$key=$value") ;;
+      duration_ms) [[ "$value" =~ ^[0-9]+$ ]] && fields+=("$key=$value") ;;
+    esac
+  done
+  if [ "${LOG_FORMAT:-text}" = "json" ]; then
+    local -a args=(--arg timestamp "$timestamp" --arg level "$level" --arg code "$code" --arg message "$message")
+    local filter='{timestamp: $timestamp, level: $level, code: $code, message: $message}'
+    for pair in "${fields[@]}"; do
+      key="${pair%%=*}"
+      value="${pair#*=}"
+      if [ "$key" = "duration_ms" ]; then
+        args+=(--argjson "$key" "$value")
+      else
+        args+=(--arg "$key" "$value")
+      fi
+      filter+=" + {$key: \$$key}"
+    done
+    jq -cn "${args[@]}" "$filter"
+  elif [ "${#fields[@]}" -gt 0 ]; then
+    printf '%s [%s] [%s] %s | %s\n' "$timestamp" "$level" "$code" "$message" "${fields[*]}"
+  else
+    printf '%s [%s] [%s] %s\n' "$timestamp" "$level" "$code" "$message"
+  fi
+}
+
+#--- log_chain_prev
+# SHA-256 of the last line in LOG_PATH (the genesis hash for an empty log), so
+# every entry names the one before it.
+log_chain_prev() {
+  local last=""
+  [ -s "$LOG_PATH" ] && last="$(tail -n 1 "$LOG_PATH")"
+  if [ -z "$last" ]; then
+    printf '%064d\n' 0
+  else
+    printf '%s' "$last" | sha256sum | cut -d' ' -f1
+  fi
+}
+
+#--- log_is_sealed
+log_is_sealed() {
+  local last
+  last="$(tail -n 1 "$LOG_PATH" 2>/dev/null || true)"
+  [[ "$last" =~ ^[^\ ]+\ \[[A-Z]+\]\ \[SEAL\] ]] || [[ "$last" == *'"code":"SEAL"'* ]]
+}
+
 #--- log_event
+# Usage: log_event LEVEL CODE MESSAGE [key=value ...]
+# A sealed log is never appended to; the entry still reaches the console.
 log_event() {
   if [ $# -lt 3 ]; then
     return 1
   fi
-  local level="$1" code="$2" message="$3"
+  local level="$1" code="$2" message="${3//$'\n'/ }"
+  shift 3
   local timestamp
   timestamp="$(date -u +"%Y-%m-%dT%H:%M:%SZ")"
-  if log_should_write "$level"; then
-    printf '%s [%s] [%s] %s\n' "$timestamp" "$level" "$code" "$message" >>"$LOG_PATH"
+  if log_should_write "$level" && ! log_is_sealed; then
+    log_format_line "$timestamp" "$level" "$code" "$message" "run_id=${RUN_ID:-}" "$@" "prev=$(log_chain_prev)" >>"$LOG_PATH"
   fi
   if [ "$LOG_VERBOSE" = "1" ] || [ "$level" = "ERROR" ] || [ "$level" = "WARN" ]; then
     printf '%s [%s] %s\n' "$timestamp" "$level" "$message"
@@ -157,27 +218,35 @@ log_event() {
 
 #--- log_info
 log_info() {
-  log_event "INFO" "$1" "$2"
+  log_event "INFO" "$@"
 }
 
 #--- log_warn
 log_warn() {
-  log_event "WARN" "$1" "$2"
+  log_event "WARN" "$@"
 }
 
 #--- log_error
 log_error() {
-  log_event "ERROR" "$1" "$2"
+  log_event "ERROR" "$@"
 }
 
 #--- log_debug
 log_debug() {
-  log_event "DEBUG" "$1" "$2"
+  log_event "DEBUG" "$@"
 }
 
 #--- log_finalize
+# Seal the log with a final chained entry, then write the SHA-256 sidecar.
 log_finalize() {
   if [ -n "${LOG_PATH:-}" ] && [ -s "$LOG_PATH" ]; then
+    if ! log_is_sealed; then
+      local entries timestamp
+      entries="$(wc -l <"$LOG_PATH")"
+      timestamp="$(date -u +"%Y-%m-%dT%H:%M:%SZ")"
+      log_format_line "$timestamp" "INFO" "SEAL" "Log sealed after $entries entries" \
+        "run_id=${RUN_ID:-}" "prev=$(log_chain_prev)" >>"$LOG_PATH"
+    fi
     local hash_path
     hash_path="${LOG_PATH}.hash"
     sha256sum "$LOG_PATH" >"$hash_path"
@@ -192,13 +261,16 @@ handle_exit() {
   else
     log_info "EXIT" "Syn-Syu completed successfully"
   fi
+  journal_end "$status"
   log_finalize
   exit "$status"
 }
 
 #--- handle_interrupt
+# The EXIT trap (handle_exit) seals the log; sealing here as well would put
+# the EXIT entry after the SEAL.
 handle_interrupt() {
   log_warn "INT" "Syn-Syu interrupted by user"
-  log_finalize
+  journal_end 130
   exit 130
 }
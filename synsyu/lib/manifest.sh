@@ -42,7 +42,12 @@ manifest_require() {
     log_info "MANIFEST" "Rebuilding manifest via synsyu_core"
     manifest_rebuild || return 1
   fi
+  if [ "${SIGNING_REQUIRE:-0}" = "1" ] && ! signature_run verify "$manifest_path"; then
+    log_error "E305" "Refusing manifest $manifest_path: no valid signature from a trusted key"
+    exit 305
+  fi
   manifest_apply_application_flags
+  journal_manifest "$manifest_path"
 }
 
 #--- manifest_rebuild
@@ -92,6 +97,7 @@ manifest_rebuild() {
   fi
 
   manifest_update_applications_section
+  signature_refresh "$manifest_path"
 }
 
 #--- manifest_update_applications_section
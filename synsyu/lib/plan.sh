@@ -50,7 +50,9 @@ plan_generate_core() {
 
   if [ "${EDIT_PLAN:-0}" = "1" ]; then
     plan_edit_file "$plan_path"
+    signature_refresh "$plan_path"
   fi
+  journal_plan "$plan_path"
 }
 
 #--- plan_edit_file
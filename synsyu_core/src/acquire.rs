@@ -0,0 +1,584 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::acquire
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Run official repo package acquisition with bounded mirror
+    failover: one temporary pacman config per attempt, ranked
+    from MirrorState, with classified failures and a JSON
+    attempt log.
+
+  Security / Safety Notes:
+    Pacman runs through the configured privilege tool and keeps
+    all of its own trust, signature, and transaction checks. The
+    system pacman.conf and mirrorlist are never edited; only
+    per-attempt copies under a private temporary directory are
+    written, and they are removed after each attempt. Only
+    retrieval failures advance to the next mirror; pacman's
+    stdout goes to stderr so `--json` output stays parseable.
+============================================================*/
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::time::sleep;
+
+use crate::config::{CoreConfig, MirrorConfig};
+use crate::error::{Result, SynsyuError};
+use crate::logger::Logger;
+use crate::mirror_history::{append_events, history_path, MirrorHistoryEvent};
+use crate::mirrors::epoch_seconds;
+use crate::privilege::privileged_command;
+use crate::temp_dir::TempDir;
+
+/// Category of a failed pacman run, derived from its stderr.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureClass {
+    Retrieval,
+    Signature,
+    Lock,
+    Conflict,
+    Dependency,
+    Other,
+}
+
+impl FailureClass {
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureClass::Retrieval => "retrieval",
+            FailureClass::Signature => "signature",
+            FailureClass::Lock => "lock",
+            FailureClass::Conflict => "conflict",
+            FailureClass::Dependency => "dependency",
+            FailureClass::Other => "other",
+        }
+    }
+
+    /// Only retrieval failures are attributed to the mirror.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, FailureClass::Retrieval)
+    }
+}
+
+const SIGNATURE_PATTERNS: &[&str] = &[
+    "invalid or corrupted package",
+    "corrupted package",
+    "signature",
+    "pgp",
+    "gpgme",
+    "keyring",
+    "unknown trust",
+    "marginal trust",
+    "checksum",
+    "integrity",
+];
+const LOCK_PATTERNS: &[&str] = &["database is locked", "unable to lock database"];
+const CONFLICT_PATTERNS: &[&str] = &[
+    "conflicting files",
+    "exists in filesystem",
+    "unresolvable package conflicts",
+    "are in conflict",
+];
+const DEPENDENCY_PATTERNS: &[&str] = &[
+    "could not satisfy dependencies",
+    "unable to satisfy dependency",
+    "target not found",
+    "breaks dependency",
+];
+const RETRIEVAL_PATTERNS: &[&str] = &[
+    "failed retrieving file",
+    "failed to retrieve some files",
+    "could not resolve host",
+    "connection timed out",
+    "operation too slow",
+    "the requested url returned error",
+    "failed to synchronize all databases",
+    "download library error",
+    "failed to retrieve",
+    "server returned error",
+    "connection refused",
+    "connection reset",
+];
+
+/// Classify pacman stderr. Trust, lock, conflict, and dependency markers win
+/// over retrieval markers so a mixed message never triggers a mirror retry.
+/// A failed commit stays terminal even when it names missing files, as the
+/// orchestrator always treated it.
+pub fn classify_pacman_failure(stderr: &str) -> FailureClass {
+    let lower = stderr.to_lowercase();
+    let has = |patterns: &[&str]| patterns.iter().any(|p| lower.contains(p));
+    if has(SIGNATURE_PATTERNS) {
+        FailureClass::Signature
+    } else if has(LOCK_PATTERNS) {
+        FailureClass::Lock
+    } else if has(CONFLICT_PATTERNS) {
+        FailureClass::Conflict
+    } else if has(DEPENDENCY_PATTERNS) {
+        FailureClass::Dependency
+    } else if lower.contains("not enough free disk space")
+        || lower.contains("failed to commit transaction")
+    {
+        FailureClass::Other
+    } else if has(RETRIEVAL_PATTERNS) {
+        FailureClass::Retrieval
+    } else {
+        FailureClass::Other
+    }
+}
+
+/// Rewrite `Include = <mirrorlist>` lines to point at `temp_mirrorlist`.
+pub fn render_pacman_config(
+    source: &str,
+    mirrorlist_path: &str,
+    temp_mirrorlist: &Path,
+) -> std::result::Result<String, String> {
+    let mut replaced = 0usize;
+    let mut out = String::with_capacity(source.len());
+    for line in source.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let indent_len = body.len() - body.trim_start().len();
+        let trimmed = body.trim_start();
+        let is_include = trimmed
+            .strip_prefix("Include")
+            .map(str::trim_start)
+            .and_then(|rest| rest.strip_prefix('='))
+            .map(|rest| {
+                let value = rest.split('#').next().unwrap_or("").trim();
+                value == mirrorlist_path
+            })
+            .unwrap_or(false);
+        if is_include {
+            out.push_str(&body[..indent_len]);
+            out.push_str(&format!("Include = {}\n", temp_mirrorlist.display()));
+            replaced += 1;
+        } else {
+            out.push_str(line);
+        }
+    }
+    if replaced == 0 {
+        return Err(format!("no Include = {mirrorlist_path} line found"));
+    }
+    Ok(out)
+}
+
+/// One pacman invocation in the acquisition loop.
+#[derive(Debug, Serialize, Clone)]
+pub struct AcquireAttempt {
+    pub attempt: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub exit_code: i32,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_class: Option<FailureClass>,
+    pub retryable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr_excerpt: Option<String>,
+}
+
+/// JSON attempt log for one repo acquisition run.
+#[derive(Debug, Serialize)]
+pub struct AcquireReport {
+    pub generated_at: String,
+    pub packages: Vec<String>,
+    pub privilege_tool: String,
+    pub mirror_source: String,
+    pub attempt_limit: usize,
+    pub max_failovers: usize,
+    pub retry_delay_seconds: u64,
+    pub status: String,
+    pub exit_code: i32,
+    pub attempts: Vec<AcquireAttempt>,
+}
+
+/// Inputs for a repo acquisition run.
+pub struct RepoAcquisition<'a> {
+    pub packages: &'a [String],
+    pub servers: Vec<String>,
+    pub mirror_source: String,
+    pub mirror_config: &'a MirrorConfig,
+    pub core_config: &'a CoreConfig,
+    pub noconfirm: bool,
+}
+
+/// Usable servers from a manifest's `network.mirrors`, in rank order.
+pub fn manifest_mirror_servers(doc: &Value) -> Option<Vec<String>> {
+    let candidates = doc.pointer("/network/mirrors/candidates")?.as_array()?;
+    Some(
+        candidates
+            .iter()
+            .filter(|c| c.get("usable").and_then(Value::as_bool) == Some(true))
+            .filter_map(|c| c.get("server").and_then(Value::as_str).map(String::from))
+            .collect(),
+    )
+}
+
+/// Run the bounded acquisition loop and return the attempt log.
+pub async fn acquire_repo_packages(request: RepoAcquisition<'_>, logger: &Logger) -> AcquireReport {
+    let config = request.mirror_config;
+    let privilege_tool = request.core_config.privilege_tool.clone();
+    let mut report = AcquireReport {
+        generated_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        packages: request.packages.to_vec(),
+        privilege_tool: privilege_tool.clone(),
+        mirror_source: request.mirror_source.clone(),
+        attempt_limit: 1,
+        max_failovers: config.max_failovers,
+        retry_delay_seconds: config.retry_delay_seconds,
+        status: "pending".to_string(),
+        exit_code: 0,
+        attempts: Vec::new(),
+    };
+
+    if !config.enabled || request.servers.is_empty() {
+        if config.enabled {
+            logger.warn(
+                "MIRROR",
+                "No usable mirror candidates; using pacman default mirror configuration",
+            );
+        } else {
+            logger.info(
+                "MIRROR",
+                "Mirror failover disabled; using pacman default mirror configuration",
+            );
+        }
+        report.mirror_source = "pacman-default".to_string();
+        let attempt = run_attempt(&request, &privilege_tool, 1, None, None).await;
+        finish(&mut report, attempt, false);
+        return report;
+    }
+
+    let attempt_limit = (config.max_failovers + 1).max(1).min(request.servers.len());
+    report.attempt_limit = attempt_limit;
+    let history = config.history.enabled.then(|| history_path(config));
+
+    for (idx, server) in request.servers.iter().take(attempt_limit).enumerate() {
+        let number = idx + 1;
+        logger.info(
+            "MIRROR",
+            format!("Repo batch attempt {number}/{attempt_limit} using mirror: {server}"),
+        );
+        let workspace = match AttemptWorkspace::create(server, config) {
+            Ok(ws) => ws,
+            Err(reason) => {
+                logger.warn(
+                    "MIRROR",
+                    format!(
+                        "Unable to prepare temporary pacman config: {reason}; \
+                         using pacman default mirror configuration"
+                    ),
+                );
+                report.mirror_source = "pacman-default".to_string();
+                let attempt = run_attempt(&request, &privilege_tool, number, None, None).await;
+                finish(&mut report, attempt, false);
+                return report;
+            }
+        };
+        let attempt = run_attempt(
+            &request,
+            &privilege_tool,
+            number,
+            Some(server),
+            Some(&workspace.config_path),
+        )
+        .await;
+        drop(workspace);
+
+        if let Some(path) = &history {
+            record_history(path, config, &attempt, logger);
+        }
+
+        if attempt.success {
+            logger.info(
+                "MIRROR",
+                format!("Repo batch succeeded on mirror attempt {number}"),
+            );
+            finish(&mut report, attempt, false);
+            return report;
+        }
+
+        let class = attempt.failure_class.unwrap_or(FailureClass::Other);
+        logger.warn(
+            "MIRROR",
+            format!(
+                "Repo batch failed on mirror attempt {number} with pacman exit {} ({})",
+                attempt.exit_code,
+                class.label()
+            ),
+        );
+        if !class.is_retryable() {
+            logger.error(
+                "MIRROR",
+                "Pacman failure is not mirror-retryable; stopping mirror failover",
+            );
+            finish(&mut report, attempt, false);
+            return report;
+        }
+        let exhausted = number == attempt_limit;
+        finish(&mut report, attempt, exhausted);
+        if exhausted {
+            break;
+        }
+        if config.retry_delay_seconds > 0 {
+            logger.info(
+                "MIRROR",
+                format!(
+                    "Waiting {}s before next mirror attempt",
+                    config.retry_delay_seconds
+                ),
+            );
+            sleep(Duration::from_secs(config.retry_delay_seconds)).await;
+        }
+    }
+
+    logger.error(
+        "MIRROR",
+        format!("Mirror retry budget exhausted after {attempt_limit} attempt(s)"),
+    );
+    report
+}
+
+/// Write the attempt log as pretty JSON.
+pub fn write_attempt_log(path: &Path, report: &AcquireReport) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(report).map_err(|err| {
+        SynsyuError::Serialization(format!("Unable to encode acquisition log: {err}"))
+    })?;
+    fs::write(path, json).map_err(|err| {
+        SynsyuError::Filesystem(format!(
+            "Unable to write acquisition log {}: {err}",
+            path.display()
+        ))
+    })
+}
+
+fn finish(report: &mut AcquireReport, attempt: AcquireAttempt, exhausted: bool) {
+    report.exit_code = attempt.exit_code;
+    report.status = if attempt.success {
+        "succeeded".to_string()
+    } else if exhausted {
+        report.exit_code = 1;
+        "exhausted".to_string()
+    } else if attempt.retryable {
+        "retrying".to_string()
+    } else {
+        "failed".to_string()
+    };
+    report.attempts.push(attempt);
+}
+
+fn record_history(path: &Path, config: &MirrorConfig, attempt: &AcquireAttempt, logger: &Logger) {
+    let Some(server) = &attempt.server else {
+        return;
+    };
+    // Terminal failures say nothing about mirror health.
+    if !attempt.success && !attempt.retryable {
+        return;
+    }
+    let event = MirrorHistoryEvent {
+        at_epoch: epoch_seconds(),
+        server: server.clone(),
+        kind: "attempt".to_string(),
+        outcome: if attempt.success {
+            "success".to_string()
+        } else {
+            "retryable".to_string()
+        },
+        success: attempt.success,
+        freshness: None,
+        latency_ms: None,
+        attempt: Some(attempt.attempt),
+    };
+    if let Err(err) = append_events(path, &[event], config.history.max_entries) {
+        logger.warn("MIRROR", format!("Unable to append mirror history: {err}"));
+    }
+}
+
+async fn run_attempt(
+    request: &RepoAcquisition<'_>,
+    privilege_tool: &str,
+    number: usize,
+    server: Option<&String>,
+    pacman_config: Option<&Path>,
+) -> AcquireAttempt {
+    let started_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    let start = Instant::now();
+    let mut command = Command::from(privileged_command(privilege_tool, "pacman"));
+    if let Some(path) = pacman_config {
+        command.arg("--config").arg(path);
+    }
+    command.arg("-S");
+    if request.noconfirm {
+        command.arg("--noconfirm");
+    }
+    command.args(request.packages);
+    command
+        .stdout(Stdio::from(std::io::stderr()))
+        .stderr(Stdio::piped());
+
+    let (exit_code, stderr) = match command.spawn() {
+        Ok(mut child) => {
+            let mut captured = Vec::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let mut buf = [0u8; 4096];
+                let mut sink = tokio::io::stderr();
+                while let Ok(read) = pipe.read(&mut buf).await {
+                    if read == 0 {
+                        break;
+                    }
+                    let _ = sink.write_all(&buf[..read]).await;
+                    captured.extend_from_slice(&buf[..read]);
+                }
+            }
+            let status = child
+                .wait()
+                .await
+                .map(|s| s.code().unwrap_or(1))
+                .unwrap_or(1);
+            (status, String::from_utf8_lossy(&captured).into_owned())
+        }
+        Err(err) => (127, format!("failed to spawn pacman: {err}")),
+    };
+
+    let success = exit_code == 0;
+    let failure_class = (!success).then(|| classify_pacman_failure(&stderr));
+    let excerpt = stderr.split_whitespace().collect::<Vec<_>>().join(" ");
+    AcquireAttempt {
+        attempt: number,
+        server: server.cloned(),
+        started_at,
+        duration_ms: start.elapsed().as_millis().min(u64::MAX as u128) as u64,
+        exit_code,
+        success,
+        failure_class,
+        retryable: failure_class
+            .map(FailureClass::is_retryable)
+            .unwrap_or(false),
+        stderr_excerpt: (!excerpt.is_empty()).then(|| excerpt.chars().take(2000).collect()),
+    }
+}
+
+/// Private temporary directory holding one attempt's pacman config; removed
+/// on drop.
+struct AttemptWorkspace {
+    _dir: TempDir,
+    config_path: PathBuf,
+}
+
+impl AttemptWorkspace {
+    fn create(server: &str, config: &MirrorConfig) -> std::result::Result<Self, String> {
+        let dir = TempDir::new("synsyu_mirror_").map_err(|err| err.to_string())?;
+        let mirrorlist = dir.path().join("mirrorlist");
+        let workspace = Self {
+            config_path: dir.path().join("pacman.conf"),
+            _dir: dir,
+        };
+        fs::write(&mirrorlist, format!("Server = {server}\n"))
+            .map_err(|err| format!("{}: {err}", mirrorlist.display()))?;
+        let source = fs::read_to_string(&config.pacman_conf_path).map_err(|err| {
+            format!(
+                "failed to read pacman config {}: {err}",
+                config.pacman_conf_path
+            )
+        })?;
+        let rendered = render_pacman_config(&source, &config.mirrorlist_path, &mirrorlist)
+            .map_err(|reason| format!("{reason} in {}", config.pacman_conf_path))?;
+        fs::write(&workspace.config_path, rendered)
+            .map_err(|err| format!("{}: {err}", workspace.config_path.display()))?;
+        Ok(workspace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_pacman_failures() {
+        let cases = [
+            (
+                "error: failed retrieving file 'core.db' from mirror : Could not resolve host",
+                FailureClass::Retrieval,
+            ),
+            (
+                "error: failed retrieving file 'linux.pkg.tar.zst' from mirror : Operation too slow\nerror: failed to retrieve some files",
+                FailureClass::Retrieval,
+            ),
+            (
+                "error: failed to retrieve some files\nerror: failed to commit transaction (failed to retrieve some files)",
+                FailureClass::Other,
+            ),
+            (
+                "error: linux: signature from \"X\" is unknown trust",
+                FailureClass::Signature,
+            ),
+            (
+                "error: failed to commit transaction (invalid or corrupted package (PGP signature))",
+                FailureClass::Signature,
+            ),
+            (
+                "error: failed to init transaction (unable to lock database)\nerror: could not lock database: File exists",
+                FailureClass::Lock,
+            ),
+            (
+                "error: failed to commit transaction (conflicting files)\nfoo: /usr/bin/foo exists in filesystem",
+                FailureClass::Conflict,
+            ),
+            (
+                "error: failed to prepare transaction (could not satisfy dependencies)",
+                FailureClass::Dependency,
+            ),
+            ("error: target not found: nosuchpkg", FailureClass::Dependency),
+            (
+                "error: Partition / too full: 1 blocks needed\nerror: not enough free disk space",
+                FailureClass::Other,
+            ),
+            ("", FailureClass::Other),
+        ];
+        for (stderr, expected) in cases {
+            assert_eq!(classify_pacman_failure(stderr), expected, "{stderr}");
+        }
+    }
+
+    #[test]
+    fn trust_markers_win_over_retrieval_markers() {
+        let mixed = "error: failed retrieving file 'x.pkg.tar.zst'\nerror: x: signature is invalid";
+        let class = classify_pacman_failure(mixed);
+        assert_eq!(class, FailureClass::Signature);
+        assert!(!class.is_retryable());
+    }
+
+    #[test]
+    fn renders_pacman_config_with_temporary_mirrorlist() {
+        let source = "[options]\nHoldPkg = pacman\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n\n[extra]\n  Include=/etc/pacman.d/mirrorlist # main\n\n[custom]\nInclude = /etc/pacman.d/custom\n";
+        let rendered = render_pacman_config(
+            source,
+            "/etc/pacman.d/mirrorlist",
+            Path::new("/tmp/synsyu/mirrorlist"),
+        )
+        .expect("rendered");
+        assert_eq!(
+            rendered,
+            "[options]\nHoldPkg = pacman\n\n[core]\nInclude = /tmp/synsyu/mirrorlist\n\n[extra]\n  Include = /tmp/synsyu/mirrorlist\n\n[custom]\nInclude = /etc/pacman.d/custom\n"
+        );
+        assert!(render_pacman_config(
+            "[core]\nServer = x\n",
+            "/etc/pacman.d/mirrorlist",
+            Path::new("/tmp/m")
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,613 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::audit
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Record structured audit events (manifest written, plan
+    generated, package applied or failed, snapshot taken,
+    config changed) and fan them out to the configured
+    backends: an append-only JSON-lines file, the syslog
+    `/dev/log` datagram socket, and the journald native
+    protocol socket.
+
+  Security / Safety Notes:
+    Events carry package names, paths, and failure reasons,
+    never configuration values. The audit file is created
+    0600 and only ever appended to. A failing backend does
+    not stop the others; callers decide whether to warn.
+============================================================*/
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{Local, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::AuditConfig;
+use crate::error::{Result, SynsyuError};
+
+/// journald `MESSAGE_ID` shared by every Syn-Syu audit event.
+pub const AUDIT_MESSAGE_ID: &str = "5f3c0e7a9b2d4c61a8e1f4b7c2d9e063";
+
+/// Significant actions that produce an audit event.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    ManifestWritten,
+    PlanGenerated,
+    PackageApplied,
+    PackageFailed,
+    SnapshotTaken,
+    ConfigChanged,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::ManifestWritten => "manifest_written",
+            AuditAction::PlanGenerated => "plan_generated",
+            AuditAction::PackageApplied => "package_applied",
+            AuditAction::PackageFailed => "package_failed",
+            AuditAction::SnapshotTaken => "snapshot_taken",
+            AuditAction::ConfigChanged => "config_changed",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    /// Accepts `snake_case` and `kebab-case` spellings.
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().replace('-', "_").to_ascii_lowercase().as_str() {
+            "manifest_written" => Ok(AuditAction::ManifestWritten),
+            "plan_generated" => Ok(AuditAction::PlanGenerated),
+            "package_applied" => Ok(AuditAction::PackageApplied),
+            "package_failed" => Ok(AuditAction::PackageFailed),
+            "snapshot_taken" => Ok(AuditAction::SnapshotTaken),
+            "config_changed" => Ok(AuditAction::ConfigChanged),
+            other => Err(format!("unknown audit action '{other}'")),
+        }
+    }
+}
+
+/// Whether the audited action succeeded.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Failure => "failure",
+        }
+    }
+
+    /// syslog severity: notice for success, err for failure.
+    fn severity(self) -> u8 {
+        match self {
+            AuditOutcome::Success => 5,
+            AuditOutcome::Failure => 3,
+        }
+    }
+}
+
+impl fmt::Display for AuditOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditOutcome {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "success" | "ok" => Ok(AuditOutcome::Success),
+            "failure" | "failed" => Ok(AuditOutcome::Failure),
+            other => Err(format!("unknown audit outcome '{other}'")),
+        }
+    }
+}
+
+/// One structured audit event.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEvent {
+    pub timestamp: String,
+    pub action: AuditAction,
+    pub outcome: AuditOutcome,
+    /// Package name, file path, or snapshot phase the action applied to.
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub uid: u32,
+    pub pid: u32,
+    pub host: String,
+}
+
+impl AuditEvent {
+    /// Event stamped now for the current process.
+    pub fn new(action: AuditAction, outcome: AuditOutcome, subject: impl Into<String>) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            action,
+            outcome,
+            subject: subject.into(),
+            detail: None,
+            uid: unsafe { libc::getuid() },
+            pid: std::process::id(),
+            host: hostname(),
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        let detail = detail.into();
+        self.detail = (!detail.trim().is_empty()).then_some(detail);
+        self
+    }
+
+    /// Human-readable one-line summary.
+    pub fn message(&self) -> String {
+        let mut message = format!("{} {}: {}", self.action, self.outcome, self.subject);
+        if let Some(detail) = &self.detail {
+            message.push_str(" (");
+            message.push_str(detail);
+            message.push(')');
+        }
+        message
+    }
+
+    /// `key=value` rendering used for syslog, quoting values that need it.
+    pub fn key_values(&self) -> String {
+        let mut pairs = vec![
+            format!("action={}", self.action),
+            format!("outcome={}", self.outcome),
+            format!("subject={}", quote_value(&self.subject)),
+        ];
+        if let Some(detail) = &self.detail {
+            pairs.push(format!("detail={}", quote_value(detail)));
+        }
+        pairs.push(format!("uid={}", self.uid));
+        pairs.join(" ")
+    }
+}
+
+fn quote_value(value: &str) -> String {
+    let value = value.replace(['\n', '\r'], " ");
+    if !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '=' || c == '\\')
+    {
+        return value;
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if rc != 0 {
+        return "localhost".to_string();
+    }
+    let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
+
+/// Audit backend selectable in `[audit].backends`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditBackendKind {
+    File,
+    Syslog,
+    Journald,
+}
+
+impl fmt::Display for AuditBackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditBackendKind::File => write!(f, "file"),
+            AuditBackendKind::Syslog => write!(f, "syslog"),
+            AuditBackendKind::Journald => write!(f, "journald"),
+        }
+    }
+}
+
+/// Destination for audit events.
+pub trait AuditBackend: Send + Sync {
+    /// Short name used in failure reports.
+    fn name(&self) -> &str;
+    /// Deliver one event.
+    fn record(&self, event: &AuditEvent) -> Result<()>;
+}
+
+/// Append-only JSON-lines audit file.
+pub struct FileBackend {
+    path: PathBuf,
+}
+
+impl FileBackend {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl AuditBackend for FileBackend {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn record(&self, event: &AuditEvent) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|err| {
+                SynsyuError::Filesystem(format!(
+                    "Failed to create audit directory {}: {err}",
+                    parent.display()
+                ))
+            })?;
+        }
+        let mut line = serde_json::to_string(event)
+            .map_err(|err| SynsyuError::Serialization(err.to_string()))?;
+        line.push('\n');
+        // One write per event so concurrent writers never interleave lines.
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|err| {
+                SynsyuError::Filesystem(format!(
+                    "Failed to append audit event to {}: {err}",
+                    self.path.display()
+                ))
+            })
+    }
+}
+
+/// Local syslog over the `/dev/log` datagram socket (RFC 3164 framing).
+pub struct SyslogBackend {
+    socket: PathBuf,
+    identifier: String,
+    facility: u8,
+}
+
+impl SyslogBackend {
+    pub fn new(socket: PathBuf, identifier: String, facility: u8) -> Self {
+        Self {
+            socket,
+            identifier,
+            facility,
+        }
+    }
+
+    /// `<PRI>Mmm dd hh:mm:ss ident[pid]: key=value ...`
+    pub fn frame(&self, event: &AuditEvent) -> String {
+        let pri = u16::from(self.facility) * 8 + u16::from(event.outcome.severity());
+        format!(
+            "<{pri}>{} {}[{}]: {}",
+            Local::now().format("%b %e %H:%M:%S"),
+            self.identifier,
+            event.pid,
+            event.key_values()
+        )
+    }
+}
+
+impl AuditBackend for SyslogBackend {
+    fn name(&self) -> &str {
+        "syslog"
+    }
+
+    fn record(&self, event: &AuditEvent) -> Result<()> {
+        send_datagram(&self.socket, self.frame(event).as_bytes())
+    }
+}
+
+/// journald native protocol socket.
+pub struct JournaldBackend {
+    socket: PathBuf,
+    identifier: String,
+    facility: u8,
+}
+
+impl JournaldBackend {
+    pub fn new(socket: PathBuf, identifier: String, facility: u8) -> Self {
+        Self {
+            socket,
+            identifier,
+            facility,
+        }
+    }
+
+    /// Native protocol payload: `KEY=value\n` fields, with the length-prefixed
+    /// binary form for values containing newlines.
+    pub fn payload(&self, event: &AuditEvent) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let priority = event.outcome.severity().to_string();
+        let facility = self.facility.to_string();
+        let message = event.message();
+        let mut fields: Vec<(&str, &str)> = vec![
+            ("MESSAGE", &message),
+            ("MESSAGE_ID", AUDIT_MESSAGE_ID),
+            ("PRIORITY", &priority),
+            ("SYSLOG_FACILITY", &facility),
+            ("SYSLOG_IDENTIFIER", &self.identifier),
+            ("SYNSYU_ACTION", event.action.as_str()),
+            ("SYNSYU_OUTCOME", event.outcome.as_str()),
+            ("SYNSYU_SUBJECT", &event.subject),
+            ("SYNSYU_TIMESTAMP", &event.timestamp),
+        ];
+        if let Some(detail) = &event.detail {
+            fields.push(("SYNSYU_DETAIL", detail));
+        }
+        for (key, value) in fields {
+            journal_field(&mut buf, key, value);
+        }
+        buf
+    }
+}
+
+fn journal_field(buf: &mut Vec<u8>, key: &str, value: &str) {
+    buf.extend_from_slice(key.as_bytes());
+    if value.contains('\n') {
+        buf.push(b'\n');
+        buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        buf.extend_from_slice(value.as_bytes());
+    } else {
+        buf.push(b'=');
+        buf.extend_from_slice(value.as_bytes());
+    }
+    buf.push(b'\n');
+}
+
+impl AuditBackend for JournaldBackend {
+    fn name(&self) -> &str {
+        "journald"
+    }
+
+    fn record(&self, event: &AuditEvent) -> Result<()> {
+        send_datagram(&self.socket, &self.payload(event))
+    }
+}
+
+fn send_datagram(socket: &Path, payload: &[u8]) -> Result<()> {
+    let sock = UnixDatagram::unbound()?;
+    sock.send_to(payload, socket).map_err(|err| {
+        SynsyuError::Runtime(format!(
+            "Failed to send audit event to {}: {err}",
+            socket.display()
+        ))
+    })?;
+    Ok(())
+}
+
+/// syslog facility code for a facility name.
+pub fn syslog_facility(name: &str) -> Option<u8> {
+    let code = match name.trim().to_ascii_lowercase().as_str() {
+        "user" => 1,
+        "daemon" => 3,
+        "auth" => 4,
+        "syslog" => 5,
+        "authpriv" => 10,
+        other => {
+            let n: u8 = other.strip_prefix("local")?.parse().ok()?;
+            if n > 7 {
+                return None;
+            }
+            16 + n
+        }
+    };
+    Some(code)
+}
+
+/// A backend that failed to take an event.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditFailure {
+    pub backend: String,
+    pub error: String,
+}
+
+/// Fans audit events out to every registered backend.
+#[derive(Default)]
+pub struct Auditor {
+    backends: Vec<Box<dyn AuditBackend>>,
+}
+
+impl Auditor {
+    /// Backends selected by `[audit]`; none when the list is empty.
+    pub fn from_config(config: &AuditConfig) -> Result<Self> {
+        let facility = syslog_facility(&config.facility).ok_or_else(|| {
+            SynsyuError::Config(format!(
+                "Unknown audit.facility '{}' (expected user, daemon, auth, syslog, authpriv, or local0-local7)",
+                config.facility
+            ))
+        })?;
+        let mut auditor = Auditor::default();
+        for kind in &config.backends {
+            let backend: Box<dyn AuditBackend> = match kind {
+                AuditBackendKind::File => Box::new(FileBackend::new(config.file_path())),
+                AuditBackendKind::Syslog => Box::new(SyslogBackend::new(
+                    PathBuf::from(&config.syslog_socket),
+                    config.identifier.clone(),
+                    facility,
+                )),
+                AuditBackendKind::Journald => Box::new(JournaldBackend::new(
+                    PathBuf::from(&config.journald_socket),
+                    config.identifier.clone(),
+                    facility,
+                )),
+            };
+            auditor.register(backend);
+        }
+        Ok(auditor)
+    }
+
+    pub fn register(&mut self, backend: Box<dyn AuditBackend>) {
+        self.backends.push(backend);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backends.is_empty()
+    }
+
+    /// Deliver `event` to every backend, returning the ones that failed.
+    pub fn record(&self, event: &AuditEvent) -> Vec<AuditFailure> {
+        self.backends
+            .iter()
+            .filter_map(|backend| {
+                backend.record(event).err().map(|err| AuditFailure {
+                    backend: backend.name().to_string(),
+                    error: err.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn event() -> AuditEvent {
+        AuditEvent::new(
+            AuditAction::PackageFailed,
+            AuditOutcome::Failure,
+            "linux-firmware",
+        )
+        .with_detail("pacman batch failed (exit 1)")
+    }
+
+    #[test]
+    fn file_backend_appends_and_fan_out_survives_failures() {
+        let tmp = TempDir::new("synsyu_audit_file_").unwrap();
+        let dir = tmp.path();
+        let path = dir.join("audit").join("audit.jsonl");
+        let mut auditor = Auditor::default();
+        auditor.register(Box::new(FileBackend::new(path.clone())));
+        auditor.register(Box::new(JournaldBackend::new(
+            dir.join("missing.socket"),
+            "syn-syu".into(),
+            10,
+        )));
+
+        let failures = auditor.record(&event());
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].backend, "journald");
+        auditor.record(&AuditEvent::new(
+            AuditAction::ManifestWritten,
+            AuditOutcome::Success,
+            "/tmp/manifest.json",
+        ));
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let events: Vec<AuditEvent> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], event_with_time(&events[0]));
+        assert_eq!(events[1].action, AuditAction::ManifestWritten);
+        assert!(events[1].detail.is_none());
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(
+            "package-applied".parse::<AuditAction>(),
+            Ok(AuditAction::PackageApplied)
+        );
+    }
+
+    fn event_with_time(recorded: &AuditEvent) -> AuditEvent {
+        AuditEvent {
+            timestamp: recorded.timestamp.clone(),
+            ..event()
+        }
+    }
+
+    #[test]
+    fn syslog_backend_frames_rfc3164_datagrams() {
+        let tmp = TempDir::new("synsyu_audit_syslog_").unwrap();
+        let dir = tmp.path();
+        let socket = dir.join("log");
+        let listener = UnixDatagram::bind(&socket).unwrap();
+        let backend = SyslogBackend::new(
+            socket,
+            "syn-syu".into(),
+            syslog_facility("authpriv").unwrap(),
+        );
+        backend.record(&event()).unwrap();
+
+        let mut buf = [0u8; 2048];
+        let n = listener.recv(&mut buf).unwrap();
+        let frame = String::from_utf8_lossy(&buf[..n]).to_string();
+        // authpriv (10) * 8 + err (3)
+        assert!(frame.starts_with("<83>"), "{frame}");
+        assert!(frame.contains(&format!(" syn-syu[{}]: ", std::process::id())));
+        assert!(frame.ends_with(&format!(
+            "action=package_failed outcome=failure subject=linux-firmware detail=\"pacman batch failed (exit 1)\" uid={}",
+            unsafe { libc::getuid() }
+        )));
+        assert_eq!(syslog_facility("local7"), Some(23));
+        assert_eq!(syslog_facility("local8"), None);
+    }
+
+    #[test]
+    fn journald_backend_speaks_the_native_protocol() {
+        let tmp = TempDir::new("synsyu_audit_journald_").unwrap();
+        let dir = tmp.path();
+        let socket = dir.join("socket");
+        let listener = UnixDatagram::bind(&socket).unwrap();
+        let backend = JournaldBackend::new(socket, "syn-syu".into(), 10);
+        let multiline = AuditEvent::new(AuditAction::SnapshotTaken, AuditOutcome::Success, "pre")
+            .with_detail("snapshot 42\nnumber=42");
+        backend.record(&multiline).unwrap();
+
+        let mut buf = [0u8; 4096];
+        let n = listener.recv(&mut buf).unwrap();
+        let mut fields = std::collections::BTreeMap::new();
+        let mut rest = &buf[..n];
+        while !rest.is_empty() {
+            let line_end = rest.iter().position(|b| *b == b'\n').unwrap();
+            let line = std::str::from_utf8(&rest[..line_end]).unwrap();
+            if let Some((key, value)) = line.split_once('=') {
+                fields.insert(key.to_string(), value.to_string());
+                rest = &rest[line_end + 1..];
+            } else {
+                let len_start = line_end + 1;
+                let len =
+                    u64::from_le_bytes(rest[len_start..len_start + 8].try_into().unwrap()) as usize;
+                let value = &rest[len_start + 8..len_start + 8 + len];
+                assert_eq!(rest[len_start + 8 + len], b'\n');
+                fields.insert(line.to_string(), String::from_utf8_lossy(value).to_string());
+                rest = &rest[len_start + 8 + len + 1..];
+            }
+        }
+        assert_eq!(fields["MESSAGE_ID"], AUDIT_MESSAGE_ID);
+        assert_eq!(fields["PRIORITY"], "5");
+        assert_eq!(fields["SYSLOG_IDENTIFIER"], "syn-syu");
+        assert_eq!(fields["SYNSYU_ACTION"], "snapshot_taken");
+        assert_eq!(fields["SYNSYU_SUBJECT"], "pre");
+        assert_eq!(fields["SYNSYU_DETAIL"], "snapshot 42\nnumber=42");
+        assert!(fields["MESSAGE"].starts_with("snapshot_taken success: pre ("));
+    }
+}
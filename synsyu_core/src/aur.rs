@@ -36,8 +36,9 @@ use serde::Deserialize;
 use tokio::time::sleep;
 use urlencoding::encode;
 
-use crate::config::AurConfig;
+use crate::config::{AurConfig, NetworkConfig};
 use crate::error::{Result, SynsyuError};
+use crate::http::build_client;
 use crate::package_info::VersionInfo;
 
 /// Client for interacting with the AUR RPC API.
@@ -50,12 +51,8 @@ pub struct AurClient {
 
 impl AurClient {
     /// Construct a new client from configuration.
-    pub fn new(config: &AurConfig) -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.timeout))
-            .user_agent("Syn-Syu-Core/0.13 (linux)")
-            .build()
-            .map_err(|err| SynsyuError::Network(format!("Failed to build HTTP client: {err}")))?;
+    pub fn new(config: &AurConfig, network: &NetworkConfig) -> Result<Self> {
+        let client = build_client(network, Duration::from_secs(config.timeout))?;
 
         Ok(Self {
             client,
@@ -0,0 +1,369 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::build_history
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Measure peak build-directory growth and built package size
+    for AUR builds, keep the observations in a bounded local
+    history, and turn the latest observation into build-size
+    estimates for plans and space checks.
+
+  Security / Safety Notes:
+    The wrapped build command runs with the caller's privileges
+    and inherited stdio; measurement only stats files and never
+    follows symlinks. History is advisory sizing data only.
+============================================================*/
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::config::SpaceConfig;
+use crate::error::{Result, SynsyuError};
+
+/// Bounds applied when scaling an old observation by snapshot size.
+const MIN_SCALE: f64 = 0.25;
+const MAX_SCALE: f64 = 4.0;
+
+/// One measured AUR build.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BuildSample {
+    pub at_epoch: u64,
+    pub package: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub helper: Option<String>,
+    /// Peak growth of the build directory while the build ran.
+    pub peak_build_bytes: u64,
+    /// Size of the package archive(s) produced.
+    pub package_bytes: u64,
+    /// Downloaded sources left in the build directory.
+    pub source_bytes: u64,
+    /// AUR snapshot download size the helper reported for this version; the
+    /// figure later download sizes are compared against. 0 when unknown.
+    #[serde(default)]
+    pub snapshot_bytes: u64,
+}
+
+/// Build estimate derived from history.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BuildEstimate {
+    pub build_bytes: u64,
+    pub package_bytes: u64,
+    pub observed_version: String,
+    pub scale: f64,
+}
+
+/// History file path; defaults to the Syn-Syu cache directory.
+pub fn history_path(config: &SpaceConfig) -> PathBuf {
+    if let Some(path) = &config.build_history_path {
+        return crate::config::expand_home(path);
+    }
+    dirs::cache_dir()
+        .unwrap_or_else(|| crate::config::expand_home("~/.cache"))
+        .join("syn-syu")
+        .join("aur-build-history.jsonl")
+}
+
+/// Directory a helper builds `package` in, below its base build directory.
+pub fn helper_build_dir(base: &Path, helper: Option<&str>, package: &str) -> PathBuf {
+    match helper {
+        Some("paru") => base.join("clone").join(package),
+        Some(_) => base.join(package),
+        None => base.to_path_buf(),
+    }
+}
+
+/// Append a sample and keep only the newest `max_entries` lines.
+pub fn append_sample(path: &Path, sample: &BuildSample, max_entries: usize) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            SynsyuError::Filesystem(format!(
+                "Unable to create build history directory {}: {err}",
+                parent.display()
+            ))
+        })?;
+    }
+    let line = serde_json::to_string(sample).map_err(|err| {
+        SynsyuError::Serialization(format!("Unable to encode build sample: {err}"))
+    })?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| {
+            SynsyuError::Filesystem(format!(
+                "Unable to open build history {}: {err}",
+                path.display()
+            ))
+        })?;
+    writeln!(file, "{line}")?;
+    drop(file);
+
+    if max_entries > 0 {
+        let contents = fs::read_to_string(path)?;
+        let lines: Vec<&str> = contents.lines().filter(|l| !l.trim().is_empty()).collect();
+        if lines.len() > max_entries {
+            let tmp = path.with_extension("jsonl.tmp");
+            let mut body = lines[lines.len() - max_entries..].join("\n");
+            body.push('\n');
+            fs::write(&tmp, body)?;
+            fs::rename(&tmp, path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Latest sample per package, skipping malformed lines.
+pub fn latest_samples(path: &Path) -> BTreeMap<String, BuildSample> {
+    let mut latest: BTreeMap<String, BuildSample> = BTreeMap::new();
+    let Ok(contents) = fs::read_to_string(path) else {
+        return latest;
+    };
+    for sample in contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<BuildSample>(line).ok())
+    {
+        match latest.get(&sample.package) {
+            Some(existing) if existing.at_epoch > sample.at_epoch => {}
+            _ => {
+                latest.insert(sample.package.clone(), sample);
+            }
+        }
+    }
+    latest
+}
+
+/// Scale the last observation by the current AUR snapshot size relative to
+/// the one recorded with it, when both are known; otherwise reuse it as-is.
+pub fn estimate(sample: &BuildSample, current_snapshot_bytes: Option<u64>) -> BuildEstimate {
+    let scale = match current_snapshot_bytes {
+        Some(now) if now > 0 && sample.snapshot_bytes > 0 => {
+            (now as f64 / sample.snapshot_bytes as f64).clamp(MIN_SCALE, MAX_SCALE)
+        }
+        _ => 1.0,
+    };
+    let scaled = |bytes: u64| (bytes as f64 * scale).round() as u64;
+    BuildEstimate {
+        build_bytes: scaled(sample.peak_build_bytes),
+        package_bytes: scaled(sample.package_bytes),
+        observed_version: sample.version.clone(),
+        scale,
+    }
+}
+
+/// Apparent size of a tree without following symlinks.
+pub fn tree_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| tree_size(&entry.path()))
+        .fold(0u64, u64::saturating_add)
+}
+
+fn is_package_archive(name: &str, package: &str) -> bool {
+    name.starts_with(package) && name.contains(".pkg.tar")
+}
+
+/// Package archives written at or after `since` and downloaded sources
+/// (top-level entries other than `src/`, `pkg/`, VCS metadata, and archives).
+fn output_sizes(dir: &Path, package: &str, since: SystemTime) -> (u64, u64) {
+    let mut package_bytes = 0u64;
+    let mut source_bytes = 0u64;
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        if is_package_archive(&name, package) {
+            let fresh = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .map(|modified| modified >= since)
+                .unwrap_or(false);
+            if fresh {
+                package_bytes = package_bytes.saturating_add(tree_size(&path));
+            }
+        } else if !matches!(
+            name.as_str(),
+            "src" | "pkg" | ".git" | ".SRCINFO" | "PKGBUILD"
+        ) {
+            source_bytes = source_bytes.saturating_add(tree_size(&path));
+        }
+    }
+    (package_bytes, source_bytes)
+}
+
+/// Build measurement request.
+pub struct BuildMeasurement<'a> {
+    pub package: &'a str,
+    pub version: &'a str,
+    pub helper: Option<&'a str>,
+    pub build_dir: PathBuf,
+    pub interval: Duration,
+    pub command: &'a [String],
+}
+
+/// Run the build command while sampling the build directory. Returns the
+/// command's exit code and, on success, the measured sample.
+pub async fn measure_build(request: BuildMeasurement<'_>) -> Result<(i32, Option<BuildSample>)> {
+    let Some((program, args)) = request.command.split_first() else {
+        return Err(SynsyuError::Config("No build command given".into()));
+    };
+    let started = SystemTime::now();
+    let baseline = tree_size(&request.build_dir);
+    let peak = Arc::new(AtomicU64::new(baseline));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let sampler = {
+        let (peak, done, dir) = (
+            Arc::clone(&peak),
+            Arc::clone(&done),
+            request.build_dir.clone(),
+        );
+        let interval = request.interval.max(Duration::from_millis(100));
+        tokio::spawn(async move {
+            while !done.load(Ordering::Relaxed) {
+                let dir = dir.clone();
+                let size = tokio::task::spawn_blocking(move || tree_size(&dir))
+                    .await
+                    .unwrap_or(0);
+                peak.fetch_max(size, Ordering::Relaxed);
+                tokio::time::sleep(interval).await;
+            }
+        })
+    };
+
+    let status = Command::new(program)
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .await
+        .map_err(|err| SynsyuError::CommandFailure {
+            command: program.clone(),
+            status: -1,
+            stderr: err.to_string(),
+        })?;
+    done.store(true, Ordering::Relaxed);
+    let _ = sampler.await;
+    let code = status.code().unwrap_or(1);
+    if !status.success() {
+        return Ok((code, None));
+    }
+
+    let final_size = tree_size(&request.build_dir);
+    let peak_bytes = peak.load(Ordering::Relaxed).max(final_size);
+    let (package_bytes, source_bytes) = output_sizes(&request.build_dir, request.package, started);
+    let sample = BuildSample {
+        at_epoch: started
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        package: request.package.to_string(),
+        version: request.version.to_string(),
+        helper: request.helper.map(String::from),
+        peak_build_bytes: peak_bytes.saturating_sub(baseline),
+        package_bytes,
+        source_bytes,
+        snapshot_bytes: 0,
+    };
+    Ok((code, Some(sample)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn sample(package: &str, at: u64, peak: u64, snapshot: u64) -> BuildSample {
+        BuildSample {
+            at_epoch: at,
+            package: package.into(),
+            version: format!("{at}-1"),
+            helper: Some("paru".into()),
+            peak_build_bytes: peak,
+            package_bytes: peak / 10,
+            // Upstream sources dwarf the AUR snapshot and never enter the ratio.
+            source_bytes: snapshot * 1_000,
+            snapshot_bytes: snapshot,
+        }
+    }
+
+    #[test]
+    fn estimates_scale_by_snapshot_size_within_bounds() {
+        let base = sample("foo", 1, 1_000, 100);
+        assert_eq!(estimate(&base, Some(200)).build_bytes, 2_000);
+        assert_eq!(estimate(&base, Some(50)).package_bytes, 50);
+        assert_eq!(estimate(&base, Some(100_000)).scale, MAX_SCALE);
+        assert_eq!(estimate(&base, None).build_bytes, 1_000);
+        assert_eq!(estimate(&sample("foo", 1, 1_000, 0), Some(10)).scale, 1.0);
+
+        // Samples recorded before snapshot sizes were kept are reused as-is.
+        let legacy: BuildSample = serde_json::from_str(
+            r#"{"at_epoch":1,"package":"foo","peak_build_bytes":1000,"package_bytes":100,"source_bytes":900000}"#,
+        )
+        .unwrap();
+        assert_eq!(estimate(&legacy, Some(4_096)).build_bytes, 1_000);
+    }
+
+    #[test]
+    fn keeps_latest_sample_per_package_and_bounds_file() {
+        let dir = TempDir::new("synsyu_build_").expect("tmp");
+        let path = dir.path().join("aur-build-history.jsonl");
+        for (pkg, at) in [("foo", 3), ("foo", 1), ("bar", 2), ("foo", 5)] {
+            append_sample(&path, &sample(pkg, at, at * 10, 1), 3).expect("append");
+        }
+        let latest = latest_samples(&path);
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest["foo"].at_epoch, 5);
+        assert_eq!(latest["bar"].peak_build_bytes, 20);
+    }
+
+    #[tokio::test]
+    async fn measures_build_growth_and_outputs() {
+        let tmp = TempDir::new("synsyu_build_run_").expect("tmp");
+        let dir = tmp.path();
+        fs::write(dir.join("PKGBUILD"), "pkgname=foo").expect("pkgbuild");
+        let script = format!(
+            "head -c 4096 /dev/zero > {d}/foo-src.tar.gz && mkdir -p {d}/src && head -c 65536 /dev/zero > {d}/src/blob && sleep 0.3 && rm -rf {d}/src && head -c 1024 /dev/zero > {d}/foo-1-1-x86_64.pkg.tar.zst",
+            d = dir.display()
+        );
+        let command = vec!["sh".to_string(), "-c".to_string(), script];
+        let (code, sample) = measure_build(BuildMeasurement {
+            package: "foo",
+            version: "1-1",
+            helper: None,
+            build_dir: dir.to_path_buf(),
+            interval: Duration::from_millis(100),
+            command: &command,
+        })
+        .await
+        .expect("measure");
+        let sample = sample.expect("sample");
+        assert_eq!(code, 0);
+        assert!(sample.peak_build_bytes >= 65_536, "{sample:?}");
+        assert_eq!(sample.package_bytes, 1024);
+        assert_eq!(sample.source_bytes, 4096);
+    }
+}
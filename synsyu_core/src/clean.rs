@@ -0,0 +1,522 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::clean
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Native replacement for `paccache -rk N`: scan pacman cache
+    directories and AUR helper caches, group package archives by
+    name and architecture, order them by version, and select the
+    files beyond the retention count for removal.
+
+  Security / Safety Notes:
+    Installed versions and versions referenced by the current
+    plan are never selected. Files in directories the caller
+    cannot write are removed through the configured privilege
+    tool with `rm -f --`; nothing else is executed elevated.
+============================================================*/
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{CleanConfig, SpaceConfig};
+use crate::error::{Result, SynsyuError};
+use crate::privilege::privileged_command;
+
+/// Directory depth searched below each cache root (helper caches nest
+/// archives under `<root>/<pkg>` or `<root>/clone/<pkg>`).
+const MAX_SCAN_DEPTH: usize = 3;
+/// Paths passed to one elevated `rm` invocation.
+const RM_BATCH: usize = 200;
+
+/// One package archive found in a cache directory.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CachedPackage {
+    pub path: PathBuf,
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    /// Archive plus detached signature.
+    pub bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<PathBuf>,
+}
+
+/// Why a cached archive is retained.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeepReason {
+    Installed,
+    Planned,
+    Recent,
+}
+
+/// Selection result for a cache scan.
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanReport {
+    pub keep_versions: u64,
+    pub scanned: Vec<PathBuf>,
+    pub kept: usize,
+    pub protected: BTreeMap<String, Vec<String>>,
+    pub remove: Vec<CachedPackage>,
+    pub reclaimable_bytes: u64,
+}
+
+/// Split `name-pkgver-pkgrel-arch.pkg.tar.*` into name, version, and arch.
+pub fn parse_package_filename(file_name: &str) -> Option<(String, String, String)> {
+    let (stem, _) = file_name.split_once(".pkg.tar")?;
+    let mut parts = stem.rsplitn(4, '-');
+    let arch = parts.next()?;
+    let pkgrel = parts.next()?;
+    let pkgver = parts.next()?;
+    let name = parts.next()?;
+    if name.is_empty() || pkgver.is_empty() || pkgrel.is_empty() || arch.is_empty() {
+        return None;
+    }
+    Some((
+        name.to_string(),
+        format!("{pkgver}-{pkgrel}"),
+        arch.to_string(),
+    ))
+}
+
+/// Compare two full versions (`[epoch:]pkgver[-pkgrel]`) the way libalpm does.
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (epoch_a, ver_a, rel_a) = split_evr(a);
+    let (epoch_b, ver_b, rel_b) = split_evr(b);
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(ver_a, ver_b))
+        .then_with(|| match (rel_a, rel_b) {
+            (Some(x), Some(y)) => rpmvercmp(x, y),
+            _ => Ordering::Equal,
+        })
+}
+
+fn split_evr(full: &str) -> (&str, &str, Option<&str>) {
+    let digits = full.bytes().take_while(u8::is_ascii_digit).count();
+    let (epoch, rest) = if full.as_bytes().get(digits) == Some(&b':') {
+        (
+            if digits == 0 { "0" } else { &full[..digits] },
+            &full[digits + 1..],
+        )
+    } else {
+        ("0", full)
+    };
+    match rest.rsplit_once('-') {
+        Some((version, release)) => (epoch, version, Some(release)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// Segment-wise comparison from rpm/libalpm.
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let one = a.as_bytes();
+    let two = b.as_bytes();
+    let (mut i, mut j) = (0usize, 0usize);
+
+    while i < one.len() && j < two.len() {
+        let (sep_i, sep_j) = (i, j);
+        while i < one.len() && !one[i].is_ascii_alphanumeric() {
+            i += 1;
+        }
+        while j < two.len() && !two[j].is_ascii_alphanumeric() {
+            j += 1;
+        }
+        if i >= one.len() || j >= two.len() {
+            break;
+        }
+        // Differing separator lengths decide the comparison.
+        if i - sep_i != j - sep_j {
+            return (i - sep_i).cmp(&(j - sep_j));
+        }
+
+        let (start_i, start_j) = (i, j);
+        let numeric = one[i].is_ascii_digit();
+        let class = |c: &u8| {
+            if numeric {
+                c.is_ascii_digit()
+            } else {
+                c.is_ascii_alphabetic()
+            }
+        };
+        while i < one.len() && class(&one[i]) {
+            i += 1;
+        }
+        while j < two.len() && class(&two[j]) {
+            j += 1;
+        }
+        if j == start_j {
+            // Numeric segments are newer than alpha segments.
+            return if numeric {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let mut seg_a = &one[start_i..i];
+        let mut seg_b = &two[start_j..j];
+        if numeric {
+            while seg_a.len() > 1 && seg_a[0] == b'0' {
+                seg_a = &seg_a[1..];
+            }
+            while seg_b.len() > 1 && seg_b[0] == b'0' {
+                seg_b = &seg_b[1..];
+            }
+            match seg_a.len().cmp(&seg_b.len()) {
+                Ordering::Equal => {}
+                other => return other,
+            }
+        }
+        match seg_a.cmp(seg_b) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+    }
+
+    let rest_a = one.get(i);
+    let rest_b = two.get(j);
+    match (rest_a, rest_b) {
+        (None, None) => Ordering::Equal,
+        // A remaining alpha segment never beats an empty string.
+        (None, Some(c)) if !c.is_ascii_alphabetic() => Ordering::Less,
+        (Some(c), _) if c.is_ascii_alphabetic() => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+/// Every `CacheDir` listed in pacman.conf (pacman's default when none).
+pub fn pacman_cache_dirs(pacman_conf: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_to_string(pacman_conf)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| {
+                    let line = line.split('#').next()?.trim();
+                    let (key, value) = line.split_once('=')?;
+                    (key.trim() == "CacheDir").then(|| value.to_string())
+                })
+                .flat_map(|value| {
+                    value
+                        .split_whitespace()
+                        .map(PathBuf::from)
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if dirs.is_empty() {
+        dirs.push(PathBuf::from("/var/cache/pacman/pkg"));
+    }
+    dirs
+}
+
+/// Cache roots to scan: pacman's `CacheDir`s plus, when enabled, the build
+/// directory of each configured AUR helper.
+pub fn cache_roots(
+    pacman_conf: &Path,
+    clean: &CleanConfig,
+    space: &SpaceConfig,
+    helpers: &[String],
+) -> Vec<PathBuf> {
+    let mut roots = pacman_cache_dirs(pacman_conf);
+    if clean.helper_caches {
+        for helper in helpers {
+            let root = space.aur_build_path(Some(helper));
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+    }
+    roots
+}
+
+/// Package archives below `root`, paired with their detached signatures.
+pub fn scan_cache_dir(root: &Path) -> Vec<CachedPackage> {
+    let mut found = Vec::new();
+    scan_into(root, 0, &mut found);
+    found
+}
+
+fn scan_into(dir: &Path, depth: usize, found: &mut Vec<CachedPackage>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_type.is_dir() {
+            // Build trees never hold finished archives worth keeping.
+            if depth < MAX_SCAN_DEPTH && !matches!(file_name.as_str(), "src" | "pkg" | ".git") {
+                scan_into(&path, depth + 1, found);
+            }
+            continue;
+        }
+        if !file_type.is_file() || file_name.ends_with(".sig") || file_name.ends_with(".part") {
+            continue;
+        }
+        let Some((name, version, arch)) = parse_package_filename(&file_name) else {
+            continue;
+        };
+        let signature_path = dir.join(format!("{file_name}.sig"));
+        let signature = signature_path.is_file().then_some(signature_path);
+        let size = |p: &Path| fs::symlink_metadata(p).map(|m| m.len()).unwrap_or(0);
+        let bytes = size(&path).saturating_add(signature.as_deref().map(size).unwrap_or(0));
+        found.push(CachedPackage {
+            path,
+            name,
+            version,
+            arch,
+            bytes,
+            signature,
+        });
+    }
+}
+
+/// `(name, version)` pairs the plan will install.
+pub fn plan_versions(plan: &Value) -> HashSet<(String, String)> {
+    ["pacman_updates", "aur_updates"]
+        .iter()
+        .filter_map(|key| plan.get(*key).and_then(Value::as_array))
+        .flatten()
+        .filter_map(|item| {
+            Some((
+                item.get("name")?.as_str()?.to_string(),
+                item.get("available")?.as_str()?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Keep the newest `keep_versions` versions per name and architecture plus
+/// every installed or planned version; select the rest for removal.
+pub fn select_removals(
+    scanned: Vec<PathBuf>,
+    packages: Vec<CachedPackage>,
+    keep_versions: u64,
+    installed: &HashMap<String, String>,
+    planned: &HashSet<(String, String)>,
+) -> CleanReport {
+    let mut groups: BTreeMap<(String, String), Vec<CachedPackage>> = BTreeMap::new();
+    for package in packages {
+        groups
+            .entry((package.name.clone(), package.arch.clone()))
+            .or_default()
+            .push(package);
+    }
+
+    let mut report = CleanReport {
+        keep_versions,
+        scanned,
+        kept: 0,
+        protected: BTreeMap::new(),
+        remove: Vec::new(),
+        reclaimable_bytes: 0,
+    };
+    for ((name, _), mut files) in groups {
+        files.sort_by(|a, b| vercmp(&b.version, &a.version));
+        let mut versions: Vec<&str> = files.iter().map(|f| f.version.as_str()).collect();
+        versions.dedup();
+        let recent: HashSet<String> = versions
+            .iter()
+            .take(usize::try_from(keep_versions).unwrap_or(usize::MAX))
+            .map(|v| v.to_string())
+            .collect();
+
+        for file in files {
+            let reason = if installed.get(&name) == Some(&file.version) {
+                Some(KeepReason::Installed)
+            } else if planned.contains(&(name.clone(), file.version.clone())) {
+                Some(KeepReason::Planned)
+            } else if recent.contains(&file.version) {
+                Some(KeepReason::Recent)
+            } else {
+                None
+            };
+            match reason {
+                Some(reason) => {
+                    report.kept += 1;
+                    if reason != KeepReason::Recent {
+                        let entry = report.protected.entry(name.clone()).or_default();
+                        if !entry.contains(&file.version) {
+                            entry.push(file.version.clone());
+                        }
+                    }
+                }
+                None => {
+                    report.reclaimable_bytes = report.reclaimable_bytes.saturating_add(file.bytes);
+                    report.remove.push(file);
+                }
+            }
+        }
+    }
+    report
+}
+
+/// Delete the selected archives and signatures. Files in directories the
+/// caller can write are removed directly; the rest through `privilege_tool`.
+/// Returns the number of archives removed.
+pub fn remove_packages(packages: &[CachedPackage], privilege_tool: &str) -> Result<usize> {
+    let mut direct = Vec::new();
+    let mut elevated = Vec::new();
+    for package in packages {
+        let paths = std::iter::once(package.path.clone()).chain(package.signature.clone());
+        if package.path.parent().map(dir_writable).unwrap_or(false) {
+            direct.extend(paths);
+        } else {
+            elevated.extend(paths);
+        }
+    }
+
+    for path in &direct {
+        match fs::remove_file(path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(SynsyuError::Filesystem(format!(
+                    "Unable to remove {}: {err}",
+                    path.display()
+                )))
+            }
+        }
+    }
+    for chunk in elevated.chunks(RM_BATCH) {
+        let mut command = privileged_command(privilege_tool, "rm");
+        command.arg("-f").arg("--").args(chunk);
+        let output = command.output().map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                SynsyuError::CommandMissing {
+                    command: privilege_tool.trim().to_string(),
+                }
+            } else {
+                SynsyuError::Io(err)
+            }
+        })?;
+        if !output.status.success() {
+            return Err(SynsyuError::CommandFailure {
+                command: format!("{} rm -f", privilege_tool.trim()),
+                status: output.status.code().unwrap_or(-1),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+    }
+    Ok(packages.len())
+}
+
+fn dir_writable(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `path` is a valid NUL-terminated string for the call's duration.
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn vercmp_matches_libalpm_ordering() {
+        let ordered = [
+            "1.0a", "1.0b", "1.0", "1.0.a", "1.0.1", "1.1", "1.2", "1.10", "1:0.1",
+        ];
+        for pair in ordered.windows(2) {
+            assert_eq!(vercmp(pair[0], pair[1]), Ordering::Less, "{pair:?}");
+            assert_eq!(vercmp(pair[1], pair[0]), Ordering::Greater, "{pair:?}");
+        }
+        assert_eq!(vercmp("1.0-1", "1.0-2"), Ordering::Less);
+        assert_eq!(vercmp("1.0-1", "1.0"), Ordering::Equal);
+        assert_eq!(vercmp("1.01", "1.1"), Ordering::Equal);
+        assert_eq!(vercmp("1.0rc1", "1.0"), Ordering::Less);
+        assert_eq!(
+            parse_package_filename("lib32-foo-bar-2:1.2.3-4-x86_64.pkg.tar.zst"),
+            Some((
+                "lib32-foo-bar".to_string(),
+                "2:1.2.3-4".to_string(),
+                "x86_64".to_string()
+            ))
+        );
+        assert_eq!(parse_package_filename("foo.db.tar.gz"), None);
+    }
+
+    #[test]
+    fn keeps_recent_installed_and_planned_versions() {
+        let tmp = TempDir::new("synsyu_clean_").unwrap();
+        let dir = tmp.path().to_path_buf();
+        let nested = dir.join("clone").join("foo");
+        fs::create_dir_all(&nested).unwrap();
+        for version in ["1.0-1", "1.1-1", "1.2-1", "1.10-1", "2.0-1"] {
+            let file = dir.join(format!("foo-{version}-x86_64.pkg.tar.zst"));
+            fs::write(&file, vec![0u8; 100]).unwrap();
+            fs::write(
+                dir.join(format!("foo-{version}-x86_64.pkg.tar.zst.sig")),
+                b"sig",
+            )
+            .unwrap();
+        }
+        fs::write(nested.join("foo-0.9-1-any.pkg.tar.zst"), b"old").unwrap();
+        fs::write(dir.join("bar-1-1-x86_64.pkg.tar.zst.part"), b"partial").unwrap();
+
+        let packages = scan_cache_dir(&dir);
+        assert_eq!(packages.len(), 6);
+        let installed = HashMap::from([("foo".to_string(), "1.0-1".to_string())]);
+        let planned = HashSet::from([("foo".to_string(), "1.1-1".to_string())]);
+        let report = select_removals(vec![dir.clone()], packages, 2, &installed, &planned);
+
+        let mut removed: Vec<&str> = report.remove.iter().map(|p| p.version.as_str()).collect();
+        removed.sort();
+        // Newest two (2.0, 1.10) are kept; 1.0 is installed and 1.1 planned.
+        // The `any` build is its own group and within the retention count.
+        assert_eq!(removed, vec!["1.2-1"]);
+        assert_eq!(report.reclaimable_bytes, 103);
+        assert_eq!(report.kept, 5);
+
+        assert_eq!(remove_packages(&report.remove, "").unwrap(), 1);
+        assert!(!dir.join("foo-1.2-1-x86_64.pkg.tar.zst").exists());
+        assert!(!dir.join("foo-1.2-1-x86_64.pkg.tar.zst.sig").exists());
+        assert!(dir.join("foo-1.10-1-x86_64.pkg.tar.zst").exists());
+    }
+
+    #[test]
+    fn reads_every_cache_dir_and_plan_target() {
+        let tmp = TempDir::new("synsyu_clean_").unwrap();
+        let conf = tmp.path().join("pacman.conf");
+        fs::write(
+            &conf,
+            "[options]\nCacheDir = /srv/pkg /var/cache/pacman/pkg\n#CacheDir = /ignored\nCacheDir=/mnt/extra\n",
+        )
+        .unwrap();
+        assert_eq!(
+            pacman_cache_dirs(&conf),
+            vec![
+                PathBuf::from("/srv/pkg"),
+                PathBuf::from("/var/cache/pacman/pkg"),
+                PathBuf::from("/mnt/extra"),
+            ]
+        );
+
+        let plan = serde_json::json!({
+            "pacman_updates": [{"name": "linux", "available": "6.1-1"}],
+            "aur_updates": [{"name": "foo-git", "available": "r10-1"}],
+        });
+        let planned = plan_versions(&plan);
+        assert!(planned.contains(&("linux".to_string(), "6.1-1".to_string())));
+        assert!(planned.contains(&("foo-git".to_string(), "r10-1".to_string())));
+    }
+}
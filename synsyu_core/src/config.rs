@@ -27,17 +27,28 @@
     - Deterministic error reporting with context
 ============================================================*/
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use dirs::config_dir;
 use serde::{Deserialize, Serialize};
 
+use crate::audit::AuditBackendKind;
+use crate::config_layers;
 use crate::error::{Result, SynsyuError};
+use crate::logger::{LevelFilter, LogThresholds};
+use crate::space::{BtrfsMode, SpaceLimits};
 
 /// Top-level configuration for Syn-Syu-Core.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct SynsyuConfig {
+    /// Layout version stamped by `synsyu_core config migrate`.
+    #[serde(default)]
+    pub config_version: Option<u32>,
+    /// `[profiles.<name>]` overlay applied while loading; not a file key.
+    #[serde(skip)]
+    pub profile: Option<String>,
     #[serde(default)]
     pub aur: AurConfig,
     #[serde(default)]
@@ -60,80 +71,38 @@ pub struct SynsyuConfig {
     pub safety: SafetyConfig,
     #[serde(default)]
     pub clean: CleanConfig,
+    #[serde(default)]
+    pub prefetch: PrefetchConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub runs: RunsConfig,
+    #[serde(default)]
+    pub signing: SigningConfig,
 }
 
 impl SynsyuConfig {
-    /// Load configuration, applying defaults and overriding with file contents if present.
+    /// Load configuration from every layer: defaults, the system file, the
+    /// user file (`path`, or the default location), and `SYNSYU_*` variables.
     pub fn load_from_optional_path(path: Option<&Path>) -> Result<Self> {
-        let mut config = SynsyuConfig::default();
-        if let Some(path) = path {
-            if path.exists() {
-                ensure_secure_permissions(path)?;
-                let contents = fs::read_to_string(path).map_err(|err| {
-                    SynsyuError::Filesystem(format!(
-                        "Failed to read configuration {}: {err}",
-                        path.display()
-                    ))
-                })?;
-                let external: SynsyuConfig = toml::from_str(&contents).map_err(|err| {
-                    SynsyuError::Config(format!(
-                        "Failed to parse configuration {}: {err}",
-                        path.display()
-                    ))
-                })?;
-                config.merge(external);
-            } else {
-                return Err(SynsyuError::Config(format!(
-                    "Configuration file {} does not exist",
-                    path.display()
-                )));
-            }
-        } else if let Some(default_path) = default_config_path() {
-            if default_path.exists() {
-                ensure_secure_permissions(&default_path)?;
-                let contents = fs::read_to_string(&default_path).map_err(|err| {
-                    SynsyuError::Filesystem(format!(
-                        "Failed to read configuration {}: {err}",
-                        default_path.display()
-                    ))
-                })?;
-                let external: SynsyuConfig = toml::from_str(&contents).map_err(|err| {
-                    SynsyuError::Config(format!(
-                        "Failed to parse configuration {}: {err}",
-                        default_path.display()
-                    ))
-                })?;
-                config.merge(external);
-            }
-        }
-        Ok(config)
-    }
-
-    fn merge(&mut self, other: SynsyuConfig) {
-        self.aur = other.aur;
-        self.core = other.core;
-        self.helpers = other.helpers;
-        self.mirrors = other.mirrors;
-        self.acquisition = other.acquisition;
-        self.space = other.space;
-        self.applications = other.applications;
-        self.logging = other.logging;
-        self.snapshots = other.snapshots;
-        self.safety = other.safety;
-        self.clean = other.clean;
+        Ok(config_layers::load_layered(path, &[])?.config)
     }
 
     /// Manifest path resolved from configuration.
     pub fn manifest_path(&self) -> PathBuf {
-        PathBuf::from(&self.core.manifest_path)
+        expand_home(&self.core.manifest_path)
     }
 
-    /// Optional log directory defined by operator.
+    /// Optional log directory defined by operator; `logging.directory` wins over
+    /// the legacy `core.log_directory` key.
     pub fn log_dir(&self) -> PathBuf {
-        self.core
-            .log_directory
+        self.logging
+            .directory
             .as_ref()
-            .map(|p| PathBuf::from(p.as_str()))
+            .or(self.core.log_directory.as_ref())
+            .map(|p| expand_home(p))
             .unwrap_or_else(default_log_dir)
     }
 
@@ -149,6 +118,7 @@ impl SynsyuConfig {
     }
 
     /// Policy for handling low space relative to the configured buffer.
+    #[allow(dead_code)]
     pub fn space_policy(&self) -> SpacePolicy {
         self.space.policy
     }
@@ -163,6 +133,13 @@ impl SynsyuConfig {
         self.applications.fwupd
     }
 
+    /// Copy safe to print: proxy credentials are redacted.
+    fn redacted(&self) -> SynsyuConfig {
+        let mut copy = self.clone();
+        copy.network.proxy = copy.network.proxy.as_deref().map(redact_url_credentials);
+        copy
+    }
+
     /// Snapshot of merged configuration suitable for reporting.
     pub fn to_report(&self) -> ConfigReport {
         ConfigReport {
@@ -182,6 +159,10 @@ impl SynsyuConfig {
             mirrors_cache_path: self.mirrors.cache_path.clone(),
             mirrors_cache_ttl_hours: self.mirrors.cache_ttl_hours,
             mirrors_servers: self.mirrors.servers.clone(),
+            mirrors_status_url: self.mirrors.generate.status_url.clone(),
+            mirrors_status_path: self.mirrors.generate.status_path.clone(),
+            mirrors_history_enabled: self.mirrors.history.enabled,
+            mirrors_history_window_days: self.mirrors.history.window_days,
             acquisition_aur_rpc_enabled: self.acquisition.aur_rpc.enabled,
             acquisition_aur_rpc_max_retries: self.resolved_aur_rpc_max_retries(),
             acquisition_aur_rpc_retry_delay_seconds: self.acquisition.aur_rpc.retry_delay_seconds,
@@ -194,11 +175,16 @@ impl SynsyuConfig {
             space_min_free_bytes: self.space.min_free_bytes(),
             space_policy: self.space.policy.to_string(),
             batch_size: self.core.batch_size,
+            privilege_tool: self.core.privilege_tool.clone(),
             applications_flatpak: self.applications.flatpak,
             applications_fwupd: self.applications.fwupd,
             log_level: self.logging.level.clone(),
             log_retention_days: self.logging.retention_days,
             log_retention_megabytes: self.logging.retention_megabytes,
+            log_format: self.logging.format.to_string(),
+            log_stderr_level: self.logging.stderr_level.clone(),
+            log_code_levels: self.logging.codes.clone(),
+            log_rotate_megabytes: self.logging.rotate_megabytes,
             snapshots_enabled: self.snapshots.enabled,
             snapshot_pre_command: self.snapshots.pre_command.clone(),
             snapshot_post_command: self.snapshots.post_command.clone(),
@@ -208,24 +194,30 @@ impl SynsyuConfig {
             clean_keep_versions: self.clean.keep_versions,
             clean_remove_orphans: self.clean.remove_orphans,
             clean_check_pacnew: self.clean.check_pacnew,
-        }
-    }
-}
-
-impl Default for SynsyuConfig {
-    fn default() -> Self {
-        Self {
-            aur: AurConfig::default(),
-            core: CoreConfig::default(),
-            helpers: HelperConfig::default(),
-            mirrors: MirrorConfig::default(),
-            acquisition: AcquisitionConfig::default(),
-            space: SpaceConfig::default(),
-            applications: ApplicationsConfig::default(),
-            logging: LoggingConfig::default(),
-            snapshots: SnapshotsConfig::default(),
-            safety: SafetyConfig::default(),
-            clean: CleanConfig::default(),
+            prefetch_staging_dir: self.prefetch.staging_path(),
+            prefetch_max_concurrency: self.prefetch.max_concurrency,
+            prefetch_max_kib_per_sec: self.prefetch.max_kib_per_sec,
+            network_proxy: self.network.proxy.as_deref().map(redact_url_credentials),
+            network_no_proxy: self.network.no_proxy.clone(),
+            network_ca_bundle: self.network.ca_bundle.clone(),
+            network_ip_version: self.network.ip_version.to_string(),
+            network_bind_interface: self.network.bind_interface.clone(),
+            network_user_agent: self.network.user_agent(),
+            audit_backends: self
+                .audit
+                .backends
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            audit_file: self.audit.file_path(),
+            runs_directory: self.runs.directory_path(),
+            runs_keep: self.runs.keep,
+            signing_enabled: self.signing.enabled,
+            signing_require: self.signing.require,
+            signing_key: self.signing.key_path(),
+            signing_trusted_keys: self.signing.trusted_key_paths(),
+            profile: self.profile.clone(),
+            effective: self.redacted(),
         }
     }
 }
@@ -241,7 +233,7 @@ impl SynsyuConfig {
 }
 
 /// Configuration options for AUR interactions.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AurConfig {
     #[serde(default = "AurConfig::default_base_url")]
     pub base_url: String,
@@ -280,7 +272,7 @@ impl Default for AurConfig {
 }
 
 /// Configuration for core runtime.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CoreConfig {
     #[serde(default = "CoreConfig::default_manifest_path")]
     pub manifest_path: String,
@@ -289,6 +281,8 @@ pub struct CoreConfig {
     #[serde(default = "CoreConfig::default_batch_size")]
     #[allow(dead_code)]
     pub batch_size: usize,
+    #[serde(default = "CoreConfig::default_privilege_tool")]
+    pub privilege_tool: String,
 }
 
 impl CoreConfig {
@@ -305,6 +299,10 @@ impl CoreConfig {
     fn default_batch_size() -> usize {
         10
     }
+
+    fn default_privilege_tool() -> String {
+        "sudo".to_string()
+    }
 }
 
 impl Default for CoreConfig {
@@ -313,17 +311,47 @@ impl Default for CoreConfig {
             manifest_path: Self::default_manifest_path(),
             log_directory: None,
             batch_size: Self::default_batch_size(),
+            privilege_tool: Self::default_privilege_tool(),
         }
     }
 }
 
 /// Disk space requirements.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct SpaceConfig {
     #[serde(default = "SpaceConfig::default_min_free_gb")]
     pub min_free_gb: f64,
-    #[serde(default = "SpaceConfig::default_policy")]
+    /// Documented as `space.mode`; `policy` is the original key.
+    #[serde(default = "SpaceConfig::default_policy", alias = "mode")]
     pub policy: SpacePolicy,
+    /// Where AUR builds land; defaults to `~/.cache/<helper>`.
+    #[serde(default)]
+    pub aur_build_dir: Option<String>,
+    /// Flatpak installation; defaults to `/var/lib/flatpak` when present,
+    /// otherwise `~/.local/share/flatpak`.
+    #[serde(default)]
+    pub flatpak_dir: Option<String>,
+    /// EFI system partition for firmware capsules; defaults to the first of
+    /// `/efi`, `/boot/efi`, `/boot` holding an `EFI` directory.
+    #[serde(default)]
+    pub esp_dir: Option<String>,
+    /// Free space to keep on the filesystems holding /boot and the ESP.
+    #[serde(default = "SpaceConfig::default_boot_margin_mb")]
+    pub boot_margin_mb: u64,
+    /// Free inodes to keep beyond the estimated file count.
+    #[serde(default = "SpaceConfig::default_inode_margin")]
+    pub inode_margin: u64,
+    /// `off`, `report`, or `enforce` btrfs chunk-allocation checks.
+    #[serde(default)]
+    pub btrfs_mode: BtrfsMode,
+    /// Measured AUR build sizes; defaults to
+    /// `~/.cache/syn-syu/aur-build-history.jsonl`.
+    #[serde(default)]
+    pub build_history_path: Option<String>,
+    #[serde(default = "SpaceConfig::default_build_history_max_entries")]
+    pub build_history_max_entries: usize,
+    #[serde(default = "SpaceConfig::default_build_sample_interval_ms")]
+    pub build_sample_interval_ms: u64,
 }
 
 impl SpaceConfig {
@@ -335,6 +363,22 @@ impl SpaceConfig {
         SpacePolicy::Warn
     }
 
+    fn default_boot_margin_mb() -> u64 {
+        16
+    }
+
+    fn default_inode_margin() -> u64 {
+        10_000
+    }
+
+    fn default_build_history_max_entries() -> usize {
+        1000
+    }
+
+    fn default_build_sample_interval_ms() -> u64 {
+        1000
+    }
+
     pub fn min_free_bytes(&self) -> u64 {
         if self.min_free_gb <= 0.0 {
             0
@@ -342,6 +386,64 @@ impl SpaceConfig {
             (self.min_free_gb * 1024.0_f64 * 1024.0_f64 * 1024.0_f64).round() as u64
         }
     }
+
+    /// Byte buffer plus the configured inode buffer and btrfs mode.
+    pub fn limits(&self, margin_bytes: u64) -> SpaceLimits {
+        SpaceLimits {
+            margin_bytes,
+            inode_margin: self.inode_margin,
+            btrfs_mode: self.btrfs_mode,
+        }
+    }
+
+    /// AUR build directory for the given helper.
+    pub fn aur_build_path(&self, helper: Option<&str>) -> PathBuf {
+        if let Some(path) = &self.aur_build_dir {
+            return expand_home(path);
+        }
+        let cache = dirs::cache_dir().unwrap_or_else(|| expand_home("~/.cache"));
+        match helper {
+            Some(helper) => cache.join(helper),
+            None => cache,
+        }
+    }
+
+    /// Flatpak installation directory.
+    pub fn flatpak_path(&self) -> PathBuf {
+        if let Some(path) = &self.flatpak_dir {
+            return expand_home(path);
+        }
+        let system = PathBuf::from("/var/lib/flatpak");
+        if system.exists() {
+            system
+        } else {
+            expand_home("~/.local/share/flatpak")
+        }
+    }
+
+    /// Directory of a named Flatpak installation (`system` or `user`);
+    /// an explicit `flatpak_dir` applies to both.
+    pub fn flatpak_installation_path(&self, installation: &str) -> PathBuf {
+        if let Some(path) = &self.flatpak_dir {
+            return expand_home(path);
+        }
+        match installation {
+            "user" => expand_home("~/.local/share/flatpak"),
+            "system" => PathBuf::from("/var/lib/flatpak"),
+            _ => self.flatpak_path(),
+        }
+    }
+
+    /// EFI system partition mount, when one can be found.
+    pub fn esp_path(&self) -> Option<PathBuf> {
+        if let Some(path) = &self.esp_dir {
+            return Some(expand_home(path));
+        }
+        ["/efi", "/boot/efi", "/boot"]
+            .iter()
+            .map(PathBuf::from)
+            .find(|path| path.join("EFI").is_dir())
+    }
 }
 
 impl Default for SpaceConfig {
@@ -349,11 +451,20 @@ impl Default for SpaceConfig {
         Self {
             min_free_gb: Self::default_min_free_gb(),
             policy: Self::default_policy(),
+            aur_build_dir: None,
+            flatpak_dir: None,
+            esp_dir: None,
+            boot_margin_mb: Self::default_boot_margin_mb(),
+            inode_margin: Self::default_inode_margin(),
+            btrfs_mode: BtrfsMode::default(),
+            build_history_path: None,
+            build_history_max_entries: Self::default_build_history_max_entries(),
+            build_sample_interval_ms: Self::default_build_sample_interval_ms(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 pub enum SpacePolicy {
     Warn,
@@ -370,7 +481,7 @@ impl std::fmt::Display for SpacePolicy {
 }
 
 /// Preferred helper prioritization.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct HelperConfig {
     #[serde(default = "HelperConfig::default_priority")]
     #[allow(dead_code)]
@@ -400,7 +511,7 @@ impl Default for HelperConfig {
 }
 
 /// Source-aware bounded acquisition policy.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct AcquisitionConfig {
     #[serde(default)]
     pub aur_rpc: AcquisitionAurRpcConfig,
@@ -408,17 +519,8 @@ pub struct AcquisitionConfig {
     pub aur_helper: AcquisitionRetryConfig,
 }
 
-impl Default for AcquisitionConfig {
-    fn default() -> Self {
-        Self {
-            aur_rpc: AcquisitionAurRpcConfig::default(),
-            aur_helper: AcquisitionRetryConfig::default_aur_helper(),
-        }
-    }
-}
-
 /// Bounded retry policy for direct AUR RPC access in synsyu_core.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AcquisitionAurRpcConfig {
     #[serde(default = "AcquisitionAurRpcConfig::default_enabled")]
     pub enabled: bool,
@@ -449,7 +551,7 @@ impl Default for AcquisitionAurRpcConfig {
 }
 
 /// Generic bounded retry policy used by source-specific Bash execution paths.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AcquisitionRetryConfig {
     #[serde(default = "AcquisitionRetryConfig::default_enabled")]
     pub enabled: bool,
@@ -488,7 +590,7 @@ impl Default for AcquisitionRetryConfig {
 }
 
 /// Mirror discovery, probing, and failover policy.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct MirrorConfig {
     #[serde(default = "MirrorConfig::default_enabled")]
     pub enabled: bool,
@@ -514,6 +616,10 @@ pub struct MirrorConfig {
     pub cache_ttl_hours: u64,
     #[serde(default)]
     pub servers: Vec<String>,
+    #[serde(default)]
+    pub generate: MirrorGenerateConfig,
+    #[serde(default)]
+    pub history: MirrorHistoryConfig,
 }
 
 impl MirrorConfig {
@@ -573,30 +679,165 @@ impl Default for MirrorConfig {
             cache_path: None,
             cache_ttl_hours: Self::default_cache_ttl_hours(),
             servers: Vec::new(),
+            generate: MirrorGenerateConfig::default(),
+            history: MirrorHistoryConfig::default(),
         }
     }
 }
 
-/// Application metadata collection toggles.
-#[derive(Debug, Deserialize, Clone)]
-pub struct ApplicationsConfig {
+/// Append-only probe history used for long-term mirror statistics.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MirrorHistoryConfig {
+    #[serde(default = "MirrorHistoryConfig::default_enabled")]
+    pub enabled: bool,
     #[serde(default)]
-    pub flatpak: bool,
+    pub path: Option<String>,
+    #[serde(default = "MirrorHistoryConfig::default_max_entries")]
+    pub max_entries: usize,
+    #[serde(default = "MirrorHistoryConfig::default_window_days")]
+    pub window_days: u64,
+    #[serde(default = "MirrorHistoryConfig::default_flaky_min_samples")]
+    pub flaky_min_samples: usize,
+    #[serde(default = "MirrorHistoryConfig::default_flaky_penalty_ms")]
+    pub flaky_penalty_ms: u64,
+}
+
+impl MirrorHistoryConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_max_entries() -> usize {
+        5000
+    }
+
+    fn default_window_days() -> u64 {
+        14
+    }
+
+    fn default_flaky_min_samples() -> usize {
+        5
+    }
+
+    fn default_flaky_penalty_ms() -> u64 {
+        5000
+    }
+}
+
+impl Default for MirrorHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            path: None,
+            max_entries: Self::default_max_entries(),
+            window_days: Self::default_window_days(),
+            flaky_min_samples: Self::default_flaky_min_samples(),
+            flaky_penalty_ms: Self::default_flaky_penalty_ms(),
+        }
+    }
+}
+
+/// Inputs and filters for `synsyu_core mirrors --generate`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MirrorGenerateConfig {
+    #[serde(default = "MirrorGenerateConfig::default_status_url")]
+    pub status_url: String,
     #[serde(default)]
-    pub fwupd: bool,
+    pub status_path: Option<String>,
+    #[serde(default = "MirrorGenerateConfig::default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    #[serde(default)]
+    pub countries: Vec<String>,
+    #[serde(default = "MirrorGenerateConfig::default_protocols")]
+    pub protocols: Vec<String>,
+    #[serde(default = "MirrorGenerateConfig::default_ip_version")]
+    pub ip_version: MirrorIpVersion,
+    #[serde(default = "MirrorGenerateConfig::default_min_completion_percent")]
+    pub min_completion_percent: f64,
+    #[serde(default = "MirrorGenerateConfig::default_max_delay_minutes")]
+    pub max_delay_minutes: u64,
+    #[serde(default = "MirrorGenerateConfig::default_limit")]
+    pub limit: usize,
+}
+
+impl MirrorGenerateConfig {
+    fn default_status_url() -> String {
+        "https://archlinux.org/mirrors/status/json/".to_string()
+    }
+
+    fn default_timeout_seconds() -> u64 {
+        10
+    }
+
+    fn default_protocols() -> Vec<String> {
+        vec!["https".into()]
+    }
+
+    fn default_ip_version() -> MirrorIpVersion {
+        MirrorIpVersion::Any
+    }
+
+    fn default_min_completion_percent() -> f64 {
+        100.0
+    }
+
+    fn default_max_delay_minutes() -> u64 {
+        60
+    }
+
+    fn default_limit() -> usize {
+        20
+    }
 }
 
-impl Default for ApplicationsConfig {
+impl Default for MirrorGenerateConfig {
     fn default() -> Self {
         Self {
-            flatpak: false,
-            fwupd: false,
+            status_url: Self::default_status_url(),
+            status_path: None,
+            timeout_seconds: Self::default_timeout_seconds(),
+            countries: Vec::new(),
+            protocols: Self::default_protocols(),
+            ip_version: Self::default_ip_version(),
+            min_completion_percent: Self::default_min_completion_percent(),
+            max_delay_minutes: Self::default_max_delay_minutes(),
+            limit: Self::default_limit(),
         }
     }
 }
 
+/// IP family a generated mirror must support, also used as the outbound
+/// connection preference in `[network]`.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MirrorIpVersion {
+    #[default]
+    Any,
+    Ipv4,
+    Ipv6,
+}
+
+impl std::fmt::Display for MirrorIpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MirrorIpVersion::Any => write!(f, "any"),
+            MirrorIpVersion::Ipv4 => write!(f, "ipv4"),
+            MirrorIpVersion::Ipv6 => write!(f, "ipv6"),
+        }
+    }
+}
+
+/// Application metadata collection toggles.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ApplicationsConfig {
+    #[serde(default)]
+    pub flatpak: bool,
+    #[serde(default)]
+    pub fwupd: bool,
+}
+
 /// Logging preferences.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct LoggingConfig {
     #[serde(default)]
     pub directory: Option<String>,
@@ -606,6 +847,18 @@ pub struct LoggingConfig {
     pub retention_days: Option<u64>,
     #[serde(default)]
     pub retention_megabytes: Option<u64>,
+    /// Log line format: `text` (default) or `json` (JSON lines).
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Threshold for stderr (`--verbose` lowers it to debug).
+    #[serde(default)]
+    pub stderr_level: Option<String>,
+    /// Per-code thresholds, e.g. `MIRRORS = "info"`.
+    #[serde(default)]
+    pub codes: BTreeMap<String, String>,
+    /// Rotate the current log into a gzip segment at this size.
+    #[serde(default)]
+    pub rotate_megabytes: Option<u64>,
 }
 
 impl Default for LoggingConfig {
@@ -615,12 +868,71 @@ impl Default for LoggingConfig {
             level: Some("info".to_string()),
             retention_days: None,
             retention_megabytes: None,
+            format: LogFormat::default(),
+            stderr_level: None,
+            codes: BTreeMap::new(),
+            rotate_megabytes: None,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// Parsed file, stderr, and per-code thresholds.
+    pub fn thresholds(&self) -> Result<LogThresholds> {
+        let parse = |key: &str, value: &str| {
+            LevelFilter::parse(value).ok_or_else(|| {
+                SynsyuError::Config(format!(
+                    "Invalid {key} '{value}' (expected debug, info, warn, error, or off)"
+                ))
+            })
+        };
+        let mut thresholds = LogThresholds::default();
+        if let Some(level) = &self.level {
+            thresholds.file = parse("logging.level", level)?;
+        }
+        if let Some(level) = &self.stderr_level {
+            thresholds.stderr = parse("logging.stderr_level", level)?;
+        }
+        for (code, level) in &self.codes {
+            thresholds.codes.insert(
+                code.to_ascii_uppercase(),
+                parse(&format!("logging.codes.{code}"), level)?,
+            );
+        }
+        Ok(thresholds)
+    }
+
+    /// Rotation size in bytes; 0 when rotation is off.
+    pub fn rotate_bytes(&self) -> u64 {
+        self.rotate_megabytes
+            .unwrap_or(0)
+            .saturating_mul(1024 * 1024)
+    }
+}
+
+/// Log line format.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `timestamp [LEVEL] [CODE] message | key=value ...`
+    #[default]
+    Text,
+    /// One JSON object per line.
+    #[serde(alias = "jsonl")]
+    Json,
+}
+
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
         }
     }
 }
 
 /// Snapshot hooks configuration.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct SnapshotsConfig {
     #[serde(default)]
     pub enabled: bool,
@@ -632,19 +944,8 @@ pub struct SnapshotsConfig {
     pub require_success: bool,
 }
 
-impl Default for SnapshotsConfig {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            pre_command: None,
-            post_command: None,
-            require_success: false,
-        }
-    }
-}
-
 /// Safety tuning.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct SafetyConfig {
     #[serde(default = "SafetyConfig::default_disk_check")]
     pub disk_check: bool,
@@ -668,7 +969,7 @@ impl Default for SafetyConfig {
 }
 
 /// Cache/cleanup preferences.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CleanConfig {
     #[serde(default = "CleanConfig::default_keep_versions")]
     pub keep_versions: u64,
@@ -676,6 +977,9 @@ pub struct CleanConfig {
     pub remove_orphans: bool,
     #[serde(default = "CleanConfig::default_check_pacnew")]
     pub check_pacnew: bool,
+    /// Also prune package archives left in AUR helper caches.
+    #[serde(default = "CleanConfig::default_helper_caches")]
+    pub helper_caches: bool,
 }
 
 impl CleanConfig {
@@ -685,6 +989,9 @@ impl CleanConfig {
     fn default_check_pacnew() -> bool {
         true
     }
+    fn default_helper_caches() -> bool {
+        true
+    }
 }
 
 impl Default for CleanConfig {
@@ -693,10 +1000,258 @@ impl Default for CleanConfig {
             keep_versions: Self::default_keep_versions(),
             remove_orphans: false,
             check_pacnew: Self::default_check_pacnew(),
+            helper_caches: Self::default_helper_caches(),
+        }
+    }
+}
+
+/// Parallel repo package prefetch (`synsyu_core prefetch`).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PrefetchConfig {
+    #[serde(default)]
+    pub staging_dir: Option<String>,
+    #[serde(default = "PrefetchConfig::default_sync_db_dir")]
+    pub sync_db_dir: String,
+    #[serde(default = "PrefetchConfig::default_max_concurrency")]
+    pub max_concurrency: usize,
+    #[serde(default = "PrefetchConfig::default_max_mirrors")]
+    pub max_mirrors: usize,
+    #[serde(default)]
+    pub max_kib_per_sec: u64,
+    #[serde(default = "PrefetchConfig::default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+impl PrefetchConfig {
+    fn default_sync_db_dir() -> String {
+        "/var/lib/pacman/sync".to_string()
+    }
+
+    fn default_max_concurrency() -> usize {
+        4
+    }
+
+    fn default_max_mirrors() -> usize {
+        3
+    }
+
+    fn default_timeout_seconds() -> u64 {
+        300
+    }
+
+    /// Staging directory, defaulting to the user cache.
+    pub fn staging_path(&self) -> PathBuf {
+        if let Some(path) = &self.staging_dir {
+            return expand_home(path);
+        }
+        dirs::cache_dir()
+            .unwrap_or_else(|| expand_home("~/.cache"))
+            .join("syn-syu")
+            .join("pkg")
+    }
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            staging_dir: None,
+            sync_db_dir: Self::default_sync_db_dir(),
+            max_concurrency: Self::default_max_concurrency(),
+            max_mirrors: Self::default_max_mirrors(),
+            max_kib_per_sec: 0,
+            timeout_seconds: Self::default_timeout_seconds(),
+        }
+    }
+}
+
+/// Outbound HTTP settings shared by every Syn-Syu-Core client.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct NetworkConfig {
+    /// Proxy URL for all schemes; unset defers to the standard proxy env vars.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+    /// PEM bundle added to the system trust store.
+    #[serde(default)]
+    pub ca_bundle: Option<String>,
+    #[serde(default)]
+    pub ip_version: MirrorIpVersion,
+    #[serde(default)]
+    pub bind_interface: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+}
+
+impl NetworkConfig {
+    /// User agent sent with every request.
+    pub fn user_agent(&self) -> String {
+        self.user_agent
+            .clone()
+            .filter(|ua| !ua.trim().is_empty())
+            .unwrap_or_else(|| format!("Syn-Syu-Core/{} (linux)", env!("CARGO_PKG_VERSION")))
+    }
+}
+
+/// Audit event fan-out (`synsyu_core audit record`).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AuditConfig {
+    /// Backends that receive every event; empty disables auditing.
+    #[serde(default)]
+    pub backends: Vec<AuditBackendKind>,
+    /// Append-only JSON-lines file for the `file` backend.
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default = "AuditConfig::default_syslog_socket")]
+    pub syslog_socket: String,
+    #[serde(default = "AuditConfig::default_journald_socket")]
+    pub journald_socket: String,
+    /// `SYSLOG_IDENTIFIER` / syslog tag.
+    #[serde(default = "AuditConfig::default_identifier")]
+    pub identifier: String,
+    #[serde(default = "AuditConfig::default_facility")]
+    pub facility: String,
+}
+
+impl AuditConfig {
+    fn default_syslog_socket() -> String {
+        "/dev/log".to_string()
+    }
+
+    fn default_journald_socket() -> String {
+        "/run/systemd/journal/socket".to_string()
+    }
+
+    fn default_identifier() -> String {
+        "syn-syu".to_string()
+    }
+
+    fn default_facility() -> String {
+        "authpriv".to_string()
+    }
+
+    /// Audit file, defaulting next to the log directory.
+    pub fn file_path(&self) -> PathBuf {
+        match &self.file {
+            Some(path) => expand_home(path),
+            None => default_log_dir()
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default()
+                .join("audit.jsonl"),
+        }
+    }
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            backends: Vec::new(),
+            file: None,
+            syslog_socket: Self::default_syslog_socket(),
+            journald_socket: Self::default_journald_socket(),
+            identifier: Self::default_identifier(),
+            facility: Self::default_facility(),
+        }
+    }
+}
+
+/// Run journal (`synsyu_core runs`).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RunsConfig {
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// Newest runs kept when a new run starts; 0 keeps every run.
+    #[serde(default = "RunsConfig::default_keep")]
+    pub keep: usize,
+}
+
+impl RunsConfig {
+    fn default_keep() -> usize {
+        500
+    }
+
+    /// Journal directory, defaulting next to the log directory.
+    pub fn directory_path(&self) -> PathBuf {
+        match &self.directory {
+            Some(path) => expand_home(path),
+            None => default_log_dir()
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default()
+                .join("runs"),
+        }
+    }
+}
+
+impl Default for RunsConfig {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            keep: Self::default_keep(),
         }
     }
 }
 
+/// Detached ed25519 signatures for manifests and plans (`synsyu_core sign`,
+/// `synsyu_core verify`).
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct SigningConfig {
+    /// Sign every manifest and plan as it is written.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Refuse to act on manifests and plans without a valid signature from a
+    /// trusted key.
+    #[serde(default)]
+    pub require: bool,
+    /// Private key file; its public half sits next to it with a `.pub` extension.
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Public key files accepted by `verify`; defaults to the public half of `key`.
+    #[serde(default)]
+    pub trusted_keys: Vec<String>,
+}
+
+impl SigningConfig {
+    /// Private key path, defaulting to `~/.config/syn-syu/signing.key`.
+    pub fn key_path(&self) -> PathBuf {
+        match &self.key {
+            Some(path) => expand_home(path),
+            None => config_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("syn-syu")
+                .join("signing.key"),
+        }
+    }
+
+    pub fn public_key_path(&self) -> PathBuf {
+        self.key_path().with_extension("pub")
+    }
+
+    pub fn trusted_key_paths(&self) -> Vec<PathBuf> {
+        if self.trusted_keys.is_empty() {
+            vec![self.public_key_path()]
+        } else {
+            self.trusted_keys
+                .iter()
+                .map(|path| expand_home(path))
+                .collect()
+        }
+    }
+}
+
+/// Hide `user:password@` in URLs before they reach reports or logs.
+pub fn redact_url_credentials(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    match rest[..authority_end].rsplit_once('@') {
+        Some((_, host)) => format!("{scheme}://***@{host}{}", &rest[authority_end..]),
+        None => url.to_string(),
+    }
+}
+
 /// Serializable configuration summary.
 #[derive(Debug, Serialize, Clone)]
 pub struct ConfigReport {
@@ -716,6 +1271,10 @@ pub struct ConfigReport {
     pub mirrors_cache_path: Option<String>,
     pub mirrors_cache_ttl_hours: u64,
     pub mirrors_servers: Vec<String>,
+    pub mirrors_status_url: String,
+    pub mirrors_status_path: Option<String>,
+    pub mirrors_history_enabled: bool,
+    pub mirrors_history_window_days: u64,
     pub acquisition_aur_rpc_enabled: bool,
     pub acquisition_aur_rpc_max_retries: usize,
     pub acquisition_aur_rpc_retry_delay_seconds: u64,
@@ -725,11 +1284,16 @@ pub struct ConfigReport {
     pub space_min_free_bytes: u64,
     pub space_policy: String,
     pub batch_size: usize,
+    pub privilege_tool: String,
     pub applications_flatpak: bool,
     pub applications_fwupd: bool,
     pub log_level: Option<String>,
     pub log_retention_days: Option<u64>,
     pub log_retention_megabytes: Option<u64>,
+    pub log_format: String,
+    pub log_stderr_level: Option<String>,
+    pub log_code_levels: BTreeMap<String, String>,
+    pub log_rotate_megabytes: Option<u64>,
     pub snapshots_enabled: bool,
     pub snapshot_pre_command: Option<String>,
     pub snapshot_post_command: Option<String>,
@@ -739,9 +1303,40 @@ pub struct ConfigReport {
     pub clean_keep_versions: u64,
     pub clean_remove_orphans: bool,
     pub clean_check_pacnew: bool,
+    pub prefetch_staging_dir: PathBuf,
+    pub prefetch_max_concurrency: usize,
+    pub prefetch_max_kib_per_sec: u64,
+    pub network_proxy: Option<String>,
+    pub network_no_proxy: Vec<String>,
+    pub network_ca_bundle: Option<String>,
+    pub network_ip_version: String,
+    pub network_bind_interface: Option<String>,
+    pub network_user_agent: String,
+    pub audit_backends: Vec<String>,
+    pub audit_file: PathBuf,
+    pub runs_directory: PathBuf,
+    pub runs_keep: usize,
+    pub signing_enabled: bool,
+    pub signing_require: bool,
+    pub signing_key: PathBuf,
+    pub signing_trusted_keys: Vec<PathBuf>,
+    pub profile: Option<String>,
+    /// Every setting after layering and the profile overlay.
+    pub effective: SynsyuConfig,
 }
 
-fn default_config_path() -> Option<PathBuf> {
+/// Expand a leading `~/` against `$HOME`.
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => {
+            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".into())).join(rest)
+        }
+        None => PathBuf::from(path),
+    }
+}
+
+/// `~/.config/syn-syu/config.toml`, used when no `--config` is given.
+pub fn default_config_path() -> Option<PathBuf> {
     config_dir().map(|dir| dir.join("syn-syu").join("config.toml"))
 }
 
@@ -752,7 +1347,7 @@ fn default_log_dir() -> PathBuf {
         .join("logs")
 }
 
-fn ensure_secure_permissions(path: &Path) -> Result<()> {
+pub(crate) fn ensure_secure_permissions(path: &Path) -> Result<()> {
     let metadata = fs::metadata(path).map_err(|err| {
         SynsyuError::Filesystem(format!(
             "Failed to inspect permissions for {}: {err}",
@@ -789,3 +1384,23 @@ fn ensure_secure_permissions(path: &Path) -> Result<()> {
     })?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn home_relative_paths_are_expanded() {
+        let config: SynsyuConfig = toml::from_str(
+            "[core]\nmanifest_path = \"~/state/manifest.json\"\n[logging]\ndirectory = \"~/logs\"\n",
+        )
+        .unwrap();
+        let home = PathBuf::from(std::env::var("HOME").unwrap());
+        assert_eq!(config.log_dir(), home.join("logs"));
+        assert_eq!(config.manifest_path(), home.join("state/manifest.json"));
+        assert_eq!(
+            expand_home("/var/log/syn-syu"),
+            PathBuf::from("/var/log/syn-syu")
+        );
+    }
+}
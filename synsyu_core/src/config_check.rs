@@ -0,0 +1,418 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::config_check
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Validate layered configuration for `synsyu_core config
+    --check`: unknown keys (with the nearest known key),
+    values out of range, and settings that contradict each
+    other.
+
+  Security / Safety Notes:
+    Read-only; every layer is checked before anything acts
+    on it, so a typo cannot silently fall back to a default.
+============================================================*/
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::SynsyuConfig;
+use crate::config_layers::{deep_merge, ConfigSource, RawLayers};
+use crate::error::{Result, SynsyuError};
+use crate::mirrorlist::pacman_protocol;
+
+/// Keys serde accepts under another name: (accepted, canonical).
+const KEY_ALIASES: &[(&str, &str)] = &[("space.mode", "space.policy")];
+
+/// One problem found in the configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigIssue {
+    pub key: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<ConfigSource>,
+}
+
+/// Outcome of `config --check`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckReport {
+    pub ok: bool,
+    pub issues: Vec<ConfigIssue>,
+}
+
+/// Check every key set by any layer, then the resolved values, then each
+/// profile applied on top of the base layers.
+pub fn check_layers(raw: RawLayers) -> CheckReport {
+    let mut issues = Vec::new();
+    let sources = raw.sources.clone();
+    let source_of = |key: &str| sources.get(key).cloned();
+    let profiles = raw.profiles.clone();
+    let base = raw.merged.clone();
+
+    let known = known_keys(None);
+    let mut set = BTreeMap::new();
+    collect_leaves(
+        "",
+        &toml_to_json(&toml::Value::Table(base.clone())),
+        &mut set,
+    );
+    let resolved = raw.resolve();
+    let known = match &resolved {
+        Ok(layered) => {
+            let mut known = known;
+            known.extend(known_keys(Some(&layered.config)));
+            known
+        }
+        Err(_) => known,
+    };
+    // An empty file flattens to a single "" leaf.
+    for key in set.keys().filter(|key| !key.is_empty()) {
+        if is_known(key, &known) {
+            continue;
+        }
+        issues.push(ConfigIssue {
+            key: key.clone(),
+            message: "unknown key; it has no effect".to_string(),
+            suggestion: nearest_key(key, &known),
+            source: source_of(key),
+        });
+    }
+
+    let base_issues = match resolved {
+        Ok(layered) => {
+            let found = check_values(&layered.config);
+            for (key, message) in found.iter().cloned() {
+                let source = source_of(&key).or_else(|| {
+                    KEY_ALIASES
+                        .iter()
+                        .find(|(_, canonical)| *canonical == key)
+                        .and_then(|(alias, _)| source_of(alias))
+                });
+                issues.push(ConfigIssue {
+                    key,
+                    message,
+                    suggestion: None,
+                    source,
+                });
+            }
+            found
+        }
+        Err(err) => {
+            issues.push(ConfigIssue {
+                key: String::new(),
+                message: err.to_string(),
+                suggestion: None,
+                source: None,
+            });
+            Vec::new()
+        }
+    };
+
+    for (name, overlay) in &profiles {
+        let prefix = format!("profiles.{name}");
+        let mut keys = BTreeMap::new();
+        collect_leaves("", &toml_to_json(overlay), &mut keys);
+        for key in keys.keys().filter(|key| !is_known(key, &known)) {
+            let full = format!("{prefix}.{key}");
+            issues.push(ConfigIssue {
+                message: "unknown key; it has no effect".to_string(),
+                suggestion: nearest_key(key, &known).map(|nearest| format!("{prefix}.{nearest}")),
+                source: source_of(&full),
+                key: full,
+            });
+        }
+        let mut merged = base.clone();
+        deep_merge(&mut merged, overlay.as_table().cloned().unwrap_or_default());
+        match toml::Value::Table(merged).try_into::<SynsyuConfig>() {
+            Ok(config) => {
+                for (key, message) in check_values(&config) {
+                    if base_issues.contains(&(key.clone(), message.clone())) {
+                        continue;
+                    }
+                    let full = format!("{prefix}.{key}");
+                    issues.push(ConfigIssue {
+                        source: source_of(&full),
+                        key: full,
+                        message,
+                        suggestion: None,
+                    });
+                }
+            }
+            Err(err) => issues.push(ConfigIssue {
+                key: prefix.clone(),
+                message: format!("profile does not apply cleanly: {err}"),
+                suggestion: None,
+                source: None,
+            }),
+        }
+    }
+
+    CheckReport {
+        ok: issues.is_empty(),
+        issues,
+    }
+}
+
+/// Fail unless `key` names a setting, suggesting the nearest one.
+pub fn require_known_key(key: &str) -> Result<()> {
+    let known = known_keys(None);
+    if is_known(key, &known) {
+        return Ok(());
+    }
+    let hint = nearest_key(key, &known)
+        .map(|nearest| format!("; did you mean `{nearest}`?"))
+        .unwrap_or_default();
+    Err(SynsyuError::Config(format!(
+        "Unknown configuration key `{key}`{hint}"
+    )))
+}
+
+/// Other spellings serde accepts for `key`, canonical name first.
+pub fn key_spellings(key: &str) -> Vec<&str> {
+    let mut spellings = vec![key];
+    for (alias, canonical) in KEY_ALIASES {
+        if key == *alias {
+            spellings.insert(0, canonical);
+        } else if key == *canonical {
+            spellings.push(alias);
+        }
+    }
+    spellings
+}
+
+/// Range and cross-field checks on a resolved configuration, as
+/// (key, message) pairs.
+pub fn check_values(config: &SynsyuConfig) -> Vec<(String, String)> {
+    let mut issues = Vec::new();
+    let mut require = |ok: bool, key: &str, message: &str| {
+        if !ok {
+            issues.push((key.to_string(), message.to_string()));
+        }
+    };
+
+    require(
+        config.aur.timeout > 0,
+        "aur.timeout",
+        "must be greater than 0",
+    );
+    require(
+        config.aur.max_args >= 1,
+        "aur.max_args",
+        "must be at least 1",
+    );
+    require(
+        config.core.batch_size >= 1,
+        "core.batch_size",
+        "must be at least 1",
+    );
+    require(
+        config.mirrors.probe_timeout_seconds > 0,
+        "mirrors.probe_timeout_seconds",
+        "must be greater than 0",
+    );
+    require(
+        config.mirrors.max_candidates >= 1,
+        "mirrors.max_candidates",
+        "must be at least 1",
+    );
+    require(
+        config.mirrors.generate.timeout_seconds > 0,
+        "mirrors.generate.timeout_seconds",
+        "must be greater than 0",
+    );
+    require(
+        config
+            .mirrors
+            .generate
+            .protocols
+            .iter()
+            .all(|proto| pacman_protocol(proto)),
+        "mirrors.generate.protocols",
+        "pacman can only use https, http or ftp mirrors",
+    );
+    require(
+        config.prefetch.timeout_seconds > 0,
+        "prefetch.timeout_seconds",
+        "must be greater than 0",
+    );
+    require(
+        config.space.min_free_gb >= 0.0,
+        "space.min_free_gb",
+        "must not be negative",
+    );
+
+    let has_command =
+        |command: &Option<String>| command.as_deref().is_some_and(|c| !c.trim().is_empty());
+    let snapshots = &config.snapshots;
+    require(
+        !snapshots.enabled
+            || has_command(&snapshots.pre_command)
+            || has_command(&snapshots.post_command),
+        "snapshots.enabled",
+        "snapshots are enabled but neither pre_command nor post_command is set",
+    );
+    require(
+        !snapshots.require_success || has_command(&snapshots.pre_command),
+        "snapshots.require_success",
+        "require_success needs a pre_command to succeed",
+    );
+    issues
+}
+
+/// Every key the configuration understands, mapped to whether it is a table
+/// of arbitrary keys. Defaults serialize unset options as null, so optional
+/// keys are included; a resolved configuration adds the entries of open
+/// tables such as `logging.codes`.
+fn known_keys(config: Option<&SynsyuConfig>) -> BTreeMap<String, bool> {
+    let default = SynsyuConfig::default();
+    let value = serde_json::to_value(config.unwrap_or(&default)).unwrap_or(Value::Null);
+    let mut leaves = BTreeMap::new();
+    collect_leaves("", &value, &mut leaves);
+    let mut keys: BTreeMap<String, bool> = leaves
+        .into_iter()
+        .map(|(key, value)| (key, value.is_object()))
+        .collect();
+    keys.extend(
+        KEY_ALIASES
+            .iter()
+            .map(|(alias, _)| (alias.to_string(), false)),
+    );
+    keys
+}
+
+/// A key is known when it, or an open table above it, is known.
+fn is_known(key: &str, known: &BTreeMap<String, bool>) -> bool {
+    if known.contains_key(key) {
+        return true;
+    }
+    let mut prefix = key;
+    while let Some((parent, _)) = prefix.rsplit_once('.') {
+        if known.get(parent) == Some(&true) {
+            return true;
+        }
+        prefix = parent;
+    }
+    false
+}
+
+/// The closest known key, when it is close enough to be a likely typo.
+fn nearest_key(key: &str, known: &BTreeMap<String, bool>) -> Option<String> {
+    known
+        .keys()
+        .map(|candidate| (strsim::levenshtein(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= (key.len() / 4).max(2))
+        .min()
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// Flatten JSON into dotted keys; arrays, scalars, and empty objects are
+/// leaves.
+fn collect_leaves(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                collect_leaves(&path, child, out);
+            }
+        }
+        leaf => {
+            out.insert(prefix.to_string(), leaf.clone());
+        }
+    }
+}
+
+fn toml_to_json(value: &toml::Value) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_layers::{collect_layers, UserConfig};
+    use crate::temp_dir::TempDir;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn check(contents: &str) -> CheckReport {
+        let dir = TempDir::new("synsyu_check_").unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, contents).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        let raw = collect_layers(None, &UserConfig::Explicit(path), None, Vec::new(), &[]).unwrap();
+        check_layers(raw)
+    }
+
+    #[test]
+    fn unknown_keys_get_nearest_suggestions() {
+        let report = check(
+            "[mirrors]\nmax_candidate = 4\n[space]\nmode = \"enforce\"\n\
+             [logging]\nlevel = \"debug\"\n[logging.codes]\nE305 = \"debug\"\n\
+             [aur]\nmax_parallel_requests = 4\n",
+        );
+        let keys: Vec<&str> = report.issues.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec!["aur.max_parallel_requests", "mirrors.max_candidate"]
+        );
+        assert_eq!(report.issues[0].suggestion, None);
+        assert_eq!(
+            report.issues[1].suggestion.as_deref(),
+            Some("mirrors.max_candidates")
+        );
+        assert!(!report.ok);
+    }
+
+    #[test]
+    fn ranges_and_cross_field_rules_are_enforced() {
+        let report = check(
+            "[aur]\ntimeout = 0\n[mirrors]\nmax_candidates = 0\n\
+             [snapshots]\nenabled = true\nrequire_success = true\n",
+        );
+        let keys: Vec<&str> = report.issues.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "aur.timeout",
+                "mirrors.max_candidates",
+                "snapshots.enabled",
+                "snapshots.require_success"
+            ]
+        );
+
+        let invalid = check("[space]\nmode = \"sometimes\"\n");
+        assert!(!invalid.ok);
+        assert!(invalid.issues[0].message.contains("sometimes"));
+
+        assert!(check("[space]\nmode = \"enforce\"\n").ok);
+    }
+
+    #[test]
+    fn profiles_are_checked_against_the_base() {
+        let report = check(
+            "[core]\nbatch_size = 4\n[profiles.laptop.core]\nbatch_sise = 2\n\
+             [profiles.server.mirrors]\nmax_candidates = 0\n",
+        );
+        let keys: Vec<&str> = report.issues.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "profiles.laptop.core.batch_sise",
+                "profiles.server.mirrors.max_candidates"
+            ]
+        );
+        assert_eq!(
+            report.issues[0].suggestion.as_deref(),
+            Some("profiles.laptop.core.batch_size")
+        );
+        assert!(check("[profiles.laptop.space]\nmode = \"enforce\"\n").ok);
+    }
+}
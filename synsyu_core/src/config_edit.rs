@@ -0,0 +1,138 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::config_edit
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Read and change single configuration keys for
+    `synsyu_core config get` / `config set`, so scripts and
+    the helper selector never rewrite the whole file.
+
+  Security / Safety Notes:
+    A new value is written only after the edited file still
+    deserializes as SynsyuConfig and passes the same range
+    and cross-field rules as `config --check`.
+============================================================*/
+
+use toml_edit::{DocumentMut, Item};
+
+use crate::config::SynsyuConfig;
+use crate::config_check::{check_values, key_spellings, require_known_key};
+use crate::config_layers::parse_value;
+use crate::config_migrate::table_at;
+use crate::error::{Result, SynsyuError};
+
+/// The effective value at a dotted key (a table for section keys), or `None`
+/// for a known key that is unset.
+pub fn get_value(config: &SynsyuConfig, key: &str) -> Result<Option<toml::Value>> {
+    let effective = toml::Value::try_from(config).map_err(|err| {
+        SynsyuError::Serialization(format!("Failed to render configuration: {err}"))
+    })?;
+    for spelling in key_spellings(key) {
+        let found = spelling
+            .split('.')
+            .try_fold(&effective, |current, part| current.get(part));
+        if let Some(found) = found {
+            return Ok(Some(found.clone()));
+        }
+    }
+    require_known_key(key)?;
+    Ok(None)
+}
+
+/// `contents` with `key` set to `raw` (a TOML literal, or text), keeping the
+/// rest of the file and any comment on the replaced value.
+pub fn set_value(contents: &str, key: &str, raw: &str) -> Result<String> {
+    require_known_key(key)?;
+    let parsed = parse_value(raw);
+    if parsed.is_table() {
+        return Err(SynsyuError::Config(format!(
+            "Set the keys inside `{key}` one at a time"
+        )));
+    }
+    let mut new_value: toml_edit::Value = parsed
+        .to_string()
+        .parse()
+        .map_err(|err| SynsyuError::Config(format!("Invalid value for {key}: {err}")))?;
+
+    let mut doc: DocumentMut = contents
+        .parse()
+        .map_err(|err| SynsyuError::Config(format!("Failed to parse configuration: {err}")))?;
+    // Keep whichever spelling the file already uses (`space.mode` vs `policy`).
+    let spelling = key_spellings(key)
+        .into_iter()
+        .find(|spelling| {
+            spelling
+                .split('.')
+                .try_fold(doc.as_item(), |item, part| item.get(part))
+                .is_some()
+        })
+        .unwrap_or(key);
+    let mut parts: Vec<&str> = spelling.split('.').collect();
+    let name = parts.pop().unwrap_or_default();
+    let table = table_at(&mut doc, &parts)?;
+    match table.get_mut(name) {
+        Some(Item::Value(existing)) => {
+            *new_value.decor_mut() = existing.decor().clone();
+            *existing = new_value;
+        }
+        _ => {
+            table.insert(name, Item::Value(new_value));
+        }
+    }
+    let updated = doc.to_string();
+
+    let refuse =
+        |reason: String| SynsyuError::Config(format!("Refusing to set {key} = {raw}: {reason}"));
+    let config: SynsyuConfig =
+        toml::from_str(&updated).map_err(|err| refuse(err.message().to_string()))?;
+    let existing_issues = toml::from_str::<SynsyuConfig>(contents)
+        .map(|config| check_values(&config))
+        .unwrap_or_default();
+    if let Some((issue_key, message)) = check_values(&config)
+        .into_iter()
+        .find(|issue| !existing_issues.contains(issue))
+    {
+        return Err(refuse(format!("{issue_key} {message}")));
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_keeps_formatting_and_validates() {
+        let original =
+            "# tuned for the laptop\n[core]\nbatch_size = 4 # small\n\n[space]\nmode = \"warn\"\n";
+        let updated = set_value(original, "core.batch_size", "8").unwrap();
+        assert_eq!(
+            updated,
+            "# tuned for the laptop\n[core]\nbatch_size = 8 # small\n\n[space]\nmode = \"warn\"\n"
+        );
+
+        let updated = set_value(&updated, "space.policy", "enforce").unwrap();
+        assert!(updated.contains("mode = \"enforce\""));
+        let updated = set_value(&updated, "acquisition.aur_rpc.max_retries", "5").unwrap();
+        assert!(updated.ends_with("[acquisition.aur_rpc]\nmax_retries = 5\n"));
+
+        assert!(set_value(original, "core.batch_size", "many").is_err());
+        assert!(set_value(original, "core.batch_size", "0").is_err());
+        assert!(set_value(original, "space.mode", "sometimes").is_err());
+        let unknown = set_value(original, "core.batch_sise", "8").unwrap_err();
+        assert!(unknown.to_string().contains("core.batch_size"));
+
+        let config: SynsyuConfig = toml::from_str(&updated).unwrap();
+        assert_eq!(
+            get_value(&config, "core.batch_size").unwrap(),
+            Some(toml::Value::Integer(8))
+        );
+        assert_eq!(
+            get_value(&config, "space.mode").unwrap(),
+            Some(toml::Value::String("enforce".into()))
+        );
+        assert_eq!(get_value(&config, "network.proxy").unwrap(), None);
+        assert!(get_value(&config, "logging.levle").is_err());
+    }
+}
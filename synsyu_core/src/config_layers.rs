@@ -0,0 +1,615 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::config_layers
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Load configuration in layers - built-in defaults, the
+    system file (/etc/syn-syu/config.toml), the user file,
+    the selected `[profiles.<name>]` overlay, `SYNSYU_*`
+    environment variables, and `--set` overrides - merged key
+    by key, remembering which layer set each value for
+    `synsyu_core config --explain`.
+
+  Security / Safety Notes:
+    Both files pass the same permission checks as before.
+    Environment and CLI values are parsed as TOML literals
+    and fall back to plain strings; they never name files to
+    read.
+============================================================*/
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use toml::{Table, Value};
+
+use crate::config::{default_config_path, ensure_secure_permissions, SynsyuConfig};
+use crate::config_check::key_spellings;
+use crate::error::{Result, SynsyuError};
+
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/syn-syu/config.toml";
+/// Replaces the system file path; set it empty to skip the system layer.
+pub const SYSTEM_CONFIG_ENV: &str = "SYNSYU_SYSTEM_CONFIG";
+/// Selects a `[profiles.<name>]` overlay; `--profile` sets it for the process.
+pub const PROFILE_ENV: &str = "SYNSYU_PROFILE";
+const PROFILES_KEY: &str = "profiles";
+const ENV_PREFIX: &str = "SYNSYU_";
+/// Separates nesting levels in variable names: `SYNSYU_SPACE__MIN_FREE_GB`.
+const ENV_NESTING: &str = "__";
+
+/// Where a configuration value came from, lowest precedence first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLayer {
+    Default,
+    System,
+    User,
+    Profile,
+    Env,
+    Cli,
+}
+
+impl fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            ConfigLayer::Default => "default",
+            ConfigLayer::System => "system",
+            ConfigLayer::User => "user",
+            ConfigLayer::Profile => "profile",
+            ConfigLayer::Env => "env",
+            ConfigLayer::Cli => "cli",
+        })
+    }
+}
+
+/// The layer that set one key, and the file, variable, or flag within it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigSource {
+    pub layer: ConfigLayer,
+    pub origin: String,
+}
+
+/// Which user file to read.
+#[derive(Debug, Clone)]
+pub enum UserConfig {
+    /// `--config PATH`; it must exist.
+    Explicit(PathBuf),
+    /// `~/.config/syn-syu/config.toml` when present.
+    Default(Option<PathBuf>),
+}
+
+/// Effective configuration plus the provenance of every key set above the
+/// built-in defaults.
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub config: SynsyuConfig,
+    /// Every layer merged, without built-in defaults.
+    pub merged: Table,
+    pub sources: BTreeMap<String, ConfigSource>,
+}
+
+/// One `config --explain` row.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainEntry {
+    pub key: String,
+    pub value: String,
+    pub layer: ConfigLayer,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// Set by a layer but not a recognised key, so it has no effect.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub ignored: bool,
+}
+
+/// Layers read and merged but not yet checked against the configuration
+/// types, so `config --check` can report on all of them at once.
+#[derive(Debug, Clone)]
+pub struct RawLayers {
+    pub merged: Table,
+    pub sources: BTreeMap<String, ConfigSource>,
+    /// Every `[profiles.<name>]` defined by the files, keyed by name.
+    pub profiles: Table,
+    pub profile: Option<String>,
+    files: Vec<(PathBuf, String)>,
+}
+
+/// The profile named by `$SYNSYU_PROFILE`, if any.
+pub fn active_profile() -> Option<String> {
+    std::env::var(PROFILE_ENV)
+        .ok()
+        .filter(|profile| !profile.trim().is_empty())
+}
+
+/// Load every layer from the real system: the system file (or
+/// `$SYNSYU_SYSTEM_CONFIG`), the user file, the process environment, and
+/// `overrides` in `KEY=VALUE` form.
+pub fn load_layered(user: Option<&Path>, overrides: &[String]) -> Result<LayeredConfig> {
+    collect_layered(user, overrides)?.resolve()
+}
+
+/// Read and merge every layer from the real system without resolving it.
+pub fn collect_layered(user: Option<&Path>, overrides: &[String]) -> Result<RawLayers> {
+    let system = match std::env::var(SYSTEM_CONFIG_ENV) {
+        Ok(path) if path.is_empty() => None,
+        Ok(path) => Some(PathBuf::from(path)),
+        Err(_) => Some(PathBuf::from(SYSTEM_CONFIG_PATH)),
+    };
+    let user = match user {
+        Some(path) => UserConfig::Explicit(path.to_path_buf()),
+        None => UserConfig::Default(default_config_path()),
+    };
+    collect_layers(
+        system.as_deref(),
+        &user,
+        active_profile().as_deref(),
+        std::env::vars(),
+        overrides,
+    )
+}
+
+/// Merge the given layers key by key; later layers win.
+pub fn collect_layers(
+    system: Option<&Path>,
+    user: &UserConfig,
+    profile: Option<&str>,
+    env: impl IntoIterator<Item = (String, String)>,
+    overrides: &[String],
+) -> Result<RawLayers> {
+    let mut merged = Table::new();
+    let mut sources = BTreeMap::new();
+    let mut files = Vec::new();
+
+    let user_path = match user {
+        UserConfig::Explicit(path) if !path.exists() => {
+            return Err(SynsyuError::Config(format!(
+                "Configuration file {} does not exist",
+                path.display()
+            )));
+        }
+        UserConfig::Explicit(path) => Some(path.as_path()),
+        UserConfig::Default(path) => path.as_deref().filter(|path| path.exists()),
+    };
+    let file_layers = [
+        (system.filter(|path| path.exists()), ConfigLayer::System),
+        (user_path, ConfigLayer::User),
+    ];
+    for (path, kind) in file_layers {
+        let Some(path) = path else { continue };
+        let (layer, contents) = read_layer(path)?;
+        let origin = path.display().to_string();
+        apply_layer(&mut merged, &mut sources, layer, kind, &|_| origin.clone());
+        files.push((path.to_path_buf(), contents));
+    }
+
+    let profiles = match merged.remove(PROFILES_KEY) {
+        None => Table::new(),
+        Some(Value::Table(profiles)) if profiles.values().all(Value::is_table) => profiles,
+        Some(_) => {
+            return Err(SynsyuError::Config(
+                "`profiles` must only hold [profiles.<name>] tables".into(),
+            ));
+        }
+    };
+    if let Some(name) = profile {
+        let overlay = profiles
+            .get(name)
+            .and_then(Value::as_table)
+            .ok_or_else(|| {
+                let defined: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                SynsyuError::Config(format!(
+                    "Profile `{name}` is not defined (available: {})",
+                    if defined.is_empty() {
+                        "none".to_string()
+                    } else {
+                        defined.join(", ")
+                    }
+                ))
+            })?;
+        let file_of = |key: &str| {
+            sources
+                .get(&format!("{PROFILES_KEY}.{name}.{key}"))
+                .map(|source| format!(" in {}", source.origin))
+                .unwrap_or_default()
+        };
+        let origins: BTreeMap<String, String> = {
+            let mut leaves = BTreeMap::new();
+            collect_leaves("", &Value::Table(overlay.clone()), &mut leaves);
+            leaves
+                .into_keys()
+                .map(|key| {
+                    let origin = format!("[{PROFILES_KEY}.{name}]{}", file_of(&key));
+                    (key, origin)
+                })
+                .collect()
+        };
+        apply_layer(
+            &mut merged,
+            &mut sources,
+            overlay.clone(),
+            ConfigLayer::Profile,
+            &|key| origins[key].clone(),
+        );
+    }
+
+    let mut env: Vec<(String, String)> = env
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let key = name.strip_prefix(ENV_PREFIX)?;
+            if !key.contains(ENV_NESTING) {
+                return None;
+            }
+            let dotted = key.to_ascii_lowercase().replace(ENV_NESTING, ".");
+            Some((name, format!("{dotted}={value}")))
+        })
+        .collect();
+    env.sort();
+    for (name, assignment) in env {
+        apply_assignment(
+            &mut merged,
+            &mut sources,
+            &assignment,
+            ConfigLayer::Env,
+            name,
+        )?;
+    }
+    for assignment in overrides {
+        let origin = format!("--set {assignment}");
+        apply_assignment(
+            &mut merged,
+            &mut sources,
+            assignment,
+            ConfigLayer::Cli,
+            origin,
+        )?;
+    }
+
+    Ok(RawLayers {
+        merged,
+        sources,
+        profiles,
+        profile: profile.map(str::to_string),
+        files,
+    })
+}
+
+impl RawLayers {
+    /// Deserialize the merged layers into the effective configuration.
+    pub fn resolve(self) -> Result<LayeredConfig> {
+        let mut config = match Value::Table(self.merged.clone()).try_into::<SynsyuConfig>() {
+            Ok(config) => config,
+            Err(err) => {
+                // Name the file at fault when one fails on its own.
+                for (path, contents) in &self.files {
+                    if let Err(err) = toml::from_str::<SynsyuConfig>(contents) {
+                        return Err(SynsyuError::Config(format!(
+                            "Failed to parse configuration {}: {err}",
+                            path.display()
+                        )));
+                    }
+                }
+                return Err(SynsyuError::Config(format!(
+                    "Invalid configuration after applying environment and CLI overrides: {err}"
+                )));
+            }
+        };
+        config.profile = self.profile;
+        Ok(LayeredConfig {
+            config,
+            merged: self.merged,
+            sources: self.sources,
+        })
+    }
+}
+
+impl LayeredConfig {
+    /// Every effective value with the layer that set it, sorted by key.
+    /// Keys a layer set that the configuration does not know are listed as
+    /// ignored.
+    pub fn explain(&self) -> Result<Vec<ExplainEntry>> {
+        let effective = Value::try_from(&self.config).map_err(|err| {
+            SynsyuError::Serialization(format!("Failed to render configuration: {err}"))
+        })?;
+        let mut leaves = BTreeMap::new();
+        collect_leaves("", &effective, &mut leaves);
+        let mut entries: Vec<ExplainEntry> = leaves
+            .iter()
+            .map(|(key, value)| {
+                let source = key_spellings(key)
+                    .into_iter()
+                    .find_map(|spelling| self.sources.get(spelling));
+                ExplainEntry {
+                    key: key.clone(),
+                    value: value.to_string(),
+                    layer: source.map_or(ConfigLayer::Default, |source| source.layer),
+                    origin: source.map(|source| source.origin.clone()),
+                    ignored: false,
+                }
+            })
+            .collect();
+        let mut set = BTreeMap::new();
+        collect_leaves("", &Value::Table(self.merged.clone()), &mut set);
+        for (key, value) in set {
+            let canonical = key_spellings(&key)[0];
+            if leaves.contains_key(canonical) {
+                continue;
+            }
+            let source = &self.sources[&key];
+            entries.push(ExplainEntry {
+                key,
+                value: value.to_string(),
+                layer: source.layer,
+                origin: Some(source.origin.clone()),
+                ignored: true,
+            });
+        }
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(entries)
+    }
+}
+
+fn read_layer(path: &Path) -> Result<(Table, String)> {
+    ensure_secure_permissions(path)?;
+    let contents = fs::read_to_string(path).map_err(|err| {
+        SynsyuError::Filesystem(format!(
+            "Failed to read configuration {}: {err}",
+            path.display()
+        ))
+    })?;
+    let table = toml::from_str::<Table>(&contents).map_err(|err| {
+        SynsyuError::Config(format!(
+            "Failed to parse configuration {}: {err}",
+            path.display()
+        ))
+    })?;
+    Ok((table, contents))
+}
+
+fn apply_layer(
+    merged: &mut Table,
+    sources: &mut BTreeMap<String, ConfigSource>,
+    layer: Table,
+    kind: ConfigLayer,
+    origin: &dyn Fn(&str) -> String,
+) {
+    let value = Value::Table(layer);
+    let mut leaves = BTreeMap::new();
+    collect_leaves("", &value, &mut leaves);
+    for key in leaves.into_keys() {
+        let origin = origin(&key);
+        sources.insert(
+            key,
+            ConfigSource {
+                layer: kind,
+                origin,
+            },
+        );
+    }
+    if let Value::Table(layer) = value {
+        deep_merge(merged, layer);
+    }
+}
+
+fn apply_assignment(
+    merged: &mut Table,
+    sources: &mut BTreeMap<String, ConfigSource>,
+    assignment: &str,
+    layer: ConfigLayer,
+    origin: String,
+) -> Result<()> {
+    let (key, raw) = assignment
+        .split_once('=')
+        .ok_or_else(|| SynsyuError::Config(format!("Override '{assignment}' must be KEY=VALUE")))?;
+    let key = key.trim();
+    let mut overlay = Table::new();
+    set_dotted(&mut overlay, key, parse_value(raw))?;
+    let mut leaves = BTreeMap::new();
+    collect_leaves("", &Value::Table(overlay.clone()), &mut leaves);
+    for leaf in leaves.into_keys() {
+        sources.insert(
+            leaf,
+            ConfigSource {
+                layer,
+                origin: origin.clone(),
+            },
+        );
+    }
+    deep_merge(merged, overlay);
+    Ok(())
+}
+
+/// A TOML literal (`5`, `true`, `["a", "b"]`, `"text"`), or the raw text as
+/// a string when it is not one.
+pub fn parse_value(raw: &str) -> Value {
+    toml::from_str::<Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// Insert `value` at a dotted key, creating intermediate tables.
+pub fn set_dotted(table: &mut Table, key: &str, value: Value) -> Result<()> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|part| part.is_empty()) {
+        return Err(SynsyuError::Config(format!(
+            "Invalid configuration key '{key}'"
+        )));
+    }
+    let (last, parents) = parts.split_last().expect("split yields one part");
+    let mut current = table;
+    for part in parents {
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+        current = entry.as_table_mut().ok_or_else(|| {
+            SynsyuError::Config(format!(
+                "Configuration key '{part}' in '{key}' is not a table"
+            ))
+        })?;
+    }
+    current.insert(last.to_string(), value);
+    Ok(())
+}
+
+/// Merge `overlay` into `base`: tables merge recursively, anything else
+/// (including arrays) replaces the base value.
+pub fn deep_merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(existing)), Value::Table(incoming)) => {
+                deep_merge(existing, incoming)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Flatten a value into dotted keys; arrays are leaves.
+fn collect_leaves(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Table(table) => {
+            for (key, child) in table {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                collect_leaves(&path, child, out);
+            }
+        }
+        leaf => {
+            out.insert(prefix.to_string(), leaf.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn write_config(path: &Path, contents: &str) {
+        fs::write(path, contents).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600)).unwrap();
+    }
+
+    #[test]
+    fn later_layers_override_single_keys() {
+        let tmp = TempDir::new("synsyu_layers_").unwrap();
+        let dir = tmp.path();
+        let (system, user) = (dir.join("system.toml"), dir.join("user.toml"));
+        write_config(
+            &system,
+            "[space]\nmin_free_gb = 8.0\n[core]\nbatch_size = 4\n[helpers]\npriority = [\"paru\"]\n",
+        );
+        write_config(&user, "[space]\nmin_free_gb = 2.5\n");
+
+        let env = vec![
+            ("SYNSYU_CORE__BATCH_SIZE".to_string(), "12".to_string()),
+            ("SYNSYU_RUN_ID".to_string(), "not-a-key".to_string()),
+        ];
+        let layered = collect_layers(
+            Some(&system),
+            &UserConfig::Explicit(user.clone()),
+            None,
+            env,
+            &["logging.level=debug".to_string()],
+        )
+        .and_then(RawLayers::resolve)
+        .unwrap();
+
+        assert_eq!(layered.config.space.min_free_gb, 2.5);
+        assert_eq!(layered.config.core.batch_size, 12);
+        assert_eq!(layered.config.helpers.priority, vec!["paru".to_string()]);
+        assert_eq!(layered.config.logging.level.as_deref(), Some("debug"));
+
+        let explain = layered.explain().unwrap();
+        let layer_of = |key: &str| {
+            explain
+                .iter()
+                .find(|entry| entry.key == key)
+                .map(|entry| entry.layer)
+        };
+        assert_eq!(layer_of("space.min_free_gb"), Some(ConfigLayer::User));
+        assert_eq!(layer_of("helpers.priority"), Some(ConfigLayer::System));
+        assert_eq!(layer_of("core.batch_size"), Some(ConfigLayer::Env));
+        assert_eq!(layer_of("logging.level"), Some(ConfigLayer::Cli));
+        assert_eq!(layer_of("clean.keep_versions"), Some(ConfigLayer::Default));
+
+        let missing = collect_layers(
+            None,
+            &UserConfig::Explicit(dir.join("absent.toml")),
+            None,
+            Vec::new(),
+            &[],
+        );
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn profiles_overlay_the_files_below_env() {
+        let dir = TempDir::new("synsyu_profiles_").unwrap();
+        let user = dir.path().join("user.toml");
+        write_config(
+            &user,
+            "[core]\nbatch_size = 4\n[applications]\nflatpak = true\n\
+             [profiles.server.applications]\nflatpak = false\n\
+             [profiles.laptop.space]\nmode = \"enforce\"\n\
+             [profiles.laptop.core]\nbatch_size = 6\n",
+        );
+        let load = |profile: Option<&str>, env: Vec<(String, String)>| {
+            collect_layers(None, &UserConfig::Explicit(user.clone()), profile, env, &[])
+                .and_then(RawLayers::resolve)
+        };
+
+        let base = load(None, Vec::new()).unwrap();
+        assert!(base.config.applications.flatpak);
+        assert_eq!(base.config.profile, None);
+        assert!(!base.merged.contains_key(PROFILES_KEY));
+
+        let env = vec![("SYNSYU_CORE__BATCH_SIZE".to_string(), "12".to_string())];
+        let laptop = load(Some("laptop"), env).unwrap();
+        assert_eq!(laptop.config.profile.as_deref(), Some("laptop"));
+        assert!(matches!(
+            laptop.config.space.policy,
+            crate::config::SpacePolicy::Enforce
+        ));
+        assert_eq!(laptop.config.core.batch_size, 12);
+        assert!(laptop.config.applications.flatpak);
+        let explain = laptop.explain().unwrap();
+        let policy = explain.iter().find(|e| e.key == "space.policy").unwrap();
+        assert_eq!(policy.layer, ConfigLayer::Profile);
+        assert!(policy
+            .origin
+            .as_deref()
+            .unwrap()
+            .starts_with("[profiles.laptop] in "));
+        assert!(!explain.iter().any(|e| e.ignored));
+
+        let server = load(Some("server"), Vec::new()).unwrap();
+        assert!(!server.config.applications.flatpak);
+
+        let unknown = load(Some("desktop"), Vec::new()).unwrap_err();
+        assert!(unknown.to_string().contains("laptop, server"));
+    }
+
+    #[test]
+    fn override_values_parse_as_toml_or_text() {
+        assert_eq!(parse_value("5"), Value::Integer(5));
+        assert_eq!(parse_value("true"), Value::Boolean(true));
+        assert_eq!(parse_value("enforce"), Value::String("enforce".into()));
+        assert_eq!(
+            parse_value("[\"a\", \"b\"]"),
+            Value::Array(vec!["a".into(), "b".into()])
+        );
+        let mut table = Table::new();
+        set_dotted(&mut table, "a.b", Value::Integer(1)).unwrap();
+        assert!(set_dotted(&mut table, "a.b.c", Value::Integer(2)).is_err());
+        assert!(set_dotted(&mut table, "a..c", Value::Integer(2)).is_err());
+    }
+}
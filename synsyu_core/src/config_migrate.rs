@@ -0,0 +1,258 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::config_migrate
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Rewrite legacy configuration keys into the current layout
+    for `synsyu_core config migrate`, keeping the operator's
+    comments and ordering, and stamp `config_version`.
+
+  Security / Safety Notes:
+    Edits go through toml_edit so untouched lines stay
+    byte-for-byte identical; callers show the diff and keep
+    a backup before writing.
+============================================================*/
+
+use toml_edit::{value, DocumentMut, Item, Key, Table};
+
+use crate::error::{Result, SynsyuError};
+
+/// Layout version this build writes.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Keys moved to a new home: (legacy, current). The current key already wins
+/// when both are set, so a shadowed legacy key is dropped.
+const RENAMES: &[(&str, &str)] = &[
+    ("core.log_directory", "logging.directory"),
+    ("aur.max_retries", "acquisition.aur_rpc.max_retries"),
+];
+
+/// A migrated document and what changed in it.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub changes: Vec<String>,
+    pub before: String,
+    pub after: String,
+}
+
+impl Migration {
+    pub fn is_noop(&self) -> bool {
+        self.before == self.after
+    }
+}
+
+/// Move legacy keys and stamp the current `config_version`.
+pub fn migrate_document(contents: &str) -> Result<Migration> {
+    let mut doc: DocumentMut = contents
+        .parse()
+        .map_err(|err| SynsyuError::Config(format!("Failed to parse configuration: {err}")))?;
+    let version = match doc.get("config_version") {
+        None => 0,
+        Some(item) => item
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                SynsyuError::Config("config_version must be a non-negative integer".into())
+            })?,
+    };
+    if version > CONFIG_VERSION {
+        return Err(SynsyuError::Config(format!(
+            "config_version {version} is newer than this synsyu_core supports ({CONFIG_VERSION})"
+        )));
+    }
+
+    let mut changes = Vec::new();
+    for (legacy, current) in RENAMES {
+        let Some((key, item)) = take(&mut doc, legacy) else {
+            continue;
+        };
+        let (parents, name) = split_key(current);
+        let table = table_at(&mut doc, &parents)?;
+        if table.contains_key(name) {
+            changes.push(format!("removed {legacy} (shadowed by {current})"));
+        } else {
+            table.insert_formatted(&key_named(&key, name), item);
+            changes.push(format!("moved {legacy} to {current}"));
+        }
+    }
+    if version < CONFIG_VERSION {
+        doc["config_version"] = value(i64::from(CONFIG_VERSION));
+        changes.push(format!("set config_version = {CONFIG_VERSION}"));
+    }
+
+    Ok(Migration {
+        changes,
+        before: contents.to_string(),
+        after: doc.to_string(),
+    })
+}
+
+fn split_key(dotted: &str) -> (Vec<&str>, &str) {
+    let mut parts: Vec<&str> = dotted.split('.').collect();
+    let name = parts.pop().unwrap_or_default();
+    (parts, name)
+}
+
+/// Remove a key, keeping its comments when it sits in a standard table.
+fn take(doc: &mut DocumentMut, dotted: &str) -> Option<(Key, Item)> {
+    let (parents, name) = split_key(dotted);
+    let mut item = doc.as_item_mut();
+    for part in parents {
+        item = item.get_mut(part)?;
+    }
+    match item {
+        Item::Table(table) => table.remove_entry(name),
+        other => {
+            let removed = other.as_table_like_mut()?.remove(name)?;
+            Some((Key::new(name), removed))
+        }
+    }
+}
+
+/// The standard table at `path`, created (with implicit parents) if missing.
+pub(crate) fn table_at<'a>(doc: &'a mut DocumentMut, path: &[&str]) -> Result<&'a mut Table> {
+    let mut table = doc.as_table_mut();
+    for (depth, part) in path.iter().enumerate() {
+        let item = table.entry(part).or_insert_with(|| {
+            let mut created = Table::new();
+            created.set_implicit(depth + 1 < path.len());
+            Item::Table(created)
+        });
+        table = item.as_table_mut().ok_or_else(|| {
+            SynsyuError::Config(format!(
+                "Cannot migrate into `{}`: it is not a [table] section",
+                path[..=depth].join(".")
+            ))
+        })?;
+    }
+    Ok(table)
+}
+
+/// `name` carrying the comments attached to `original`.
+fn key_named(original: &Key, name: &str) -> Key {
+    let mut key = Key::new(name);
+    *key.leaf_decor_mut() = original.leaf_decor().clone();
+    key
+}
+
+/// Line diff of `before` and `after` in unified format with three lines of
+/// context.
+pub fn unified_diff(label: &str, before: &str, after: &str) -> String {
+    const CONTEXT: usize = 3;
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    // Longest common subsequence table, filled from the end.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    // (tag, old line number, new line number, text)
+    let mut ops: Vec<(char, usize, usize, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((' ', i, j, old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            ops.push(('+', i, j, new[j]));
+            j += 1;
+        } else {
+            ops.push(('-', i, j, old[i]));
+            i += 1;
+        }
+    }
+
+    // Changes closer than two contexts apart share a hunk.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (index, _) in ops.iter().enumerate().filter(|(_, op)| op.0 != ' ') {
+        match hunks.last_mut() {
+            Some((_, last)) if index - *last <= CONTEXT * 2 => *last = index,
+            _ => hunks.push((index, index)),
+        }
+    }
+
+    let mut out = String::new();
+    if !hunks.is_empty() {
+        out.push_str(&format!("--- {label}\n+++ {label} (migrated)\n"));
+    }
+    for (first, last) in hunks {
+        let hunk = &ops[first.saturating_sub(CONTEXT)..(last + CONTEXT + 1).min(ops.len())];
+        let old_count = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_count = hunk.iter().filter(|op| op.0 != '-').count();
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk[0].1 + usize::from(old_count > 0),
+            old_count,
+            hunk[0].2 + usize::from(new_count > 0),
+            new_count
+        ));
+        for (tag, _, _, text) in hunk {
+            out.push_str(&format!("{tag}{text}\n"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_keys_move_and_comments_survive() {
+        let legacy = "\
+# Operator notes stay put.
+[aur]
+timeout = 10
+# retry AUR RPC a few times
+max_retries = 5
+
+[core]
+batch_size = 4 # small batches
+log_directory = \"~/logs\"
+
+[logging]
+level = \"info\"
+";
+        let migration = migrate_document(legacy).unwrap();
+        let after = &migration.after;
+        assert!(after.starts_with("config_version = 1\n# Operator notes stay put.\n"));
+        assert!(after.contains("batch_size = 4 # small batches"));
+        assert!(after.contains("[logging]\nlevel = \"info\"\ndirectory = \"~/logs\""));
+        assert!(
+            after.contains("[acquisition.aur_rpc]\n# retry AUR RPC a few times\nmax_retries = 5")
+        );
+        assert!(!after.contains("[acquisition]\n"));
+        assert_eq!(migration.changes.len(), 3);
+
+        let config: crate::config::SynsyuConfig = toml::from_str(after).unwrap();
+        assert_eq!(config.resolved_aur_rpc_max_retries(), 5);
+        assert_eq!(config.logging.directory.as_deref(), Some("~/logs"));
+
+        let again = migrate_document(after).unwrap();
+        assert!(again.is_noop());
+        assert!(unified_diff("config.toml", after, after).is_empty());
+
+        let diff = unified_diff("config.toml", legacy, after);
+        assert!(diff.contains("-log_directory = \"~/logs\""));
+        assert!(diff.contains("+config_version = 1"));
+    }
+
+    #[test]
+    fn shadowed_legacy_keys_are_dropped() {
+        let migration =
+            migrate_document("[core]\nlog_directory = \"/old\"\n[logging]\ndirectory = \"/new\"\n")
+                .unwrap();
+        assert!(!migration.after.contains("/old"));
+        assert!(migration.after.contains("directory = \"/new\""));
+        assert!(migrate_document("config_version = 99\n").is_err());
+    }
+}
@@ -0,0 +1,275 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::du
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Build the `synsyu_core du` disk-usage report: packages
+    ranked by installed size and totalled per source, Flatpak
+    apps and runtimes, and the on-disk footprint of package
+    caches, AUR build caches, Syn-Syu logs, and manifest/plan
+    history.
+
+  Security / Safety Notes:
+    Read-only. Directory walks use apparent sizes and never
+    follow symlinks; unreadable entries are skipped.
+============================================================*/
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::flatpak::FlatpakUsage;
+
+/// One installed package and its size.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PackageUsage {
+    pub name: String,
+    pub source: String,
+    pub installed_size: u64,
+}
+
+/// Packages and bytes for one source or Flatpak kind.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GroupUsage {
+    pub group: String,
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// Footprint of a cache, log, or history location.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LocationUsage {
+    pub category: String,
+    pub path: PathBuf,
+    pub exists: bool,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Complete `du` report.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuReport {
+    pub package_bytes: u64,
+    pub by_source: Vec<GroupUsage>,
+    pub top_packages: Vec<PackageUsage>,
+    pub unsized_packages: Vec<String>,
+    pub flatpak_bytes: u64,
+    pub flatpak_by_kind: Vec<GroupUsage>,
+    pub flatpak: Vec<FlatpakUsage>,
+    pub locations_bytes: u64,
+    pub locations: Vec<LocationUsage>,
+}
+
+/// Packages from a manifest, largest first, plus names without size data.
+pub fn package_usage(manifest: &Value) -> (Vec<PackageUsage>, Vec<String>) {
+    let mut sized = Vec::new();
+    let mut without_size = Vec::new();
+    if let Some(packages) = manifest.get("packages").and_then(Value::as_object) {
+        for (name, entry) in packages {
+            let source = entry
+                .get("source")
+                .and_then(Value::as_str)
+                .unwrap_or("UNKNOWN")
+                .to_string();
+            match entry.get("installed_size").and_then(Value::as_u64) {
+                Some(installed_size) => sized.push(PackageUsage {
+                    name: name.clone(),
+                    source,
+                    installed_size,
+                }),
+                None => without_size.push(name.clone()),
+            }
+        }
+    }
+    sized.sort_by(|a, b| {
+        b.installed_size
+            .cmp(&a.installed_size)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    (sized, without_size)
+}
+
+/// Total `(key, bytes)` pairs per key, largest group first.
+pub fn group_totals<'a>(items: impl Iterator<Item = (&'a str, u64)>) -> Vec<GroupUsage> {
+    let mut groups: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+    for (key, bytes) in items {
+        let entry = groups.entry(key).or_default();
+        entry.0 += 1;
+        entry.1 = entry.1.saturating_add(bytes);
+    }
+    let mut totals: Vec<GroupUsage> = groups
+        .into_iter()
+        .map(|(group, (count, bytes))| GroupUsage {
+            group: group.to_string(),
+            count,
+            bytes,
+        })
+        .collect();
+    totals.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.group.cmp(&b.group)));
+    totals
+}
+
+/// Apparent size and file count of a file or directory tree.
+pub fn location_usage(category: &str, path: &Path) -> LocationUsage {
+    let mut usage = LocationUsage {
+        category: category.to_string(),
+        path: path.to_path_buf(),
+        exists: fs::symlink_metadata(path).is_ok(),
+        files: 0,
+        bytes: 0,
+    };
+    walk(path, &mut usage);
+    usage
+}
+
+fn walk(path: &Path, usage: &mut LocationUsage) {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return;
+    };
+    if meta.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                walk(&entry.path(), usage);
+            }
+        }
+    } else {
+        usage.files += 1;
+        usage.bytes = usage.bytes.saturating_add(meta.len());
+    }
+}
+
+/// Manifest, plan, and rotated copies of either that sit next to the manifest.
+pub fn manifest_history_files(manifest: &Path) -> Vec<PathBuf> {
+    let mut files = vec![manifest.to_path_buf()];
+    let (Some(dir), Some(stem)) = (
+        manifest.parent(),
+        manifest
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string()),
+    ) else {
+        return files;
+    };
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path();
+            let related = name.starts_with(&stem) || name.starts_with("plan");
+            if related && name.contains(".json") && path.is_file() && !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Assemble the report; `top` limits the ranked package list (0 = all).
+pub fn build_report(
+    manifest: &Value,
+    flatpak: Vec<FlatpakUsage>,
+    locations: Vec<LocationUsage>,
+    top: usize,
+) -> DuReport {
+    let (packages, unsized_packages) = package_usage(manifest);
+    let by_source = group_totals(
+        packages
+            .iter()
+            .map(|p| (p.source.as_str(), p.installed_size)),
+    );
+    let flatpak_by_kind = group_totals(
+        flatpak
+            .iter()
+            .map(|f| (f.kind.as_str(), f.installed_size.unwrap_or(0))),
+    );
+    let mut flatpak = flatpak;
+    flatpak.sort_by_key(|f| std::cmp::Reverse(f.installed_size));
+    let limit = if top == 0 { packages.len() } else { top };
+    DuReport {
+        package_bytes: by_source.iter().map(|g| g.bytes).sum(),
+        by_source,
+        top_packages: packages.into_iter().take(limit).collect(),
+        unsized_packages,
+        flatpak_bytes: flatpak_by_kind.iter().map(|g| g.bytes).sum(),
+        flatpak_by_kind,
+        flatpak,
+        locations_bytes: locations.iter().map(|l| l.bytes).sum(),
+        locations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn ranks_packages_and_totals_sources() {
+        let manifest = serde_json::json!({
+            "packages": {
+                "firefox": {"source": "PACMAN", "installed_size": 250},
+                "glibc": {"source": "PACMAN", "installed_size": 50},
+                "paru": {"source": "AUR", "installed_size": 10},
+                "custom": {"source": "LOCAL"},
+            }
+        });
+        let flatpak = vec![
+            FlatpakUsage {
+                application: "org.gnome.Platform".into(),
+                branch: "46".into(),
+                kind: "runtime".into(),
+                installation: "system".into(),
+                installed_size: Some(900),
+            },
+            FlatpakUsage {
+                application: "org.app.App".into(),
+                branch: "stable".into(),
+                kind: "app".into(),
+                installation: "user".into(),
+                installed_size: Some(100),
+            },
+        ];
+        let report = build_report(&manifest, flatpak, Vec::new(), 2);
+        assert_eq!(report.package_bytes, 310);
+        assert_eq!(
+            report
+                .top_packages
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["firefox", "glibc"]
+        );
+        assert_eq!(report.by_source[0].group, "PACMAN");
+        assert_eq!(report.by_source[0].count, 2);
+        assert_eq!(report.unsized_packages, vec!["custom"]);
+        assert_eq!(report.flatpak_bytes, 1000);
+        assert_eq!(report.flatpak_by_kind[0].group, "runtime");
+        assert_eq!(report.flatpak[0].application, "org.gnome.Platform");
+    }
+
+    #[test]
+    fn walks_locations_and_finds_manifest_history() {
+        let tmp = TempDir::new("synsyu_du_").unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("manifest.json"), vec![0u8; 10]).unwrap();
+        fs::write(dir.join("manifest.json.1"), vec![0u8; 20]).unwrap();
+        fs::write(dir.join("plan.json"), vec![0u8; 5]).unwrap();
+        fs::write(dir.join("config.toml"), b"x").unwrap();
+        fs::write(dir.join("nested").join("log.txt"), vec![0u8; 7]).unwrap();
+
+        let usage = location_usage("logs", dir);
+        assert_eq!((usage.files, usage.bytes), (5, 43));
+        assert!(!location_usage("logs", &dir.join("missing")).exists);
+
+        let history = manifest_history_files(&dir.join("manifest.json"));
+        let names: Vec<String> = history
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["manifest.json", "manifest.json.1", "plan.json"]);
+    }
+}
@@ -26,6 +26,10 @@ pub struct FlatpakUpdate {
     pub branch: String,
     pub origin: String,
     pub available: String,
+    /// `system` or `user`; selects the installation directory charged.
+    pub installation: String,
+    pub download_size: Option<u64>,
+    pub installed_size: Option<u64>,
 }
 
 /// Collect installed flatpak applications and pending updates.
@@ -93,7 +97,7 @@ async fn capture_installed() -> Option<Vec<FlatpakApp>> {
         if parts.is_empty() {
             continue;
         }
-        let application = parts.get(0).unwrap_or(&"").trim().to_string();
+        let application = parts.first().unwrap_or(&"").trim().to_string();
         if application.is_empty() {
             continue;
         }
@@ -111,12 +115,22 @@ async fn capture_installed() -> Option<Vec<FlatpakApp>> {
 }
 
 async fn capture_updates() -> Option<Vec<FlatpakUpdate>> {
+    let system = capture_installation_updates("system").await;
+    let user = capture_installation_updates("user").await;
+    if system.is_none() && user.is_none() {
+        return None;
+    }
+    Some(system.into_iter().chain(user).flatten().collect())
+}
+
+/// Pending updates (apps and runtimes) for one installation, with sizes.
+pub async fn capture_installation_updates(installation: &str) -> Option<Vec<FlatpakUpdate>> {
     let output = Command::new("flatpak")
         .args([
             "remote-ls",
             "--updates",
-            "--columns=application,branch,origin,version",
-            "--app",
+            &format!("--{installation}"),
+            "--columns=application,branch,origin,version,download-size,installed-size",
         ])
         .output()
         .await
@@ -127,25 +141,118 @@ async fn capture_updates() -> Option<Vec<FlatpakUpdate>> {
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut updates = Vec::new();
-    for line in stdout.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.is_empty() {
-            continue;
-        }
-        let application = parts.get(0).unwrap_or(&"").trim().to_string();
-        if application.is_empty() {
-            continue;
+    Some(
+        stdout
+            .lines()
+            .filter_map(|line| parse_update_line(line, installation))
+            .collect(),
+    )
+}
+
+/// Parse one tab-separated `remote-ls` row; sizes may contain spaces.
+fn parse_update_line(line: &str, installation: &str) -> Option<FlatpakUpdate> {
+    let parts: Vec<&str> = line.split('\t').map(str::trim).collect();
+    let application = parts.first().copied().unwrap_or("").to_string();
+    if application.is_empty() {
+        return None;
+    }
+    let field = |index: usize| parts.get(index).copied().unwrap_or("").to_string();
+    Some(FlatpakUpdate {
+        application,
+        branch: field(1),
+        origin: field(2),
+        available: field(3),
+        installation: installation.to_string(),
+        download_size: parts.get(4).and_then(|v| parse_size(v)),
+        installed_size: parts.get(5).and_then(|v| parse_size(v)),
+    })
+}
+
+/// Installed size of one Flatpak ref.
+#[derive(Debug, Serialize, Clone)]
+pub struct FlatpakUsage {
+    pub application: String,
+    pub branch: String,
+    /// `app` or `runtime`.
+    pub kind: String,
+    pub installation: String,
+    pub installed_size: Option<u64>,
+}
+
+/// Installed apps and runtimes with their sizes; `None` when flatpak is unavailable.
+pub async fn capture_usage() -> Option<Vec<FlatpakUsage>> {
+    let mut usage = Vec::new();
+    for kind in ["app", "runtime"] {
+        let output = Command::new("flatpak")
+            .args([
+                "list",
+                &format!("--{kind}"),
+                "--columns=application,branch,installation,size",
+            ])
+            .output()
+            .await
+            .ok()?;
+        if !output.status.success() {
+            return None;
         }
-        let branch = parts.get(1).unwrap_or(&"").trim().to_string();
-        let origin = parts.get(2).unwrap_or(&"").trim().to_string();
-        let available = parts.get(3).unwrap_or(&"").trim().to_string();
-        updates.push(FlatpakUpdate {
-            application,
-            branch,
-            origin,
-            available,
-        });
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        usage.extend(stdout.lines().filter_map(|line| {
+            let parts: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let application = parts.first().copied().filter(|a| !a.is_empty())?;
+            let field = |index: usize| parts.get(index).copied().unwrap_or("").to_string();
+            Some(FlatpakUsage {
+                application: application.to_string(),
+                branch: field(1),
+                kind: kind.to_string(),
+                installation: field(2),
+                installed_size: parts.get(3).and_then(|v| parse_size(v)),
+            })
+        }));
+    }
+    Some(usage)
+}
+
+/// Parse flatpak's human-readable sizes (`1.2 MB`, `830 bytes`, `4.0 GiB`).
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim().replace('\u{a0}', " ");
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(value.len());
+    let number: f64 = value[..split].replace(',', ".").parse().ok()?;
+    let multiplier: f64 = match value[split..].trim() {
+        "" | "B" | "bytes" | "byte" => 1.0,
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * multiplier).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_remote_ls_rows_with_sizes() {
+        let update = parse_update_line(
+            "org.gnome.Platform\t46\tflathub\t\t312.5 MB\t1.1 GB",
+            "system",
+        )
+        .expect("row");
+        assert_eq!(update.application, "org.gnome.Platform");
+        assert_eq!(update.available, "");
+        assert_eq!(update.download_size, Some(312_500_000));
+        assert_eq!(update.installed_size, Some(1_100_000_000));
+        assert_eq!(parse_size("830 bytes"), Some(830));
+        assert_eq!(parse_size("2,0 kB"), Some(2000));
+        assert_eq!(parse_size("1.5 GiB"), Some(1_610_612_736));
+        assert_eq!(parse_size("unknown"), None);
+        assert!(parse_update_line("", "user").is_none());
     }
-    Some(updates)
 }
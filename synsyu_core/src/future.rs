@@ -6,20 +6,23 @@
   Purpose:
     Provide scaffolding for Syn-Syu-Core roadmap features such
     as multi-core vercmp computation, changelog inspection, and
-    the plugin system.
+    the plugin system. Audit backends have graduated to
+    `crate::audit`; plugins register theirs with an `Auditor`.
 
   Security / Safety Notes:
     No operational code is executed; this module documents
     planned extension points to guide safe implementations.
 
   Dependencies:
-    None at runtime; placeholder traits only.
+    crate::audit for the audit backend trait; otherwise
+    placeholder traits only.
 
   Operational Scope:
     Referenced by developers when implementing Syn-Syu v3+.
 
   Revision History:
     2024-11-04 COD  Added future expansion scaffolding.
+    2026-10-18 COD  AuditBackend implemented in crate::audit.
   ------------------------------------------------------------
   SSE Principles Observed:
     - Explicit documentation of deferred capabilities
@@ -40,16 +43,14 @@ pub trait ChangelogProvider {
     fn fetch_changelog(&self, package: &str) -> Vec<String>;
 }
 
-/// Planned hook for audit logging backends.
-pub trait AuditBackend {
-    /// Record an append-only audit entry.
-    fn record(&self, message: &str);
-}
+pub use crate::audit::AuditBackend;
+use crate::audit::Auditor;
 
-/// Plugin registration entry point. Currently a stub.
-pub fn register_plugin<T>(_plugin: T)
+/// Plugin registration entry point. The audit backend joins `auditor`'s
+/// fan-out; comparator and changelog registries land in Syn-Syu v3.
+pub fn register_plugin<T>(auditor: &mut Auditor, plugin: T)
 where
-    T: VersionComparator + ChangelogProvider + AuditBackend + Send + Sync + 'static,
+    T: VersionComparator + ChangelogProvider + AuditBackend + 'static,
 {
-    // Placeholder: dynamic plugin registry lands in Syn-Syu v3.
+    auditor.register(Box::new(plugin));
 }
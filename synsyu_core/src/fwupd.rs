@@ -5,13 +5,14 @@ use crate::logger::Logger;
 
 #[derive(Debug, Deserialize)]
 struct FwupdJson {
-    #[serde(default)]
-    Devices: Vec<FwupdDeviceRaw>,
+    #[serde(rename = "Devices", default)]
+    devices_upper: Vec<FwupdDeviceRaw>,
     #[serde(default)]
     devices: Vec<FwupdDeviceRaw>,
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct FwupdDeviceRaw {
     #[serde(rename = "Id")]
     id: Option<String>,
@@ -44,6 +45,7 @@ struct FwupdDeviceRaw {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct FwupdReleaseRaw {
     #[serde(rename = "Version")]
     version: Option<String>,
@@ -102,6 +104,12 @@ pub struct FwupdUpdate {
     pub summary: String,
     pub available_hash: String,
     pub trust: String,
+    /// fwupd plugin handling the device; `uefi_capsule` stages on the ESP.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+    /// Release payload size in bytes, when published.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
 }
 
 pub async fn collect_fwupd(logger: &Logger, include_updates: bool) -> Result<Option<FwupdState>> {
@@ -130,8 +138,8 @@ pub async fn collect_fwupd(logger: &Logger, include_updates: bool) -> Result<Opt
     let parsed: FwupdJson = serde_json::from_slice(&output.stdout).map_err(|err| {
         SynsyuError::Serialization(format!("Failed to parse fwupd JSON output: {err}"))
     })?;
-    let devices_raw = if !parsed.Devices.is_empty() {
-        parsed.Devices
+    let devices_raw = if !parsed.devices_upper.is_empty() {
+        parsed.devices_upper
     } else {
         parsed.devices
     };
@@ -147,14 +155,14 @@ pub async fn collect_fwupd(logger: &Logger, include_updates: bool) -> Result<Opt
             .version
             .or(raw.version_bootloader)
             .unwrap_or_else(String::new);
-        let summary = raw.summary.or(raw.description).unwrap_or_else(String::new);
+        let summary = raw.summary.or(raw.description).unwrap_or_default();
 
         let checksum = truncate_hash(select_checksum(
             raw.checksum,
             raw.checksums,
             raw.checksums_lower,
         ));
-        let trust = join_trust(raw.trust_flags, raw.trust_flags_lower).unwrap_or_else(String::new);
+        let trust = join_trust(raw.trust_flags, raw.trust_flags_lower).unwrap_or_default();
 
         // Manifest should reflect current firmware state; exclude pending release data.
         let releases = Vec::new();
@@ -235,6 +243,7 @@ async fn collect_fwupd_updates() -> std::result::Result<Vec<FwupdUpdate>, String
             .unwrap_or_else(|| "unknown".to_string());
         let name = dev.name.clone().unwrap_or_else(|| dev_id.clone());
         let installed = dev.installed.unwrap_or_default();
+        let plugin = dev.plugin.filter(|p| !p.is_empty());
         let releases = dev.releases.or(dev.releases_lower).unwrap_or_default();
         for rel in releases {
             let available = rel.version.unwrap_or_default();
@@ -267,6 +276,8 @@ async fn collect_fwupd_updates() -> std::result::Result<Vec<FwupdUpdate>, String
                 summary,
                 available_hash: checksum,
                 trust,
+                plugin: plugin.clone(),
+                size: rel.size.filter(|size| *size > 0),
             });
         }
     }
@@ -292,6 +303,8 @@ struct FwupdUpdateDevice {
     name: Option<String>,
     #[serde(rename = "Version")]
     installed: Option<String>,
+    #[serde(rename = "Plugin")]
+    plugin: Option<String>,
     #[serde(rename = "Releases")]
     releases: Option<Vec<FwupdUpdateRelease>>,
     #[serde(rename = "releases")]
@@ -318,6 +331,8 @@ struct FwupdUpdateRelease {
     trust_flags_lower: Option<Vec<String>>,
     #[serde(rename = "Signed")]
     signed: Option<bool>,
+    #[serde(rename = "Size")]
+    size: Option<u64>,
 }
 
 fn select_checksum(
@@ -0,0 +1,544 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::kernel
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Recognise kernel, initramfs-generator, and bootloader
+    packages in an update set, estimate what a kernel update
+    writes to /boot (and the ESP for unified kernel images), and
+    verify after an update that every installed kernel has its
+    module tree and matching boot images.
+
+  Security / Safety Notes:
+    Read-only: inspects /usr/lib/modules, /boot, and the ESP and
+    queries pacman's local database without privileges.
+============================================================*/
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::Serialize;
+use tokio::process::Command;
+
+use crate::error::{Result, SynsyuError};
+use crate::space::{SpacePhase, SpaceRequirement};
+
+/// Where kernel packages install their module trees.
+pub const MODULES_ROOT: &str = "/usr/lib/modules";
+/// Where kernel packages and initramfs generators place boot images.
+pub const BOOT_DIR: &str = "/boot";
+
+const INITRAMFS_GENERATORS: &[&str] = &["mkinitcpio", "dracut", "booster", "ugrd"];
+const BOOTLOADERS: &[&str] = &[
+    "grub",
+    "systemd",
+    "refind",
+    "limine",
+    "syslinux",
+    "efibootmgr",
+];
+/// `linux-*` packages that are not kernels.
+const NON_KERNEL_PREFIXES: &[&str] = &[
+    "linux-api-headers",
+    "linux-firmware",
+    "linux-tools",
+    "linux-atm",
+];
+const NON_KERNEL_SUFFIXES: &[&str] = &["-headers", "-docs"];
+
+/// Boot-relevant role of a package.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BootRole {
+    Kernel,
+    InitramfsGenerator,
+    Bootloader,
+}
+
+/// Estimated /boot and ESP impact of one kernel update.
+#[derive(Debug, Clone, Serialize)]
+pub struct KernelImpact {
+    pub package: String,
+    pub images: Vec<PathBuf>,
+    pub bytes: u64,
+    /// True when sized from another kernel because this one has no images yet.
+    pub estimated_from_peer: bool,
+}
+
+/// Boot-related findings for an update set.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct BootAssessment {
+    pub kernels: Vec<KernelImpact>,
+    pub initramfs_generators: Vec<String>,
+    pub bootloaders: Vec<String>,
+    #[serde(skip)]
+    pub requirements: Vec<SpaceRequirement>,
+    pub warnings: Vec<String>,
+}
+
+impl BootAssessment {
+    pub fn is_empty(&self) -> bool {
+        self.kernels.is_empty()
+            && self.initramfs_generators.is_empty()
+            && self.bootloaders.is_empty()
+    }
+}
+
+/// Post-update consistency of one installed kernel.
+#[derive(Debug, Clone, Serialize)]
+pub struct KernelCheck {
+    pub package: String,
+    pub modules_version: Option<String>,
+    pub boot_image_version: Option<String>,
+    pub images: Vec<PathBuf>,
+    pub problems: Vec<String>,
+}
+
+/// Kernel package names recorded in `<modules_root>/*/pkgbase`, mapped to the
+/// module trees claiming them.
+pub fn module_trees(modules_root: &Path) -> BTreeMap<String, Vec<String>> {
+    let mut trees: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let Ok(entries) = fs::read_dir(modules_root) else {
+        return trees;
+    };
+    for entry in entries.flatten() {
+        let Ok(pkgbase) = fs::read_to_string(entry.path().join("pkgbase")) else {
+            continue;
+        };
+        let pkgbase = pkgbase.trim();
+        if !pkgbase.is_empty() {
+            trees
+                .entry(pkgbase.to_string())
+                .or_default()
+                .push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    for versions in trees.values_mut() {
+        versions.sort();
+    }
+    trees
+}
+
+/// Classify a package; `known_kernels` are pkgbase names found on disk.
+pub fn classify(name: &str, known_kernels: &HashSet<String>) -> Option<BootRole> {
+    let base = name.strip_suffix("-git").unwrap_or(name);
+    if known_kernels.contains(name) {
+        Some(BootRole::Kernel)
+    } else if INITRAMFS_GENERATORS.contains(&base) {
+        Some(BootRole::InitramfsGenerator)
+    } else if BOOTLOADERS.contains(&base) {
+        Some(BootRole::Bootloader)
+    } else if is_kernel_name(name) {
+        Some(BootRole::Kernel)
+    } else {
+        None
+    }
+}
+
+fn is_kernel_name(name: &str) -> bool {
+    (name == "linux" || name.starts_with("linux-"))
+        && !NON_KERNEL_PREFIXES.iter().any(|p| name.starts_with(p))
+        && !NON_KERNEL_SUFFIXES.iter().any(|s| name.ends_with(s))
+}
+
+/// Boot images belonging to `package` in the boot directory and, for unified
+/// kernel images, `<esp>/EFI/Linux`.
+pub fn boot_images(boot_dir: &Path, esp: Option<&Path>, package: &str) -> Vec<PathBuf> {
+    let names = [
+        format!("vmlinuz-{package}"),
+        format!("initramfs-{package}.img"),
+        format!("initramfs-{package}-fallback.img"),
+        format!("booster-{package}.img"),
+    ];
+    let mut images: Vec<PathBuf> = names
+        .iter()
+        .map(|name| boot_dir.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    if let Some(esp) = esp {
+        let uki_dir = esp.join("EFI").join("Linux");
+        if let Ok(entries) = fs::read_dir(&uki_dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Some(stem) = name.strip_suffix(".efi") else {
+                    continue;
+                };
+                let stem = stem.strip_suffix("-fallback").unwrap_or(stem);
+                if stem == package || stem.ends_with(&format!("-{package}")) {
+                    images.push(entry.path());
+                }
+            }
+        }
+    }
+    images.sort();
+    images.dedup();
+    images
+}
+
+fn total_size(paths: &[PathBuf]) -> u64 {
+    paths
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .fold(0u64, u64::saturating_add)
+}
+
+/// Detect boot-relevant packages among `names` and size what kernel updates
+/// write. Replacing an image briefly keeps old and new side by side, so each
+/// kernel is charged its current image set on the filesystem holding it.
+pub fn assess_update(
+    names: &[String],
+    modules_root: &Path,
+    boot_dir: &Path,
+    esp: Option<&Path>,
+) -> BootAssessment {
+    let trees = module_trees(modules_root);
+    let known: HashSet<String> = trees.keys().cloned().collect();
+    let mut assessment = BootAssessment::default();
+    let mut kernels = Vec::new();
+    for name in names {
+        match classify(name, &known) {
+            Some(BootRole::Kernel) => kernels.push(name.clone()),
+            Some(BootRole::InitramfsGenerator) => {
+                assessment.initramfs_generators.push(name.clone())
+            }
+            Some(BootRole::Bootloader) => assessment.bootloaders.push(name.clone()),
+            None => {}
+        }
+    }
+    if kernels.is_empty() {
+        return assessment;
+    }
+
+    // Largest installed image set stands in for kernels without images yet.
+    let peer = known
+        .iter()
+        .map(|pkg| boot_images(boot_dir, esp, pkg))
+        .max_by_key(|images| total_size(images))
+        .unwrap_or_default();
+
+    for package in kernels {
+        let own = boot_images(boot_dir, esp, &package);
+        let estimated_from_peer = own.is_empty();
+        let images = if estimated_from_peer {
+            peer.clone()
+        } else {
+            own
+        };
+        if images.is_empty() {
+            assessment.warnings.push(format!(
+                "No boot images found to size {package}; /boot usage is unknown"
+            ));
+        }
+        for image in &images {
+            let bytes = fs::metadata(image).map(|m| m.len()).unwrap_or(0);
+            let path = image.parent().unwrap_or(boot_dir).to_path_buf();
+            assessment.requirements.push(SpaceRequirement {
+                phase: SpacePhase::Boot,
+                path,
+                bytes: i64::try_from(bytes).unwrap_or(i64::MAX),
+                files: 1,
+            });
+        }
+        assessment.kernels.push(KernelImpact {
+            package,
+            bytes: total_size(&images),
+            images,
+            estimated_from_peer,
+        });
+    }
+    assessment
+}
+
+/// Kernel release embedded in an x86 bzImage setup header.
+pub fn bzimage_version(path: &Path) -> Option<String> {
+    let mut header = [0u8; 0x210];
+    let mut file = fs::File::open(path).ok()?;
+    file.read_exact(&mut header).ok()?;
+    if &header[0x202..0x206] != b"HdrS" {
+        return None;
+    }
+    let offset = u64::from(u16::from_le_bytes([header[0x20E], header[0x20F]])) + 0x200;
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut raw = Vec::with_capacity(256);
+    file.take(256).read_to_end(&mut raw).ok()?;
+    let end = raw.iter().position(|b| *b == 0).unwrap_or(raw.len());
+    String::from_utf8_lossy(&raw[..end])
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+}
+
+/// Compare one installed kernel against its module tree and boot images.
+/// `owned_versions` are the module trees the package owns per pacman.
+pub fn verify_kernel(
+    package: &str,
+    owned_versions: &[String],
+    modules_root: &Path,
+    boot_dir: &Path,
+    esp: Option<&Path>,
+) -> KernelCheck {
+    let mut check = KernelCheck {
+        package: package.to_string(),
+        modules_version: owned_versions.first().cloned(),
+        boot_image_version: None,
+        images: boot_images(boot_dir, esp, package),
+        problems: Vec::new(),
+    };
+
+    let Some(version) = check.modules_version.clone() else {
+        check.problems.push(format!(
+            "{package} owns no module tree under {}",
+            modules_root.display()
+        ));
+        return check;
+    };
+    let tree = modules_root.join(&version);
+    if !tree.is_dir() {
+        check
+            .problems
+            .push(format!("{} is missing", tree.display()));
+    } else if !tree.join("modules.dep").is_file() {
+        check.problems.push(format!(
+            "{} has no modules.dep (depmod did not run)",
+            tree.display()
+        ));
+    }
+
+    let uki = check
+        .images
+        .iter()
+        .any(|p| p.extension().is_some_and(|ext| ext == "efi"));
+    let vmlinuz = boot_dir.join(format!("vmlinuz-{package}"));
+    if vmlinuz.is_file() {
+        check.boot_image_version = bzimage_version(&vmlinuz);
+        if let Some(found) = &check.boot_image_version {
+            if found != &version {
+                check.problems.push(format!(
+                    "{} is {found} but modules are {version}; /boot may not have been mounted during the update",
+                    vmlinuz.display()
+                ));
+            }
+        }
+    } else if !uki {
+        check
+            .problems
+            .push(format!("{} is missing", vmlinuz.display()));
+    }
+
+    let initramfs: Vec<&PathBuf> = check
+        .images
+        .iter()
+        .filter(|p| {
+            let name = p
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default();
+            name.ends_with(".img")
+        })
+        .collect();
+    if initramfs.is_empty() && !uki {
+        check.problems.push(format!(
+            "no initramfs for {package} in {}",
+            boot_dir.display()
+        ));
+    } else {
+        // An initramfs older than the installed kernel was not regenerated.
+        let installed_at = modified(&tree.join("vmlinuz"));
+        for image in initramfs {
+            if let (Some(kernel), Some(built)) = (installed_at, modified(image)) {
+                if built < kernel {
+                    check.problems.push(format!(
+                        "{} predates the installed kernel; regenerate it",
+                        image.display()
+                    ));
+                }
+            }
+        }
+    }
+    check
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Installed kernel packages and the module trees each owns, from pacman.
+/// Packages named like kernels that own nothing under `modules_root` and are
+/// not a recorded pkgbase (e.g. `linux-wifi-hotspot`) are skipped.
+pub async fn installed_kernels(modules_root: &Path) -> Result<BTreeMap<String, Vec<String>>> {
+    let known: HashSet<String> = module_trees(modules_root).into_keys().collect();
+    let output = Command::new("pacman")
+        .arg("-Qq")
+        .output()
+        .await
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => SynsyuError::CommandMissing {
+                command: "pacman".into(),
+            },
+            _ => SynsyuError::Io(err),
+        })?;
+    if !output.status.success() {
+        return Err(SynsyuError::CommandFailure {
+            command: "pacman -Qq".into(),
+            status: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    let kernels: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|name| classify(name, &known) == Some(BootRole::Kernel))
+        .map(str::to_string)
+        .collect();
+
+    let mut owned = BTreeMap::new();
+    for kernel in kernels {
+        let listing = Command::new("pacman")
+            .args(["-Qlq", &kernel])
+            .output()
+            .await
+            .map(|out| String::from_utf8_lossy(&out.stdout).to_string())
+            .unwrap_or_default();
+        let versions = listed_module_trees(&listing, modules_root);
+        if versions.is_empty() && !known.contains(&kernel) {
+            continue;
+        }
+        owned.insert(kernel, versions);
+    }
+    Ok(owned)
+}
+
+/// Module tree names under `modules_root` in a `pacman -Qlq` listing.
+fn listed_module_trees(listing: &str, modules_root: &Path) -> Vec<String> {
+    let prefix = format!("{}/", modules_root.display());
+    let mut versions: Vec<String> = listing
+        .lines()
+        .filter_map(|line| line.strip_prefix(&prefix))
+        .filter_map(|rest| rest.split('/').next())
+        .filter(|version| !version.is_empty())
+        .map(str::to_string)
+        .collect();
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn bzimage(version: &str) -> Vec<u8> {
+        let mut image = vec![0u8; 0x400];
+        image[0x202..0x206].copy_from_slice(b"HdrS");
+        image[0x20E..0x210].copy_from_slice(&0x100u16.to_le_bytes());
+        let text = format!("{version} (builder@arch) #1 SMP");
+        image[0x300..0x300 + text.len()].copy_from_slice(text.as_bytes());
+        image
+    }
+
+    #[test]
+    fn classifies_boot_packages() {
+        let known = HashSet::from(["linux-custom".to_string()]);
+        assert_eq!(classify("linux", &known), Some(BootRole::Kernel));
+        assert_eq!(classify("linux-zen", &known), Some(BootRole::Kernel));
+        assert_eq!(classify("linux-custom", &known), Some(BootRole::Kernel));
+        assert_eq!(classify("linux-zen-headers", &known), None);
+        assert_eq!(classify("linux-firmware-intel", &known), None);
+        assert_eq!(classify("linux-api-headers", &known), None);
+        assert_eq!(
+            classify("mkinitcpio", &known),
+            Some(BootRole::InitramfsGenerator)
+        );
+        assert_eq!(
+            classify("dracut-git", &known),
+            Some(BootRole::InitramfsGenerator)
+        );
+        assert_eq!(classify("grub", &known), Some(BootRole::Bootloader));
+        assert_eq!(classify("bash", &known), None);
+    }
+
+    #[test]
+    fn sizes_kernel_updates_from_current_images() {
+        let tmp = TempDir::new("synsyu_kernel_").unwrap();
+        let root = tmp.path();
+        let modules = root.join("modules");
+        let boot = root.join("boot");
+        fs::create_dir_all(modules.join("6.9.1-arch1-1")).unwrap();
+        fs::write(modules.join("6.9.1-arch1-1/pkgbase"), "linux\n").unwrap();
+        fs::create_dir_all(&boot).unwrap();
+        fs::write(boot.join("vmlinuz-linux"), vec![0u8; 1000]).unwrap();
+        fs::write(boot.join("initramfs-linux.img"), vec![0u8; 3000]).unwrap();
+        fs::write(boot.join("initramfs-linux-lts.img"), vec![0u8; 7]).unwrap();
+
+        let names = vec![
+            "linux".to_string(),
+            "linux-zen".to_string(),
+            "mkinitcpio".to_string(),
+            "vim".to_string(),
+        ];
+        let assessment = assess_update(&names, &modules, &boot, None);
+        assert_eq!(assessment.initramfs_generators, vec!["mkinitcpio"]);
+        assert_eq!(assessment.kernels.len(), 2);
+        assert_eq!(assessment.kernels[0].bytes, 4000);
+        assert!(!assessment.kernels[0].estimated_from_peer);
+        // linux-zen has no images yet and is sized like the installed kernel.
+        assert_eq!(assessment.kernels[1].bytes, 4000);
+        assert!(assessment.kernels[1].estimated_from_peer);
+        let total: i64 = assessment.requirements.iter().map(|r| r.bytes).sum();
+        assert_eq!(total, 8000);
+    }
+
+    #[test]
+    fn verify_flags_stale_boot_images() {
+        let tmp = TempDir::new("synsyu_kernel_").unwrap();
+        let root = tmp.path();
+        let modules = root.join("modules");
+        let boot = root.join("boot");
+        let tree = modules.join("6.9.2-arch1-1");
+        fs::create_dir_all(&tree).unwrap();
+        fs::create_dir_all(&boot).unwrap();
+        fs::write(boot.join("initramfs-linux.img"), b"img").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(boot.join("initramfs-linux.img"))
+            .and_then(|f| f.set_modified(SystemTime::UNIX_EPOCH))
+            .unwrap();
+        fs::write(tree.join("vmlinuz"), bzimage("6.9.2-arch1-1")).unwrap();
+        fs::write(tree.join("modules.dep"), b"").unwrap();
+        fs::write(boot.join("vmlinuz-linux"), bzimage("6.9.1-arch1-1")).unwrap();
+
+        let owned = vec!["6.9.2-arch1-1".to_string()];
+        let check = verify_kernel("linux", &owned, &modules, &boot, None);
+        assert_eq!(check.boot_image_version.as_deref(), Some("6.9.1-arch1-1"));
+        assert!(check
+            .problems
+            .iter()
+            .any(|p| p.contains("may not have been mounted")));
+        assert!(check.problems.iter().any(|p| p.contains("predates")));
+
+        fs::write(boot.join("vmlinuz-linux"), bzimage("6.9.2-arch1-1")).unwrap();
+        fs::write(boot.join("initramfs-linux.img"), b"img").unwrap();
+        let check = verify_kernel("linux", &owned, &modules, &boot, None);
+        assert!(check.problems.is_empty(), "{:?}", check.problems);
+
+        let check = verify_kernel("linux-lts", &[], &modules, &boot, None);
+        assert!(check.problems[0].contains("owns no module tree"));
+    }
+
+    #[test]
+    fn lists_module_trees_owned_by_a_package() {
+        let modules = Path::new("/usr/lib/modules");
+        let kernel = "/usr/\n/usr/lib/modules/\n/usr/lib/modules/6.9.2-arch1-1/\n/usr/lib/modules/6.9.2-arch1-1/vmlinuz\n/usr/lib/modules/6.9.2-arch1-1/pkgbase\n";
+        assert_eq!(listed_module_trees(kernel, modules), vec!["6.9.2-arch1-1"]);
+        // Named like a kernel but ships only userspace files.
+        let hotspot =
+            "/usr/bin/\n/usr/bin/linux-wifi-hotspot\n/usr/lib/systemd/system/create_ap.service\n";
+        assert!(listed_module_trees(hotspot, modules).is_empty());
+    }
+}
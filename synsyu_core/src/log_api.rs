@@ -1,13 +1,14 @@
 use std::fs::{self, OpenOptions};
-use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use chrono::{SecondsFormat, Utc};
-use serde::Serialize;
-use sha2::{Digest, Sha256};
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
 
-use crate::config::SynsyuConfig;
+use crate::config::{LogFormat, SynsyuConfig};
 use crate::error::{Result, SynsyuError};
+use crate::log_chain::{
+    append_chained, decode_log, is_log_name, seal_log, sidecar_path, write_sidecar,
+};
 
 #[derive(Debug, Serialize, Clone)]
 pub struct LogInit {
@@ -44,52 +45,302 @@ pub fn log_init(config: &SynsyuConfig) -> Result<LogInit> {
     })
 }
 
-pub fn log_emit(path: &PathBuf, level: &str, code: &str, message: &str) -> Result<()> {
-    let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
-    let payload = format!("{timestamp} [{}] [{}] {}\n", level, code, message);
-    OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(path)
-        .map_err(|err| {
-            SynsyuError::Filesystem(format!("Failed to open log {}: {err}", path.display()))
-        })?
-        .write_all(payload.as_bytes())
-        .map_err(|err| {
-            SynsyuError::Filesystem(format!("Failed to write log {}: {err}", path.display()))
-        })?;
-    Ok(())
+/// Optional typed fields attached to a log entry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LogFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// SHA-256 of the previous line in the same log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
 }
 
-pub fn log_hash(path: &PathBuf) -> Result<PathBuf> {
-    let data = fs::read(path).map_err(|err| {
-        SynsyuError::Filesystem(format!("Failed to read log {}: {err}", path.display()))
-    })?;
-    let mut hasher = Sha256::new();
-    hasher.update(&data);
-    let digest = hasher.finalize();
-    let mut hash_os = path.as_os_str().to_os_string();
-    hash_os.push(".hash");
-    let hash_path = PathBuf::from(hash_os);
-    let mut file = fs::File::create(&hash_path).map_err(|err| {
-        SynsyuError::Filesystem(format!(
-            "Failed to create hash {}: {err}",
-            hash_path.display()
-        ))
-    })?;
-    writeln!(
-        file,
-        "{:x}  {}",
-        digest,
-        path.file_name().unwrap_or_default().to_string_lossy()
-    )
-    .map_err(|err| {
-        SynsyuError::Filesystem(format!(
-            "Failed to write hash {}: {err}",
-            hash_path.display()
-        ))
-    })?;
-    Ok(hash_path)
+impl LogFields {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `key=value` pairs in the text format's trailing field list.
+    fn to_text(&self) -> String {
+        let mut pairs = Vec::new();
+        if let Some(value) = &self.run_id {
+            pairs.push(format!("run_id={value}"));
+        }
+        if let Some(value) = &self.package {
+            pairs.push(format!("package={value}"));
+        }
+        if let Some(value) = &self.source {
+            pairs.push(format!("source={value}"));
+        }
+        if let Some(value) = self.duration_ms {
+            pairs.push(format!("duration_ms={value}"));
+        }
+        if let Some(value) = &self.prev {
+            pairs.push(format!("prev={value}"));
+        }
+        pairs.join(" ")
+    }
+
+    /// Parse a trailing field list; `None` unless every token is a known key.
+    fn from_text(text: &str) -> Option<Self> {
+        let mut fields = Self::default();
+        for token in text.split_whitespace() {
+            let (key, value) = token.split_once('=')?;
+            match key {
+                "run_id" => fields.run_id = Some(value.to_string()),
+                "package" => fields.package = Some(value.to_string()),
+                "source" => fields.source = Some(value.to_string()),
+                "duration_ms" => fields.duration_ms = Some(value.parse().ok()?),
+                "prev" => fields.prev = Some(value.to_string()),
+                _ => return None,
+            }
+        }
+        (!fields.is_empty()).then_some(fields)
+    }
+}
+
+/// One log entry, as written in either format.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LogRecord {
+    pub timestamp: String,
+    pub level: String,
+    pub code: String,
+    pub message: String,
+    #[serde(flatten)]
+    pub fields: LogFields,
+}
+
+impl LogRecord {
+    /// Entry stamped with the current UTC time.
+    pub fn now(level: &str, code: &str, message: &str, fields: LogFields) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            level: level.to_ascii_uppercase(),
+            code: code.to_string(),
+            message: message.to_string(),
+            fields,
+        }
+    }
+
+    /// Render as one line (without the trailing newline).
+    pub fn render(&self, format: LogFormat) -> String {
+        match format {
+            LogFormat::Json => serde_json::to_string(self).unwrap_or_default(),
+            LogFormat::Text => {
+                let line = format!(
+                    "{} [{}] [{}] {}",
+                    self.timestamp, self.level, self.code, self.message
+                );
+                if self.fields.is_empty() {
+                    line
+                } else {
+                    format!("{line} | {}", self.fields.to_text())
+                }
+            }
+        }
+    }
+
+    /// Parse a JSON-lines or text log line.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.starts_with('{') {
+            return serde_json::from_str(line).ok();
+        }
+        let (timestamp, rest) = line.split_once(' ')?;
+        let (level, rest) = rest.strip_prefix('[')?.split_once("] ")?;
+        let (code, message) = match rest.strip_prefix('[') {
+            Some(tagged) => tagged
+                .split_once("] ")
+                .or_else(|| tagged.strip_suffix(']').map(|code| (code, "")))?,
+            None => ("", rest),
+        };
+        let (message, fields) = match message.rsplit_once(" | ") {
+            Some((head, tail)) => match LogFields::from_text(tail) {
+                Some(fields) => (head, fields),
+                None => (message, LogFields::default()),
+            },
+            None => (message, LogFields::default()),
+        };
+        Some(Self {
+            timestamp: timestamp.to_string(),
+            level: level.to_string(),
+            code: code.to_string(),
+            message: message.to_string(),
+            fields,
+        })
+    }
+
+    fn time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+}
+
+/// Append `record` to the log at `path`, chained to the entry before it.
+pub fn log_emit(path: &Path, format: LogFormat, record: &LogRecord) -> Result<()> {
+    append_chained(path, format, record)
+}
+
+/// Filters for `logs query`; empty lists and `None` match everything.
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    pub levels: Vec<String>,
+    pub codes: Vec<String>,
+    pub run: Option<String>,
+    pub package: Option<String>,
+    pub source: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub contains: Option<String>,
+}
+
+impl LogQuery {
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        let level_ok = self.levels.is_empty()
+            || self
+                .levels
+                .iter()
+                .any(|level| normalize_level(level) == normalize_level(&record.level));
+        let code_ok = self.codes.is_empty()
+            || self
+                .codes
+                .iter()
+                .any(|code| code.eq_ignore_ascii_case(&record.code));
+        let run_ok = match (&self.run, &record.fields.run_id) {
+            (None, _) => true,
+            (Some(wanted), Some(run_id)) => wanted == run_id,
+            (Some(_), None) => false,
+        };
+        // Entries without a package field fall back to a whole-word match on
+        // the message so text logs written before typed fields still match.
+        let package_ok = match (&self.package, &record.fields.package) {
+            (None, _) => true,
+            (Some(wanted), Some(package)) => wanted == package,
+            (Some(wanted), None) => record
+                .message
+                .split(|c: char| c.is_whitespace() || matches!(c, ',' | ':' | ';' | '(' | ')'))
+                .any(|word| word == wanted),
+        };
+        let source_ok = match (&self.source, &record.fields.source) {
+            (None, _) => true,
+            (Some(wanted), Some(source)) => wanted.eq_ignore_ascii_case(source),
+            (Some(_), None) => false,
+        };
+        let contains_ok = self
+            .contains
+            .as_ref()
+            .map(|needle| record.message.contains(needle.as_str()))
+            .unwrap_or(true);
+        let time_ok = if self.since.is_none() && self.until.is_none() {
+            true
+        } else {
+            match record.time() {
+                Some(time) => {
+                    self.since.map(|since| time >= since).unwrap_or(true)
+                        && self.until.map(|until| time <= until).unwrap_or(true)
+                }
+                None => false,
+            }
+        };
+        level_ok && code_ok && run_ok && package_ok && source_ok && contains_ok && time_ok
+    }
+}
+
+fn normalize_level(level: &str) -> String {
+    match level.to_ascii_uppercase().as_str() {
+        "WARNING" => "WARN".to_string(),
+        "ERR" => "ERROR".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Parse a `--since`/`--until` bound: RFC 3339, `YYYY-MM-DD` (midnight UTC),
+/// or an age such as `30d`, `12h`, `45m`, `2w` counted back from `now`.
+pub fn parse_time_bound(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        if let Some(midnight) = date.and_hms_opt(0, 0, 0) {
+            return Ok(midnight.and_utc());
+        }
+    }
+    let unit_at = value.char_indices().last().map(|(at, _)| at).unwrap_or(0);
+    let (amount, unit) = value.split_at(unit_at);
+    if let Ok(amount) = amount.parse::<i64>() {
+        let age = match unit {
+            "s" => Duration::try_seconds(amount),
+            "m" => Duration::try_minutes(amount),
+            "h" => Duration::try_hours(amount),
+            "d" => Duration::try_days(amount),
+            "w" => Duration::try_weeks(amount),
+            _ => None,
+        };
+        if let Some(start) = age.and_then(|age| now.checked_sub_signed(age)) {
+            return Ok(start);
+        }
+    }
+    Err(SynsyuError::Config(format!(
+        "Invalid time '{value}'; use RFC 3339, YYYY-MM-DD, or an age like 30d"
+    )))
+}
+
+/// Log files in `dir`, including rotated `.log.gz` segments, oldest first by
+/// modification time then name.
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| is_log_name(&entry.file_name().to_string_lossy()))
+                .filter_map(|entry| {
+                    let meta = entry.metadata().ok()?;
+                    meta.is_file().then(|| {
+                        (
+                            meta.modified().unwrap_or(std::time::SystemTime::UNIX_EPOCH),
+                            entry.path(),
+                        )
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Matching records across every log in `dir`, ordered by timestamp.
+/// Lines that are neither JSON nor Syn-Syu text entries are skipped.
+pub fn query_logs(dir: &Path, query: &LogQuery) -> Vec<LogRecord> {
+    let mut records = Vec::new();
+    for path in log_files(dir) {
+        let Ok(data) = fs::read(&path) else {
+            continue;
+        };
+        let contents = decode_log(&path, &data);
+        records.extend(
+            contents
+                .lines()
+                .filter_map(LogRecord::parse)
+                .filter(|record| query.matches(record)),
+        );
+    }
+    records.sort_by_key(|record| record.time());
+    records
+}
+
+/// Seal the log (if it is not sealed yet) and write its `.hash` sidecar.
+pub fn log_hash(path: &Path) -> Result<PathBuf> {
+    seal_log(path)?;
+    write_sidecar(path)
 }
 
 pub fn log_prune(config: &SynsyuConfig) -> Result<()> {
@@ -113,12 +364,10 @@ pub fn log_prune(config: &SynsyuConfig) -> Result<()> {
                 if let Ok(meta) = entry.metadata() {
                     if meta.is_file()
                         && meta.modified().unwrap_or(std::time::SystemTime::now()) < cutoff
-                        && entry.file_name().to_string_lossy().ends_with(".log")
+                        && is_log_name(&entry.file_name().to_string_lossy())
                     {
                         let _ = fs::remove_file(entry.path());
-                        let mut hash_path = entry.path();
-                        hash_path.set_extension("log.hash");
-                        let _ = fs::remove_file(hash_path);
+                        let _ = fs::remove_file(sidecar_path(&entry.path()));
                     }
                 }
             }
@@ -130,7 +379,7 @@ pub fn log_prune(config: &SynsyuConfig) -> Result<()> {
         if let Ok(entries) = fs::read_dir(&dir) {
             for entry in entries.flatten() {
                 if let Ok(meta) = entry.metadata() {
-                    if meta.is_file() && entry.file_name().to_string_lossy().ends_with(".log") {
+                    if meta.is_file() && is_log_name(&entry.file_name().to_string_lossy()) {
                         logs.push((
                             meta.modified().unwrap_or(std::time::SystemTime::now()),
                             entry.path(),
@@ -147,12 +396,102 @@ pub fn log_prune(config: &SynsyuConfig) -> Result<()> {
                 break;
             }
             let _ = fs::remove_file(&path);
-            let mut hash_path = path.clone();
-            hash_path.set_extension("log.hash");
-            let _ = fs::remove_file(hash_path);
+            let _ = fs::remove_file(sidecar_path(&path));
             total = total.saturating_sub(size);
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn records_round_trip_in_both_formats() {
+        let record = LogRecord {
+            timestamp: "2025-06-01T10:00:00Z".into(),
+            level: "ERROR".into(),
+            code: "ACQUIRE".into(),
+            message: "AUR helper failed for foo | exit 1".into(),
+            fields: LogFields {
+                package: Some("foo".into()),
+                source: Some("AUR".into()),
+                duration_ms: Some(5321),
+                ..LogFields::default()
+            },
+        };
+        for format in [LogFormat::Text, LogFormat::Json] {
+            let line = record.render(format);
+            assert_eq!(LogRecord::parse(&line), Some(record.clone()), "{line}");
+        }
+        assert_eq!(
+            record.render(LogFormat::Text),
+            "2025-06-01T10:00:00Z [ERROR] [ACQUIRE] AUR helper failed for foo | exit 1 | package=foo source=AUR duration_ms=5321"
+        );
+
+        let legacy = LogRecord::parse("2025-06-01T10:00:00Z [INFO] [INIT] Syn-Syu Core awakening.")
+            .expect("legacy line");
+        assert_eq!(legacy.code, "INIT");
+        assert_eq!(legacy.fields, LogFields::default());
+        assert!(LogRecord::parse("garbage").is_none());
+    }
+
+    #[test]
+    fn query_filters_across_the_log_directory() {
+        let tmp = TempDir::new("synsyu_logq_").unwrap();
+        let dir = tmp.path();
+        fs::write(
+            dir.join("a.log"),
+            "2025-05-01T00:00:00Z [ERROR] [ACQUIRE] AUR helper retry budget exhausted for bar\n\
+             2025-06-01T00:00:00Z [INFO] [INIT] started\n",
+        )
+        .unwrap();
+        let json = LogRecord {
+            timestamp: "2025-06-10T00:00:00Z".into(),
+            level: "ERROR".into(),
+            code: "ACQUIRE".into(),
+            message: "AUR helper failure is terminal".into(),
+            fields: LogFields {
+                package: Some("foo".into()),
+                source: Some("AUR".into()),
+                ..LogFields::default()
+            },
+        };
+        fs::write(
+            dir.join("b.log"),
+            format!("{}\nnot a log line\n", json.render(LogFormat::Json)),
+        )
+        .unwrap();
+        fs::write(dir.join("b.log.hash"), "ignored").unwrap();
+
+        let now = DateTime::parse_from_rfc3339("2025-06-15T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let failed_aur = LogQuery {
+            levels: vec!["error".into()],
+            source: Some("aur".into()),
+            since: Some(parse_time_bound("30d", now).unwrap()),
+            ..LogQuery::default()
+        };
+        assert_eq!(query_logs(dir, &failed_aur), vec![json.clone()]);
+
+        let legacy_package = LogQuery {
+            package: Some("bar".into()),
+            ..LogQuery::default()
+        };
+        let hits = query_logs(dir, &legacy_package);
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].message.ends_with("bar"));
+
+        let by_code = LogQuery {
+            codes: vec!["acquire".into()],
+            until: Some(parse_time_bound("2025-06-01", now).unwrap()),
+            ..LogQuery::default()
+        };
+        assert_eq!(query_logs(dir, &by_code).len(), 1);
+        assert!(parse_time_bound("soon", now).is_err());
+    }
+}
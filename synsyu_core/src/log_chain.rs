@@ -0,0 +1,496 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::log_chain
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Hash-chain log entries (each entry carries the SHA-256 of
+    the line before it), seal a log at run end, write the
+    `.hash` sidecar, archive rotated segments as `.log.gz`,
+    and verify chains, seals, and sidecars.
+
+  Security / Safety Notes:
+    The chain is unkeyed: it exposes edits, deletions, and
+    insertions that were not followed by rewriting every later
+    line, the seal, and the sidecar. Verification is read-only.
+============================================================*/
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::LogFormat;
+use crate::error::{Result, SynsyuError};
+use crate::log_api::{LogFields, LogRecord};
+
+/// `prev` of the first entry in a log.
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Event code of the run-end seal.
+pub const SEAL_CODE: &str = "SEAL";
+
+/// Suffix of rotated, gzip-compressed log segments.
+pub const ROTATED_SUFFIX: &str = ".log.gz";
+
+/// Whether a file name is a log: plain `.log` or a rotated `.log.gz` segment.
+pub fn is_log_name(name: &str) -> bool {
+    name.ends_with(".log") || name.ends_with(ROTATED_SUFFIX)
+}
+
+/// Text of a log's raw bytes, decompressing rotated segments.
+pub fn decode_log(path: &Path, data: &[u8]) -> String {
+    if path.to_string_lossy().ends_with(ROTATED_SUFFIX) {
+        let mut text = String::new();
+        if GzDecoder::new(data).read_to_string(&mut text).is_ok() {
+            return text;
+        }
+    }
+    String::from_utf8_lossy(data).into_owned()
+}
+
+/// Next unused `<stem>.N.log.gz` beside the log at `path`.
+pub fn rotated_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "log".into());
+    (1..)
+        .map(|n| path.with_file_name(format!("{stem}.{n}{ROTATED_SUFFIX}")))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| path.with_extension("log.gz"))
+}
+
+/// Gzip the (sealed) log at `path` into its next segment, write the
+/// segment's sidecar, and remove the plain file. Returns the segment and the
+/// SHA-256 of its compressed bytes.
+pub fn archive_segment(path: &Path) -> Result<(PathBuf, String)> {
+    let data = fs::read(path).map_err(|err| {
+        SynsyuError::Filesystem(format!("Failed to read log {}: {err}", path.display()))
+    })?;
+    let segment = rotated_path(path);
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let compressed = encoder
+        .write_all(&data)
+        .and_then(|_| encoder.finish())
+        .map_err(|err| {
+            SynsyuError::Filesystem(format!("Failed to compress log {}: {err}", path.display()))
+        })?;
+    fs::write(&segment, &compressed).map_err(|err| {
+        SynsyuError::Filesystem(format!(
+            "Failed to write log segment {}: {err}",
+            segment.display()
+        ))
+    })?;
+    write_sidecar(&segment)?;
+    fs::remove_file(path).map_err(|err| {
+        SynsyuError::Filesystem(format!(
+            "Failed to remove rotated log {}: {err}",
+            path.display()
+        ))
+    })?;
+    let _ = fs::remove_file(sidecar_path(path));
+    Ok((segment, format!("{:x}", Sha256::digest(&compressed))))
+}
+
+/// SHA-256 of one log line, excluding its line terminator.
+pub fn line_hash(line: &str) -> String {
+    let digest = Sha256::digest(line.trim_end_matches(['\n', '\r']).as_bytes());
+    format!("{digest:x}")
+}
+
+/// `prev` for the next entry appended after `contents`.
+pub fn next_prev(contents: &str) -> String {
+    contents
+        .lines()
+        .last()
+        .filter(|line| !line.trim().is_empty())
+        .map(line_hash)
+        .unwrap_or_else(|| GENESIS.to_string())
+}
+
+/// Format of the entries already in a log; text when it is empty.
+pub fn detect_format(contents: &str) -> LogFormat {
+    match contents.lines().find(|line| !line.trim().is_empty()) {
+        Some(line) if line.starts_with('{') => LogFormat::Json,
+        _ => LogFormat::Text,
+    }
+}
+
+/// Whether the last entry of `contents` is a seal.
+pub fn is_sealed(contents: &str) -> bool {
+    contents
+        .lines()
+        .last()
+        .and_then(LogRecord::parse)
+        .map(|record| record.code == SEAL_CODE)
+        .unwrap_or(false)
+}
+
+/// Seal entry binding the last of `entries` entries through `prev`.
+pub fn seal_record(entries: usize, prev: String) -> LogRecord {
+    LogRecord::now(
+        "INFO",
+        SEAL_CODE,
+        &format!("Log sealed after {entries} entries"),
+        LogFields {
+            prev: Some(prev),
+            ..LogFields::default()
+        },
+    )
+}
+
+fn read_log(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(SynsyuError::Filesystem(format!(
+            "Failed to read log {}: {err}",
+            path.display()
+        ))),
+    }
+}
+
+/// Number of lines in the log at `path` (as `str::lines` would count them),
+/// streamed so long logs are never held in memory. Zero when it does not
+/// exist.
+pub fn count_lines(path: &Path) -> Result<usize> {
+    let fail = |err: std::io::Error| {
+        SynsyuError::Filesystem(format!("Failed to read log {}: {err}", path.display()))
+    };
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(fail(err)),
+    };
+    BufReader::new(file)
+        .split(b'\n')
+        .try_fold(0, |count, line| line.map(|_| count + 1))
+        .map_err(fail)
+}
+
+/// Last line of the log at `path` (as `str::lines` would yield it), read
+/// backwards from the end so appending stays cheap on long logs. Empty when
+/// the log does not exist.
+pub fn read_last_line(path: &Path) -> Result<String> {
+    const CHUNK: u64 = 4096;
+    let fail = |err: std::io::Error| {
+        SynsyuError::Filesystem(format!("Failed to read log {}: {err}", path.display()))
+    };
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(err) => return Err(fail(err)),
+    };
+    let mut end = file.seek(SeekFrom::End(0)).map_err(fail)?;
+    let mut tail: Vec<u8> = Vec::new();
+    let line = loop {
+        let start = end.saturating_sub(CHUNK);
+        let mut chunk = vec![0u8; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_exact(&mut chunk))
+            .map_err(fail)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+        end = start;
+        let body = tail.strip_suffix(b"\n").unwrap_or(&tail);
+        if let Some(pos) = body.iter().rposition(|byte| *byte == b'\n') {
+            break &body[pos + 1..];
+        }
+        if start == 0 {
+            break body;
+        }
+    };
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    Ok(String::from_utf8_lossy(line).into_owned())
+}
+
+/// Append `record` to the log at `path`, chained to its last entry.
+/// Refuses to write past a seal.
+pub fn append_chained(path: &Path, format: LogFormat, record: &LogRecord) -> Result<()> {
+    let last = read_last_line(path)?;
+    if is_sealed(&last) {
+        return Err(SynsyuError::Runtime(format!(
+            "Log {} is sealed; start a new log",
+            path.display()
+        )));
+    }
+    let mut record = record.clone();
+    record.fields.prev = Some(next_prev(&last));
+    let payload = format!("{}\n", record.render(format));
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(payload.as_bytes()))
+        .map_err(|err| {
+            SynsyuError::Filesystem(format!("Failed to write log {}: {err}", path.display()))
+        })
+}
+
+/// Seal the log at `path` in the format its entries use. Returns false when
+/// it was already sealed.
+pub fn seal_log(path: &Path) -> Result<bool> {
+    let contents = read_log(path)?;
+    if is_sealed(&contents) {
+        return Ok(false);
+    }
+    let entries = contents.lines().count();
+    let seal = seal_record(entries, next_prev(&contents));
+    let format = detect_format(&contents);
+    append_chained(path, format, &seal)?;
+    Ok(true)
+}
+
+/// `<log>.hash` next to a log file.
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut os = path.as_os_str().to_os_string();
+    os.push(".hash");
+    PathBuf::from(os)
+}
+
+/// Write the `sha256sum`-style sidecar for the log at `path`.
+pub fn write_sidecar(path: &Path) -> Result<PathBuf> {
+    let data = fs::read(path).map_err(|err| {
+        SynsyuError::Filesystem(format!("Failed to read log {}: {err}", path.display()))
+    })?;
+    let digest = Sha256::digest(&data);
+    let hash_path = sidecar_path(path);
+    fs::write(
+        &hash_path,
+        format!(
+            "{digest:x}  {}\n",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ),
+    )
+    .map_err(|err| {
+        SynsyuError::Filesystem(format!(
+            "Failed to write hash {}: {err}",
+            hash_path.display()
+        ))
+    })?;
+    Ok(hash_path)
+}
+
+/// State of a log's `.hash` sidecar.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SidecarState {
+    Missing,
+    Match,
+    Mismatch,
+}
+
+/// Verification outcome for one log file.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainReport {
+    pub path: PathBuf,
+    /// Entries excluding the seal.
+    pub entries: usize,
+    /// False for logs written before entries carried `prev` hashes.
+    pub chained: bool,
+    pub sealed: bool,
+    pub sidecar: SidecarState,
+    /// 1-based line number of the first entry that fails verification.
+    pub broken_line: Option<usize>,
+    pub problem: Option<String>,
+    pub ok: bool,
+}
+
+/// Walk the chain of the log at `path`, then check its seal and sidecar.
+pub fn verify_log(path: &Path) -> Result<ChainReport> {
+    let data = fs::read(path).map_err(|err| {
+        SynsyuError::Filesystem(format!("Failed to read log {}: {err}", path.display()))
+    })?;
+    let contents = decode_log(path, &data);
+    let mut report = ChainReport {
+        path: path.to_path_buf(),
+        entries: 0,
+        chained: false,
+        sealed: false,
+        sidecar: SidecarState::Missing,
+        broken_line: None,
+        problem: None,
+        ok: true,
+    };
+    let mut expected = GENESIS.to_string();
+    let mut chained: Option<bool> = None;
+    for (idx, line) in contents.lines().enumerate() {
+        let number = idx + 1;
+        if report.sealed {
+            break_at(&mut report, number, "entry written after the seal".into());
+            break;
+        }
+        let Some(record) = LogRecord::parse(line) else {
+            break_at(&mut report, number, "unparsable entry".into());
+            break;
+        };
+        match (&record.fields.prev, chained) {
+            (None, None) => chained = Some(false),
+            (None, Some(false)) => {}
+            (None, Some(true)) => {
+                break_at(&mut report, number, "entry has no prev hash".into());
+                break;
+            }
+            (Some(_), Some(false)) => {
+                break_at(
+                    &mut report,
+                    number,
+                    "chained entry follows unchained ones".into(),
+                );
+                break;
+            }
+            (Some(prev), _) => {
+                chained = Some(true);
+                if *prev != expected {
+                    let problem = if idx == 0 {
+                        "first entry does not start a chain".to_string()
+                    } else {
+                        format!("prev hash does not match line {idx}")
+                    };
+                    break_at(&mut report, number, problem);
+                    break;
+                }
+            }
+        }
+        if record.code == SEAL_CODE && chained == Some(true) {
+            report.sealed = true;
+        } else {
+            report.entries += 1;
+        }
+        expected = line_hash(line);
+    }
+    report.chained = chained.unwrap_or(false);
+
+    let hash_path = sidecar_path(path);
+    if let Ok(sidecar) = fs::read_to_string(&hash_path) {
+        let recorded = sidecar.split_whitespace().next().unwrap_or("");
+        let actual = format!("{:x}", Sha256::digest(&data));
+        report.sidecar = if recorded.eq_ignore_ascii_case(&actual) {
+            SidecarState::Match
+        } else {
+            SidecarState::Mismatch
+        };
+    }
+    if report.broken_line.is_none() {
+        if report.sidecar == SidecarState::Mismatch {
+            report.problem = Some(format!("{} does not match the log", hash_path.display()));
+        } else if report.chained && !report.sealed && report.sidecar == SidecarState::Match {
+            report.problem = Some("sidecar present but the log has no seal".into());
+        }
+    }
+    report.ok = report.problem.is_none();
+    Ok(report)
+}
+
+fn break_at(report: &mut ChainReport, line: usize, problem: String) {
+    if report.broken_line.is_none() {
+        report.broken_line = Some(line);
+        report.problem = Some(problem);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn entry(message: &str) -> LogRecord {
+        LogRecord::now("INFO", "TEST", message, LogFields::default())
+    }
+
+    #[test]
+    fn chained_logs_verify_and_edits_are_located() {
+        let dir = TempDir::new("synsyu_chain_").unwrap();
+        for format in [LogFormat::Text, LogFormat::Json] {
+            let path = dir.path().join(format!("{format}.log"));
+            for message in ["one", "two", "three"] {
+                append_chained(&path, format, &entry(message)).unwrap();
+            }
+            assert!(seal_log(&path).unwrap());
+            assert!(!seal_log(&path).unwrap(), "sealing twice is a no-op");
+            assert!(append_chained(&path, format, &entry("late")).is_err());
+            write_sidecar(&path).unwrap();
+
+            let report = verify_log(&path).unwrap();
+            assert!(report.ok, "{report:?}");
+            assert!(report.chained && report.sealed);
+            assert_eq!(report.entries, 3);
+            assert_eq!(report.sidecar, SidecarState::Match);
+
+            // Rewrite the second entry and regenerate the sidecar: the chain
+            // still points at the edited line.
+            let contents = fs::read_to_string(&path).unwrap();
+            fs::write(&path, contents.replacen("two", "TWO", 1)).unwrap();
+            write_sidecar(&path).unwrap();
+            let report = verify_log(&path).unwrap();
+            assert!(!report.ok);
+            assert_eq!(report.broken_line, Some(3));
+            assert_eq!(
+                report.problem.as_deref(),
+                Some("prev hash does not match line 2")
+            );
+
+            // Restore the entry but leave the stale sidecar.
+            fs::write(&path, &contents).unwrap();
+            fs::write(sidecar_path(&path), format!("{GENESIS}  x\n")).unwrap();
+            let report = verify_log(&path).unwrap();
+            assert_eq!(report.broken_line, None);
+            assert_eq!(report.sidecar, SidecarState::Mismatch);
+            assert!(!report.ok);
+        }
+    }
+
+    #[test]
+    fn last_line_matches_lines_across_chunks() {
+        let dir = TempDir::new("synsyu_chain_").unwrap();
+        let path = dir.path().join("tail.log");
+        assert_eq!(read_last_line(&path).unwrap(), "");
+        let long = "x".repeat(10_000);
+        for contents in [
+            String::new(),
+            "a".into(),
+            "a\n".into(),
+            "a\n\n".into(),
+            "a\r\nb\r\n".into(),
+            format!("first\n{long}\n"),
+            format!("{long}\nlast"),
+            format!("{long}\n\n"),
+        ] {
+            fs::write(&path, &contents).unwrap();
+            let expected = contents.lines().last().unwrap_or("");
+            assert_eq!(read_last_line(&path).unwrap(), expected, "{contents:.20?}");
+        }
+    }
+
+    #[test]
+    fn legacy_and_truncated_logs() {
+        let tmp = TempDir::new("synsyu_chain_legacy_").unwrap();
+        let dir = tmp.path();
+        let legacy = dir.join("legacy.log");
+        fs::write(
+            &legacy,
+            "2025-01-01T00:00:00Z [INFO] [INIT] start\n2025-01-01T00:00:01Z [INFO] [EXIT] done\n",
+        )
+        .unwrap();
+        let report = verify_log(&legacy).unwrap();
+        assert!(report.ok && !report.chained && !report.sealed);
+
+        // Dropping the seal after the sidecar was written is caught twice over.
+        let path = dir.join("cut.log");
+        append_chained(&path, LogFormat::Text, &entry("one")).unwrap();
+        seal_log(&path).unwrap();
+        write_sidecar(&path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        let first = contents.lines().next().unwrap();
+        fs::write(&path, format!("{first}\n")).unwrap();
+        let report = verify_log(&path).unwrap();
+        assert!(!report.ok && !report.sealed);
+        assert_eq!(report.sidecar, SidecarState::Mismatch);
+    }
+}
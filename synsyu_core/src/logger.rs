@@ -15,8 +15,10 @@
     std::fs::File, std::sync::Mutex, sha2 for integrity hashing.
 
   Operational Scope:
-    Used by runtime components to emit RFC-3339 UTC stamped
-    log entries and produce session hash digests.
+    Used by runtime components to emit RFC-3339 UTC stamped,
+    hash-chained log entries filtered by per-sink and per-code
+    level thresholds, rotate oversized logs into sealed
+    `.log.gz` segments, and seal them at session end.
 
   Revision History:
     2024-11-04 COD  Established logging module for Syn-Syu-Core.
@@ -27,15 +29,19 @@
     - Graceful error propagation on I/O failures
 ============================================================*/
 
+use std::collections::BTreeMap;
 use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-use chrono::{SecondsFormat, Utc};
-use sha2::{Digest, Sha256};
-
+use crate::config::LogFormat;
 use crate::error::{Result, SynsyuError};
+use crate::log_api::{LogFields, LogRecord};
+use crate::log_chain::{
+    archive_segment, count_lines, is_sealed, line_hash, next_prev, read_last_line, seal_record,
+    write_sidecar, GENESIS,
+};
 
 /// Structured log level for Syn-Syu-Core events.
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -57,11 +63,95 @@ impl LogLevel {
     }
 }
 
+/// Minimum level a sink accepts; `Off` accepts nothing.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum LevelFilter {
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Off,
+}
+
+impl LevelFilter {
+    /// Parse a `logging` level name (`warning` and `none` are accepted).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "debug" => Some(LevelFilter::Debug),
+            "info" => Some(LevelFilter::Info),
+            "warn" | "warning" => Some(LevelFilter::Warn),
+            "error" => Some(LevelFilter::Error),
+            "off" | "none" => Some(LevelFilter::Off),
+            _ => None,
+        }
+    }
+
+    fn allows(self, level: LogLevel) -> bool {
+        let rank = match level {
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Error => LevelFilter::Error,
+        };
+        self != LevelFilter::Off && rank >= self
+    }
+}
+
+/// Level thresholds for the file and stderr sinks plus per-code overrides.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogThresholds {
+    pub file: LevelFilter,
+    pub stderr: LevelFilter,
+    /// Keyed by upper-case event code. An override replaces the file
+    /// threshold; on stderr it can only make the threshold stricter.
+    pub codes: BTreeMap<String, LevelFilter>,
+}
+
+impl Default for LogThresholds {
+    fn default() -> Self {
+        Self {
+            file: LevelFilter::Info,
+            stderr: LevelFilter::Warn,
+            codes: BTreeMap::new(),
+        }
+    }
+}
+
+impl LogThresholds {
+    /// Whether `(level, code)` reaches the file and stderr respectively.
+    pub fn admits(&self, level: LogLevel, code: &str, verbose: bool) -> (bool, bool) {
+        let code_filter = self.codes.get(&code.to_ascii_uppercase()).copied();
+        let file = code_filter.unwrap_or(self.file);
+        let stderr = if verbose {
+            LevelFilter::Debug
+        } else {
+            self.stderr
+        };
+        let stderr = stderr.max(code_filter.unwrap_or(LevelFilter::Debug));
+        (file.allows(level), stderr.allows(level))
+    }
+}
+
+/// Open log file plus the chain state needed for the next entry.
+struct LogSink {
+    writer: BufWriter<File>,
+    prev: String,
+    entries: usize,
+    sealed: bool,
+    bytes: u64,
+}
+
 /// Shared logger that emits append-only entries in Synavera format.
 pub struct Logger {
-    file: Option<Mutex<BufWriter<File>>>,
+    file: Option<Mutex<LogSink>>,
     path: Option<PathBuf>,
     verbose: bool,
+    format: LogFormat,
+    thresholds: LogThresholds,
+    /// Rotate once the file reaches this many bytes; 0 disables rotation.
+    rotate_bytes: u64,
+    /// Correlation ID stamped on every entry.
+    run_id: Option<String>,
 }
 
 impl Logger {
@@ -77,17 +167,18 @@ impl Logger {
                 })?;
             }
 
-            let file = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(file_path)
-                .map_err(|err| {
-                    SynsyuError::Filesystem(format!(
-                        "Failed to open log file {}: {err}",
-                        file_path.display()
-                    ))
-                })?;
-            Some(Mutex::new(BufWriter::new(file)))
+            // Appending to an existing log continues its chain; only its last
+            // line is read, so long logs stay cheap to reopen.
+            let last = read_last_line(file_path)?;
+            Some(Mutex::new(LogSink {
+                writer: BufWriter::new(open_log(file_path)?),
+                prev: next_prev(&last),
+                entries: count_lines(file_path)?,
+                sealed: is_sealed(&last),
+                bytes: std::fs::metadata(file_path)
+                    .map(|meta| meta.len())
+                    .unwrap_or(0),
+            }))
         } else {
             None
         };
@@ -96,47 +187,134 @@ impl Logger {
             file,
             path,
             verbose,
+            format: LogFormat::Text,
+            thresholds: LogThresholds::default(),
+            rotate_bytes: 0,
+            run_id: None,
         })
     }
 
+    /// Filter entries per sink and per code.
+    pub fn with_thresholds(mut self, thresholds: LogThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Rotate the file into a sealed `.log.gz` segment whenever it reaches
+    /// `bytes`; 0 disables rotation.
+    pub fn with_rotation(mut self, bytes: u64) -> Self {
+        self.rotate_bytes = bytes;
+        self
+    }
+
+    /// Stamp every entry, including seals and rotation markers, with the
+    /// orchestrator's run ID.
+    pub fn with_run_id(mut self, run_id: Option<String>) -> Self {
+        self.run_id = run_id;
+        self
+    }
+
+    /// Write file entries in `format`; stderr stays plain text.
+    pub fn with_format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
     /// Emit a log entry with the given level, code, and message.
     pub fn log<S: AsRef<str>>(&self, level: LogLevel, code: &str, message: S) {
-        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
-        let payload = format!(
-            "{timestamp} [{}] [{}] {}",
-            level.as_str(),
-            code,
-            message.as_ref()
-        );
+        let mut record =
+            LogRecord::now(level.as_str(), code, message.as_ref(), LogFields::default());
+        let timestamp = record.timestamp.clone();
+        let (to_file, to_stderr) = self.thresholds.admits(level, code, self.verbose);
 
-        if self.verbose || level == LogLevel::Error || level == LogLevel::Warn {
-            eprintln!("{payload}");
+        if to_stderr {
+            eprintln!("{}", record.render(LogFormat::Text));
         }
 
+        if !to_file {
+            return;
+        }
         if let Some(file) = &self.file {
-            if let Ok(mut guard) = file.lock() {
-                if writeln!(guard, "{payload}").is_err() {
-                    eprintln!(
-                        "{} [{}] [{}] {}",
-                        timestamp,
-                        LogLevel::Error.as_str(),
-                        "LOGGER",
-                        "Failed to write to log file"
-                    );
+            if let Ok(mut sink) = file.lock() {
+                // Never append after a seal: archive the sealed log and
+                // continue in a fresh segment.
+                if sink.sealed {
+                    if let Err(err) = self.start_segment(&mut sink) {
+                        eprintln!(
+                            "{} [{}] [LOGGER] Log is sealed and a new segment could not be started: {err}",
+                            timestamp,
+                            LogLevel::Error.as_str()
+                        );
+                        return;
+                    }
                 }
-                if guard.flush().is_err() {
+                record.fields.run_id = self.run_id.clone();
+                record.fields.prev = Some(sink.prev.clone());
+                if sink.write(&record.render(self.format)).is_err() {
                     eprintln!(
-                        "{} [{}] [{}] {}",
+                        "{} [{}] [LOGGER] Failed to write to log file",
                         timestamp,
-                        LogLevel::Warn.as_str(),
-                        "LOGGER",
-                        "Failed to flush log writer"
+                        LogLevel::Error.as_str()
                     );
+                } else if self.rotate_bytes > 0 && sink.bytes >= self.rotate_bytes {
+                    if let Err(err) = self.rotate(&mut sink) {
+                        eprintln!(
+                            "{} [{}] [LOGGER] Log rotation failed: {err}",
+                            timestamp,
+                            LogLevel::Error.as_str()
+                        );
+                    }
                 }
             }
         }
     }
 
+    /// Seal the current file, archive it as the next `.log.gz` segment, and
+    /// start a fresh chain whose first entry names the segment and its hash.
+    fn rotate(&self, sink: &mut LogSink) -> Result<()> {
+        let Some(path) = self.path() else {
+            return Ok(());
+        };
+        let mut seal = seal_record(sink.entries, sink.prev.clone());
+        seal.fields.run_id = self.run_id.clone();
+        sink.write(&seal.render(self.format)).map_err(|err| {
+            SynsyuError::Filesystem(format!("Failed to seal log {}: {err}", path.display()))
+        })?;
+        self.start_segment(sink)
+    }
+
+    /// Archive the sealed file as the next `.log.gz` segment and start a
+    /// fresh chain whose first entry names the segment and its hash.
+    fn start_segment(&self, sink: &mut LogSink) -> Result<()> {
+        let Some(path) = self.path() else {
+            return Ok(());
+        };
+        let (segment, digest) = archive_segment(path)?;
+        *sink = LogSink {
+            writer: BufWriter::new(open_log(path)?),
+            prev: GENESIS.to_string(),
+            entries: 0,
+            sealed: false,
+            bytes: 0,
+        };
+        let record = LogRecord::now(
+            LogLevel::Info.as_str(),
+            "ROTATE",
+            &format!(
+                "Continues {} (sha256 {digest})",
+                segment.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            LogFields {
+                run_id: self.run_id.clone(),
+                prev: Some(GENESIS.to_string()),
+                ..LogFields::default()
+            },
+        );
+        sink.write(&record.render(self.format)).map_err(|err| {
+            SynsyuError::Filesystem(format!("Failed to write log {}: {err}", path.display()))
+        })
+    }
+
     /// Convenience wrapper for `INFO` level events.
     pub fn info<S: AsRef<str>>(&self, code: &str, message: S) {
         self.log(LogLevel::Info, code, message);
@@ -148,7 +326,6 @@ impl Logger {
     }
 
     /// Convenience wrapper for `ERROR` level events.
-    #[allow(dead_code)]
     pub fn error<S: AsRef<str>>(&self, code: &str, message: S) {
         self.log(LogLevel::Error, code, message);
     }
@@ -163,40 +340,184 @@ impl Logger {
         self.path.as_deref()
     }
 
-    /// Compute and persist SHA-256 digest of the log file.
+    /// Seal the log with a final chained entry and write its SHA-256 sidecar.
     pub fn finalize(&self) -> Result<()> {
-        if let Some(path) = self.path() {
-            let data = std::fs::read(path).map_err(|err| {
-                SynsyuError::Filesystem(format!(
-                    "Failed to read log for hashing {}: {err}",
-                    path.display()
-                ))
-            })?;
-            let mut hasher = Sha256::new();
-            hasher.update(&data);
-            let digest = hasher.finalize();
-            let mut hash_os = path.as_os_str().to_os_string();
-            hash_os.push(".hash");
-            let hash_path = PathBuf::from(hash_os);
-            let mut file = File::create(&hash_path).map_err(|err| {
-                SynsyuError::Filesystem(format!(
-                    "Failed to create hash file {}: {err}",
-                    hash_path.display()
-                ))
-            })?;
-            writeln!(
-                file,
-                "{:x}  {}",
-                digest,
-                path.file_name().unwrap_or_default().to_string_lossy()
-            )
-            .map_err(|err| {
-                SynsyuError::Filesystem(format!(
-                    "Failed to write hash file {}: {err}",
-                    hash_path.display()
-                ))
-            })?;
+        let Some(path) = self.path() else {
+            return Ok(());
+        };
+        if let Some(file) = &self.file {
+            if let Ok(mut sink) = file.lock() {
+                if !sink.sealed {
+                    let mut seal = seal_record(sink.entries, sink.prev.clone());
+                    seal.fields.run_id = self.run_id.clone();
+                    sink.write(&seal.render(self.format)).map_err(|err| {
+                        SynsyuError::Filesystem(format!(
+                            "Failed to seal log {}: {err}",
+                            path.display()
+                        ))
+                    })?;
+                    sink.sealed = true;
+                }
+            }
         }
+        write_sidecar(path)?;
         Ok(())
     }
 }
+
+impl LogSink {
+    /// Write and flush one line, then advance the chain to it.
+    fn write(&mut self, line: &str) -> std::io::Result<()> {
+        writeln!(self.writer, "{line}")?;
+        self.writer.flush()?;
+        self.prev = line_hash(line);
+        self.entries += 1;
+        self.bytes += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+fn open_log(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| {
+            SynsyuError::Filesystem(format!("Failed to open log file {}: {err}", path.display()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_api::{query_logs, LogQuery};
+    use crate::log_chain::verify_log;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn thresholds_apply_per_sink_and_per_code() {
+        let mut thresholds = LogThresholds::default();
+        thresholds.codes.insert("MIRRORS".into(), LevelFilter::Info);
+        thresholds
+            .codes
+            .insert("AUR_RPC".into(), LevelFilter::Debug);
+
+        assert_eq!(
+            thresholds.admits(LogLevel::Debug, "PACKAGES", false),
+            (false, false)
+        );
+        assert_eq!(
+            thresholds.admits(LogLevel::Warn, "PACKAGES", false),
+            (true, true)
+        );
+        // Overrides replace the file threshold but only tighten stderr.
+        assert_eq!(
+            thresholds.admits(LogLevel::Debug, "aur_rpc", false),
+            (true, false)
+        );
+        assert_eq!(
+            thresholds.admits(LogLevel::Debug, "MIRRORS", true),
+            (false, false)
+        );
+        assert_eq!(
+            thresholds.admits(LogLevel::Debug, "PACKAGES", true),
+            (false, true)
+        );
+        assert!(!LevelFilter::Off.allows(LogLevel::Error));
+        assert_eq!(LevelFilter::parse("Warning"), Some(LevelFilter::Warn));
+    }
+
+    #[test]
+    fn rotation_archives_sealed_gzip_segments() {
+        let tmp = TempDir::new("synsyu_rotate_").unwrap();
+        let dir = tmp.path();
+        let path = dir.join("core_test.log");
+        let logger = Logger::new(Some(path.clone()), false)
+            .unwrap()
+            .with_rotation(400);
+        for n in 0..12 {
+            logger.info("TEST", format!("entry number {n}"));
+        }
+        logger.finalize().unwrap();
+
+        let segments: Vec<PathBuf> = (1..)
+            .map(|n| dir.join(format!("core_test.{n}.log.gz")))
+            .take_while(|p| p.exists())
+            .collect();
+        assert!(segments.len() >= 2, "{segments:?}");
+        for log in segments.iter().chain(std::iter::once(&path)) {
+            let report = verify_log(log).unwrap();
+            assert!(report.ok && report.sealed, "{report:?}");
+        }
+        let records = query_logs(
+            dir,
+            &LogQuery {
+                codes: vec!["TEST".into()],
+                ..LogQuery::default()
+            },
+        );
+        assert_eq!(records.len(), 12);
+    }
+
+    #[test]
+    fn reopening_a_log_never_appends_after_its_seal() {
+        let tmp = TempDir::new("synsyu_reopen_").unwrap();
+        let dir = tmp.path();
+        let path = dir.join("core_test.log");
+        let first = Logger::new(Some(path.clone()), false).unwrap();
+        first.info("TEST", "first run");
+        first.info("TEST", "still first run");
+        drop(first);
+
+        // An unsealed log continues its chain and its entry count.
+        let second = Logger::new(Some(path.clone()), false).unwrap();
+        second.info("TEST", "second run");
+        second.finalize().unwrap();
+        assert!(read_last_line(&path)
+            .unwrap()
+            .contains("Log sealed after 3 entries"));
+
+        // A sealed one is archived and the next entry starts a new segment.
+        let third = Logger::new(Some(path.clone()), false).unwrap();
+        third.info("TEST", "third run");
+        third.finalize().unwrap();
+
+        let segment = dir.join("core_test.1.log.gz");
+        let archived = verify_log(&segment).unwrap();
+        assert!(archived.ok && archived.sealed, "{archived:?}");
+        let current = verify_log(&path).unwrap();
+        assert!(current.ok && current.sealed, "{current:?}");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.matches("[SEAL]").count(), 1, "{contents}");
+        assert!(contents.contains("[ROTATE] Continues core_test.1.log.gz"));
+    }
+
+    #[test]
+    fn run_id_stamps_entries_for_cross_log_queries() {
+        let tmp = TempDir::new("synsyu_run_id_").unwrap();
+        let dir = tmp.path();
+        for (name, run_id) in [("a.log", "run-1"), ("b.log", "run-2"), ("c.log", "run-1")] {
+            let logger = Logger::new(Some(dir.join(name)), false)
+                .unwrap()
+                .with_run_id(Some(run_id.to_string()));
+            logger.info("TEST", format!("from {name}"));
+            logger.finalize().unwrap();
+        }
+        let legacy = Logger::new(Some(dir.join("d.log")), false).unwrap();
+        legacy.info("TEST", "no run");
+
+        let records = query_logs(
+            dir,
+            &LogQuery {
+                run: Some("run-1".into()),
+                ..LogQuery::default()
+            },
+        );
+        // Two entries and two seals, one pair per file.
+        assert_eq!(records.len(), 4);
+        assert!(records
+            .iter()
+            .all(|record| record.fields.run_id.as_deref() == Some("run-1")));
+        assert!(verify_log(&dir.join("a.log")).unwrap().ok);
+    }
+}
@@ -27,24 +27,43 @@
     - Configurable execution via CLI and config file
 ============================================================*/
 
+mod acquire;
+mod audit;
+mod build_history;
 mod build_info;
+mod clean;
 mod config;
+mod config_check;
+mod config_edit;
+mod config_layers;
+mod config_migrate;
+mod du;
 mod error;
 mod flatpak;
 mod future;
 mod fwupd;
+mod http;
+mod kernel;
 mod log_api;
+mod log_chain;
 mod logger;
 mod manifest;
+mod mirror_history;
+mod mirrorlist;
 mod mirrors;
 mod package_info;
 mod pacman;
 mod plan;
+mod prefetch;
+mod privilege;
+mod runs;
+mod signing;
 mod space;
+mod temp_dir;
 mod updates;
 
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
 use chrono::Utc;
@@ -52,21 +71,56 @@ use clap::{ArgAction, Parser, Subcommand};
 use std::io::IsTerminal;
 use std::io::{self, Write};
 
+use acquire::{acquire_repo_packages, manifest_mirror_servers, write_attempt_log, RepoAcquisition};
+use audit::{AuditAction, AuditEvent, AuditOutcome, Auditor};
+use build_history::{
+    append_sample, helper_build_dir, history_path as build_history_path, latest_samples,
+    measure_build, BuildMeasurement,
+};
 use build_info::BUILD_INFO;
-use config::SynsyuConfig;
-use error::Result;
+use clean::{
+    cache_roots, plan_versions, remove_packages, scan_cache_dir, select_removals, CleanReport,
+};
+use config::{MirrorConfig, MirrorIpVersion, NetworkConfig, SynsyuConfig};
+use error::{Result, SynsyuError};
 use flatpak::collect_flatpak;
 use fwupd::collect_fwupd;
-use log_api::{log_emit, log_hash, log_init, log_prune};
+use log_api::{
+    log_emit, log_files, log_hash, log_init, log_prune, parse_time_bound, query_logs, LogFields,
+    LogQuery, LogRecord,
+};
+use log_chain::{verify_log, SidecarState};
 use logger::Logger;
 use manifest::{build_manifest, write_manifest, ManifestDocument, NetworkState};
-use mirrors::collect_mirror_state;
+use mirror_history::{
+    compute_stats, flakiness_penalties, history_path, read_events, window_start, MirrorStatsReport,
+};
+use mirrorlist::{
+    load_status_feed, render_mirrorlist, select_mirrors, write_mirrorlist, GeneratedMirrorlist,
+    MirrorFilter,
+};
+use mirrors::{
+    collect_mirror_state, epoch_seconds, mirror_cache_path, pacman_arch, penalised_server_rank,
+    read_probe_cache,
+};
 use pacman::{
     enumerate_installed_packages, query_aur_helper_versions, query_repo_versions, InstalledPackage,
 };
 use plan::PlanCommand;
+use prefetch::{
+    build_report, install_into_cache, load_sync_dbs, pacman_cache_dir, pacman_print_targets,
+    plan_repo_targets, prefetch_packages, resolve_entries, staged_files, PrefetchRequest,
+};
+use runs::{
+    append_event, file_hash, find_run, load_runs, prune_runs, run_id_from_env, RunEvent, RunRecord,
+};
+use space::{PackageSizing, PhasePaths, RequirementSet, SpacePhase, SpaceRequirement};
 use updates::{collect_updates, UpdatesFilter};
 
+/// fwupd state directory where non-capsule firmware payloads are staged.
+const FWUPD_STATE_DIR: &str = "/var/lib/fwupd";
+const PACMAN_LOCAL_DB: &str = "/var/lib/pacman/local";
+
 /// Top-level CLI entrypoint.
 #[derive(Debug, Parser)]
 #[command(
@@ -78,6 +132,9 @@ use updates::{collect_updates, UpdatesFilter};
     arg_required_else_help = false
 )]
 struct Cli {
+    /// Apply the `[profiles.NAME]` overlay (same as `SYNSYU_PROFILE=NAME`).
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
     #[command(subcommand)]
     command: Option<Commands>,
     #[command(flatten)]
@@ -96,9 +153,29 @@ enum Commands {
     /// List applicable updates with filtering.
     Updates(UpdatesCommand),
     /// Logging helper commands.
-    Logs(LogsCommand),
+    Logs(Box<LogsCommand>),
     /// Inspect pacman mirror candidates and probe state.
     Mirrors(MirrorsCommand),
+    /// Acquire packages with bounded, classified failover.
+    Acquire(AcquireCommand),
+    /// Download and verify plan packages ahead of the transaction.
+    Prefetch(PrefetchCommand),
+    /// Measure AUR builds and inspect recorded build sizes.
+    BuildHistory(BuildHistoryCommand),
+    /// Prune cached package archives.
+    Clean(CleanCommand),
+    /// Kernel and boot image checks.
+    Kernel(KernelCommand),
+    /// Report disk usage by package, source, Flatpak, cache, and log.
+    Du(DuCommand),
+    /// Record audit events to the configured backends.
+    Audit(AuditCommand),
+    /// Record and inspect the per-run journal.
+    Runs(RunsCommand),
+    /// Sign manifests and plans, or generate the signing key.
+    Sign(SignCommand),
+    /// Verify detached signatures of manifests and plans.
+    Verify(VerifyCommand),
 }
 
 /// Core manifest-building arguments (also used as default when no subcommand is given).
@@ -154,6 +231,533 @@ struct ConfigCommand {
     /// Emit JSON output.
     #[arg(long, action = ArgAction::SetTrue)]
     json: bool,
+    /// Show every effective key with the layer that set it.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "merged")]
+    explain: bool,
+    /// Validate every layer: unknown keys, ranges, and conflicting settings.
+    /// Exits 1 when any issue is found.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with_all = ["merged", "explain"])]
+    check: bool,
+    /// Print the merged system, user, environment, and CLI layers as JSON,
+    /// without built-in defaults.
+    #[arg(long, action = ArgAction::SetTrue)]
+    merged: bool,
+    /// Override one key for this invocation (repeatable), e.g. `space.mode=warn`.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+    #[command(subcommand)]
+    action: Option<ConfigAction>,
+}
+
+/// Configuration file maintenance.
+#[derive(Debug, Subcommand, Clone)]
+enum ConfigAction {
+    /// Rewrite legacy keys into the current layout, keeping comments, and
+    /// stamp `config_version`.
+    Migrate(ConfigMigrateCommand),
+    /// Print the effective value of one dotted key; exits 1 when it is unset.
+    Get(ConfigGetCommand),
+    /// Change one dotted key in the user file, validating it first.
+    Set(ConfigSetCommand),
+}
+
+#[derive(Debug, Parser, Clone)]
+struct ConfigGetCommand {
+    /// Dotted key, e.g. `space.mode` or `helpers.priority`.
+    key: String,
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Emit the value as JSON.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct ConfigSetCommand {
+    /// Dotted key, e.g. `helpers.default`.
+    key: String,
+    /// TOML literal (`5`, `true`, `["paru", "yay"]`) or plain text.
+    #[arg(allow_hyphen_values = true)]
+    value: String,
+    /// File to edit (defaults to ~/.config/syn-syu/config.toml).
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct ConfigMigrateCommand {
+    /// File to migrate (defaults to ~/.config/syn-syu/config.toml).
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Show the diff without writing.
+    #[arg(long, action = ArgAction::SetTrue)]
+    dry_run: bool,
+}
+
+/// Package acquisition subcommand.
+#[derive(Debug, Parser, Clone)]
+struct AcquireCommand {
+    #[command(subcommand)]
+    target: AcquireTarget,
+}
+
+/// Acquisition sources.
+#[derive(Debug, Subcommand, Clone)]
+enum AcquireTarget {
+    /// Install official repo packages with mirror failover.
+    Repo(AcquireRepoCommand),
+}
+
+/// Repo acquisition arguments.
+#[derive(Debug, Parser, Clone)]
+struct AcquireRepoCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Manifest providing ranked mirror candidates (defaults to core.manifest_path).
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<PathBuf>,
+    /// Pass --noconfirm to pacman.
+    #[arg(long, action = ArgAction::SetTrue)]
+    noconfirm: bool,
+    /// Do not probe mirrors when the manifest has no mirror state.
+    #[arg(long, action = ArgAction::SetTrue)]
+    offline: bool,
+    /// Write the JSON attempt log to PATH.
+    #[arg(long = "attempt-log", value_name = "PATH")]
+    attempt_log: Option<PathBuf>,
+    /// Print the JSON attempt log to stdout.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+    /// Repo packages to install.
+    #[arg(value_name = "PKG", required = true)]
+    packages: Vec<String>,
+}
+
+/// Run journal subcommands.
+#[derive(Debug, Parser, Clone)]
+struct RunsCommand {
+    #[command(subcommand)]
+    action: RunsAction,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum RunsAction {
+    /// List recorded runs, newest last.
+    List(RunsListCommand),
+    /// Show one run by ID or unique ID prefix.
+    Show(RunsShowCommand),
+    /// Show the most recent run.
+    Last(RunsLastCommand),
+    /// Open a run: command, arguments, config hash, and log path.
+    Start(RunsStartCommand),
+    /// Record the manifest the run acts on.
+    Manifest(RunsManifestCommand),
+    /// Record the plan file the run produces or acts on.
+    Plan(RunsPlanCommand),
+    /// Record a finished phase and its duration.
+    Phase(RunsPhaseCommand),
+    /// Record a per-item outcome.
+    Item(RunsItemCommand),
+    /// Record a snapshot and its ID.
+    Snapshot(RunsSnapshotCommand),
+    /// Close a run with its exit status.
+    End(RunsEndCommand),
+}
+
+/// Run selected by `--run-id`.
+#[derive(Debug, Parser, Clone)]
+struct RunTarget {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Run to append to.
+    #[arg(long = "run-id", value_name = "ID")]
+    run_id: String,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsListCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Only runs started at or after this time (RFC 3339, YYYY-MM-DD, or an age such as 7d).
+    #[arg(long, value_name = "TIME")]
+    since: Option<String>,
+    /// Only runs started before this time.
+    #[arg(long, value_name = "TIME")]
+    until: Option<String>,
+    /// Only runs of this command (sync, update, ...).
+    #[arg(long, value_name = "CMD")]
+    command: Option<String>,
+    /// Keep only the newest N runs (0 = all).
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    limit: usize,
+    /// Emit JSON output.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsShowCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Run ID or unique prefix.
+    #[arg(value_name = "RUN_ID")]
+    run_id: String,
+    /// Emit JSON output.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsLastCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Most recent run of this command.
+    #[arg(long, value_name = "CMD")]
+    command: Option<String>,
+    /// Emit JSON output.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsStartCommand {
+    #[command(flatten)]
+    target: RunTarget,
+    /// Orchestrator command being run.
+    #[arg(long, value_name = "CMD")]
+    command: String,
+    /// Log file the run writes.
+    #[arg(long, value_name = "PATH")]
+    log: Option<PathBuf>,
+    /// Command arguments.
+    #[arg(value_name = "ARGS", last = true)]
+    arguments: Vec<String>,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsManifestCommand {
+    #[command(flatten)]
+    target: RunTarget,
+    /// Manifest file; its SHA-256 is recorded.
+    #[arg(long, value_name = "PATH")]
+    manifest: PathBuf,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsPlanCommand {
+    #[command(flatten)]
+    target: RunTarget,
+    /// Plan file; its SHA-256 is recorded.
+    #[arg(long, value_name = "PATH")]
+    plan: PathBuf,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsPhaseCommand {
+    #[command(flatten)]
+    target: RunTarget,
+    /// Phase name.
+    #[arg(long, value_name = "NAME")]
+    name: String,
+    #[arg(long = "duration-ms", value_name = "MS")]
+    duration_ms: u64,
+    /// Phase exit status (0 = success).
+    #[arg(
+        long,
+        value_name = "STATUS",
+        default_value_t = 0,
+        allow_hyphen_values = true
+    )]
+    status: i32,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsItemCommand {
+    #[command(flatten)]
+    target: RunTarget,
+    /// Package or application name.
+    #[arg(long, value_name = "NAME")]
+    name: String,
+    #[arg(long, value_name = "SOURCE")]
+    source: Option<String>,
+    /// success, failure, or skipped.
+    #[arg(long, value_name = "OUTCOME")]
+    outcome: String,
+    #[arg(long, value_name = "TEXT")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsSnapshotCommand {
+    #[command(flatten)]
+    target: RunTarget,
+    /// pre or post.
+    #[arg(long, value_name = "PHASE")]
+    phase: String,
+    /// Snapshot ID reported by the snapshot command.
+    #[arg(long, value_name = "ID")]
+    id: Option<String>,
+    #[arg(
+        long,
+        value_name = "STATUS",
+        default_value_t = 0,
+        allow_hyphen_values = true
+    )]
+    status: i32,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct RunsEndCommand {
+    #[command(flatten)]
+    target: RunTarget,
+    /// Run exit status.
+    #[arg(long, value_name = "STATUS", allow_hyphen_values = true)]
+    status: i32,
+}
+
+/// Audit subcommand.
+#[derive(Debug, Parser, Clone)]
+struct AuditCommand {
+    #[command(subcommand)]
+    action: AuditSubcommand,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum AuditSubcommand {
+    /// Record one event (package applied/failed, snapshot taken, config changed, ...).
+    Record(AuditRecordCommand),
+}
+
+/// Audit event arguments.
+#[derive(Debug, Parser, Clone)]
+struct AuditRecordCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// manifest_written, plan_generated, package_applied, package_failed,
+    /// snapshot_taken, or config_changed.
+    #[arg(long, value_name = "ACTION")]
+    action: AuditAction,
+    /// success or failure.
+    #[arg(long, value_name = "OUTCOME", default_value = "success")]
+    outcome: AuditOutcome,
+    /// Package, path, or phase the action applied to.
+    #[arg(long, value_name = "SUBJECT")]
+    subject: String,
+    /// Free-form detail such as a failure reason.
+    #[arg(long, value_name = "TEXT")]
+    detail: Option<String>,
+}
+
+/// Signing arguments.
+#[derive(Debug, Parser, Clone)]
+struct SignCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Create the ed25519 key pair at `signing.key` (and its `.pub`).
+    #[arg(long = "generate-key", action = ArgAction::SetTrue)]
+    generate_key: bool,
+    /// Replace an existing key pair.
+    #[arg(long, action = ArgAction::SetTrue, requires = "generate_key")]
+    force: bool,
+    /// Files to sign; each gets a `<file>.sig`.
+    #[arg(value_name = "FILE", required_unless_present = "generate_key")]
+    files: Vec<PathBuf>,
+}
+
+/// Signature verification arguments.
+#[derive(Debug, Parser, Clone)]
+struct VerifyCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Trust this public key file instead of `signing.trusted_keys` (repeatable).
+    #[arg(long = "key", value_name = "PATH", action = ArgAction::Append)]
+    keys: Vec<PathBuf>,
+    /// Emit results as JSON.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+    /// Manifests or plans to check against their `.sig`.
+    #[arg(value_name = "FILE", required = true)]
+    files: Vec<PathBuf>,
+}
+
+/// Disk usage report arguments.
+#[derive(Debug, Parser, Clone)]
+struct DuCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Override manifest path.
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<PathBuf>,
+    /// Number of largest packages to list (0 lists all).
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    top: usize,
+    /// Skip querying Flatpak.
+    #[arg(long = "no-flatpak", action = ArgAction::SetTrue)]
+    no_flatpak: bool,
+    /// Emit JSON output.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+}
+
+/// Kernel subcommand.
+#[derive(Debug, Parser, Clone)]
+struct KernelCommand {
+    #[command(subcommand)]
+    action: KernelAction,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum KernelAction {
+    /// Check every installed kernel against its module tree and boot images.
+    Verify(KernelVerifyCommand),
+}
+
+/// Kernel verification arguments.
+#[derive(Debug, Parser, Clone)]
+struct KernelVerifyCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Directory holding kernels and initramfs images.
+    #[arg(long = "boot-dir", value_name = "PATH", default_value = kernel::BOOT_DIR)]
+    boot_dir: PathBuf,
+    /// Emit JSON output.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+}
+
+/// Cleanup subcommand.
+#[derive(Debug, Parser, Clone)]
+struct CleanCommand {
+    #[command(subcommand)]
+    target: CleanTarget,
+}
+
+/// Cleanup targets.
+#[derive(Debug, Subcommand, Clone)]
+enum CleanTarget {
+    /// Remove old package archives from pacman and AUR helper caches.
+    Cache(CleanCacheCommand),
+}
+
+/// Package cache pruning arguments.
+#[derive(Debug, Parser, Clone)]
+struct CleanCacheCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Plan whose target versions must be kept.
+    #[arg(long, value_name = "PATH")]
+    plan: Option<PathBuf>,
+    /// Versions to keep per package and architecture (overrides clean.keep_versions).
+    #[arg(long, value_name = "N")]
+    keep: Option<u64>,
+    /// Scan only these directories instead of the configured caches.
+    #[arg(long = "cache-dir", value_name = "PATH", action = ArgAction::Append)]
+    cache_dirs: Vec<PathBuf>,
+    /// Skip AUR helper caches.
+    #[arg(long = "no-helper-caches", action = ArgAction::SetTrue)]
+    no_helper_caches: bool,
+    /// Report what would be removed without deleting anything.
+    #[arg(long = "dry-run", action = ArgAction::SetTrue)]
+    dry_run: bool,
+    /// Emit JSON output.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+}
+
+/// Build history subcommands.
+#[derive(Debug, Parser, Clone)]
+struct BuildHistoryCommand {
+    #[command(subcommand)]
+    action: BuildHistoryAction,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum BuildHistoryAction {
+    /// Run a build command and record its peak build-dir usage on success.
+    Record(BuildRecordCommand),
+    /// Show the latest recorded build per package.
+    List(BuildListCommand),
+}
+
+/// Build measurement arguments.
+#[derive(Debug, Parser, Clone)]
+struct BuildRecordCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// AUR package being built.
+    #[arg(long, value_name = "PKG")]
+    package: String,
+    /// Version being built.
+    #[arg(long, value_name = "VERSION", default_value = "")]
+    version: String,
+    /// Helper performing the build (selects its build directory layout).
+    #[arg(long, value_name = "HELPER")]
+    helper: Option<String>,
+    /// Build directory to watch (defaults to the helper's directory for PKG).
+    #[arg(long = "build-dir", value_name = "PATH")]
+    build_dir: Option<PathBuf>,
+    /// Build command and arguments.
+    #[arg(value_name = "CMD", required = true, last = true)]
+    command: Vec<String>,
+}
+
+/// Build history listing arguments.
+#[derive(Debug, Parser, Clone)]
+struct BuildListCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Emit JSON output.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
+}
+
+/// Parallel repo package prefetch arguments.
+#[derive(Debug, Parser, Clone)]
+struct PrefetchCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Plan JSON to prefetch (defaults to ~/.config/syn-syu/plan.json).
+    #[arg(long, value_name = "PATH")]
+    plan: Option<PathBuf>,
+    /// Manifest providing ranked mirror candidates (defaults to core.manifest_path).
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<PathBuf>,
+    /// Destination directory (defaults to prefetch.staging_dir).
+    #[arg(long, value_name = "PATH", conflicts_with = "cache_dir")]
+    dest: Option<PathBuf>,
+    /// Stage files, then install them into pacman's CacheDir via the privilege tool.
+    #[arg(long = "cache-dir", action = ArgAction::SetTrue)]
+    cache_dir: bool,
+    /// Override prefetch.max_concurrency.
+    #[arg(long = "max-concurrency", value_name = "N")]
+    max_concurrency: Option<usize>,
+    /// Override prefetch.max_kib_per_sec (0 = unlimited).
+    #[arg(long = "max-kib-per-sec", value_name = "KIB")]
+    max_kib_per_sec: Option<u64>,
+    /// Do not probe mirrors when the manifest has no mirror state.
+    #[arg(long, action = ArgAction::SetTrue)]
+    offline: bool,
+    /// Resolve and report targets without downloading.
+    #[arg(long = "dry-run", action = ArgAction::SetTrue)]
+    dry_run: bool,
+    /// Print the JSON report to stdout.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
 }
 
 /// Disk space assessment subcommand.
@@ -174,9 +778,16 @@ struct SpaceCommand {
     /// Extra disk margin in megabytes (adds to min-free).
     #[arg(long = "disk-margin-mb", value_name = "MB")]
     disk_margin_mb: Option<u64>,
+    /// Packages the transaction removes; their installed size is credited back.
+    #[arg(long = "remove", value_name = "PKG", action = ArgAction::Append)]
+    removals: Vec<String>,
     /// Target path to assess (falls back to defaults when omitted).
     #[arg(long = "path", value_name = "PATH")]
     path: Option<PathBuf>,
+    /// Plan whose Flatpak and firmware updates are charged (defaults to the
+    /// manifest's application state).
+    #[arg(long, value_name = "PATH")]
+    plan: Option<PathBuf>,
     /// Emit JSON output.
     #[arg(long, action = ArgAction::SetTrue)]
     json: bool,
@@ -232,6 +843,75 @@ struct LogsCommand {
     /// Explicit log path for emit.
     #[arg(long = "path", value_name = "PATH")]
     path: Option<PathBuf>,
+    /// Run identifier recorded with the emitted entry.
+    #[arg(long = "run-id", value_name = "ID", requires = "emit")]
+    run_id: Option<String>,
+    /// Package recorded with the emitted entry.
+    #[arg(long = "package", value_name = "PKG", requires = "emit")]
+    package: Option<String>,
+    /// Package source (PACMAN, AUR, ...) recorded with the emitted entry.
+    #[arg(long = "source", value_name = "SOURCE", requires = "emit")]
+    source: Option<String>,
+    /// Duration in milliseconds recorded with the emitted entry.
+    #[arg(long = "duration-ms", value_name = "MS", requires = "emit")]
+    duration_ms: Option<u64>,
+    /// Verify hash chains, seals, and sidecars of PATH (every log when omitted).
+    #[arg(long = "verify", value_name = "PATH", num_args = 0..=1)]
+    verify: Option<Option<PathBuf>>,
+    /// With --verify, check every log in the log directory.
+    #[arg(long = "all", action = ArgAction::SetTrue, requires = "verify", conflicts_with = "path")]
+    all: bool,
+    /// Emit --verify results as JSON.
+    #[arg(long, action = ArgAction::SetTrue, requires = "verify")]
+    json: bool,
+    #[command(subcommand)]
+    action: Option<LogsAction>,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum LogsAction {
+    /// Filter entries across the log directory.
+    Query(LogsQueryCommand),
+}
+
+#[derive(Debug, Parser, Clone)]
+struct LogsQueryCommand {
+    /// Override configuration file path.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Log directory to search (defaults to the configured one).
+    #[arg(long, value_name = "PATH")]
+    dir: Option<PathBuf>,
+    /// Match this level (repeatable): debug, info, warn, error.
+    #[arg(long = "level", value_name = "LEVEL", action = ArgAction::Append)]
+    levels: Vec<String>,
+    /// Match this event code (repeatable), e.g. ACQUIRE.
+    #[arg(long = "code", value_name = "CODE", action = ArgAction::Append)]
+    codes: Vec<String>,
+    /// Match entries stamped with this run ID, across orchestrator and core logs.
+    #[arg(long, value_name = "RUN_ID")]
+    run: Option<String>,
+    /// Match entries for this package.
+    #[arg(long, value_name = "PKG")]
+    package: Option<String>,
+    /// Match entries for this package source (PACMAN, AUR, ...).
+    #[arg(long, value_name = "SOURCE")]
+    source: Option<String>,
+    /// Earliest entry: RFC 3339, YYYY-MM-DD, or an age such as 30d or 12h.
+    #[arg(long, value_name = "TIME")]
+    since: Option<String>,
+    /// Latest entry, in the same forms as --since.
+    #[arg(long, value_name = "TIME")]
+    until: Option<String>,
+    /// Match entries whose message contains this text.
+    #[arg(long, value_name = "TEXT")]
+    contains: Option<String>,
+    /// Show only the newest N matches.
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+    /// Emit matches as JSON lines.
+    #[arg(long, action = ArgAction::SetTrue)]
+    json: bool,
 }
 
 /// Mirror inspection command.
@@ -252,11 +932,59 @@ struct MirrorsCommand {
     /// Skip network probing entirely.
     #[arg(long, action = ArgAction::SetTrue)]
     offline: bool,
+    /// Generate a ranked mirrorlist from the mirror status feed.
+    #[arg(long, action = ArgAction::SetTrue)]
+    generate: bool,
+    /// Mirror status JSON URL (overrides mirrors.generate.status_url).
+    #[arg(long = "status-url", value_name = "URL")]
+    status_url: Option<String>,
+    /// Read mirror status JSON from a local file instead of the URL.
+    #[arg(long = "status-file", value_name = "PATH")]
+    status_file: Option<PathBuf>,
+    /// Restrict to a country name or code (repeatable).
+    #[arg(long = "country", value_name = "COUNTRY", action = ArgAction::Append)]
+    countries: Vec<String>,
+    /// Restrict to a protocol: https, http or ftp (repeatable).
+    #[arg(long = "protocol", value_name = "PROTO", action = ArgAction::Append)]
+    protocols: Vec<String>,
+    /// Only mirrors reachable over IPv4.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "ipv6")]
+    ipv4: bool,
+    /// Only mirrors reachable over IPv6.
+    #[arg(long, action = ArgAction::SetTrue)]
+    ipv6: bool,
+    /// Minimum completion percentage (0-100).
+    #[arg(long = "min-completion", value_name = "PERCENT")]
+    min_completion: Option<f64>,
+    /// Maximum sync delay in minutes (0 disables the check).
+    #[arg(long = "max-delay-minutes", value_name = "MINUTES")]
+    max_delay_minutes: Option<u64>,
+    /// Maximum number of servers to emit (0 = unlimited).
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+    /// Write the generated mirrorlist to PATH instead of stdout.
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Show long-term mirror health statistics from the probe history.
+    #[arg(long, action = ArgAction::SetTrue)]
+    stats: bool,
+    /// Stats window in days (overrides mirrors.history.window_days).
+    #[arg(long, value_name = "N")]
+    days: Option<u64>,
 }
 
-#[tokio::main]
-async fn main() -> ExitCode {
-    match run().await {
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if let Some(profile) = &cli.profile {
+        // Exported so every config load, and any child process, sees it. Set
+        // before the runtime exists: no other thread can be reading the
+        // environment yet.
+        std::env::set_var(config_layers::PROFILE_ENV, profile);
+    }
+    let result = tokio::runtime::Runtime::new()
+        .map_err(SynsyuError::Io)
+        .and_then(|runtime| runtime.block_on(run(cli)));
+    match result {
         Ok(code) => code,
         Err(err) => {
             eprintln!("[Syn-Syu-Core] {}", err);
@@ -265,9 +993,7 @@ async fn main() -> ExitCode {
     }
 }
 
-async fn run() -> Result<ExitCode> {
-    let cli = Cli::parse();
-
+async fn run(cli: Cli) -> Result<ExitCode> {
     if let Some(cmd) = &cli.command {
         return match cmd {
             Commands::Plan(plan_cmd) => run_plan(plan_cmd).await,
@@ -276,6 +1002,27 @@ async fn run() -> Result<ExitCode> {
             Commands::Updates(up_cmd) => run_updates(up_cmd),
             Commands::Logs(log_cmd) => run_logs(log_cmd),
             Commands::Mirrors(mirror_cmd) => run_mirrors(mirror_cmd).await,
+            Commands::Acquire(acquire_cmd) => match &acquire_cmd.target {
+                AcquireTarget::Repo(repo_cmd) => run_acquire_repo(repo_cmd).await,
+            },
+            Commands::Prefetch(prefetch_cmd) => run_prefetch(prefetch_cmd).await,
+            Commands::BuildHistory(history_cmd) => match &history_cmd.action {
+                BuildHistoryAction::Record(record_cmd) => run_build_record(record_cmd).await,
+                BuildHistoryAction::List(list_cmd) => run_build_list(list_cmd),
+            },
+            Commands::Du(du_cmd) => run_du(du_cmd).await,
+            Commands::Runs(runs_cmd) => run_runs(&runs_cmd.action),
+            Commands::Sign(sign_cmd) => run_sign(sign_cmd),
+            Commands::Verify(verify_cmd) => run_verify(verify_cmd),
+            Commands::Audit(audit_cmd) => match &audit_cmd.action {
+                AuditSubcommand::Record(record_cmd) => run_audit_record(record_cmd),
+            },
+            Commands::Kernel(kernel_cmd) => match &kernel_cmd.action {
+                KernelAction::Verify(verify_cmd) => run_kernel_verify(verify_cmd).await,
+            },
+            Commands::Clean(clean_cmd) => match &clean_cmd.target {
+                CleanTarget::Cache(cache_cmd) => run_clean_cache(cache_cmd).await,
+            },
         };
     }
 
@@ -291,6 +1038,26 @@ async fn run_plan(cmd: &PlanCommand) -> Result<ExitCode> {
             .join("syn-syu/plan.json")
     });
     let output = cmd.execute(&config, plan_path.clone()).await?;
+    sign_output(&config, &plan_path, None)?;
+    let counts = output.plan_json.get("counts").cloned().unwrap_or_default();
+    let count = |key: &str| counts.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    emit_audit(
+        &config,
+        AuditEvent::new(
+            AuditAction::PlanGenerated,
+            AuditOutcome::Success,
+            plan_path.display().to_string(),
+        )
+        .with_detail(format!(
+            "pacman={} aur={} flatpak={} fwupd={} blocked={}",
+            count("pacman"),
+            count("aur"),
+            count("flatpak"),
+            count("fwupd"),
+            output.blocked
+        )),
+        None,
+    );
 
     if cmd.json {
         println!(
@@ -300,11 +1067,10 @@ async fn run_plan(cmd: &PlanCommand) -> Result<ExitCode> {
         return Ok(ExitCode::SUCCESS);
     }
 
-    let counts = output.plan_json.get("counts").cloned().unwrap_or_default();
-    let pac = counts.get("pacman").and_then(|v| v.as_u64()).unwrap_or(0);
-    let aur = counts.get("aur").and_then(|v| v.as_u64()).unwrap_or(0);
-    let flat = counts.get("flatpak").and_then(|v| v.as_u64()).unwrap_or(0);
-    let fw = counts.get("fwupd").and_then(|v| v.as_u64()).unwrap_or(0);
+    let pac = count("pacman");
+    let aur = count("aur");
+    let flat = count("flatpak");
+    let fw = count("fwupd");
     let total = pac + aur + flat + fw;
     let meta = output
         .plan_json
@@ -390,7 +1156,11 @@ async fn run_core(args: &CoreArgs) -> Result<ExitCode> {
         .log
         .clone()
         .or_else(|| Some(config.log_dir().join(format!("core_{session_stamp}.log"))));
-    let logger = Logger::new(log_path.clone(), args.verbose)?;
+    let logger = Logger::new(log_path.clone(), args.verbose)?
+        .with_format(config.logging.format)
+        .with_thresholds(config.logging.thresholds()?)
+        .with_rotation(config.logging.rotate_bytes())
+        .with_run_id(run_id_from_env());
     logger.info("INIT", "Syn-Syu Core awakening.");
     let aur_pkg = if BUILD_INFO.aur_pkgver.is_empty() {
         "n/a".to_string()
@@ -472,9 +1242,14 @@ async fn run_core(args: &CoreArgs) -> Result<ExitCode> {
     if args.no_mirrors {
         mirror_config.enabled = false;
     }
-    let mirror_state =
-        collect_mirror_state(&mirror_config, &logger, args.offline || args.no_repo).await;
-    document.network = Some(NetworkState {
+    let mirror_state = collect_mirror_state(
+        &mirror_config,
+        &config.network,
+        &logger,
+        args.offline || args.no_repo,
+    )
+    .await;
+    document.network = Some(NetworkState {
         mirrors: mirror_state,
     });
 
@@ -508,11 +1283,27 @@ async fn run_core(args: &CoreArgs) -> Result<ExitCode> {
     if args.dry_run {
         print_summary(&document);
     } else {
-        write_manifest(&document, &manifest_path)?;
+        let subject = manifest_path.display().to_string();
+        if let Err(err) = write_manifest(&document, &manifest_path) {
+            emit_audit(
+                &config,
+                AuditEvent::new(AuditAction::ManifestWritten, AuditOutcome::Failure, subject)
+                    .with_detail(err.to_string()),
+                Some(&logger),
+            );
+            return Err(err);
+        }
         logger.info(
             "MANIFEST",
             format!("Manifest written to {}", manifest_path.display()),
         );
+        sign_output(&config, &manifest_path, Some(&logger))?;
+        emit_audit(
+            &config,
+            AuditEvent::new(AuditAction::ManifestWritten, AuditOutcome::Success, subject)
+                .with_detail(format!("packages={}", document.metadata.total_packages)),
+            Some(&logger),
+        );
     }
 
     logger.info(
@@ -532,111 +1323,1419 @@ async fn run_core(args: &CoreArgs) -> Result<ExitCode> {
     Ok(ExitCode::SUCCESS)
 }
 
-fn run_config(cmd: &ConfigCommand) -> Result<ExitCode> {
+/// Fan `event` out to the configured audit backends. Backend failures are
+/// warnings: an unreachable journald must not fail the action being audited.
+fn emit_audit(config: &SynsyuConfig, event: AuditEvent, logger: Option<&Logger>) -> usize {
+    let warn = |message: String| match logger {
+        Some(logger) => logger.warn("AUDIT", message),
+        None => eprintln!("[Syn-Syu-Core] {message}"),
+    };
+    let auditor = match Auditor::from_config(&config.audit) {
+        Ok(auditor) => auditor,
+        Err(err) => {
+            warn(format!("Audit disabled: {err}"));
+            return 1;
+        }
+    };
+    if auditor.is_empty() {
+        return 0;
+    }
+    let failures = auditor.record(&event);
+    for failure in &failures {
+        warn(format!(
+            "Audit backend {} rejected {}: {}",
+            failure.backend, event.action, failure.error
+        ));
+    }
+    failures.len()
+}
+
+/// Sign a freshly written manifest or plan when `signing.enabled` is set.
+fn sign_output(config: &SynsyuConfig, path: &Path, logger: Option<&Logger>) -> Result<()> {
+    if !config.signing.enabled {
+        return Ok(());
+    }
+    let key = signing::load_signing_key(&config.signing.key_path())?;
+    let sig_path = signing::sign_file(path, &key)?;
+    let message = format!("Signed {} ({})", path.display(), sig_path.display());
+    match logger {
+        Some(logger) => logger.info("SIGN", message),
+        None => eprintln!("[Syn-Syu-Core] {message}"),
+    }
+    Ok(())
+}
+
+fn run_sign(cmd: &SignCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let secret = config.signing.key_path();
+    if cmd.generate_key {
+        let public = config.signing.public_key_path();
+        let key = signing::generate_key(&secret, &public, cmd.force)?;
+        println!("Private key: {}", secret.display());
+        println!("Public key : {}", public.display());
+        println!(
+            "ed25519 {}",
+            key.as_bytes()
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<String>()
+        );
+    }
+    if !cmd.files.is_empty() {
+        let key = signing::load_signing_key(&secret)?;
+        for file in &cmd.files {
+            println!("{}", signing::sign_file(file, &key)?.display());
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_verify(cmd: &VerifyCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let paths = if cmd.keys.is_empty() {
+        config.signing.trusted_key_paths()
+    } else {
+        cmd.keys.clone()
+    };
+    let trusted = signing::load_trusted_keys(&paths)?;
+    let reports = cmd
+        .files
+        .iter()
+        .map(|file| signing::verify_file(file, &trusted))
+        .collect::<Result<Vec<_>>>()?;
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).unwrap_or_else(|_| "[]".to_string())
+        );
+    } else {
+        for report in &reports {
+            let verdict = if report.ok { "OK" } else { "FAIL" };
+            match (&report.public_key, &report.signed_at) {
+                (Some(key), Some(at)) if report.ok => println!(
+                    "{verdict} {}: {} (key {}, signed {at})",
+                    report.path.display(),
+                    report.status.describe(),
+                    &key[..16.min(key.len())]
+                ),
+                _ => println!(
+                    "{verdict} {}: {}",
+                    report.path.display(),
+                    report.status.describe()
+                ),
+            }
+        }
+    }
+    Ok(if reports.iter().all(|report| report.ok) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    })
+}
+
+fn run_audit_record(cmd: &AuditRecordCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let mut event = AuditEvent::new(cmd.action, cmd.outcome, cmd.subject.clone());
+    if let Some(detail) = &cmd.detail {
+        event = event.with_detail(detail.clone());
+    }
+    if emit_audit(&config, event, None) > 0 {
+        return Ok(ExitCode::from(1));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_runs(action: &RunsAction) -> Result<ExitCode> {
+    let append = |target: &RunTarget, event: RunEvent| -> Result<ExitCode> {
+        let config = SynsyuConfig::load_from_optional_path(target.config.as_deref())?;
+        append_event(&config.runs.directory_path(), &target.run_id, event)?;
+        Ok(ExitCode::SUCCESS)
+    };
+    match action {
+        RunsAction::List(cmd) => run_runs_list(cmd),
+        RunsAction::Show(cmd) => {
+            let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+            let run = find_run(&config.runs.directory_path(), &cmd.run_id)?;
+            print_run(&run, cmd.json);
+            Ok(ExitCode::SUCCESS)
+        }
+        RunsAction::Last(cmd) => {
+            let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+            let dir = config.runs.directory_path();
+            let Some(run) = load_runs(&dir)
+                .into_iter()
+                .rev()
+                .find(|run| cmd.command.as_ref().is_none_or(|c| run.command == *c))
+            else {
+                eprintln!("No runs recorded in {}", dir.display());
+                return Ok(ExitCode::from(1));
+            };
+            print_run(&run, cmd.json);
+            Ok(ExitCode::SUCCESS)
+        }
+        RunsAction::Start(cmd) => {
+            let config = SynsyuConfig::load_from_optional_path(cmd.target.config.as_deref())?;
+            let config_path = cmd
+                .target
+                .config
+                .clone()
+                .or_else(config::default_config_path)
+                .filter(|path| path.is_file());
+            let dir = config.runs.directory_path();
+            append_event(
+                &dir,
+                &cmd.target.run_id,
+                RunEvent::Start {
+                    command: cmd.command.clone(),
+                    arguments: cmd.arguments.clone(),
+                    config_hash: config_path.as_deref().and_then(file_hash),
+                    config_path: config_path.map(|path| path.display().to_string()),
+                    log_path: cmd.log.as_ref().map(|path| path.display().to_string()),
+                },
+            )?;
+            prune_runs(&dir, config.runs.keep);
+            Ok(ExitCode::SUCCESS)
+        }
+        RunsAction::Manifest(cmd) => {
+            let hash = file_hash(&cmd.manifest).ok_or_else(|| {
+                SynsyuError::Filesystem(format!(
+                    "Failed to read manifest {}",
+                    cmd.manifest.display()
+                ))
+            })?;
+            append(
+                &cmd.target,
+                RunEvent::Manifest {
+                    path: cmd.manifest.display().to_string(),
+                    hash,
+                },
+            )
+        }
+        RunsAction::Plan(cmd) => {
+            let hash = file_hash(&cmd.plan).ok_or_else(|| {
+                SynsyuError::Filesystem(format!("Failed to read plan {}", cmd.plan.display()))
+            })?;
+            append(
+                &cmd.target,
+                RunEvent::Plan {
+                    path: cmd.plan.display().to_string(),
+                    hash,
+                },
+            )
+        }
+        RunsAction::Phase(cmd) => append(
+            &cmd.target,
+            RunEvent::Phase {
+                name: cmd.name.clone(),
+                duration_ms: cmd.duration_ms,
+                status: cmd.status,
+            },
+        ),
+        RunsAction::Item(cmd) => append(
+            &cmd.target,
+            RunEvent::Item {
+                name: cmd.name.clone(),
+                source: cmd.source.clone(),
+                outcome: cmd.outcome.clone(),
+                detail: cmd.detail.clone(),
+            },
+        ),
+        RunsAction::Snapshot(cmd) => append(
+            &cmd.target,
+            RunEvent::Snapshot {
+                phase: cmd.phase.clone(),
+                id: cmd.id.clone(),
+                status: cmd.status,
+            },
+        ),
+        RunsAction::End(cmd) => append(&cmd.target, RunEvent::End { status: cmd.status }),
+    }
+}
+
+fn run_runs_list(cmd: &RunsListCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let now = Utc::now();
+    let since = cmd
+        .since
+        .as_deref()
+        .map(|value| parse_time_bound(value, now))
+        .transpose()?;
+    let until = cmd
+        .until
+        .as_deref()
+        .map(|value| parse_time_bound(value, now))
+        .transpose()?;
+    let mut runs: Vec<RunRecord> = load_runs(&config.runs.directory_path())
+        .into_iter()
+        .filter(|run| cmd.command.as_ref().is_none_or(|c| run.command == *c))
+        .filter(|run| {
+            let started = run.started();
+            since.is_none_or(|bound| started.is_some_and(|t| t >= bound))
+                && until.is_none_or(|bound| started.is_some_and(|t| t < bound))
+        })
+        .collect();
+    if cmd.limit > 0 && runs.len() > cmd.limit {
+        runs.drain(..runs.len() - cmd.limit);
+    }
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&runs).unwrap_or_else(|_| "[]".to_string())
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+    if runs.is_empty() {
+        println!("No runs recorded.");
+    }
+    for run in &runs {
+        println!(
+            "{}  {}  {:<8} {:<10} {:>8}  items ok={} failed={}",
+            run.run_id,
+            run.started_at,
+            run.command,
+            run.status.to_string(),
+            run.duration_ms.map(format_ms).unwrap_or_else(|| "-".into()),
+            run.items_succeeded,
+            run.items_failed
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_run(run: &RunRecord, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(run).unwrap_or_else(|_| "{}".to_string())
+        );
+        return;
+    }
+    println!("Run {}", run.run_id);
+    println!("  command: {} {}", run.command, run.arguments.join(" "));
+    println!(
+        "  status: {}{}",
+        run.status,
+        run.exit_status
+            .map(|code| format!(" (exit {code})"))
+            .unwrap_or_default()
+    );
+    println!(
+        "  started: {}  ended: {}  duration: {}",
+        run.started_at,
+        run.ended_at.as_deref().unwrap_or("-"),
+        run.duration_ms.map(format_ms).unwrap_or_else(|| "-".into())
+    );
+    if let Some(path) = &run.config_path {
+        println!(
+            "  config: {path} (sha256 {})",
+            run.config_hash.as_deref().unwrap_or("-")
+        );
+    }
+    if let Some(path) = &run.manifest_path {
+        println!(
+            "  manifest: {path} (sha256 {})",
+            run.manifest_hash.as_deref().unwrap_or("-")
+        );
+    }
+    if let Some(path) = &run.plan_path {
+        println!(
+            "  plan: {path} (sha256 {})",
+            run.plan_hash.as_deref().unwrap_or("-")
+        );
+    }
+    if let Some(path) = &run.log_path {
+        println!("  log: {path}");
+    }
+    for snapshot in &run.snapshots {
+        println!(
+            "  snapshot {}: {} (status {})",
+            snapshot.phase,
+            snapshot.id.as_deref().unwrap_or("no id"),
+            snapshot.status
+        );
+    }
+    for phase in &run.phases {
+        println!(
+            "  phase {:<14} {:>8}  status {}",
+            phase.name,
+            format_ms(phase.duration_ms),
+            phase.status
+        );
+    }
+    for item in &run.items {
+        println!(
+            "  {:<8} {}{}{}",
+            item.outcome,
+            item.name,
+            item.source
+                .as_deref()
+                .map(|source| format!(" [{source}]"))
+                .unwrap_or_default(),
+            item.detail
+                .as_deref()
+                .map(|detail| format!(": {detail}"))
+                .unwrap_or_default()
+        );
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}.{}s", secs, (ms % 1000) / 100)
+    }
+}
+
+/// `--config PATH`, or the default user configuration file.
+fn user_config_path(explicit: &Option<PathBuf>) -> Result<PathBuf> {
+    explicit
+        .clone()
+        .or_else(config::default_config_path)
+        .ok_or_else(|| SynsyuError::Config("Unable to determine configuration path".into()))
+}
+
+fn run_config_get(cmd: &ConfigGetCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let Some(value) = config_edit::get_value(&config, &cmd.key)? else {
+        return Ok(ExitCode::from(1));
+    };
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string(&value).unwrap_or_else(|_| "null".to_string())
+        );
+    } else {
+        match value {
+            toml::Value::String(text) => println!("{text}"),
+            toml::Value::Table(table) => print!("{table}"),
+            other => println!("{other}"),
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_config_set(cmd: &ConfigSetCommand) -> Result<ExitCode> {
+    let path = user_config_path(&cmd.config)?;
+    let exists = path.exists();
+    let contents = if exists {
+        config::ensure_secure_permissions(&path)?;
+        std::fs::read_to_string(&path).map_err(|err| {
+            SynsyuError::Filesystem(format!(
+                "Failed to read configuration {}: {err}",
+                path.display()
+            ))
+        })?
+    } else if cmd.config.is_some() {
+        return Err(SynsyuError::Config(format!(
+            "Configuration file {} does not exist",
+            path.display()
+        )));
+    } else {
+        String::new()
+    };
+    let updated = config_edit::set_value(&contents, &cmd.key, &cmd.value)?;
+
+    let write_err = |err: std::io::Error| {
+        SynsyuError::Filesystem(format!("Failed to write {}: {err}", path.display()))
+    };
+    if exists {
+        std::fs::write(&path, &updated).map_err(write_err)?;
+    } else {
+        use std::os::unix::fs::OpenOptionsExt;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(write_err)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+            .map_err(write_err)?;
+        file.write_all(updated.as_bytes()).map_err(write_err)?;
+    }
+
+    let config = SynsyuConfig::load_from_optional_path(Some(&path))?;
+    let event = AuditEvent::new(
+        AuditAction::ConfigChanged,
+        AuditOutcome::Success,
+        path.display().to_string(),
+    )
+    // The key only: audit events never carry configuration values.
+    .with_detail(format!("set {}", cmd.key));
+    emit_audit(&config, event, None);
+    println!("Set {} = {} in {}", cmd.key, cmd.value, path.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_config_migrate(cmd: &ConfigMigrateCommand) -> Result<ExitCode> {
+    let path = user_config_path(&cmd.config)?;
+    if !path.exists() {
+        return Err(SynsyuError::Config(format!(
+            "Configuration file {} does not exist",
+            path.display()
+        )));
+    }
+    config::ensure_secure_permissions(&path)?;
+    let contents = std::fs::read_to_string(&path).map_err(|err| {
+        SynsyuError::Filesystem(format!(
+            "Failed to read configuration {}: {err}",
+            path.display()
+        ))
+    })?;
+    let migration = config_migrate::migrate_document(&contents)?;
+    if migration.is_noop() {
+        println!(
+            "{} is already at config_version {}",
+            path.display(),
+            config_migrate::CONFIG_VERSION
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+    for change in &migration.changes {
+        println!("- {change}");
+    }
+    print!(
+        "{}",
+        config_migrate::unified_diff(
+            &path.display().to_string(),
+            &migration.before,
+            &migration.after
+        )
+    );
+    if cmd.dry_run {
+        println!("Dry run: {} left unchanged", path.display());
+        return Ok(ExitCode::SUCCESS);
+    }
+    let backup = PathBuf::from(format!("{}.bak", path.display()));
+    std::fs::copy(&path, &backup).map_err(|err| {
+        SynsyuError::Filesystem(format!("Failed to back up {}: {err}", path.display()))
+    })?;
+    std::fs::write(&path, &migration.after).map_err(|err| {
+        SynsyuError::Filesystem(format!("Failed to write {}: {err}", path.display()))
+    })?;
+    println!("Wrote {} (backup: {})", path.display(), backup.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_config(cmd: &ConfigCommand) -> Result<ExitCode> {
+    match &cmd.action {
+        Some(ConfigAction::Migrate(migrate)) => return run_config_migrate(migrate),
+        Some(ConfigAction::Get(get)) => return run_config_get(get),
+        Some(ConfigAction::Set(set)) => return run_config_set(set),
+        None => {}
+    }
+    if cmd.check {
+        let raw = config_layers::collect_layered(cmd.config.as_deref(), &cmd.overrides)?;
+        let report = config_check::check_layers(raw);
+        if cmd.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+            );
+        } else if report.ok {
+            println!("Configuration OK");
+        } else {
+            for issue in &report.issues {
+                let key = if issue.key.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}: ", issue.key)
+                };
+                let source = issue
+                    .source
+                    .as_ref()
+                    .map(|source| format!(" ({}: {})", source.layer, source.origin))
+                    .unwrap_or_default();
+                println!("error: {key}{}{source}", issue.message);
+                if let Some(suggestion) = &issue.suggestion {
+                    println!("       did you mean `{suggestion}`?");
+                }
+            }
+        }
+        return Ok(if report.ok {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(1)
+        });
+    }
+    let layered = config_layers::load_layered(cmd.config.as_deref(), &cmd.overrides)?;
+    if cmd.merged {
+        println!(
+            "{}",
+            serde_json::to_string(&layered.merged).unwrap_or_else(|_| "{}".to_string())
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+    if cmd.explain {
+        let entries = layered.explain()?;
+        if cmd.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string())
+            );
+        } else {
+            for entry in entries {
+                let origin = entry
+                    .origin
+                    .map(|origin| format!("  ({origin})"))
+                    .unwrap_or_default();
+                let ignored = if entry.ignored {
+                    "  [unrecognised]"
+                } else {
+                    ""
+                };
+                println!(
+                    "{:<8} {} = {}{}{}",
+                    entry.layer, entry.key, entry.value, origin, ignored
+                );
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    let config = layered.config;
+    let report = config.to_report();
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+        );
+    } else {
+        println!(
+            "Profile : {}",
+            report.profile.as_deref().unwrap_or("<none>")
+        );
+        println!("Manifest: {}", report.manifest_path.display());
+        println!("Log dir : {}", report.log_directory.display());
+        println!("Batch   : {}", report.batch_size);
+        println!("Privs   : {}", report.privilege_tool);
+        println!(
+            "Helpers : {}",
+            if report.helper_priority.is_empty() {
+                "<none>".to_string()
+            } else {
+                report.helper_priority.join(", ")
+            }
+        );
+        println!(
+            "Space   : min_free_bytes={} policy={}",
+            report.space_min_free_bytes, report.space_policy
+        );
+        println!(
+            "Mirrors : enabled={} probe={} candidates={} failovers={}",
+            report.mirrors_enabled,
+            report.mirrors_probe,
+            report.mirrors_max_candidates,
+            report.mirrors_max_failovers
+        );
+        println!(
+            "Acquire : aur_rpc={} retries={} delay={}s aur_helper={} retries={} delay={}s",
+            report.acquisition_aur_rpc_enabled,
+            report.acquisition_aur_rpc_max_retries,
+            report.acquisition_aur_rpc_retry_delay_seconds,
+            report.acquisition_aur_helper_enabled,
+            report.acquisition_aur_helper_max_retries,
+            report.acquisition_aur_helper_retry_delay_seconds
+        );
+        println!(
+            "Apps    : flatpak={} fwupd={}",
+            report.applications_flatpak, report.applications_fwupd
+        );
+        println!(
+            "Prefetch: staging={} concurrency={} kib_per_sec={}",
+            report.prefetch_staging_dir.display(),
+            report.prefetch_max_concurrency,
+            report.prefetch_max_kib_per_sec
+        );
+        println!(
+            "Network : proxy={} no_proxy={} ca_bundle={} ip={} interface={}",
+            report.network_proxy.as_deref().unwrap_or("env"),
+            if report.network_no_proxy.is_empty() {
+                "-".to_string()
+            } else {
+                report.network_no_proxy.join(",")
+            },
+            report.network_ca_bundle.as_deref().unwrap_or("system"),
+            report.network_ip_version,
+            report.network_bind_interface.as_deref().unwrap_or("any")
+        );
+        println!("Agent   : {}", report.network_user_agent);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_mirrors(cmd: &MirrorsCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let mut mirror_config = config.mirrors.clone();
+    if cmd.mirrors {
+        mirror_config.enabled = true;
+    }
+    if cmd.no_probe {
+        mirror_config.probe = false;
+    }
+    if cmd.generate {
+        return run_mirrors_generate(cmd, &mirror_config, &config.network).await;
+    }
+    if cmd.stats {
+        return run_mirrors_stats(cmd, &mirror_config);
+    }
+    let logger = Logger::new(None, false)?.with_thresholds(config.logging.thresholds()?);
+    let state = collect_mirror_state(&mirror_config, &config.network, &logger, cmd.offline).await;
+
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&state).unwrap_or_else(|_| "{}".to_string())
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    println!("Mirror subsystem: {}", state.status);
+    println!("Enabled: {}", state.enabled);
+    println!("Source: {}", state.source);
+    if let Some(path) = &state.source_path {
+        println!("Source path: {path}");
+    }
+    println!("Pacman config: {}", state.pacman_conf_path);
+    println!(
+        "Usable candidates: {}/{}",
+        state.usable_count, state.candidate_count
+    );
+    println!(
+        "Bounds: max_failovers={} retry_delay_seconds={} timeout_seconds={}",
+        state.max_failovers, state.retry_delay_seconds, state.timeout_seconds
+    );
+    if let Some(reason) = &state.reason {
+        println!("Reason: {reason}");
+    }
+    for candidate in state.candidates {
+        let latency = candidate
+            .latency_ms
+            .map(|ms| format!("{ms}ms"))
+            .unwrap_or_else(|| "-".to_string());
+        let reason = candidate.reason.unwrap_or_default();
+        println!(
+            "{}. [{}] outcome={} freshness={} usable={} latency={} {} {}",
+            candidate.rank,
+            candidate.status,
+            candidate.outcome,
+            candidate.freshness,
+            candidate.usable,
+            latency,
+            candidate.server,
+            reason
+        );
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_mirrors_generate(
+    cmd: &MirrorsCommand,
+    mirror_config: &MirrorConfig,
+    network: &NetworkConfig,
+) -> Result<ExitCode> {
+    let mut generate = mirror_config.generate.clone();
+    if let Some(url) = &cmd.status_url {
+        generate.status_url = url.clone();
+        generate.status_path = None;
+    }
+    if let Some(path) = &cmd.status_file {
+        generate.status_path = Some(path.display().to_string());
+    }
+    if !cmd.countries.is_empty() {
+        generate.countries = cmd.countries.clone();
+    }
+    if !cmd.protocols.is_empty() {
+        generate.protocols = cmd.protocols.clone();
+    }
+    if cmd.ipv4 {
+        generate.ip_version = MirrorIpVersion::Ipv4;
+    } else if cmd.ipv6 {
+        generate.ip_version = MirrorIpVersion::Ipv6;
+    }
+    if let Some(percent) = cmd.min_completion {
+        generate.min_completion_percent = percent;
+    }
+    if let Some(minutes) = cmd.max_delay_minutes {
+        generate.max_delay_minutes = minutes;
+    }
+    if let Some(limit) = cmd.limit {
+        generate.limit = limit;
+    }
+    if generate.status_path.is_none() && cmd.offline {
+        return Err(SynsyuError::Config(
+            "--offline requires --status-file or mirrors.generate.status_path".into(),
+        ));
+    }
+
+    let filter = MirrorFilter::from_config(&generate);
+    filter.validate()?;
+
+    let (feed, source) = load_status_feed(&generate, network).await?;
+    let cache_path = mirror_cache_path(mirror_config);
+    let cache = read_probe_cache(&cache_path, mirror_config.cache_ttl_hours);
+    let penalties = if mirror_config.history.enabled {
+        let events = read_events(
+            &history_path(mirror_config),
+            window_start(epoch_seconds(), mirror_config.history.window_days),
+        );
+        flakiness_penalties(&compute_stats(&events), &mirror_config.history)
+    } else {
+        BTreeMap::new()
+    };
+    let (matched_count, mirrors) = select_mirrors(&feed, &filter, |server| {
+        penalised_server_rank(server, &cache, &penalties)
+    });
+    let list = GeneratedMirrorlist {
+        generated_at: mirrorlist::generated_at(),
+        source,
+        feed_last_check: feed.last_check.clone(),
+        filter,
+        probe_cache_path: cache_path.display().to_string(),
+        probe_cache_entries: cache.len(),
+        feed_count: feed.urls.len(),
+        matched_count,
+        mirrors,
+    };
+
+    if list.mirrors.is_empty() {
+        return Err(SynsyuError::Runtime(format!(
+            "No mirrors matched the generation filters ({} in feed)",
+            list.feed_count
+        )));
+    }
+
+    let rendered = render_mirrorlist(&list);
+    if let Some(target) = &cmd.output {
+        write_mirrorlist(target, &rendered)?;
+    }
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&list).unwrap_or_else(|_| "{}".to_string())
+        );
+    } else if let Some(target) = &cmd.output {
+        println!(
+            "Wrote {} mirrors to {}",
+            list.mirrors.len(),
+            target.display()
+        );
+    } else {
+        print!("{rendered}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_mirrors_stats(cmd: &MirrorsCommand, mirror_config: &MirrorConfig) -> Result<ExitCode> {
+    let window_days = cmd.days.unwrap_or(mirror_config.history.window_days);
+    let path = history_path(mirror_config);
+    let events = read_events(&path, window_start(epoch_seconds(), window_days));
+    let report = MirrorStatsReport {
+        history_path: path.display().to_string(),
+        window_days,
+        event_count: events.len(),
+        mirrors: compute_stats(&events),
+    };
+
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    println!("Mirror history: {}", report.history_path);
+    println!(
+        "Window: {} day(s), {} event(s)",
+        report.window_days, report.event_count
+    );
+    let penalties = flakiness_penalties(&report.mirrors, &mirror_config.history);
+    for entry in &report.mirrors {
+        let latency = |value: Option<u64>| {
+            value
+                .map(|ms| format!("{ms}ms"))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        println!(
+            "{:>5.1}% ok ({}/{}) p50={} p95={} stale={} failovers={}/{} penalty={} {}",
+            entry.success_rate * 100.0,
+            entry.successes,
+            entry.samples,
+            latency(entry.p50_latency_ms),
+            latency(entry.p95_latency_ms),
+            entry.staleness_incidents,
+            entry.failover_successes,
+            entry.failover_attempts,
+            penalties
+                .get(&entry.server)
+                .map(|ms| format!("{ms}ms"))
+                .unwrap_or_else(|| "-".to_string()),
+            entry.server
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_acquire_repo(cmd: &AcquireRepoCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let logger = Logger::new(None, false)?.with_thresholds(config.logging.thresholds()?);
+    let manifest_path = cmd
+        .manifest
+        .clone()
+        .unwrap_or_else(|| config.manifest_path());
+
+    let manifest = signing::load_optional_document(&config.signing, &manifest_path, "manifest")?;
+    let (servers, mirror_source) = match manifest.as_ref().and_then(manifest_mirror_servers) {
+        Some(servers) => (servers, format!("manifest:{}", manifest_path.display())),
+        None if config.mirrors.enabled => {
+            let state =
+                collect_mirror_state(&config.mirrors, &config.network, &logger, cmd.offline).await;
+            let servers = state
+                .candidates
+                .iter()
+                .filter(|c| c.usable)
+                .map(|c| c.server.clone())
+                .collect();
+            (servers, format!("probe:{}", state.status))
+        }
+        None => (Vec::new(), "disabled".to_string()),
+    };
+
+    let report = acquire_repo_packages(
+        RepoAcquisition {
+            packages: &cmd.packages,
+            servers,
+            mirror_source,
+            mirror_config: &config.mirrors,
+            core_config: &config.core,
+            noconfirm: cmd.noconfirm,
+        },
+        &logger,
+    )
+    .await;
+
+    if let Some(path) = &cmd.attempt_log {
+        write_attempt_log(path, &report)?;
+    }
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+        );
+    }
+    Ok(ExitCode::from(report.exit_code.clamp(0, 255) as u8))
+}
+
+async fn run_prefetch(cmd: &PrefetchCommand) -> Result<ExitCode> {
+    let mut config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    if let Some(value) = cmd.max_concurrency {
+        config.prefetch.max_concurrency = value;
+    }
+    if let Some(value) = cmd.max_kib_per_sec {
+        config.prefetch.max_kib_per_sec = value;
+    }
+    let logger = Logger::new(None, false)?.with_thresholds(config.logging.thresholds()?);
+    let plan_path = cmd.plan.clone().unwrap_or_else(|| {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("syn-syu/plan.json")
+    });
+    let plan = signing::load_document(&config.signing, &plan_path, "plan")?;
+
+    // pacman's CacheDir is root-owned: --cache-dir downloads into the staging
+    // directory and installs the verified files through the privilege tool.
+    let system_cache = pacman_cache_dir(std::path::Path::new(&config.mirrors.pacman_conf_path));
+    let destination = cmd
+        .dest
+        .clone()
+        .unwrap_or_else(|| config.prefetch.staging_path());
+
+    let (dbs, mut errors) = load_sync_dbs(std::path::Path::new(&config.prefetch.sync_db_dir));
+    let plan_targets = plan_repo_targets(&plan);
+    let names: Vec<String> = plan_targets.iter().map(|(name, _)| name.clone()).collect();
+    let pacman_targets = pacman_print_targets(&names).await;
+    if pacman_targets.is_none() {
+        logger.warn(
+            "PREFETCH",
+            "pacman -Sp unavailable; prefetching plan entries without dependency resolution",
+        );
+    }
+    let (entries, resolve_errors) = resolve_entries(&plan_targets, pacman_targets.as_deref(), &dbs);
+    errors.extend(resolve_errors);
+
+    let manifest_path = cmd
+        .manifest
+        .clone()
+        .unwrap_or_else(|| config.manifest_path());
+    let manifest = signing::load_optional_document(&config.signing, &manifest_path, "manifest")?;
+    let mut mirrors = match manifest.as_ref().and_then(manifest_mirror_servers) {
+        Some(servers) => servers,
+        None if config.mirrors.enabled => {
+            collect_mirror_state(&config.mirrors, &config.network, &logger, cmd.offline)
+                .await
+                .candidates
+                .into_iter()
+                .filter(|c| c.usable)
+                .map(|c| c.server)
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    mirrors.truncate(config.prefetch.max_mirrors.max(1));
+    if mirrors.is_empty() && !entries.is_empty() {
+        return Err(SynsyuError::Network(
+            "No usable mirrors available for prefetch".to_string(),
+        ));
+    }
+
+    logger.info(
+        "PREFETCH",
+        format!(
+            "Prefetching {} package(s) into {} via {} mirror(s)",
+            entries.len(),
+            destination.display(),
+            mirrors.len()
+        ),
+    );
+    let items = prefetch_packages(
+        PrefetchRequest {
+            entries,
+            mirrors: mirrors.clone(),
+            destination: destination.clone(),
+            reuse_dirs: vec![system_cache.clone()],
+            arch: pacman_arch(),
+            max_concurrency: config.prefetch.max_concurrency,
+            max_kib_per_sec: config.prefetch.max_kib_per_sec,
+            timeout_seconds: config.prefetch.timeout_seconds,
+            dry_run: cmd.dry_run,
+        },
+        &config.network,
+    )
+    .await?;
+
+    let staged = if cmd.cache_dir && !cmd.dry_run && destination != system_cache {
+        staged_files(&items, &destination)
+    } else {
+        Vec::new()
+    };
+    if !staged.is_empty() {
+        logger.info(
+            "PREFETCH",
+            format!(
+                "Installing {} verified file(s) into {}",
+                staged.len(),
+                system_cache.display()
+            ),
+        );
+        install_into_cache(&staged, &system_cache, &config.core.privilege_tool).await?;
+    }
+    let placed = if cmd.cache_dir {
+        &system_cache
+    } else {
+        &destination
+    };
+
+    let report = build_report(
+        &plan_path,
+        placed,
+        mirrors,
+        &config.prefetch,
+        items,
+        errors,
+        &system_cache,
+    );
+    for item in report.items.iter().filter(|i| i.status == "failed") {
+        logger.warn(
+            "PREFETCH",
+            format!(
+                "{}: {}",
+                item.filename,
+                item.reason.as_deref().unwrap_or("failed")
+            ),
+        );
+    }
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+        );
+    } else {
+        println!(
+            "Prefetch: {} downloaded, {} cached, {} failed ({} bytes) -> {}",
+            report.downloaded,
+            report.cached,
+            report.failed,
+            report.downloaded_bytes,
+            report.destination
+        );
+        if let Some(hint) = &report.pacman_hint {
+            println!("Install with: {hint}");
+        }
+    }
+    if report.failed > 0 || !report.errors.is_empty() {
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_du(cmd: &DuCommand) -> Result<ExitCode> {
     let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
-    let report = config.to_report();
+    let manifest_path = cmd
+        .manifest
+        .clone()
+        .unwrap_or_else(|| config.manifest_path());
+    let manifest = signing::load_document(&config.signing, &manifest_path, "manifest")?;
+
+    let flatpak = if cmd.no_flatpak {
+        Vec::new()
+    } else {
+        flatpak::capture_usage().await.unwrap_or_default()
+    };
+
+    let mut locations: Vec<du::LocationUsage> =
+        clean::pacman_cache_dirs(std::path::Path::new(&config.mirrors.pacman_conf_path))
+            .iter()
+            .map(|dir| du::location_usage("pacman_cache", dir))
+            .collect();
+    let mut build_dirs: Vec<PathBuf> = Vec::new();
+    for helper in config.helper_priority() {
+        let dir = config.space.aur_build_path(Some(helper));
+        if dir.exists() && !build_dirs.contains(&dir) {
+            build_dirs.push(dir);
+        }
+    }
+    locations.extend(
+        build_dirs
+            .iter()
+            .map(|dir| du::location_usage("aur_build_cache", dir)),
+    );
+    locations.push(du::location_usage("logs", &config.log_dir()));
+    let mut history = du::manifest_history_files(&manifest_path);
+    history.push(mirror_cache_path(&config.mirrors));
+    history.push(history_path(&config.mirrors));
+    history.push(build_history_path(&config.space));
+    locations.extend(
+        history
+            .iter()
+            .map(|path| du::location_usage("history", path))
+            .filter(|usage| usage.exists),
+    );
+
+    let report = du::build_report(&manifest, flatpak, locations, cmd.top);
     if cmd.json {
         println!(
             "{}",
             serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
         );
-    } else {
-        println!("Manifest: {}", report.manifest_path.display());
-        println!("Log dir : {}", report.log_directory.display());
-        println!("Batch   : {}", report.batch_size);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    println!(
+        "Packages by source ({} total)",
+        space::format_bytes(report.package_bytes)
+    );
+    println!("  {:<10} {:>7} {:>12}", "SOURCE", "COUNT", "SIZE");
+    for group in &report.by_source {
         println!(
-            "Helpers : {}",
-            if report.helper_priority.is_empty() {
-                "<none>".to_string()
-            } else {
-                report.helper_priority.join(", ")
-            }
+            "  {:<10} {:>7} {:>12}",
+            group.group,
+            group.count,
+            space::format_bytes(group.bytes)
         );
+    }
+    if !report.unsized_packages.is_empty() {
         println!(
-            "Space   : min_free_bytes={} policy={}",
-            report.space_min_free_bytes, report.space_policy
+            "  ({} package(s) without size data)",
+            report.unsized_packages.len()
         );
+    }
+    println!();
+    println!("Largest packages");
+    println!("  {:>12} {:<8} NAME", "SIZE", "SOURCE");
+    for pkg in &report.top_packages {
         println!(
-            "Mirrors : enabled={} probe={} candidates={} failovers={}",
-            report.mirrors_enabled,
-            report.mirrors_probe,
-            report.mirrors_max_candidates,
-            report.mirrors_max_failovers
+            "  {:>12} {:<8} {}",
+            space::format_bytes(pkg.installed_size),
+            pkg.source,
+            pkg.name
         );
+    }
+    if !report.flatpak.is_empty() {
+        println!();
         println!(
-            "Acquire : aur_rpc={} retries={} delay={}s aur_helper={} retries={} delay={}s",
-            report.acquisition_aur_rpc_enabled,
-            report.acquisition_aur_rpc_max_retries,
-            report.acquisition_aur_rpc_retry_delay_seconds,
-            report.acquisition_aur_helper_enabled,
-            report.acquisition_aur_helper_max_retries,
-            report.acquisition_aur_helper_retry_delay_seconds
+            "Flatpak ({} total)",
+            space::format_bytes(report.flatpak_bytes)
         );
+        for group in &report.flatpak_by_kind {
+            println!(
+                "  {:<10} {:>7} {:>12}",
+                group.group,
+                group.count,
+                space::format_bytes(group.bytes)
+            );
+        }
+        println!("  {:>12} {:<8} {:<7} REF", "SIZE", "KIND", "INSTALL");
+        let limit = if cmd.top == 0 {
+            report.flatpak.len()
+        } else {
+            cmd.top
+        };
+        for item in report.flatpak.iter().take(limit) {
+            println!(
+                "  {:>12} {:<8} {:<7} {}/{}",
+                item.installed_size
+                    .map(space::format_bytes)
+                    .unwrap_or_else(|| "?".to_string()),
+                item.kind,
+                item.installation,
+                item.application,
+                item.branch
+            );
+        }
+    }
+    println!();
+    println!(
+        "Caches, logs and history ({} total)",
+        space::format_bytes(report.locations_bytes)
+    );
+    println!("  {:>12} {:>8} {:<16} PATH", "SIZE", "FILES", "CATEGORY");
+    for location in &report.locations {
         println!(
-            "Apps    : flatpak={} fwupd={}",
-            report.applications_flatpak, report.applications_fwupd
+            "  {:>12} {:>8} {:<16} {}{}",
+            space::format_bytes(location.bytes),
+            location.files,
+            location.category,
+            location.path.display(),
+            if location.exists { "" } else { " (missing)" }
         );
     }
     Ok(ExitCode::SUCCESS)
 }
 
-async fn run_mirrors(cmd: &MirrorsCommand) -> Result<ExitCode> {
+async fn run_kernel_verify(cmd: &KernelVerifyCommand) -> Result<ExitCode> {
     let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
-    let mut mirror_config = config.mirrors.clone();
-    if cmd.mirrors {
-        mirror_config.enabled = true;
+    let modules_root = std::path::Path::new(kernel::MODULES_ROOT);
+    let esp = config.space.esp_path();
+    let checks: Vec<kernel::KernelCheck> = kernel::installed_kernels(modules_root)
+        .await?
+        .iter()
+        .map(|(package, owned)| {
+            kernel::verify_kernel(package, owned, modules_root, &cmd.boot_dir, esp.as_deref())
+        })
+        .collect();
+    let failed = checks.iter().any(|check| !check.problems.is_empty());
+
+    if cmd.json {
+        let output = serde_json::json!({
+            "ok": !failed,
+            "boot_dir": cmd.boot_dir,
+            "esp": esp,
+            "kernels": checks,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).unwrap_or_else(|_| "{}".to_string())
+        );
+    } else {
+        if checks.is_empty() {
+            println!("No installed kernel packages found");
+        }
+        for check in &checks {
+            if check.problems.is_empty() {
+                println!(
+                    "OK: {} {} ({} boot image(s))",
+                    check.package,
+                    check.modules_version.as_deref().unwrap_or("?"),
+                    check.images.len()
+                );
+            }
+            for problem in &check.problems {
+                eprintln!("!! BOOT MISMATCH: {}: {problem}", check.package);
+            }
+        }
+        if failed {
+            eprintln!("!! The system may not boot the updated kernel; fix the issues above before rebooting.");
+        }
     }
-    if cmd.no_probe {
-        mirror_config.probe = false;
+    Ok(if failed {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// Scan the configured package caches and select archives beyond
+/// `clean.keep_versions`, protecting installed and `planned` versions.
+async fn analyze_package_cache(
+    config: &SynsyuConfig,
+    roots: Vec<PathBuf>,
+    planned: &HashSet<(String, String)>,
+) -> Result<CleanReport> {
+    let installed: HashMap<String, String> = enumerate_installed_packages()
+        .await?
+        .into_iter()
+        .map(|pkg| (pkg.name, pkg.version))
+        .collect();
+    let packages = roots.iter().flat_map(|root| scan_cache_dir(root)).collect();
+    Ok(select_removals(
+        roots,
+        packages,
+        config.clean.keep_versions,
+        &installed,
+        planned,
+    ))
+}
+
+async fn run_clean_cache(cmd: &CleanCacheCommand) -> Result<ExitCode> {
+    let mut config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    if let Some(keep) = cmd.keep {
+        config.clean.keep_versions = keep;
+    }
+    if cmd.no_helper_caches {
+        config.clean.helper_caches = false;
     }
-    let logger = Logger::new(None, false)?;
-    let state = collect_mirror_state(&mirror_config, &logger, cmd.offline).await;
+    let roots = if cmd.cache_dirs.is_empty() {
+        cache_roots(
+            std::path::Path::new(&config.mirrors.pacman_conf_path),
+            &config.clean,
+            &config.space,
+            config.helper_priority(),
+        )
+    } else {
+        cmd.cache_dirs.clone()
+    };
+
+    // An explicit plan must be readable; the default one is optional.
+    let plan_path = cmd.plan.clone().unwrap_or_else(|| {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("syn-syu/plan.json")
+    });
+    let plan = if cmd.plan.is_some() {
+        Some(signing::load_document(&config.signing, &plan_path, "plan")?)
+    } else {
+        signing::load_optional_document(&config.signing, &plan_path, "plan")?
+    };
+    let planned = plan.as_ref().map(plan_versions).unwrap_or_default();
+
+    let report = analyze_package_cache(&config, roots, &planned).await?;
+    let removed = if cmd.dry_run || report.remove.is_empty() {
+        0
+    } else {
+        remove_packages(&report.remove, &config.core.privilege_tool)?
+    };
 
     if cmd.json {
+        let output = serde_json::json!({
+            "dry_run": cmd.dry_run,
+            "removed": removed,
+            "report": report,
+        });
         println!(
             "{}",
-            serde_json::to_string_pretty(&state).unwrap_or_else(|_| "{}".to_string())
+            serde_json::to_string_pretty(&output).unwrap_or_else(|_| "{}".to_string())
         );
         return Ok(ExitCode::SUCCESS);
     }
 
-    println!("Mirror subsystem: {}", state.status);
-    println!("Enabled: {}", state.enabled);
-    println!("Source: {}", state.source);
-    if let Some(path) = &state.source_path {
-        println!("Source path: {path}");
+    for path in &report.remove {
+        println!(
+            "{} {} ({})",
+            if cmd.dry_run {
+                "would remove"
+            } else {
+                "removed"
+            },
+            path.path.display(),
+            space::format_bytes(path.bytes)
+        );
     }
-    println!("Pacman config: {}", state.pacman_conf_path);
-    println!(
-        "Usable candidates: {}/{}",
-        state.usable_count, state.candidate_count
-    );
+    let verb = if cmd.dry_run { "Reclaimable" } else { "Freed" };
     println!(
-        "Bounds: max_failovers={} retry_delay_seconds={} timeout_seconds={}",
-        state.max_failovers, state.retry_delay_seconds, state.timeout_seconds
+        "{verb}: {} across {} package file(s); kept {} (keep_versions = {}, {} package(s) protected)",
+        space::format_bytes(report.reclaimable_bytes),
+        report.remove.len(),
+        report.kept,
+        report.keep_versions,
+        report.protected.len(),
     );
-    if let Some(reason) = &state.reason {
-        println!("Reason: {reason}");
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_build_record(cmd: &BuildRecordCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let build_dir = cmd.build_dir.clone().unwrap_or_else(|| {
+        helper_build_dir(
+            &config.space.aur_build_path(cmd.helper.as_deref()),
+            cmd.helper.as_deref(),
+            &cmd.package,
+        )
+    });
+    let (code, sample) = measure_build(BuildMeasurement {
+        package: &cmd.package,
+        version: &cmd.version,
+        helper: cmd.helper.as_deref(),
+        build_dir,
+        interval: std::time::Duration::from_millis(config.space.build_sample_interval_ms),
+        command: &cmd.command,
+    })
+    .await?;
+    if let Some(mut sample) = sample {
+        // The helper's download size is the AUR snapshot size that later plans
+        // and space checks scale this build against.
+        if let Some(helper) = cmd.helper.as_deref() {
+            sample.snapshot_bytes =
+                query_aur_helper_versions(helper, std::slice::from_ref(&cmd.package))
+                    .await
+                    .ok()
+                    .and_then(|sizes| sizes.get(&cmd.package)?.download_size)
+                    .unwrap_or(0);
+        }
+        // Measurement is advisory; never turn a successful build into a failure.
+        if let Err(err) = append_sample(
+            &build_history_path(&config.space),
+            &sample,
+            config.space.build_history_max_entries,
+        ) {
+            eprintln!("synsyu_core: unable to record build size: {err}");
+        }
     }
-    for candidate in state.candidates {
-        let latency = candidate
-            .latency_ms
-            .map(|ms| format!("{ms}ms"))
-            .unwrap_or_else(|| "-".to_string());
-        let reason = candidate.reason.unwrap_or_default();
+    Ok(ExitCode::from(code.clamp(0, 255) as u8))
+}
+
+fn run_build_list(cmd: &BuildListCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let path = build_history_path(&config.space);
+    let samples = latest_samples(&path);
+    if cmd.json {
+        let output = serde_json::json!({
+            "history_path": path,
+            "packages": samples,
+        });
         println!(
-            "{}. [{}] outcome={} freshness={} usable={} latency={} {} {}",
-            candidate.rank,
-            candidate.status,
-            candidate.outcome,
-            candidate.freshness,
-            candidate.usable,
-            latency,
-            candidate.server,
-            reason
+            "{}",
+            serde_json::to_string_pretty(&output).unwrap_or_else(|_| "{}".to_string())
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+    if samples.is_empty() {
+        println!("No AUR builds recorded in {}", path.display());
+    }
+    for sample in samples.values() {
+        println!(
+            "{} {}: peak {} package {} sources {}",
+            sample.package,
+            sample.version,
+            space::format_bytes(sample.peak_build_bytes),
+            space::format_bytes(sample.package_bytes),
+            space::format_bytes(sample.source_bytes),
         );
     }
-
     Ok(ExitCode::SUCCESS)
 }
 
@@ -655,42 +2754,10 @@ async fn run_space(cmd: &SpaceCommand) -> Result<ExitCode> {
         .saturating_mul(1024 * 1024);
     let margin = min_free.saturating_add(disk_margin_bytes);
 
-    let manifest: serde_json::Value =
-        serde_json::from_reader(std::fs::File::open(&manifest_path).map_err(|err| {
-            crate::error::SynsyuError::Filesystem(format!(
-                "Failed to open manifest {}: {err}",
-                manifest_path.display()
-            ))
-        })?)
-        .map_err(|err| {
-            crate::error::SynsyuError::Serialization(format!(
-                "Failed to parse manifest {}: {err}",
-                manifest_path.display()
-            ))
-        })?;
-
-    // Pre-fetch repo sizes for requested pacman packages to avoid relying solely on manifest data.
-    let mut repo_pkg_names = Vec::new();
-    if let Some(pkgs) = manifest.get("packages").and_then(|p| p.as_object()) {
-        for pkg in &cmd.packages {
-            if let Some(entry) = pkgs.get(pkg) {
-                if entry
-                    .get("source")
-                    .and_then(|s| s.as_str())
-                    .map(|s| s.eq_ignore_ascii_case("PACMAN"))
-                    .unwrap_or(false)
-                {
-                    repo_pkg_names.push(pkg.clone());
-                }
-            }
-        }
-    }
-    let _repo_sizes = if repo_pkg_names.is_empty() {
-        std::collections::HashMap::new()
-    } else {
-        query_repo_versions(&repo_pkg_names)
-            .await
-            .unwrap_or_default()
+    let manifest = signing::load_document(&config.signing, &manifest_path, "manifest")?;
+    let app_plan: Option<serde_json::Value> = match &cmd.plan {
+        Some(plan_path) => Some(signing::load_document(&config.signing, plan_path, "plan")?),
+        None => None,
     };
 
     // Optional AUR helper size lookup.
@@ -731,11 +2798,11 @@ async fn run_space(cmd: &SpaceCommand) -> Result<ExitCode> {
         } else {
             Vec::new()
         };
-    let aur_sizes = if let Some(helper) = aur_helper {
+    let aur_sizes = if let Some(helper) = &aur_helper {
         if aur_pkg_names.is_empty() {
             std::collections::HashMap::new()
         } else {
-            query_aur_helper_versions(&helper, &aur_pkg_names)
+            query_aur_helper_versions(helper, &aur_pkg_names)
                 .await
                 .unwrap_or_default()
         }
@@ -743,83 +2810,34 @@ async fn run_space(cmd: &SpaceCommand) -> Result<ExitCode> {
         std::collections::HashMap::new()
     };
 
-    let report = if let Some(path) = &cmd.path {
-        space::assess_path(path)?
-    } else {
-        space::assess_default_paths()?
+    // Where each phase lands; --path folds every phase onto one location.
+    let paths = match &cmd.path {
+        Some(path) => PhasePaths::uniform(path),
+        None => PhasePaths {
+            download: pacman_cache_dir(std::path::Path::new(&config.mirrors.pacman_conf_path)),
+            build: config.space.aur_build_path(aur_helper.as_deref()),
+            install: PathBuf::from("/usr"),
+        },
     };
 
-    let mut failures = Vec::new();
-    let mut details = Vec::new();
-    let mut unknowns = Vec::new();
-
-    // Aggregate check using manifest metadata if present.
-    if let Some(meta) = manifest.get("metadata") {
-        let transient = meta
-            .get("transient_size_total")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0);
-        let download = meta
-            .get("download_size_total")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0);
-        let build = meta
-            .get("build_size_total")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0);
-        let install = meta
-            .get("install_size_total")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0);
-        let required_transient = if transient > 0 {
-            transient
-        } else {
-            download.saturating_add(build).saturating_add(install)
-        };
-        if required_transient > 0 {
-            let required_total = required_transient.saturating_add(margin);
-            if report.available_bytes < required_total {
-                failures.push(format!(
-                    "Insufficient space: need ~{} (download {} + build {} + install {} + buffer {}) on {}; have {}",
-                    space::format_bytes(required_total),
-                    space::format_bytes(download),
-                    space::format_bytes(build),
-                    space::format_bytes(install),
-                    space::format_bytes(margin),
-                    report.checked_path.display(),
-                    space::format_bytes(report.available_bytes),
-                ));
-            } else {
-                details.push(format!(
-                    "OK: need ~{} (download {} + build {} + install {} + buffer {}) on {}; have {}",
-                    space::format_bytes(required_total),
-                    space::format_bytes(download),
-                    space::format_bytes(build),
-                    space::format_bytes(install),
-                    space::format_bytes(margin),
-                    report.checked_path.display(),
-                    space::format_bytes(report.available_bytes),
-                ));
-            }
-        }
-    }
-
-    // Pre-fetch repo sizes for requested pacman packages to avoid relying on manifest sizes.
-    let mut repo_pkg_names = Vec::new();
-    if let Some(pkgs) = manifest.get("packages").and_then(|p| p.as_object()) {
-        for pkg in &cmd.packages {
-            if let Some(entry) = pkgs.get(pkg) {
-                if entry
+    // Repo sizes for requested pacman packages, and for names the manifest does
+    // not know yet (newly pulled dependencies).
+    let manifest_pkgs = manifest.get("packages").and_then(|p| p.as_object());
+    let repo_pkg_names: Vec<String> = cmd
+        .packages
+        .iter()
+        .filter(
+            |pkg| match manifest_pkgs.and_then(|p| p.get(pkg.as_str())) {
+                Some(entry) => entry
                     .get("source")
                     .and_then(|s| s.as_str())
                     .map(|s| s.eq_ignore_ascii_case("PACMAN"))
-                    .unwrap_or(false)
-                {
-                    repo_pkg_names.push(pkg.clone());
-                }
-            }
-        }
-    }
+                    .unwrap_or(false),
+                None => true,
+            },
+        )
+        .cloned()
+        .collect();
     let repo_sizes = if repo_pkg_names.is_empty() {
         std::collections::HashMap::new()
     } else {
@@ -828,102 +2846,166 @@ async fn run_space(cmd: &SpaceCommand) -> Result<ExitCode> {
             .unwrap_or_default()
     };
 
-    // Per-package checks when requested.
-    for pkg in &cmd.packages {
-        if let Some(entry) = manifest.get("packages").and_then(|p| p.get(pkg)) {
-            let source = entry
-                .get("source")
-                .and_then(|s| s.as_str())
-                .unwrap_or("")
-                .to_string();
-            let download = entry
-                .get("download_size_selected")
-                .or_else(|| entry.get("download_size_estimate"))
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let build = entry
-                .get("build_size_estimate")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let install = entry
-                .get("install_size_estimate")
-                .or_else(|| entry.get("installed_size_selected"))
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let transient = entry
-                .get("transient_size_estimate")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            // Prefer repo data for pacman packages to avoid relying on installed size.
-            let (download, install, build) = if source.eq_ignore_ascii_case("PACMAN") {
-                if let Some(info) = repo_sizes.get(pkg) {
-                    (
-                        info.download_size.unwrap_or(download),
-                        info.installed_size.unwrap_or(install),
-                        0u64,
-                    )
-                } else {
-                    (download, install, build)
-                }
-            } else if source.eq_ignore_ascii_case("AUR") {
-                if let Some(info) = aur_sizes.get(pkg) {
-                    (
-                        info.download_size.unwrap_or(download),
-                        info.installed_size.unwrap_or(install),
-                        0u64,
-                    )
-                } else {
-                    (download, install, build)
+    // File counts for inode checks: installed counts from the local database,
+    // new packages and build trees estimated at the system's bytes-per-file.
+    let local_files = space::local_file_counts(std::path::Path::new(PACMAN_LOCAL_DB));
+    let bytes_per_file = space::average_file_bytes(&local_files);
+    let sizing = PackageSizing {
+        manifest: &manifest,
+        repo_sizes: &repo_sizes,
+        aur_sizes: &aur_sizes,
+        build_samples: &latest_samples(&build_history_path(&config.space)),
+        local_files: &local_files,
+        bytes_per_file,
+        paths,
+    };
+    let RequirementSet {
+        mut requirements,
+        mut details,
+        mut unknowns,
+    } = sizing.requirements(&cmd.packages, &cmd.removals);
+    let signed = |bytes: u64| i64::try_from(bytes).unwrap_or(i64::MAX);
+    let files_for = |bytes: u64| signed(space::files_for_bytes(bytes, bytes_per_file));
+
+    // Flatpak and firmware updates land outside the pacman phases: each
+    // Flatpak update on its installation, UEFI capsules on the ESP.
+    let app_updates = |plan_key: &str, manifest_pointer: &str| -> Vec<serde_json::Value> {
+        app_plan
+            .as_ref()
+            .and_then(|plan| plan.get(plan_key))
+            .or_else(|| manifest.pointer(manifest_pointer))
+            .and_then(|u| u.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let field = |update: &serde_json::Value, keys: &[&str]| -> String {
+        keys.iter()
+            .find_map(|key| update.get(*key).and_then(|v| v.as_str()))
+            .unwrap_or("unknown")
+            .to_string()
+    };
+    for update in app_updates("flatpak_updates", "/applications/flatpak/updates") {
+        let name = field(&update, &["application", "name"]);
+        let size = |key: &str| update.get(key).and_then(|v| v.as_u64());
+        // OSTree stores fetched objects uncompressed and hardlinks the
+        // deployment, so the larger of the two sizes is what lands on disk.
+        let Some(bytes) = size("download_size").max(size("installed_size")) else {
+            unknowns.push(format!("flatpak:{name}"));
+            continue;
+        };
+        let installation = update
+            .get("installation")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let path = cmd
+            .path
+            .clone()
+            .unwrap_or_else(|| config.space.flatpak_installation_path(installation));
+        details.push(format!(
+            "Flatpak {name}: {} on {}",
+            space::format_bytes(bytes),
+            path.display()
+        ));
+        requirements.push(SpaceRequirement {
+            phase: SpacePhase::Flatpak,
+            path,
+            bytes: signed(bytes),
+            files: files_for(bytes),
+        });
+    }
+    for update in app_updates("fwupd_updates", "/applications/fwupd/updates") {
+        let name = field(&update, &["name", "device"]);
+        let Some(bytes) = update.get("size").and_then(|v| v.as_u64()) else {
+            unknowns.push(format!("fwupd:{name}"));
+            continue;
+        };
+        let capsule = update.get("plugin").and_then(|v| v.as_str()) == Some("uefi_capsule");
+        let path = match (&cmd.path, capsule) {
+            (Some(path), _) => path.clone(),
+            (None, true) => match config.space.esp_path() {
+                Some(esp) => esp,
+                None => {
+                    details.push(format!(
+                        "WARN: firmware capsule {name} needs the ESP but none was found; charging /boot"
+                    ));
+                    PathBuf::from("/boot")
                 }
-            } else {
-                (download, install, build)
-            };
+            },
+            (None, false) => PathBuf::from(FWUPD_STATE_DIR),
+        };
+        details.push(format!(
+            "Firmware {name}: {} on {}",
+            space::format_bytes(bytes),
+            path.display()
+        ));
+        requirements.push(SpaceRequirement {
+            phase: SpacePhase::Firmware,
+            path,
+            bytes: signed(bytes),
+            files: 1,
+        });
+    }
 
-            let required_base = if transient > 0 {
-                transient
-            } else {
-                download.saturating_add(build).saturating_add(install)
-            };
-            if required_base == 0 {
-                details.push(format!(
-                    "WARN: no size telemetry for {pkg}; unable to validate disk usage"
-                ));
-                unknowns.push(pkg.clone());
-                continue;
-            }
-            let required_total = required_base.saturating_add(margin);
-            if report.available_bytes < required_total {
-                failures.push(format!(
-                    "Package {pkg}: need ~{} (download {} + build {} + install {} + buffer {}) on {}; have {}",
-                    space::format_bytes(required_total),
-                    space::format_bytes(download),
-                    space::format_bytes(build),
-                    space::format_bytes(install),
-                    space::format_bytes(margin),
-                    report.checked_path.display(),
-                    space::format_bytes(report.available_bytes),
-                ));
-            } else {
-                details.push(format!(
-                    "Package {pkg}: OK need ~{} on {}; have {}",
-                    space::format_bytes(required_total),
-                    report.checked_path.display(),
-                    space::format_bytes(report.available_bytes),
-                ));
+    let filesystems = space::account_by_filesystem(&requirements, &config.space.limits(margin))?;
+    let mut failures = Vec::new();
+    for row in &filesystems {
+        row.report(&mut details, &mut failures);
+    }
+
+    // Suggest cache pruning when it would help a failing filesystem.
+    let mut hints = Vec::new();
+    if !failures.is_empty() {
+        let planned: HashSet<(String, String)> = repo_sizes
+            .iter()
+            .map(|(pkg, info)| (pkg.clone(), info.version.clone()))
+            .collect();
+        let roots = cache_roots(
+            std::path::Path::new(&config.mirrors.pacman_conf_path),
+            &config.clean,
+            &config.space,
+            config.helper_priority(),
+        );
+        if let Ok(report) = analyze_package_cache(&config, roots, &planned).await {
+            let mut dir_keys: HashMap<PathBuf, Option<String>> = HashMap::new();
+            let keys: Vec<Option<String>> = report
+                .remove
+                .iter()
+                .map(|pkg| {
+                    let dir = pkg.path.parent()?;
+                    dir_keys
+                        .entry(dir.to_path_buf())
+                        .or_insert_with(|| space::filesystem_key(dir).ok())
+                        .clone()
+                })
+                .collect();
+            for row in filesystems.iter().filter(|row| !row.ok) {
+                let (count, bytes) = report
+                    .remove
+                    .iter()
+                    .zip(&keys)
+                    .filter(|(_, key)| key.as_deref() == Some(row.filesystem.as_str()))
+                    .map(|(pkg, _)| pkg)
+                    .fold((0usize, 0u64), |(count, bytes), pkg| {
+                        (count + 1, bytes.saturating_add(pkg.bytes))
+                    });
+                if bytes > 0 {
+                    hints.push(format!(
+                        "Run `syn-syu clean` to free ~{} on {} ({count} cached package file(s) beyond keep_versions = {})",
+                        space::format_bytes(bytes),
+                        row.mount_point.display(),
+                        report.keep_versions,
+                    ));
+                }
             }
-        } else {
-            details.push(format!(
-                "WARN: {pkg} not found in manifest; skipping disk check"
-            ));
         }
     }
 
     if cmd.json {
         let output = serde_json::json!({
-            "checked_path": report.checked_path,
-            "available_bytes": report.available_bytes,
             "margin_bytes": margin,
+            "filesystems": filesystems,
             "failures": failures,
+            "hints": hints,
             "unknown": unknowns,
             "details": details,
         });
@@ -938,6 +3020,9 @@ async fn run_space(cmd: &SpaceCommand) -> Result<ExitCode> {
         for line in &failures {
             eprintln!("{line}");
         }
+        for line in &hints {
+            eprintln!("{line}");
+        }
         if !unknowns.is_empty() {
             eprintln!("WARN: size telemetry missing for: {}", unknowns.join(", "));
         }
@@ -958,15 +3043,15 @@ fn run_updates(cmd: &UpdatesCommand) -> Result<ExitCode> {
         .manifest
         .clone()
         .unwrap_or_else(|| config.manifest_path());
+    let manifest = signing::load_document(&config.signing, &manifest_path, "manifest")?;
     let filter = UpdatesFilter {
-        manifest: manifest_path,
         include: cmd.include.clone(),
         exclude: cmd.exclude.clone(),
         allow_repo: !cmd.no_repo,
         allow_aur: !cmd.no_aur,
         packages: cmd.packages.clone(),
     };
-    let updates = collect_updates(filter)?;
+    let updates = collect_updates(&manifest, filter);
     if cmd.json {
         println!(
             "{}",
@@ -981,8 +3066,19 @@ fn run_updates(cmd: &UpdatesCommand) -> Result<ExitCode> {
 }
 
 fn run_logs(cmd: &LogsCommand) -> Result<ExitCode> {
+    if let Some(LogsAction::Query(query)) = &cmd.action {
+        return run_logs_query(query);
+    }
     let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
 
+    if let Some(target) = &cmd.verify {
+        let paths = match target {
+            Some(path) if !cmd.all => vec![path.clone()],
+            _ => log_files(&config.log_dir()),
+        };
+        return run_logs_verify(&paths, cmd.json);
+    }
+
     if cmd.init {
         let init = log_init(&config)?;
         println!(
@@ -1014,22 +3110,109 @@ fn run_logs(cmd: &LogsCommand) -> Result<ExitCode> {
                 let init = log_init(&config)?;
                 init.path
             };
-            log_emit(&log_path, level, code, message)?;
+            let fields = LogFields {
+                run_id: cmd.run_id.clone().or_else(run_id_from_env),
+                package: cmd.package.clone(),
+                source: cmd.source.clone(),
+                duration_ms: cmd.duration_ms,
+                prev: None,
+            };
+            let record = LogRecord::now(level, code, message, fields);
+            log_emit(&log_path, config.logging.format, &record)?;
         }
     }
 
     Ok(ExitCode::SUCCESS)
 }
 
+fn run_logs_verify(paths: &[PathBuf], json: bool) -> Result<ExitCode> {
+    let reports = paths
+        .iter()
+        .map(|path| verify_log(path))
+        .collect::<Result<Vec<_>>>()?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).unwrap_or_else(|_| "[]".to_string())
+        );
+    } else {
+        for report in &reports {
+            let path = report.path.display();
+            match (&report.problem, report.broken_line) {
+                (Some(problem), Some(line)) => println!("BROKEN {path}: line {line}: {problem}"),
+                (Some(problem), None) => println!("BROKEN {path}: {problem}"),
+                (None, _) => {
+                    let chain = match (report.chained, report.sealed) {
+                        (false, _) => "unchained",
+                        (true, true) => "sealed",
+                        (true, false) => "open",
+                    };
+                    let sidecar = match report.sidecar {
+                        SidecarState::Match => "sidecar ok",
+                        SidecarState::Missing => "no sidecar",
+                        SidecarState::Mismatch => "sidecar mismatch",
+                    };
+                    println!("OK {path} ({} entries, {chain}, {sidecar})", report.entries);
+                }
+            }
+        }
+    }
+    Ok(if reports.iter().all(|report| report.ok) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    })
+}
+
+fn run_logs_query(cmd: &LogsQueryCommand) -> Result<ExitCode> {
+    let config = SynsyuConfig::load_from_optional_path(cmd.config.as_deref())?;
+    let now = Utc::now();
+    let bound = |value: &Option<String>| -> Result<Option<chrono::DateTime<Utc>>> {
+        value
+            .as_deref()
+            .map(|value| parse_time_bound(value, now))
+            .transpose()
+    };
+    let query = LogQuery {
+        levels: cmd.levels.clone(),
+        codes: cmd.codes.clone(),
+        run: cmd.run.clone(),
+        package: cmd.package.clone(),
+        source: cmd.source.clone(),
+        since: bound(&cmd.since)?,
+        until: bound(&cmd.until)?,
+        contains: cmd.contains.clone(),
+    };
+    let dir = cmd.dir.clone().unwrap_or_else(|| config.log_dir());
+    let mut records = query_logs(&dir, &query);
+    if let Some(limit) = cmd.limit {
+        let skip = records.len().saturating_sub(limit);
+        records.drain(..skip);
+    }
+    let format = if cmd.json {
+        config::LogFormat::Json
+    } else {
+        config::LogFormat::Text
+    };
+    for record in &records {
+        println!("{}", record.render(format));
+    }
+    Ok(if records.is_empty() {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
 fn filter_packages(
-    installed: &mut Vec<InstalledPackage>,
+    installed: &mut [InstalledPackage],
     requested: &[String],
     logger: &Logger,
 ) -> Result<Vec<InstalledPackage>> {
     installed.sort_by(|a, b| a.name.cmp(&b.name));
 
     if requested.is_empty() {
-        return Ok(installed.clone());
+        return Ok(installed.to_vec());
     }
 
     let mut requested_set: HashSet<String> = HashSet::new();
@@ -1099,6 +3282,7 @@ async fn classify_aur_packages(
         offline,
         &config.aur,
         &config.acquisition.aur_rpc,
+        &config.network,
         config.resolved_aur_rpc_max_retries(),
         logger,
     )
@@ -38,11 +38,13 @@ use std::os::unix::fs::PermissionsExt;
 use chrono::{SecondsFormat, Utc};
 use serde::Serialize;
 
+use crate::config_layers::active_profile;
 use crate::error::{Result, SynsyuError};
 use crate::flatpak::FlatpakState;
 use crate::logger::Logger;
 use crate::mirrors::MirrorState;
 use crate::pacman::InstalledPackage;
+use crate::runs::run_id_from_env;
 
 /// Wrapper representing the full manifest document.
 #[derive(Debug, Serialize)]
@@ -60,6 +62,12 @@ pub struct ManifestDocument {
 pub struct ManifestMetadata {
     pub generated_at: String,
     pub generated_by: String,
+    /// Orchestrator run that produced the manifest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// Configuration profile active when the manifest was built.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
     pub total_packages: usize,
     pub pacman_packages: usize,
     pub aur_packages: usize,
@@ -176,6 +184,8 @@ pub async fn build_manifest(
     let metadata = ManifestMetadata {
         generated_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
         generated_by: "synsyu_core".to_string(),
+        run_id: run_id_from_env(),
+        profile: active_profile(),
         total_packages: packages.len(),
         pacman_packages,
         aur_packages,
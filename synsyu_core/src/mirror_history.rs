@@ -0,0 +1,337 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::mirror_history
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Keep a bounded, append-only history of mirror probe and
+    acquisition outcomes and summarise it into long-term health
+    statistics and ranking penalties for chronically flaky mirrors.
+
+  Security / Safety Notes:
+    History is advisory ordering data only. Malformed lines are
+    skipped, and trimming rewrites through a temporary file so a
+    crash never leaves a truncated history behind.
+============================================================*/
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{expand_home, MirrorConfig, MirrorHistoryConfig};
+use crate::error::{Result, SynsyuError};
+use crate::mirrors::{mirror_cache_path, MirrorCandidate};
+
+/// One observed mirror outcome, either from a probe or a real repo attempt.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MirrorHistoryEvent {
+    pub at_epoch: u64,
+    pub server: String,
+    /// `probe` for HTTP probes, `attempt` for pacman acquisition attempts.
+    pub kind: String,
+    pub outcome: String,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freshness: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<usize>,
+}
+
+/// Aggregated health for one mirror over the stats window.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct MirrorStats {
+    pub server: String,
+    pub samples: usize,
+    pub successes: usize,
+    pub success_rate: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p50_latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p95_latency_ms: Option<u64>,
+    pub staleness_incidents: usize,
+    pub probes: usize,
+    pub attempts: usize,
+    pub failover_attempts: usize,
+    pub failover_successes: usize,
+    pub last_seen_epoch: u64,
+}
+
+/// Statistics report emitted by `mirrors --stats`.
+#[derive(Debug, Serialize)]
+pub struct MirrorStatsReport {
+    pub history_path: String,
+    pub window_days: u64,
+    pub event_count: usize,
+    pub mirrors: Vec<MirrorStats>,
+}
+
+/// Resolve the history path; defaults to a sibling of the probe cache.
+pub fn history_path(config: &MirrorConfig) -> PathBuf {
+    if let Some(path) = &config.history.path {
+        return expand_home(path);
+    }
+    mirror_cache_path(config).with_file_name("mirror-history.jsonl")
+}
+
+/// Build probe events for candidates that were actually probed this run.
+pub fn events_from_candidates(candidates: &[MirrorCandidate], now: u64) -> Vec<MirrorHistoryEvent> {
+    candidates
+        .iter()
+        .filter(|c| c.status != "cached" && c.status != "unprobed")
+        .map(|c| MirrorHistoryEvent {
+            at_epoch: now,
+            server: c.server.clone(),
+            kind: "probe".to_string(),
+            outcome: c.outcome.clone(),
+            success: c.usable,
+            freshness: Some(c.freshness.clone()),
+            latency_ms: c.latency_ms,
+            attempt: None,
+        })
+        .collect()
+}
+
+/// Append events and trim the file to the newest `max_entries` lines.
+pub fn append_events(path: &Path, events: &[MirrorHistoryEvent], max_entries: usize) -> Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            SynsyuError::Filesystem(format!(
+                "Unable to create mirror history directory {}: {err}",
+                parent.display()
+            ))
+        })?;
+    }
+    let mut buffer = String::new();
+    for event in events {
+        let line = serde_json::to_string(event).map_err(|err| {
+            SynsyuError::Serialization(format!("Unable to encode mirror history event: {err}"))
+        })?;
+        buffer.push_str(&line);
+        buffer.push('\n');
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| {
+            SynsyuError::Filesystem(format!(
+                "Unable to open mirror history {}: {err}",
+                path.display()
+            ))
+        })?;
+    file.write_all(buffer.as_bytes())?;
+    drop(file);
+
+    if max_entries > 0 {
+        trim_history(path, max_entries)?;
+    }
+    Ok(())
+}
+
+fn trim_history(path: &Path, max_entries: usize) -> Result<()> {
+    let contents = fs::read_to_string(path)?;
+    let lines: Vec<&str> = contents.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.len() <= max_entries {
+        return Ok(());
+    }
+    let keep = &lines[lines.len() - max_entries..];
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut body = keep.join("\n");
+    body.push('\n');
+    fs::write(&tmp, body)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Read events observed at or after `since_epoch`, skipping malformed lines.
+pub fn read_events(path: &Path, since_epoch: u64) -> Vec<MirrorHistoryEvent> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<MirrorHistoryEvent>(line).ok())
+        .filter(|event| event.at_epoch >= since_epoch)
+        .collect()
+}
+
+/// Earliest epoch inside a window of `days` ending at `now`.
+pub fn window_start(now: u64, days: u64) -> u64 {
+    now.saturating_sub(days.saturating_mul(86_400))
+}
+
+/// Aggregate per-mirror statistics, sorted by success rate then server.
+pub fn compute_stats(events: &[MirrorHistoryEvent]) -> Vec<MirrorStats> {
+    let mut grouped: BTreeMap<&str, Vec<&MirrorHistoryEvent>> = BTreeMap::new();
+    for event in events {
+        grouped
+            .entry(event.server.as_str())
+            .or_default()
+            .push(event);
+    }
+
+    let mut stats: Vec<MirrorStats> = grouped
+        .into_iter()
+        .map(|(server, events)| {
+            let samples = events.len();
+            let successes = events.iter().filter(|e| e.success).count();
+            let mut latencies: Vec<u64> = events
+                .iter()
+                .filter(|e| e.success)
+                .filter_map(|e| e.latency_ms)
+                .collect();
+            latencies.sort_unstable();
+            let attempts = events.iter().filter(|e| e.kind == "attempt").count();
+            let failover_attempts = events
+                .iter()
+                .filter(|e| e.kind == "attempt" && e.attempt.unwrap_or(1) > 1)
+                .count();
+            let failover_successes = events
+                .iter()
+                .filter(|e| e.kind == "attempt" && e.attempt.unwrap_or(1) > 1 && e.success)
+                .count();
+            MirrorStats {
+                server: server.to_string(),
+                samples,
+                successes,
+                success_rate: if samples == 0 {
+                    0.0
+                } else {
+                    successes as f64 / samples as f64
+                },
+                p50_latency_ms: percentile(&latencies, 50),
+                p95_latency_ms: percentile(&latencies, 95),
+                staleness_incidents: events
+                    .iter()
+                    .filter(|e| e.freshness.as_deref() == Some("stale") || e.outcome == "stale")
+                    .count(),
+                probes: events.iter().filter(|e| e.kind == "probe").count(),
+                attempts,
+                failover_attempts,
+                failover_successes,
+                last_seen_epoch: events.iter().map(|e| e.at_epoch).max().unwrap_or(0),
+            }
+        })
+        .collect();
+
+    stats.sort_by(|a, b| {
+        b.success_rate
+            .total_cmp(&a.success_rate)
+            .then_with(|| a.server.cmp(&b.server))
+    });
+    stats
+}
+
+/// Score penalty per server, proportional to its failure rate once enough
+/// samples exist to call it chronic rather than a single bad probe.
+pub fn flakiness_penalties(
+    stats: &[MirrorStats],
+    config: &MirrorHistoryConfig,
+) -> BTreeMap<String, u64> {
+    let mut penalties = BTreeMap::new();
+    if config.flaky_penalty_ms == 0 {
+        return penalties;
+    }
+    for entry in stats {
+        if entry.samples < config.flaky_min_samples.max(1) {
+            continue;
+        }
+        let failure_rate = 1.0 - entry.success_rate;
+        let penalty = (failure_rate * config.flaky_penalty_ms as f64).round() as u64;
+        if penalty > 0 {
+            penalties.insert(entry.server.clone(), penalty);
+        }
+    }
+    penalties
+}
+
+/// Nearest-rank percentile over an ascending slice.
+fn percentile(sorted: &[u64], pct: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn event(server: &str, kind: &str, success: bool, latency: u64) -> MirrorHistoryEvent {
+        MirrorHistoryEvent {
+            at_epoch: 1_000,
+            server: server.into(),
+            kind: kind.into(),
+            outcome: if success { "ready" } else { "timeout" }.into(),
+            success,
+            freshness: Some("fresh".into()),
+            latency_ms: Some(latency),
+            attempt: None,
+        }
+    }
+
+    #[test]
+    fn computes_rates_percentiles_and_failovers() {
+        let mut events: Vec<MirrorHistoryEvent> = (1..=20)
+            .map(|ms| event("https://a.example", "probe", true, ms * 10))
+            .collect();
+        let mut stale = event("https://a.example", "probe", false, 0);
+        stale.outcome = "stale".into();
+        stale.freshness = Some("stale".into());
+        events.push(stale);
+        let mut failover = event("https://a.example", "attempt", true, 0);
+        failover.latency_ms = None;
+        failover.attempt = Some(2);
+        events.push(failover);
+
+        let stats = compute_stats(&events);
+        assert_eq!(stats.len(), 1);
+        let a = &stats[0];
+        assert_eq!(a.samples, 22);
+        assert_eq!(a.successes, 21);
+        assert_eq!(a.p50_latency_ms, Some(100));
+        assert_eq!(a.p95_latency_ms, Some(190));
+        assert_eq!(a.staleness_incidents, 1);
+        assert_eq!(a.failover_attempts, 1);
+        assert_eq!(a.failover_successes, 1);
+    }
+
+    #[test]
+    fn penalises_only_chronically_flaky_mirrors() {
+        let mut events = Vec::new();
+        for idx in 0..10 {
+            events.push(event("https://flaky.example", "probe", idx % 2 == 0, 20));
+            events.push(event("https://solid.example", "probe", true, 40));
+        }
+        events.push(event("https://new.example", "probe", false, 0));
+        let penalties =
+            flakiness_penalties(&compute_stats(&events), &MirrorHistoryConfig::default());
+        assert_eq!(penalties.get("https://flaky.example"), Some(&2500));
+        assert!(!penalties.contains_key("https://solid.example"));
+        assert!(!penalties.contains_key("https://new.example"));
+    }
+
+    #[test]
+    fn append_is_bounded() {
+        let dir = TempDir::new("synsyu_history_").expect("tmp");
+        let path = dir.path().join("mirror-history.jsonl");
+        for idx in 0..5 {
+            let mut e = event("https://a.example", "probe", true, idx);
+            e.at_epoch = idx;
+            append_events(&path, &[e], 3).expect("append");
+        }
+        let events = read_events(&path, 0);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].at_epoch, 2);
+    }
+}
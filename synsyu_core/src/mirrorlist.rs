@@ -0,0 +1,477 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::mirrorlist
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Generate a ranked pacman mirrorlist from the Arch mirror
+    status feed, filtered by operator policy and ordered with
+    Syn-Syu's own probe cache.
+
+  Security / Safety Notes:
+    Never edits /etc/pacman.d/mirrorlist implicitly. Output goes to
+    stdout unless an explicit target path is supplied, and file
+    writes are staged through a temporary file and renamed.
+============================================================*/
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{MirrorGenerateConfig, NetworkConfig};
+use crate::error::{Result, SynsyuError};
+use crate::http::build_client;
+
+/// Feed protocols pacman can download from; rsync mirrors are for syncing
+/// other mirrors and cannot appear in a `Server =` line.
+const PACMAN_PROTOCOLS: &[&str] = &["https", "http", "ftp"];
+
+/// Subset of the Arch mirror status document used for generation.
+#[derive(Debug, Deserialize)]
+pub struct MirrorStatusFeed {
+    #[serde(default)]
+    pub last_check: Option<String>,
+    #[serde(default)]
+    pub urls: Vec<MirrorStatusEntry>,
+}
+
+/// One mirror URL entry from the status feed.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MirrorStatusEntry {
+    pub url: String,
+    #[serde(default)]
+    pub protocol: String,
+    #[serde(default)]
+    pub last_sync: Option<String>,
+    #[serde(default)]
+    pub completion_pct: Option<f64>,
+    #[serde(default)]
+    pub delay: Option<u64>,
+    #[serde(default)]
+    pub score: Option<f64>,
+    #[serde(default)]
+    pub active: bool,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub country_code: String,
+    #[serde(default)]
+    pub ipv4: bool,
+    #[serde(default)]
+    pub ipv6: bool,
+}
+
+/// Effective generation filters after CLI overrides.
+#[derive(Debug, Serialize, Clone)]
+pub struct MirrorFilter {
+    pub countries: Vec<String>,
+    pub protocols: Vec<String>,
+    pub ip_version: String,
+    pub min_completion_percent: f64,
+    pub max_delay_minutes: u64,
+    pub limit: usize,
+}
+
+/// One server selected for the generated mirrorlist.
+#[derive(Debug, Serialize, Clone)]
+pub struct GeneratedMirror {
+    pub rank: usize,
+    pub server: String,
+    pub country: String,
+    pub protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completion_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_seconds: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_sync: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_score: Option<f64>,
+    pub cache_rank: (u8, u8, u64),
+}
+
+/// Generated mirrorlist plus the inputs that produced it.
+#[derive(Debug, Serialize)]
+pub struct GeneratedMirrorlist {
+    pub generated_at: String,
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed_last_check: Option<String>,
+    pub filter: MirrorFilter,
+    pub probe_cache_path: String,
+    pub probe_cache_entries: usize,
+    pub feed_count: usize,
+    pub matched_count: usize,
+    pub mirrors: Vec<GeneratedMirror>,
+}
+
+impl MirrorFilter {
+    pub fn from_config(config: &MirrorGenerateConfig) -> Self {
+        Self {
+            countries: config.countries.clone(),
+            protocols: config.protocols.clone(),
+            ip_version: config.ip_version.to_string(),
+            min_completion_percent: config.min_completion_percent,
+            max_delay_minutes: config.max_delay_minutes,
+            limit: config.limit,
+        }
+    }
+
+    /// Reject protocol filters that could only produce unusable servers.
+    pub fn validate(&self) -> Result<()> {
+        match self.protocols.iter().find(|proto| !pacman_protocol(proto)) {
+            Some(proto) => Err(SynsyuError::Config(format!(
+                "pacman cannot download from `{proto}` mirrors; use {}",
+                PACMAN_PROTOCOLS.join(", ")
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn accepts(&self, entry: &MirrorStatusEntry) -> bool {
+        if !entry.active || !pacman_protocol(&entry.protocol) {
+            return false;
+        }
+        if !self.countries.is_empty()
+            && !self.countries.iter().any(|wanted| {
+                wanted.eq_ignore_ascii_case(&entry.country_code)
+                    || wanted.eq_ignore_ascii_case(&entry.country)
+            })
+        {
+            return false;
+        }
+        if !self.protocols.is_empty()
+            && !self
+                .protocols
+                .iter()
+                .any(|proto| proto.eq_ignore_ascii_case(&entry.protocol))
+        {
+            return false;
+        }
+        match self.ip_version.as_str() {
+            "ipv4" if !entry.ipv4 => return false,
+            "ipv6" if !entry.ipv6 => return false,
+            _ => {}
+        }
+        // The feed reports completion as a 0..1 ratio.
+        let completion = entry.completion_pct.unwrap_or(0.0) * 100.0;
+        if completion + f64::EPSILON < self.min_completion_percent {
+            return false;
+        }
+        if self.max_delay_minutes > 0 {
+            match entry.delay {
+                Some(delay) if delay <= self.max_delay_minutes.saturating_mul(60) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Whether pacman can download packages over `protocol`.
+pub fn pacman_protocol(protocol: &str) -> bool {
+    PACMAN_PROTOCOLS
+        .iter()
+        .any(|known| known.eq_ignore_ascii_case(protocol))
+}
+
+/// Parse a mirror status document.
+pub fn parse_status_feed(contents: &str) -> Result<MirrorStatusFeed> {
+    serde_json::from_str(contents)
+        .map_err(|err| SynsyuError::Serialization(format!("Invalid mirror status JSON: {err}")))
+}
+
+/// Load the status feed from a local file when configured, otherwise the URL.
+pub async fn load_status_feed(
+    config: &MirrorGenerateConfig,
+    network: &NetworkConfig,
+) -> Result<(MirrorStatusFeed, String)> {
+    if let Some(path) = &config.status_path {
+        let contents = fs::read_to_string(path).map_err(|err| {
+            SynsyuError::Filesystem(format!("Unable to read mirror status file {path}: {err}"))
+        })?;
+        return Ok((parse_status_feed(&contents)?, path.clone()));
+    }
+
+    let client = build_client(network, Duration::from_secs(config.timeout_seconds.max(1)))?;
+    let response = client.get(&config.status_url).send().await.map_err(|err| {
+        SynsyuError::Network(format!(
+            "Mirror status request to {} failed: {err}",
+            config.status_url
+        ))
+    })?;
+    if !response.status().is_success() {
+        return Err(SynsyuError::Network(format!(
+            "Mirror status request to {} returned {}",
+            config.status_url,
+            response.status()
+        )));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|err| SynsyuError::Network(format!("Mirror status response unreadable: {err}")))?;
+    Ok((parse_status_feed(&body)?, config.status_url.clone()))
+}
+
+/// Render a feed URL as a pacman `Server =` template.
+pub fn server_template(url: &str) -> String {
+    format!("{}/$repo/os/$arch", url.trim().trim_end_matches('/'))
+}
+
+/// Filter and rank feed entries. `cache_rank` orders by last-known probe
+/// outcome with the history flakiness penalty folded in (see
+/// `mirrors::penalised_server_rank`); the feed score (lower is better) breaks
+/// ties.
+pub fn select_mirrors<F>(
+    feed: &MirrorStatusFeed,
+    filter: &MirrorFilter,
+    cache_rank: F,
+) -> (usize, Vec<GeneratedMirror>)
+where
+    F: Fn(&str) -> (u8, u8, u64),
+{
+    let mut selected: Vec<GeneratedMirror> = feed
+        .urls
+        .iter()
+        .filter(|entry| filter.accepts(entry))
+        .map(|entry| {
+            let server = server_template(&entry.url);
+            GeneratedMirror {
+                rank: 0,
+                cache_rank: cache_rank(&server),
+                server,
+                country: if entry.country.is_empty() {
+                    entry.country_code.clone()
+                } else {
+                    entry.country.clone()
+                },
+                protocol: entry.protocol.clone(),
+                completion_percent: entry.completion_pct.map(|pct| pct * 100.0),
+                delay_seconds: entry.delay,
+                last_sync: entry.last_sync.clone(),
+                status_score: entry.score,
+            }
+        })
+        .collect();
+    let matched = selected.len();
+
+    selected.sort_by(|a, b| {
+        a.cache_rank
+            .cmp(&b.cache_rank)
+            .then_with(|| {
+                let left = a.status_score.unwrap_or(f64::MAX);
+                let right = b.status_score.unwrap_or(f64::MAX);
+                left.total_cmp(&right)
+            })
+            .then_with(|| a.server.cmp(&b.server))
+    });
+    if filter.limit > 0 {
+        selected.truncate(filter.limit);
+    }
+    for (idx, mirror) in selected.iter_mut().enumerate() {
+        mirror.rank = idx + 1;
+    }
+    (matched, selected)
+}
+
+/// Render the mirrorlist text with a header describing its inputs.
+pub fn render_mirrorlist(list: &GeneratedMirrorlist) -> String {
+    let filter = &list.filter;
+    let mut out = String::new();
+    out.push_str("##\n## Arch Linux repository mirrorlist\n");
+    out.push_str("## Generated by synsyu_core mirrors --generate\n");
+    out.push_str(&format!("## Generated at: {}\n", list.generated_at));
+    out.push_str(&format!("## Status source: {}\n", list.source));
+    if let Some(last_check) = &list.feed_last_check {
+        out.push_str(&format!("## Status last check: {last_check}\n"));
+    }
+    out.push_str(&format!(
+        "## Filters: countries={} protocols={} ip_version={} min_completion={}% max_delay_minutes={} limit={}\n",
+        if filter.countries.is_empty() {
+            "any".to_string()
+        } else {
+            filter.countries.join(",")
+        },
+        if filter.protocols.is_empty() {
+            "any".to_string()
+        } else {
+            filter.protocols.join(",")
+        },
+        filter.ip_version,
+        filter.min_completion_percent,
+        filter.max_delay_minutes,
+        filter.limit
+    ));
+    out.push_str(&format!(
+        "## Probe cache: {} ({} entries)\n",
+        list.probe_cache_path, list.probe_cache_entries
+    ));
+    out.push_str(&format!(
+        "## Selected {} of {} matching mirrors ({} in feed)\n##\n\n",
+        list.mirrors.len(),
+        list.matched_count,
+        list.feed_count
+    ));
+    for mirror in &list.mirrors {
+        out.push_str(&format!("## {}\n", mirror.country));
+        out.push_str(&format!("Server = {}\n", mirror.server));
+    }
+    out
+}
+
+/// Write the mirrorlist atomically to `target`.
+pub fn write_mirrorlist(target: &Path, contents: &str) -> Result<()> {
+    let file_name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "mirrorlist".to_string());
+    let tmp: PathBuf = target.with_file_name(format!(".{file_name}.synsyu-tmp"));
+    fs::write(&tmp, contents).map_err(|err| {
+        SynsyuError::Filesystem(format!("Unable to write {}: {err}", tmp.display()))
+    })?;
+    fs::rename(&tmp, target).map_err(|err| {
+        let _ = fs::remove_file(&tmp);
+        SynsyuError::Filesystem(format!("Unable to replace {}: {err}", target.display()))
+    })
+}
+
+/// Current timestamp in the format used by generated headers.
+pub fn generated_at() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::mirrors::{penalised_server_rank, MirrorProbeCache};
+
+    const FEED: &str = r#"{
+        "last_check": "2026-10-18T10:00:00Z",
+        "urls": [
+            {"url": "https://de.example.org/arch/", "protocol": "https", "completion_pct": 1.0,
+             "delay": 600, "score": 1.5, "active": true, "country": "Germany",
+             "country_code": "DE", "ipv4": true, "ipv6": true},
+            {"url": "http://de.example.org/arch/", "protocol": "http", "completion_pct": 1.0,
+             "delay": 600, "score": 1.0, "active": true, "country": "Germany",
+             "country_code": "DE", "ipv4": true, "ipv6": true},
+            {"url": "https://fr.example.org/arch/", "protocol": "https", "completion_pct": 1.0,
+             "delay": 300, "score": 0.8, "active": true, "country": "France",
+             "country_code": "FR", "ipv4": true, "ipv6": false},
+            {"url": "https://slow.example.org/arch/", "protocol": "https", "completion_pct": 0.9,
+             "delay": 90000, "score": 9.0, "active": true, "country": "France",
+             "country_code": "FR", "ipv4": true, "ipv6": true},
+            {"url": "https://off.example.org/arch/", "protocol": "https", "completion_pct": 1.0,
+             "delay": 60, "score": 0.1, "active": false, "country": "Germany",
+             "country_code": "DE", "ipv4": true, "ipv6": true},
+            {"url": "rsync://de.example.org/arch/", "protocol": "rsync", "completion_pct": 1.0,
+             "delay": 60, "score": 0.1, "active": true, "country": "Germany",
+             "country_code": "DE", "ipv4": true, "ipv6": true}
+        ]
+    }"#;
+
+    fn filter() -> MirrorFilter {
+        MirrorFilter::from_config(&MirrorGenerateConfig::default())
+    }
+
+    #[test]
+    fn filters_feed_by_protocol_completion_delay_and_activity() {
+        let feed = parse_status_feed(FEED).expect("feed");
+        let (matched, mirrors) = select_mirrors(&feed, &filter(), |_| (1, 1, 0));
+        assert_eq!(matched, 2);
+        let servers: Vec<&str> = mirrors.iter().map(|m| m.server.as_str()).collect();
+        assert_eq!(
+            servers,
+            vec![
+                "https://fr.example.org/arch/$repo/os/$arch",
+                "https://de.example.org/arch/$repo/os/$arch",
+            ]
+        );
+
+        let mut by_country = filter();
+        by_country.countries = vec!["germany".into()];
+        by_country.ip_version = "ipv6".into();
+        let (matched, mirrors) = select_mirrors(&feed, &by_country, |_| (1, 1, 0));
+        assert_eq!(matched, 1);
+        assert_eq!(mirrors[0].country, "Germany");
+    }
+
+    #[test]
+    fn rsync_mirrors_are_never_generated() {
+        let feed = parse_status_feed(FEED).expect("feed");
+        let mut any_protocol = filter();
+        any_protocol.protocols.clear();
+        let (_, mirrors) = select_mirrors(&feed, &any_protocol, |_| (1, 1, 0));
+        assert!(mirrors.iter().all(|m| m.protocol != "rsync"));
+
+        let mut rsync = filter();
+        rsync.protocols = vec!["https".into(), "RSYNC".into()];
+        assert!(rsync.validate().is_err());
+        assert!(filter().validate().is_ok());
+    }
+
+    #[test]
+    fn probe_cache_outranks_feed_score() {
+        let feed = parse_status_feed(FEED).expect("feed");
+        let (_, mirrors) = select_mirrors(&feed, &filter(), |server| {
+            if server.contains("de.example.org") {
+                (0, 0, 50)
+            } else {
+                (2, 3, 0)
+            }
+        });
+        assert_eq!(
+            mirrors[0].server,
+            "https://de.example.org/arch/$repo/os/$arch"
+        );
+        assert_eq!(mirrors[0].rank, 1);
+        assert_eq!(mirrors[1].rank, 2);
+    }
+
+    #[test]
+    fn flaky_history_demotes_generated_mirrors() {
+        let feed = parse_status_feed(FEED).expect("feed");
+        let cache = MirrorProbeCache::default();
+        let penalties = BTreeMap::from([(
+            "https://fr.example.org/arch/$repo/os/$arch".to_string(),
+            5_000,
+        )]);
+        let (_, mirrors) = select_mirrors(&feed, &filter(), |server| {
+            penalised_server_rank(server, &cache, &penalties)
+        });
+        assert_eq!(
+            mirrors[0].server,
+            "https://de.example.org/arch/$repo/os/$arch"
+        );
+        assert_eq!(mirrors[1].cache_rank.2, mirrors[0].cache_rank.2 + 5_000);
+    }
+
+    #[test]
+    fn renders_header_with_inputs() {
+        let feed = parse_status_feed(FEED).expect("feed");
+        let filter = filter();
+        let (matched, mirrors) = select_mirrors(&feed, &filter, |_| (1, 1, 0));
+        let list = GeneratedMirrorlist {
+            generated_at: "2026-10-18T12:00:00Z".into(),
+            source: "status.json".into(),
+            feed_last_check: feed.last_check.clone(),
+            filter,
+            probe_cache_path: "/tmp/mirror-probes.json".into(),
+            probe_cache_entries: 0,
+            feed_count: feed.urls.len(),
+            matched_count: matched,
+            mirrors,
+        };
+        let text = render_mirrorlist(&list);
+        assert!(text.contains("## Generated at: 2026-10-18T12:00:00Z"));
+        assert!(text.contains("## Status source: status.json"));
+        assert!(text.contains("protocols=https ip_version=any min_completion=100%"));
+        assert!(text.contains("## Selected 2 of 2 matching mirrors (6 in feed)"));
+        assert!(text.contains("Server = https://fr.example.org/arch/$repo/os/$arch\n"));
+    }
+}
@@ -22,8 +22,13 @@ use chrono::{SecondsFormat, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use crate::config::MirrorConfig;
+use crate::config::{expand_home, MirrorConfig, NetworkConfig};
+use crate::http::build_client;
 use crate::logger::Logger;
+use crate::mirror_history::{
+    append_events, compute_stats, events_from_candidates, flakiness_penalties, history_path,
+    read_events, window_start,
+};
 
 const UNKNOWN_FRESHNESS_SCORE_PENALTY: u64 = 1_000_000;
 
@@ -70,10 +75,23 @@ pub struct MirrorCandidate {
     pub lastsync_age_seconds: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// History penalty added to `score` at ranking time only; `score` stays
+    /// the raw probe figure so the cache never compounds it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flakiness_penalty_ms: Option<u64>,
+}
+
+impl MirrorCandidate {
+    /// Score used for ordering: the probe score plus any flakiness penalty.
+    fn ranked_score(&self) -> u64 {
+        self.score
+            .saturating_add(self.flakiness_penalty_ms.unwrap_or(0))
+    }
 }
 
+/// Last-known probe outcomes keyed by server template.
 #[derive(Debug, Serialize, Deserialize, Default)]
-struct MirrorProbeCache {
+pub struct MirrorProbeCache {
     version: u8,
     generated_at: String,
     entries: BTreeMap<String, CachedMirrorOutcome>,
@@ -91,6 +109,17 @@ struct CachedMirrorOutcome {
     observed_at_epoch: u64,
 }
 
+impl MirrorProbeCache {
+    /// Number of live entries after TTL filtering.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
 impl MirrorState {
     fn from_config(config: &MirrorConfig, status: &str, reason: Option<String>) -> Self {
         Self {
@@ -129,6 +158,7 @@ impl MirrorState {
 /// and logs rather than aborting manifest generation.
 pub async fn collect_mirror_state(
     config: &MirrorConfig,
+    network: &NetworkConfig,
     logger: &Logger,
     offline_or_repo_disabled: bool,
 ) -> MirrorState {
@@ -160,7 +190,7 @@ pub async fn collect_mirror_state(
     let cache = read_probe_cache(&cache_path, config.cache_ttl_hours);
 
     let mut state = MirrorState::from_config(config, "ready", None);
-    state.cache_used = !cache.entries.is_empty();
+    state.cache_used = !cache.is_empty();
     state.candidate_count = servers.len();
     if servers.is_empty() {
         state.status = "empty".to_string();
@@ -169,14 +199,24 @@ pub async fn collect_mirror_state(
         return state;
     }
 
+    let penalties = if config.history.enabled {
+        let events = read_events(
+            &history_path(config),
+            window_start(epoch_seconds(), config.history.window_days),
+        );
+        flakiness_penalties(&compute_stats(&events), &config.history)
+    } else {
+        BTreeMap::new()
+    };
+
     let arch = pacman_arch();
-    let ordered_servers = order_servers_with_cache(servers, &cache);
+    let ordered_servers = order_servers_with_cache(servers, &cache, &penalties);
     let limited: Vec<String> = ordered_servers
         .into_iter()
         .take(config.max_candidates)
         .collect();
     let candidates = if config.probe {
-        probe_candidates(&limited, config, &arch, logger).await
+        probe_candidates(&limited, config, network, &arch, logger).await
     } else {
         limited
             .iter()
@@ -185,6 +225,7 @@ pub async fn collect_mirror_state(
             .collect()
     };
 
+    let candidates = apply_flakiness_penalties(candidates, &penalties);
     state.candidates = rank_candidates(candidates);
     state.usable_count = state.candidates.iter().filter(|c| c.usable).count();
     state.candidate_count = state.candidates.len();
@@ -208,6 +249,14 @@ pub async fn collect_mirror_state(
 
     if config.probe {
         write_probe_cache(&cache_path, &state.candidates, logger);
+        if config.history.enabled {
+            let events = events_from_candidates(&state.candidates, epoch_seconds());
+            if let Err(err) =
+                append_events(&history_path(config), &events, config.history.max_entries)
+            {
+                logger.warn("MIRROR", format!("Unable to append mirror history: {err}"));
+            }
+        }
     }
 
     state
@@ -266,11 +315,12 @@ fn parse_mirrorlist_contents(contents: &str) -> Vec<String> {
 async fn probe_candidates(
     servers: &[String],
     config: &MirrorConfig,
+    network: &NetworkConfig,
     arch: &str,
     logger: &Logger,
 ) -> Vec<MirrorCandidate> {
     let timeout = Duration::from_secs(config.probe_timeout_seconds.max(1));
-    let client = match Client::builder().timeout(timeout).build() {
+    let client = match build_client(network, timeout) {
         Ok(client) => client,
         Err(err) => {
             logger.warn("MIRROR", format!("Unable to build HTTP client: {err}"));
@@ -325,6 +375,7 @@ async fn probe_one(
             latency_ms: Some(elapsed_ms),
             lastsync_age_seconds: None,
             reason: Some("lastsync unavailable; freshness unknown".to_string()),
+            flakiness_penalty_ms: None,
         },
         Ok(resp) => {
             let reason = format!("HTTP probe returned {}", resp.status());
@@ -410,18 +461,48 @@ fn failed_candidate_with_outcome(
         latency_ms: None,
         lastsync_age_seconds: None,
         reason: Some(reason.to_string()),
+        flakiness_penalty_ms: None,
     }
 }
 
-fn order_servers_with_cache(mut servers: Vec<String>, cache: &MirrorProbeCache) -> Vec<String> {
+fn order_servers_with_cache(
+    mut servers: Vec<String>,
+    cache: &MirrorProbeCache,
+    penalties: &BTreeMap<String, u64>,
+) -> Vec<String> {
     servers.sort_by(|a, b| {
-        cached_server_rank(a, cache)
-            .cmp(&cached_server_rank(b, cache))
+        penalised_server_rank(a, cache, penalties)
+            .cmp(&penalised_server_rank(b, cache, penalties))
             .then_with(|| a.cmp(b))
     });
     servers
 }
 
+/// Cache rank with the history flakiness penalty folded into the score.
+pub fn penalised_server_rank(
+    server: &str,
+    cache: &MirrorProbeCache,
+    penalties: &BTreeMap<String, u64>,
+) -> (u8, u8, u64) {
+    let (usability, freshness, score) = cached_server_rank(server, cache);
+    let penalty = penalties.get(server).copied().unwrap_or(0);
+    (usability, freshness, score.saturating_add(penalty))
+}
+
+fn apply_flakiness_penalties(
+    mut candidates: Vec<MirrorCandidate>,
+    penalties: &BTreeMap<String, u64>,
+) -> Vec<MirrorCandidate> {
+    for candidate in candidates.iter_mut() {
+        if let Some(penalty) = penalties.get(&candidate.server) {
+            candidate.flakiness_penalty_ms = Some(*penalty);
+        }
+    }
+    candidates
+}
+
+/// Sort key derived from the probe cache; unknown servers land between usable
+/// and failed ones.
 fn cached_server_rank(server: &str, cache: &MirrorProbeCache) -> (u8, u8, u64) {
     let Some(entry) = cache.entries.get(server) else {
         return (1, 1, UNKNOWN_FRESHNESS_SCORE_PENALTY);
@@ -451,6 +532,7 @@ fn cached_or_unprobed_candidate(
                 latency_ms: entry.latency_ms,
                 lastsync_age_seconds: entry.lastsync_age_seconds,
                 reason: Some("using last-known probe outcome; probing disabled".to_string()),
+                flakiness_penalty_ms: None,
             };
         }
     }
@@ -467,6 +549,7 @@ fn cached_or_unprobed_candidate(
         latency_ms: None,
         lastsync_age_seconds: None,
         reason: Some("probing disabled".to_string()),
+        flakiness_penalty_ms: None,
     }
 }
 
@@ -475,7 +558,7 @@ fn rank_candidates(mut candidates: Vec<MirrorCandidate>) -> Vec<MirrorCandidate>
         b.usable
             .cmp(&a.usable)
             .then_with(|| freshness_rank(&a.freshness).cmp(&freshness_rank(&b.freshness)))
-            .then_with(|| a.score.cmp(&b.score))
+            .then_with(|| a.ranked_score().cmp(&b.ranked_score()))
             .then_with(|| a.server.cmp(&b.server))
     });
     for (idx, candidate) in candidates.iter_mut().enumerate() {
@@ -493,7 +576,8 @@ fn freshness_rank(value: &str) -> u8 {
     }
 }
 
-fn read_probe_cache(path: &PathBuf, ttl_hours: u64) -> MirrorProbeCache {
+/// Load the probe cache, discarding entries older than `ttl_hours`.
+pub fn read_probe_cache(path: &PathBuf, ttl_hours: u64) -> MirrorProbeCache {
     let Ok(contents) = fs::read_to_string(path) else {
         return MirrorProbeCache::default();
     };
@@ -564,9 +648,10 @@ fn write_probe_cache(path: &PathBuf, candidates: &[MirrorCandidate], logger: &Lo
     }
 }
 
-fn mirror_cache_path(config: &MirrorConfig) -> PathBuf {
+/// Resolve the probe cache path from configuration or the XDG cache dir.
+pub fn mirror_cache_path(config: &MirrorConfig) -> PathBuf {
     if let Some(path) = &config.cache_path {
-        return expand_tilde(path);
+        return expand_home(path);
     }
     dirs::cache_dir()
         .unwrap_or_else(|| {
@@ -576,14 +661,7 @@ fn mirror_cache_path(config: &MirrorConfig) -> PathBuf {
         .join("mirror-probes.json")
 }
 
-fn expand_tilde(path: &str) -> PathBuf {
-    if let Some(rest) = path.strip_prefix("~/") {
-        return PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".into())).join(rest);
-    }
-    PathBuf::from(path)
-}
-
-fn epoch_seconds() -> u64 {
+pub fn epoch_seconds() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_secs())
@@ -614,7 +692,7 @@ fn lastsync_url(server: &str, arch: &str) -> Option<String> {
         .map(|(base, _)| format!("{}/lastsync", base.trim_end_matches('/')))
 }
 
-fn pacman_arch() -> String {
+pub fn pacman_arch() -> String {
     match std::env::consts::ARCH {
         "x86_64" => "x86_64".to_string(),
         "aarch64" => "aarch64".to_string(),
@@ -625,6 +703,15 @@ fn pacman_arch() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::temp_dir::TempDir;
+
+    impl MirrorCandidate {
+        fn with_score(mut self, score: u64) -> Self {
+            self.score = score;
+            self.usable = true;
+            self
+        }
+    }
 
     #[test]
     fn parses_active_servers_and_ignores_comments() {
@@ -670,6 +757,7 @@ Server = https://one.example/$repo/os/$arch
                 latency_ms: Some(200),
                 lastsync_age_seconds: None,
                 reason: None,
+                flakiness_penalty_ms: None,
             },
             MirrorCandidate {
                 rank: 2,
@@ -683,6 +771,7 @@ Server = https://one.example/$repo/os/$arch
                 latency_ms: None,
                 lastsync_age_seconds: None,
                 reason: Some("timeout".into()),
+                flakiness_penalty_ms: None,
             },
             MirrorCandidate {
                 rank: 3,
@@ -696,6 +785,7 @@ Server = https://one.example/$repo/os/$arch
                 latency_ms: Some(10),
                 lastsync_age_seconds: None,
                 reason: None,
+                flakiness_penalty_ms: None,
             },
         ];
 
@@ -720,6 +810,7 @@ Server = https://one.example/$repo/os/$arch
                 latency_ms: Some(5),
                 lastsync_age_seconds: None,
                 reason: Some("lastsync unavailable; freshness unknown".into()),
+                flakiness_penalty_ms: None,
             },
             MirrorCandidate {
                 rank: 2,
@@ -733,6 +824,7 @@ Server = https://one.example/$repo/os/$arch
                 latency_ms: Some(400),
                 lastsync_age_seconds: Some(6_000),
                 reason: None,
+                flakiness_penalty_ms: None,
             },
         ];
 
@@ -766,8 +858,48 @@ Server = https://one.example/$repo/os/$arch
                 "https://good.example/$repo/os/$arch".into(),
             ],
             &cache,
+            &BTreeMap::new(),
         );
 
         assert_eq!(ordered[0], "https://good.example/$repo/os/$arch");
     }
+
+    #[test]
+    fn cached_scores_stay_raw_so_penalties_apply_once() {
+        let server = "https://flaky.example/$repo/os/$arch".to_string();
+        let other = "https://steady.example/$repo/os/$arch".to_string();
+        let candidate = |server: &str, score| {
+            cached_or_unprobed_candidate(0, server, "x86_64", &MirrorProbeCache::default())
+                .with_score(score)
+        };
+        let penalties = BTreeMap::from([(server.clone(), 300)]);
+        let ranked = rank_candidates(apply_flakiness_penalties(
+            vec![candidate(&server, 100), candidate(&other, 250)],
+            &penalties,
+        ));
+        assert_eq!(ranked[0].server, other);
+        assert_eq!(ranked[1].flakiness_penalty_ms, Some(300));
+
+        let dir = TempDir::new("synsyu_mirrors_").unwrap();
+        let path = dir.path().join("probe-cache.json");
+        let logger = Logger::new(None, false).unwrap();
+        for _ in 0..3 {
+            let cache = read_probe_cache(&path, 24);
+            let candidates = [&server, &other]
+                .into_iter()
+                .map(|s| {
+                    let score = cache.entries.get(s).map(|e| e.score).unwrap_or(100);
+                    candidate(s, score)
+                })
+                .collect();
+            let ranked = rank_candidates(apply_flakiness_penalties(candidates, &penalties));
+            write_probe_cache(&path, &ranked, &logger);
+        }
+        let cache = read_probe_cache(&path, 24);
+        assert_eq!(cache.entries[&server].score, 100);
+        assert_eq!(
+            penalised_server_rank(&server, &cache, &penalties),
+            (0, 1, 400)
+        );
+    }
 }
@@ -33,6 +33,9 @@ pub struct VersionInfo {
     pub version: String,
     pub download_size: Option<u64>,
     pub installed_size: Option<u64>,
+    /// Installed packages this one replaces (repo `Replaces` field).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub replaces: Vec<String>,
 }
 
 impl VersionInfo {
@@ -41,6 +44,7 @@ impl VersionInfo {
             version,
             download_size,
             installed_size,
+            replaces: Vec::new(),
         }
     }
 }
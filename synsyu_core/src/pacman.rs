@@ -38,8 +38,9 @@ use serde::Deserialize;
 use tokio::process::Command;
 use tokio::time::sleep;
 
-use crate::config::{AcquisitionAurRpcConfig, AurConfig};
+use crate::config::{AcquisitionAurRpcConfig, AurConfig, NetworkConfig};
 use crate::error::{Result, SynsyuError};
+use crate::http::build_client;
 use crate::logger::Logger;
 use crate::package_info::VersionInfo;
 use urlencoding::encode;
@@ -164,6 +165,7 @@ pub async fn query_repo_versions(packages: &[String]) -> Result<HashMap<String,
         let mut current_version: Option<String> = None;
         let mut download_size: Option<u64> = None;
         let mut installed_size: Option<u64> = None;
+        let mut replaces: Vec<String> = Vec::new();
         for line in stdout.lines() {
             if let Some((raw_key, raw_value)) = line.split_once(':') {
                 let key = raw_key.trim();
@@ -174,6 +176,7 @@ pub async fn query_repo_versions(packages: &[String]) -> Result<HashMap<String,
                         current_version = None;
                         download_size = None;
                         installed_size = None;
+                        replaces.clear();
                     }
                     "Version" => {
                         current_version = Some(value.to_string());
@@ -184,18 +187,30 @@ pub async fn query_repo_versions(packages: &[String]) -> Result<HashMap<String,
                     "Installed Size" => {
                         installed_size = parse_pacman_size(value);
                     }
+                    "Replaces" => {
+                        replaces = value
+                            .split_whitespace()
+                            .filter(|name| *name != "None")
+                            .map(|name| name.to_string())
+                            .collect();
+                    }
                     _ => {}
                 }
             } else if line.trim().is_empty() {
                 if let (Some(name), Some(ver)) = (current.take(), current_version.take()) {
-                    versions.insert(name, VersionInfo::new(ver, download_size, installed_size));
+                    let mut info = VersionInfo::new(ver, download_size, installed_size);
+                    info.replaces = std::mem::take(&mut replaces);
+                    versions.insert(name, info);
                 }
                 download_size = None;
                 installed_size = None;
+                replaces.clear();
             }
         }
         if let (Some(name), Some(ver)) = (current.take(), current_version.take()) {
-            versions.insert(name, VersionInfo::new(ver, download_size, installed_size));
+            let mut info = VersionInfo::new(ver, download_size, installed_size);
+            info.replaces = replaces;
+            versions.insert(name, info);
         }
     }
 
@@ -278,6 +293,7 @@ pub async fn query_aur_helper_versions(
 }
 
 /// Compare two package versions using `vercmp`.
+#[allow(dead_code)]
 pub async fn compare_versions(local: &str, remote: &str) -> Result<std::cmp::Ordering> {
     let output = Command::new("vercmp")
         .arg(local)
@@ -336,16 +352,14 @@ pub async fn aur_presence(
     offline: bool,
     aur_config: &AurConfig,
     policy: &AcquisitionAurRpcConfig,
+    network: &NetworkConfig,
     max_retries: usize,
     logger: &Logger,
 ) -> Result<HashSet<String>> {
     if offline || names.is_empty() {
         return Ok(HashSet::new());
     }
-    let client = Client::builder()
-        .timeout(Duration::from_secs(aur_config.timeout.max(1)))
-        .build()
-        .map_err(|err| SynsyuError::Network(format!("AUR RPC client setup failed: {err}")))?;
+    let client = build_client(network, Duration::from_secs(aur_config.timeout.max(1)))?;
     let mut found = HashSet::new();
     let chunk_size = aur_config.max_args.max(1);
     let attempts = if policy.enabled {
@@ -472,6 +486,7 @@ fn aur_rpc_status_retryable(status: StatusCode) -> bool {
 struct AurResponse {
     #[serde(rename = "type")]
     resp_type: Option<String>,
+    #[allow(dead_code)]
     resultcount: Option<u64>,
     results: Option<Vec<AurEntry>>,
 }
@@ -483,7 +498,7 @@ struct AurEntry {
 }
 
 pub fn parse_pacman_size(value: &str) -> Option<u64> {
-    let mut parts = value.trim().split_whitespace();
+    let mut parts = value.split_whitespace();
     let number = parts.next()?.replace(',', "");
     let unit = parts.next().unwrap_or("B");
     let magnitude = number.parse::<f64>().ok()?;
@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
 use chrono::Utc;
@@ -7,9 +8,19 @@ use serde_json::json;
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 
+use crate::build_history::{
+    estimate, history_path as build_history_path, latest_samples, BuildSample,
+};
 use crate::config::SynsyuConfig;
+use crate::config_layers::active_profile;
 use crate::error::{Result, SynsyuError};
+use crate::flatpak::capture_installation_updates;
 use crate::fwupd::collect_fwupd_updates_for_plan;
+use crate::kernel::{assess_update, BOOT_DIR, MODULES_ROOT};
+use crate::package_info::VersionInfo;
+use crate::pacman::query_aur_helper_versions;
+use crate::runs::run_id_from_env;
+use crate::space::{account_by_filesystem, format_bytes};
 
 #[derive(Debug, Args, Clone)]
 pub struct PlanCommand {
@@ -71,7 +82,24 @@ impl PlanCommand {
         if !self.no_aur && !self.offline {
             sources.push("aur".to_string());
             let helper = resolve_aur_helper(config);
-            let (updates, errs) = collect_aur_updates(helper.as_deref()).await;
+            let (mut updates, errs) = collect_aur_updates(helper.as_deref()).await;
+            // Current AUR snapshot sizes scale the measured builds, as `space` does.
+            let names: Vec<String> = updates
+                .iter()
+                .filter_map(|u| u.get("name").and_then(|n| n.as_str()))
+                .map(str::to_string)
+                .collect();
+            let sizes = match helper.as_deref() {
+                Some(helper) if !names.is_empty() => query_aur_helper_versions(helper, &names)
+                    .await
+                    .unwrap_or_default(),
+                _ => HashMap::new(),
+            };
+            annotate_build_estimates(
+                &mut updates,
+                &latest_samples(&build_history_path(&config.space)),
+                &sizes,
+            );
             aur_updates = updates;
             errors.extend(errs);
         }
@@ -90,6 +118,57 @@ impl PlanCommand {
             errors.extend(errs);
         }
 
+        // Kernel updates write to /boot (and the ESP for UKIs), which are
+        // usually small; check them separately from the main space buffer.
+        let names: Vec<String> = pacman_updates
+            .iter()
+            .chain(aur_updates.iter())
+            .filter_map(|u| u.get("name").and_then(|n| n.as_str()))
+            .map(str::to_string)
+            .collect();
+        let esp = config.space.esp_path();
+        let assessment = assess_update(
+            &names,
+            Path::new(MODULES_ROOT),
+            Path::new(BOOT_DIR),
+            esp.as_deref(),
+        );
+        let boot = if assessment.is_empty() {
+            serde_json::Value::Null
+        } else {
+            let margin = config.space.boot_margin_mb.saturating_mul(1024 * 1024);
+            let filesystems =
+                match account_by_filesystem(&assessment.requirements, &config.space.limits(margin))
+                {
+                    Ok(rows) => rows,
+                    Err(err) => {
+                        errors.push(format!("boot: {err}"));
+                        Vec::new()
+                    }
+                };
+            for row in filesystems.iter().filter(|row| !row.space_ok) {
+                errors.push(format!(
+                    "boot: kernel images need ~{} plus {} buffer on {}; have {}",
+                    format_bytes(row.required_bytes),
+                    format_bytes(row.margin_bytes),
+                    row.mount_point.display(),
+                    format_bytes(row.available_bytes),
+                ));
+            }
+            for row in filesystems.iter().filter(|row| !row.inodes_ok) {
+                errors.push(format!(
+                    "boot: kernel images need {} inode(s) plus {} buffer on {}; have {}",
+                    row.required_files,
+                    row.inode_margin,
+                    row.mount_point.display(),
+                    row.available_inodes.unwrap_or(0),
+                ));
+            }
+            let mut section = json!(assessment);
+            section["filesystems"] = json!(filesystems);
+            section
+        };
+
         let generated_at = Utc::now().to_rfc3339();
 
         let plan_json = json!({
@@ -97,6 +176,8 @@ impl PlanCommand {
                 "generated_at": generated_at,
                 "generated_by": "synsyu_core plan",
                 "plan_path": plan_path.display().to_string(),
+                "run_id": run_id_from_env(),
+                "profile": active_profile(),
                 "sources": sources,
                 "errors": errors,
             },
@@ -104,6 +185,7 @@ impl PlanCommand {
             "aur_updates": aur_updates,
             "flatpak_updates": flatpak_updates,
             "fwupd_updates": fwupd_updates,
+            "boot": boot,
             "counts": {
                 "pacman": pacman_updates.len(),
                 "aur": aur_updates.len(),
@@ -234,46 +316,27 @@ async fn collect_aur_updates(helper: Option<&str>) -> (Vec<serde_json::Value>, V
 async fn collect_flatpak_updates() -> (Vec<serde_json::Value>, Vec<String>) {
     let mut updates = Vec::new();
     let mut errors = Vec::new();
-    let output = Command::new("flatpak")
-        .args([
-            "remote-ls",
-            "--updates",
-            "--columns=application,branch,origin,version",
-        ])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .await;
-
-    let Ok(output) = output else {
-        errors.push("flatpak: failed to spawn".to_string());
-        return (updates, errors);
-    };
-    if !output.status.success() {
-        let err = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        errors.push(format!("flatpak: {}", err));
-        return (updates, errors);
-    }
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    for line in stdout.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 3 {
-            let app = parts[0].to_string();
-            let branch = parts[1].to_string();
-            let origin = parts[2].to_string();
-            let available = if parts.len() >= 4 {
-                parts[3].to_string()
-            } else {
-                String::new()
-            };
-            updates.push(json!({
-                "name": app,
-                "branch": branch,
-                "origin": origin,
-                "available": available,
+    for installation in ["system", "user"] {
+        let Some(list) = capture_installation_updates(installation).await else {
+            errors.push(format!("flatpak: unable to list {installation} updates"));
+            continue;
+        };
+        updates.extend(list.into_iter().map(|u| {
+            json!({
+                "name": u.application,
+                "branch": u.branch,
+                "origin": u.origin,
+                "available": u.available,
+                "installation": u.installation,
+                "download_size": u.download_size,
+                "installed_size": u.installed_size,
                 "source": "flatpak"
-            }));
-        }
+            })
+        }));
+    }
+    // One missing installation is normal; only report when both failed.
+    if errors.len() < 2 {
+        errors.clear();
     }
     (updates, errors)
 }
@@ -291,6 +354,8 @@ async fn collect_fwupd_updates() -> (Vec<serde_json::Value>, Vec<String>) {
                 "summary": u.summary,
                 "available_hash": u.available_hash,
                 "trust": u.trust,
+                "plugin": u.plugin,
+                "size": u.size,
                 "source": "fwupd"
             })
         })
@@ -298,6 +363,42 @@ async fn collect_fwupd_updates() -> (Vec<serde_json::Value>, Vec<String>) {
     (updates, errs)
 }
 
+/// Fill AUR build estimates from the last measured build of each package,
+/// scaled by the helper's current snapshot download size when it reports one.
+fn annotate_build_estimates(
+    updates: &mut [serde_json::Value],
+    history: &BTreeMap<String, BuildSample>,
+    sizes: &HashMap<String, VersionInfo>,
+) {
+    for update in updates.iter_mut() {
+        let Some(name) = update.get("name").and_then(|n| n.as_str()) else {
+            continue;
+        };
+        let Some(sample) = history.get(name) else {
+            continue;
+        };
+        let download = sizes
+            .get(name)
+            .and_then(|info| info.download_size)
+            .filter(|bytes| *bytes > 0);
+        let estimate = estimate(sample, download);
+        if let Some(obj) = update.as_object_mut() {
+            // The measured peak already includes the fetched sources, so there
+            // is no separate transient figure to report.
+            obj.insert("build_size_estimate".into(), json!(estimate.build_bytes));
+            obj.insert(
+                "build_estimate".into(),
+                json!({
+                    "source": "history",
+                    "observed_version": estimate.observed_version,
+                    "package_bytes": estimate.package_bytes,
+                    "scale": estimate.scale,
+                }),
+            );
+        }
+    }
+}
+
 fn resolve_aur_helper(config: &SynsyuConfig) -> Option<String> {
     let mut candidates = Vec::new();
     if let Some(default) = config.helpers.default.clone() {
@@ -316,3 +417,51 @@ fn resolve_aur_helper(config: &SynsyuConfig) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_estimates_scale_by_snapshot_size() {
+        let sample = |package: &str| BuildSample {
+            at_epoch: 1,
+            package: package.into(),
+            version: "1.0-1".into(),
+            helper: Some("paru".into()),
+            peak_build_bytes: 800 * 1024 * 1024,
+            package_bytes: 40 * 1024 * 1024,
+            source_bytes: 300 * 1024 * 1024,
+            snapshot_bytes: 4096,
+        };
+        let history = BTreeMap::from([
+            ("grown".to_string(), sample("grown")),
+            ("same".to_string(), sample("same")),
+        ]);
+        let info = |download| VersionInfo {
+            version: "1.1-1".into(),
+            download_size: Some(download),
+            installed_size: None,
+            replaces: Vec::new(),
+        };
+        // A few-KiB snapshot against hundreds of MiB of upstream sources must
+        // not collapse the estimate to the lower clamp.
+        let sizes = HashMap::from([
+            ("grown".to_string(), info(6144)),
+            ("same".to_string(), info(4096)),
+        ]);
+        let mut updates = vec![
+            json!({"name": "grown", "source": "aur"}),
+            json!({"name": "same", "source": "aur"}),
+            json!({"name": "unbuilt", "source": "aur"}),
+        ];
+        annotate_build_estimates(&mut updates, &history, &sizes);
+
+        assert_eq!(updates[0]["build_size_estimate"], json!(1200 * 1024 * 1024));
+        assert_eq!(updates[0]["build_estimate"]["scale"], json!(1.5));
+        assert_eq!(updates[1]["build_size_estimate"], json!(800 * 1024 * 1024));
+        assert_eq!(updates[1]["build_estimate"]["observed_version"], "1.0-1");
+        assert!(updates[0].get("transient_size_estimate").is_none());
+        assert!(updates[2].get("build_size_estimate").is_none());
+    }
+}
@@ -0,0 +1,725 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::prefetch
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Download every repo package in a plan concurrently across
+    several usable mirrors, verify each file against the sync
+    database, and place it where the later pacman transaction
+    will find it without downloading.
+
+  Security / Safety Notes:
+    Files are streamed to `.part` files and only renamed into
+    place after both the compressed size and sha256 match the
+    sync DB entry. Pacman still verifies package signatures at
+    install time. Concurrency and aggregate bandwidth are bounded
+    by configuration.
+============================================================*/
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{SecondsFormat, Utc};
+use flate2::read::GzDecoder;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::config::{NetworkConfig, PrefetchConfig};
+use crate::error::{Result, SynsyuError};
+use crate::http::build_client;
+use crate::privilege::privileged_command;
+
+const SYSTEM_CACHE_DIR: &str = "/var/cache/pacman/pkg";
+/// Paths passed to one elevated `install` invocation.
+const INSTALL_BATCH: usize = 200;
+
+/// Package metadata from a sync database `desc` entry.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct SyncDbEntry {
+    pub repo: String,
+    pub name: String,
+    pub version: String,
+    pub filename: String,
+    pub csize: u64,
+    pub sha256: String,
+}
+
+/// Outcome for one package file.
+#[derive(Debug, Serialize, Clone)]
+pub struct PrefetchItem {
+    pub repo: String,
+    pub name: String,
+    pub version: String,
+    pub filename: String,
+    pub size: u64,
+    /// `downloaded`, `cached`, `planned`, or `failed`.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Summary emitted by `synsyu_core prefetch`.
+#[derive(Debug, Serialize)]
+pub struct PrefetchReport {
+    pub generated_at: String,
+    pub plan_path: String,
+    pub destination: String,
+    pub mirrors: Vec<String>,
+    pub max_concurrency: usize,
+    pub max_kib_per_sec: u64,
+    pub total_bytes: u64,
+    pub downloaded_bytes: u64,
+    pub downloaded: usize,
+    pub cached: usize,
+    pub failed: usize,
+    pub items: Vec<PrefetchItem>,
+    pub errors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pacman_hint: Option<String>,
+}
+
+/// Parse a sync DB `desc` file.
+pub fn parse_desc(contents: &str, repo: &str) -> Option<SyncDbEntry> {
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        if let Some(key) = line.strip_prefix('%').and_then(|l| l.strip_suffix('%')) {
+            if let Some(value) = lines.next() {
+                fields.insert(key, value.trim());
+            }
+        }
+    }
+    Some(SyncDbEntry {
+        repo: repo.to_string(),
+        name: fields.get("NAME")?.to_string(),
+        version: fields.get("VERSION")?.to_string(),
+        filename: fields.get("FILENAME")?.to_string(),
+        csize: fields.get("CSIZE")?.parse().ok()?,
+        sha256: fields.get("SHA256SUM")?.to_lowercase(),
+    })
+}
+
+/// Read every package entry from one sync database (gzip or plain tar).
+pub fn read_sync_db(path: &Path, repo: &str) -> Result<Vec<SyncDbEntry>> {
+    let mut raw = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut raw))
+        .map_err(|err| {
+            SynsyuError::Filesystem(format!("Unable to read sync DB {}: {err}", path.display()))
+        })?;
+    let reader: Box<dyn Read> = if raw.starts_with(&[0x1f, 0x8b]) {
+        Box::new(GzDecoder::new(raw.as_slice()))
+    } else if raw.get(257..262) == Some(b"ustar") {
+        Box::new(raw.as_slice())
+    } else {
+        return Err(SynsyuError::Serialization(format!(
+            "Unsupported sync DB compression in {}",
+            path.display()
+        )));
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    let iter = archive.entries().map_err(|err| {
+        SynsyuError::Serialization(format!("Invalid sync DB {}: {err}", path.display()))
+    })?;
+    for item in iter {
+        let mut item = item.map_err(|err| {
+            SynsyuError::Serialization(format!("Invalid sync DB {}: {err}", path.display()))
+        })?;
+        let is_desc = item
+            .path()
+            .map(|p| p.file_name().map(|n| n == "desc").unwrap_or(false))
+            .unwrap_or(false);
+        if !is_desc {
+            continue;
+        }
+        let mut contents = String::new();
+        if item.read_to_string(&mut contents).is_ok() {
+            if let Some(entry) = parse_desc(&contents, repo) {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Load all `*.db` files in `dir`, keyed by `(repo, name)`.
+pub fn load_sync_dbs(dir: &Path) -> (HashMap<(String, String), SyncDbEntry>, Vec<String>) {
+    let mut map = HashMap::new();
+    let mut errors = Vec::new();
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        errors.push(format!(
+            "Unable to read sync DB directory {}",
+            dir.display()
+        ));
+        return (map, errors);
+    };
+    for item in read_dir.flatten() {
+        let path = item.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("db") {
+            continue;
+        }
+        let Some(repo) = path.file_stem().and_then(|s| s.to_str()).map(String::from) else {
+            continue;
+        };
+        match read_sync_db(&path, &repo) {
+            Ok(entries) => {
+                for entry in entries {
+                    map.insert((repo.clone(), entry.name.clone()), entry);
+                }
+            }
+            Err(err) => errors.push(err.to_string()),
+        }
+    }
+    (map, errors)
+}
+
+/// `(name, version)` pairs for repo updates recorded in a plan.
+pub fn plan_repo_targets(plan: &Value) -> Vec<(String, String)> {
+    plan.get("pacman_updates")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let name = item.get("name")?.as_str()?;
+                    let version = item.get("available")?.as_str()?;
+                    Some((name.to_string(), version.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Ask pacman for the full `-S` target set, including new dependencies.
+/// Returns `(repo, name, version)` triples; `None` when pacman is unavailable.
+pub async fn pacman_print_targets(names: &[String]) -> Option<Vec<(String, String, String)>> {
+    if names.is_empty() {
+        return Some(Vec::new());
+    }
+    let output = Command::new("pacman")
+        .args(["-Sp", "--print-format", "%r %n %v"])
+        .args(names)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                Some((
+                    parts.next()?.to_string(),
+                    parts.next()?.to_string(),
+                    parts.next()?.to_string(),
+                ))
+            })
+            .collect(),
+    )
+}
+
+/// Match targets against the sync DBs. Plan entries without a repo resolve to
+/// any repo carrying exactly the planned version.
+pub fn resolve_entries(
+    plan_targets: &[(String, String)],
+    pacman_targets: Option<&[(String, String, String)]>,
+    dbs: &HashMap<(String, String), SyncDbEntry>,
+) -> (Vec<SyncDbEntry>, Vec<String>) {
+    let mut resolved: Vec<SyncDbEntry> = Vec::new();
+    let mut errors = Vec::new();
+    let mut push = |entry: &SyncDbEntry| {
+        if !resolved.iter().any(|e| e.filename == entry.filename) {
+            resolved.push(entry.clone());
+        }
+    };
+
+    if let Some(targets) = pacman_targets {
+        for (repo, name, version) in targets {
+            match dbs.get(&(repo.clone(), name.clone())) {
+                Some(entry) if &entry.version == version => push(entry),
+                _ => errors.push(format!("{repo}/{name} {version} not found in sync DB")),
+            }
+        }
+        return (resolved, errors);
+    }
+
+    for (name, version) in plan_targets {
+        let mut matches: Vec<&SyncDbEntry> = dbs
+            .values()
+            .filter(|e| &e.name == name && &e.version == version)
+            .collect();
+        matches.sort_by(|a, b| a.repo.cmp(&b.repo));
+        match matches.first() {
+            Some(entry) => push(entry),
+            None => errors.push(format!("{name} {version} not found in sync DB")),
+        }
+    }
+    (resolved, errors)
+}
+
+/// Expand a mirror template into the package URL.
+pub fn package_url(server: &str, repo: &str, arch: &str, filename: &str) -> String {
+    let base = server
+        .trim()
+        .trim_end_matches('/')
+        .replace("$repo", repo)
+        .replace("$arch", arch);
+    format!("{base}/{filename}")
+}
+
+/// First `CacheDir` from pacman.conf, or pacman's default.
+pub fn pacman_cache_dir(pacman_conf: &Path) -> PathBuf {
+    fs::read_to_string(pacman_conf)
+        .ok()
+        .and_then(|contents| {
+            contents.lines().find_map(|line| {
+                let line = line.split('#').next()?.trim();
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "CacheDir").then(|| PathBuf::from(value.trim()))
+            })
+        })
+        .unwrap_or_else(|| PathBuf::from(SYSTEM_CACHE_DIR))
+}
+
+/// Verify an on-disk file against its sync DB entry.
+pub fn verify_file(path: &Path, entry: &SyncDbEntry) -> bool {
+    let Ok(meta) = fs::metadata(path) else {
+        return false;
+    };
+    if meta.len() != entry.csize {
+        return false;
+    }
+    let Ok(mut file) = File::open(path) else {
+        return false;
+    };
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(_) => return false,
+        }
+    }
+    hex_digest(hasher) == entry.sha256
+}
+
+fn hex_digest(hasher: Sha256) -> String {
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Aggregate bandwidth limiter shared by all download tasks.
+struct RateLimiter {
+    bytes_per_sec: u64,
+    start: Instant,
+    consumed: Mutex<u64>,
+}
+
+impl RateLimiter {
+    fn new(kib_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: kib_per_sec.saturating_mul(1024),
+            start: Instant::now(),
+            consumed: Mutex::new(0),
+        }
+    }
+
+    async fn consume(&self, bytes: u64) {
+        if self.bytes_per_sec == 0 {
+            return;
+        }
+        let total = {
+            let mut consumed = self.consumed.lock().unwrap_or_else(|p| p.into_inner());
+            *consumed = consumed.saturating_add(bytes);
+            *consumed
+        };
+        let due = Duration::from_secs_f64(total as f64 / self.bytes_per_sec as f64);
+        let elapsed = self.start.elapsed();
+        if due > elapsed {
+            tokio::time::sleep(due - elapsed).await;
+        }
+    }
+}
+
+/// Inputs for a prefetch run.
+pub struct PrefetchRequest {
+    pub entries: Vec<SyncDbEntry>,
+    pub mirrors: Vec<String>,
+    pub destination: PathBuf,
+    pub reuse_dirs: Vec<PathBuf>,
+    pub arch: String,
+    pub max_concurrency: usize,
+    pub max_kib_per_sec: u64,
+    pub timeout_seconds: u64,
+    pub dry_run: bool,
+}
+
+/// Download, verify, and place every entry; never fails the whole batch for
+/// one package.
+pub async fn prefetch_packages(
+    request: PrefetchRequest,
+    network: &NetworkConfig,
+) -> Result<Vec<PrefetchItem>> {
+    if !request.dry_run {
+        fs::create_dir_all(&request.destination).map_err(|err| {
+            SynsyuError::Filesystem(format!(
+                "Unable to create prefetch destination {}: {err}",
+                request.destination.display()
+            ))
+        })?;
+    }
+    let client = build_client(network, Duration::from_secs(request.timeout_seconds.max(1)))?;
+
+    let mirrors = Arc::new(request.mirrors);
+    let limiter = Arc::new(RateLimiter::new(request.max_kib_per_sec));
+    let semaphore = Arc::new(Semaphore::new(request.max_concurrency.max(1)));
+    let destination = Arc::new(request.destination);
+    let reuse_dirs = Arc::new(request.reuse_dirs);
+    let arch = Arc::new(request.arch);
+    let mut tasks = JoinSet::new();
+
+    for (idx, entry) in request.entries.into_iter().enumerate() {
+        let client = client.clone();
+        let mirrors = Arc::clone(&mirrors);
+        let limiter = Arc::clone(&limiter);
+        let semaphore = Arc::clone(&semaphore);
+        let destination = Arc::clone(&destination);
+        let reuse_dirs = Arc::clone(&reuse_dirs);
+        let arch = Arc::clone(&arch);
+        let dry_run = request.dry_run;
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let item = fetch_one(
+                &client,
+                &entry,
+                &mirrors,
+                idx,
+                &destination,
+                &reuse_dirs,
+                &arch,
+                &limiter,
+                dry_run,
+            )
+            .await;
+            (idx, item)
+        });
+    }
+
+    let mut items = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(pair) => items.push(pair),
+            Err(err) => {
+                return Err(SynsyuError::Runtime(format!(
+                    "Prefetch task aborted: {err}"
+                )))
+            }
+        }
+    }
+    items.sort_by_key(|(idx, _)| *idx);
+    Ok(items.into_iter().map(|(_, item)| item).collect())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn fetch_one(
+    client: &Client,
+    entry: &SyncDbEntry,
+    mirrors: &[String],
+    start: usize,
+    destination: &Path,
+    reuse_dirs: &[PathBuf],
+    arch: &str,
+    limiter: &RateLimiter,
+    dry_run: bool,
+) -> PrefetchItem {
+    let mut item = PrefetchItem {
+        repo: entry.repo.clone(),
+        name: entry.name.clone(),
+        version: entry.version.clone(),
+        filename: entry.filename.clone(),
+        size: entry.csize,
+        status: "planned".to_string(),
+        mirror: None,
+        reason: None,
+    };
+
+    let target = destination.join(&entry.filename);
+    let already = std::iter::once(target.clone())
+        .chain(reuse_dirs.iter().map(|dir| dir.join(&entry.filename)))
+        .find(|path| verify_file(path, entry));
+    if let Some(path) = already {
+        item.status = "cached".to_string();
+        item.reason = Some(format!("verified copy at {}", path.display()));
+        return item;
+    }
+    if dry_run {
+        item.mirror = mirrors
+            .get(start % mirrors.len().max(1))
+            .map(|server| package_url(server, &entry.repo, arch, &entry.filename));
+        return item;
+    }
+
+    let mut failures = Vec::new();
+    for offset in 0..mirrors.len() {
+        let server = &mirrors[(start + offset) % mirrors.len()];
+        let url = package_url(server, &entry.repo, arch, &entry.filename);
+        match download_verified(client, &url, entry, &target, limiter).await {
+            Ok(()) => {
+                item.status = "downloaded".to_string();
+                item.mirror = Some(server.clone());
+                return item;
+            }
+            Err(reason) => failures.push(format!("{server}: {reason}")),
+        }
+    }
+    item.status = "failed".to_string();
+    item.reason = Some(if failures.is_empty() {
+        "no mirrors available".to_string()
+    } else {
+        failures.join("; ")
+    });
+    item
+}
+
+async fn download_verified(
+    client: &Client,
+    url: &str,
+    entry: &SyncDbEntry,
+    target: &Path,
+    limiter: &RateLimiter,
+) -> std::result::Result<(), String> {
+    let part = target.with_file_name(format!("{}.part", entry.filename));
+    let result = async {
+        let mut response = client.get(url).send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        let mut file = File::create(&part).map_err(|e| e.to_string())?;
+        let mut hasher = Sha256::new();
+        let mut received: u64 = 0;
+        while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+            received = received.saturating_add(chunk.len() as u64);
+            if received > entry.csize {
+                return Err(format!("size exceeds sync DB value {}", entry.csize));
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).map_err(|e| e.to_string())?;
+            limiter.consume(chunk.len() as u64).await;
+        }
+        file.sync_all().map_err(|e| e.to_string())?;
+        if received != entry.csize {
+            return Err(format!("size {received} != sync DB {}", entry.csize));
+        }
+        let digest = hex_digest(hasher);
+        if digest != entry.sha256 {
+            return Err(format!("sha256 {digest} != sync DB {}", entry.sha256));
+        }
+        fs::rename(&part, target).map_err(|e| e.to_string())
+    }
+    .await;
+    if result.is_err() {
+        let _ = fs::remove_file(&part);
+    }
+    result
+}
+
+/// Staged files for items that ended up verified in `staging`.
+pub fn staged_files(items: &[PrefetchItem], staging: &Path) -> Vec<PathBuf> {
+    items
+        .iter()
+        .filter(|item| item.status == "downloaded" || item.status == "cached")
+        .map(|item| staging.join(&item.filename))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Copy staged files into pacman's root-owned cache with `install -m 0644`
+/// through `privilege_tool`, then drop the staged copies.
+pub async fn install_into_cache(
+    staged: &[PathBuf],
+    cache_dir: &Path,
+    privilege_tool: &str,
+) -> Result<()> {
+    for chunk in staged.chunks(INSTALL_BATCH) {
+        let mut command = Command::from(privileged_command(privilege_tool, "install"));
+        command
+            .arg("-m")
+            .arg("0644")
+            .arg("-t")
+            .arg(cache_dir)
+            .arg("--")
+            .args(chunk);
+        let output = command.output().await.map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                SynsyuError::CommandMissing {
+                    command: privilege_tool.trim().to_string(),
+                }
+            } else {
+                SynsyuError::Io(err)
+            }
+        })?;
+        if !output.status.success() {
+            return Err(SynsyuError::CommandFailure {
+                command: format!(
+                    "{} install -t {}",
+                    privilege_tool.trim(),
+                    cache_dir.display()
+                ),
+                status: output.status.code().unwrap_or(-1),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+    }
+    for path in staged {
+        let _ = fs::remove_file(path);
+    }
+    Ok(())
+}
+
+/// Summarise items into a report.
+pub fn build_report(
+    plan_path: &Path,
+    destination: &Path,
+    mirrors: Vec<String>,
+    config: &PrefetchConfig,
+    items: Vec<PrefetchItem>,
+    errors: Vec<String>,
+    system_cache: &Path,
+) -> PrefetchReport {
+    let count = |status: &str| items.iter().filter(|i| i.status == status).count();
+    PrefetchReport {
+        generated_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        plan_path: plan_path.display().to_string(),
+        destination: destination.display().to_string(),
+        mirrors,
+        max_concurrency: config.max_concurrency,
+        max_kib_per_sec: config.max_kib_per_sec,
+        total_bytes: items.iter().map(|i| i.size).sum(),
+        downloaded_bytes: items
+            .iter()
+            .filter(|i| i.status == "downloaded")
+            .map(|i| i.size)
+            .sum(),
+        downloaded: count("downloaded"),
+        cached: count("cached"),
+        failed: count("failed"),
+        pacman_hint: (destination != system_cache).then(|| {
+            format!(
+                "pacman --cachedir {} --cachedir {} -S ...",
+                destination.display(),
+                system_cache.display()
+            )
+        }),
+        items,
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    const DESC: &str = "%FILENAME%\nzlib-1:1.3.1-2-x86_64.pkg.tar.zst\n\n%NAME%\nzlib\n\n%VERSION%\n1:1.3.1-2\n\n%CSIZE%\n5\n\n%SHA256SUM%\n2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824\n\n%ARCH%\nx86_64\n";
+
+    #[test]
+    fn parses_desc_entries() {
+        let entry = parse_desc(DESC, "core").expect("entry");
+        assert_eq!(entry.name, "zlib");
+        assert_eq!(entry.version, "1:1.3.1-2");
+        assert_eq!(entry.filename, "zlib-1:1.3.1-2-x86_64.pkg.tar.zst");
+        assert_eq!(entry.csize, 5);
+        assert!(entry.sha256.starts_with("2cf24dba"));
+        assert!(parse_desc("%NAME%\nbroken\n", "core").is_none());
+    }
+
+    #[test]
+    fn reads_gzip_sync_db_and_resolves_plan_targets() {
+        let scratch = TempDir::new("synsyu_prefetch_").expect("scratch");
+        let dir = scratch.path();
+        let db_path = dir.join("core.db");
+        {
+            let file = File::create(&db_path).expect("db");
+            let gz = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            let mut builder = tar::Builder::new(gz);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(DESC.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, "zlib-1:1.3.1-2/desc", DESC.as_bytes())
+                .expect("append");
+            builder.into_inner().expect("tar").finish().expect("gz");
+        }
+
+        let (dbs, errors) = load_sync_dbs(dir);
+        assert!(errors.is_empty(), "{errors:?}");
+        let targets = vec![
+            ("zlib".to_string(), "1:1.3.1-2".to_string()),
+            ("missing".to_string(), "1.0-1".to_string()),
+        ];
+        let (resolved, errors) = resolve_entries(&targets, None, &dbs);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].repo, "core");
+        assert_eq!(
+            errors,
+            vec!["missing 1.0-1 not found in sync DB".to_string()]
+        );
+
+        let file = dir.join(&resolved[0].filename);
+        fs::write(&file, b"hello").expect("pkg");
+        assert!(verify_file(&file, &resolved[0]));
+        fs::write(&file, b"hellO").expect("pkg");
+        assert!(!verify_file(&file, &resolved[0]));
+
+        let item = |filename: &str, status: &str| PrefetchItem {
+            repo: "core".into(),
+            name: "zlib".into(),
+            version: "1:1.3.1-2".into(),
+            filename: filename.into(),
+            size: 5,
+            status: status.into(),
+            mirror: None,
+            reason: None,
+        };
+        let items = vec![
+            item(&resolved[0].filename, "downloaded"),
+            item("gone-1-1-x86_64.pkg.tar.zst", "downloaded"),
+            item(&resolved[0].filename, "failed"),
+        ];
+        assert_eq!(staged_files(&items, dir), vec![file]);
+    }
+
+    #[test]
+    fn builds_package_urls_from_mirror_templates() {
+        assert_eq!(
+            package_url(
+                "https://mirror.example/arch/$repo/os/$arch/",
+                "extra",
+                "x86_64",
+                "foo-1.0-1-x86_64.pkg.tar.zst"
+            ),
+            "https://mirror.example/arch/extra/os/x86_64/foo-1.0-1-x86_64.pkg.tar.zst"
+        );
+    }
+}
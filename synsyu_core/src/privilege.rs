@@ -0,0 +1,31 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::privilege
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Build commands that run through the configured privilege
+    tool (`core.privilege_tool`, e.g. `sudo` or `doas`).
+
+  Security / Safety Notes:
+    Only the named program is elevated; callers pass explicit
+    arguments and never go through a shell. Nothing is wrapped
+    when already running as root or when no tool is configured.
+============================================================*/
+
+use std::process::Command;
+
+/// Build a command for `program`, elevated unless already root or no tool is
+/// set. A tool string with arguments (`sudo -n`) is split on whitespace.
+pub fn privileged_command(privilege_tool: &str, program: &str) -> Command {
+    let tool = privilege_tool.trim();
+    // SAFETY: geteuid has no preconditions.
+    let is_root = unsafe { libc::geteuid() } == 0;
+    if tool.is_empty() || is_root {
+        return Command::new(program);
+    }
+    let mut parts = tool.split_whitespace();
+    let mut command = Command::new(parts.next().unwrap_or("sudo"));
+    command.args(parts).arg(program);
+    command
+}
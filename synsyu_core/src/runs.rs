@@ -0,0 +1,550 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::runs
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Keep the run journal: one append-only event file per
+    orchestrated run (start, plan, phases, per-item outcomes,
+    snapshots, end), folded into run records for
+    `synsyu_core runs list|show|last`.
+
+  Security / Safety Notes:
+    Run IDs are restricted to a safe character set before they
+    become file names. Records hold command arguments, paths,
+    and hashes, never configuration contents. A run without an
+    end event reads back as incomplete rather than failing.
+============================================================*/
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, SynsyuError};
+
+/// One journal line; `at` is shared by every event kind.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunEntry {
+    pub at: String,
+    #[serde(flatten)]
+    pub event: RunEvent,
+}
+
+impl RunEntry {
+    pub fn now(event: RunEvent) -> Self {
+        Self {
+            at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            event,
+        }
+    }
+}
+
+/// Events appended over the life of a run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RunEvent {
+    Start {
+        command: String,
+        #[serde(default)]
+        arguments: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        config_path: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        config_hash: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        log_path: Option<String>,
+    },
+    /// The manifest the run acted on.
+    Manifest {
+        path: String,
+        hash: String,
+    },
+    /// The plan file the run produced or acted on.
+    Plan {
+        path: String,
+        hash: String,
+    },
+    Phase {
+        name: String,
+        duration_ms: u64,
+        status: i32,
+    },
+    Item {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+        outcome: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+    Snapshot {
+        phase: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        status: i32,
+    },
+    End {
+        status: i32,
+    },
+}
+
+/// Overall state of a run.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+    /// No end event: still running, or killed before it could record one.
+    Incomplete,
+}
+
+impl std::fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunStatus::Succeeded => write!(f, "succeeded"),
+            RunStatus::Failed => write!(f, "failed"),
+            RunStatus::Incomplete => write!(f, "incomplete"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PhaseRecord {
+    pub name: String,
+    pub ended_at: String,
+    pub duration_ms: u64,
+    pub status: i32,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ItemRecord {
+    pub at: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub outcome: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SnapshotRecord {
+    pub at: String,
+    pub phase: String,
+    pub id: Option<String>,
+    pub status: i32,
+}
+
+/// A run folded from its journal events.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRecord {
+    pub run_id: String,
+    pub command: String,
+    pub arguments: Vec<String>,
+    pub config_path: Option<String>,
+    pub config_hash: Option<String>,
+    pub manifest_path: Option<String>,
+    pub manifest_hash: Option<String>,
+    pub plan_path: Option<String>,
+    pub plan_hash: Option<String>,
+    pub log_path: Option<String>,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub exit_status: Option<i32>,
+    pub status: RunStatus,
+    pub phases: Vec<PhaseRecord>,
+    pub items: Vec<ItemRecord>,
+    pub snapshots: Vec<SnapshotRecord>,
+    pub items_succeeded: usize,
+    pub items_failed: usize,
+}
+
+impl RunRecord {
+    pub fn started(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.started_at)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+}
+
+/// Environment variable the orchestrator exports so every `synsyu_core`
+/// invocation in one run shares its run ID.
+pub const RUN_ID_ENV: &str = "SYNSYU_RUN_ID";
+
+/// The run ID inherited from the orchestrator, if set and well-formed.
+pub fn run_id_from_env() -> Option<String> {
+    std::env::var(RUN_ID_ENV)
+        .ok()
+        .filter(|run_id| validate_run_id(run_id).is_ok())
+}
+
+/// Reject run IDs that could escape the journal directory.
+pub fn validate_run_id(run_id: &str) -> Result<()> {
+    let ok = !run_id.is_empty()
+        && run_id.len() <= 128
+        && !run_id.starts_with('.')
+        && run_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if ok {
+        Ok(())
+    } else {
+        Err(SynsyuError::Runtime(format!(
+            "Invalid run ID '{run_id}' (use letters, digits, '-', '_', '.')"
+        )))
+    }
+}
+
+pub fn run_path(dir: &Path, run_id: &str) -> PathBuf {
+    dir.join(format!("{run_id}.jsonl"))
+}
+
+/// SHA-256 of a file, or `None` when it cannot be read.
+pub fn file_hash(path: &Path) -> Option<String> {
+    fs::read(path)
+        .ok()
+        .map(|data| format!("{:x}", Sha256::digest(&data)))
+}
+
+/// Append one event to the run's journal file.
+pub fn append_event(dir: &Path, run_id: &str, event: RunEvent) -> Result<()> {
+    validate_run_id(run_id)?;
+    fs::create_dir_all(dir).map_err(|err| {
+        SynsyuError::Filesystem(format!(
+            "Failed to create run journal {}: {err}",
+            dir.display()
+        ))
+    })?;
+    let path = run_path(dir, run_id);
+    let mut line = serde_json::to_string(&RunEntry::now(event))
+        .map_err(|err| SynsyuError::Serialization(err.to_string()))?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|err| {
+            SynsyuError::Filesystem(format!(
+                "Failed to write run journal {}: {err}",
+                path.display()
+            ))
+        })
+}
+
+/// Fold a run's events into a record; `None` when it has no start event.
+pub fn fold_run(run_id: &str, entries: &[RunEntry]) -> Option<RunRecord> {
+    let (started_at, command, arguments, config_path, config_hash, log_path) =
+        entries.iter().find_map(|entry| match &entry.event {
+            RunEvent::Start {
+                command,
+                arguments,
+                config_path,
+                config_hash,
+                log_path,
+            } => Some((
+                entry.at.clone(),
+                command.clone(),
+                arguments.clone(),
+                config_path.clone(),
+                config_hash.clone(),
+                log_path.clone(),
+            )),
+            _ => None,
+        })?;
+    let mut record = RunRecord {
+        run_id: run_id.to_string(),
+        command,
+        arguments,
+        config_path,
+        config_hash,
+        manifest_path: None,
+        manifest_hash: None,
+        plan_path: None,
+        plan_hash: None,
+        log_path,
+        started_at,
+        ended_at: None,
+        duration_ms: None,
+        exit_status: None,
+        status: RunStatus::Incomplete,
+        phases: Vec::new(),
+        items: Vec::new(),
+        snapshots: Vec::new(),
+        items_succeeded: 0,
+        items_failed: 0,
+    };
+    for entry in entries {
+        match &entry.event {
+            RunEvent::Start { .. } => {}
+            RunEvent::Manifest { path, hash } => {
+                record.manifest_path = Some(path.clone());
+                record.manifest_hash = Some(hash.clone());
+            }
+            RunEvent::Plan { path, hash } => {
+                record.plan_path = Some(path.clone());
+                record.plan_hash = Some(hash.clone());
+            }
+            RunEvent::Phase {
+                name,
+                duration_ms,
+                status,
+            } => record.phases.push(PhaseRecord {
+                name: name.clone(),
+                ended_at: entry.at.clone(),
+                duration_ms: *duration_ms,
+                status: *status,
+            }),
+            RunEvent::Item {
+                name,
+                source,
+                outcome,
+                detail,
+            } => {
+                match outcome.as_str() {
+                    "success" => record.items_succeeded += 1,
+                    "failure" => record.items_failed += 1,
+                    _ => {}
+                }
+                record.items.push(ItemRecord {
+                    at: entry.at.clone(),
+                    name: name.clone(),
+                    source: source.clone(),
+                    outcome: outcome.clone(),
+                    detail: detail.clone(),
+                });
+            }
+            RunEvent::Snapshot { phase, id, status } => record.snapshots.push(SnapshotRecord {
+                at: entry.at.clone(),
+                phase: phase.clone(),
+                id: id.clone(),
+                status: *status,
+            }),
+            RunEvent::End { status } => {
+                record.ended_at = Some(entry.at.clone());
+                record.exit_status = Some(*status);
+                record.status = if *status == 0 {
+                    RunStatus::Succeeded
+                } else {
+                    RunStatus::Failed
+                };
+            }
+        }
+    }
+    if let (Some(start), Some(end)) = (
+        record.started(),
+        record
+            .ended_at
+            .as_deref()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok()),
+    ) {
+        record.duration_ms =
+            Some((end.with_timezone(&Utc) - start).num_milliseconds().max(0) as u64);
+    }
+    Some(record)
+}
+
+/// Read and fold one run; unparsable lines are skipped.
+pub fn load_run(dir: &Path, run_id: &str) -> Option<RunRecord> {
+    let contents = fs::read_to_string(run_path(dir, run_id)).ok()?;
+    let entries: Vec<RunEntry> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    fold_run(run_id, &entries)
+}
+
+/// Every run in `dir`, oldest first.
+pub fn load_runs(dir: &Path) -> Vec<RunRecord> {
+    let mut runs: Vec<RunRecord> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    let run_id = name.strip_suffix(".jsonl")?;
+                    load_run(dir, run_id)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    runs.sort_by(|a, b| {
+        a.started()
+            .cmp(&b.started())
+            .then_with(|| a.run_id.cmp(&b.run_id))
+    });
+    runs
+}
+
+/// Find a run by exact ID or unique prefix.
+pub fn find_run(dir: &Path, query: &str) -> Result<RunRecord> {
+    if validate_run_id(query).is_ok() {
+        if let Some(run) = load_run(dir, query) {
+            return Ok(run);
+        }
+    }
+    let mut matches: Vec<RunRecord> = load_runs(dir)
+        .into_iter()
+        .filter(|run| run.run_id.starts_with(query))
+        .collect();
+    match matches.len() {
+        1 => Ok(matches.remove(0)),
+        0 => Err(SynsyuError::Runtime(format!("No run matches '{query}'"))),
+        n => Err(SynsyuError::Runtime(format!(
+            "'{query}' matches {n} runs; give more of the run ID"
+        ))),
+    }
+}
+
+/// Delete the oldest run files beyond `keep` (0 keeps everything).
+pub fn prune_runs(dir: &Path, keep: usize) -> usize {
+    if keep == 0 {
+        return 0;
+    }
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().ends_with(".jsonl"))
+                .filter_map(|entry| {
+                    let modified = entry.metadata().ok()?.modified().ok()?;
+                    Some((modified, entry.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if files.len() <= keep {
+        return 0;
+    }
+    files.sort();
+    let excess = files.len() - keep;
+    files
+        .into_iter()
+        .take(excess)
+        .filter(|(_, path)| fs::remove_file(path).is_ok())
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn entry(at: &str, event: RunEvent) -> RunEntry {
+        RunEntry {
+            at: at.into(),
+            event,
+        }
+    }
+
+    #[test]
+    fn folds_events_into_a_run_record() {
+        let entries = vec![
+            entry(
+                "2026-10-13T08:00:00.000Z",
+                RunEvent::Start {
+                    command: "sync".into(),
+                    arguments: vec!["--noconfirm".into()],
+                    config_path: Some("/home/u/.config/syn-syu/config.toml".into()),
+                    config_hash: Some("abc".into()),
+                    log_path: Some("/tmp/run.log".into()),
+                },
+            ),
+            entry(
+                "2026-10-13T08:00:01.000Z",
+                RunEvent::Manifest {
+                    path: "/tmp/manifest.json".into(),
+                    hash: "def".into(),
+                },
+            ),
+            entry(
+                "2026-10-13T08:00:02.000Z",
+                RunEvent::Snapshot {
+                    phase: "pre".into(),
+                    id: Some("42".into()),
+                    status: 0,
+                },
+            ),
+            entry(
+                "2026-10-13T08:01:00.000Z",
+                RunEvent::Item {
+                    name: "linux".into(),
+                    source: Some("PACMAN".into()),
+                    outcome: "success".into(),
+                    detail: None,
+                },
+            ),
+            entry(
+                "2026-10-13T08:02:00.000Z",
+                RunEvent::Item {
+                    name: "foo-git".into(),
+                    source: Some("AUR".into()),
+                    outcome: "failure".into(),
+                    detail: Some("paru exited 1".into()),
+                },
+            ),
+            entry(
+                "2026-10-13T08:02:30.000Z",
+                RunEvent::Phase {
+                    name: "updates".into(),
+                    duration_ms: 148_000,
+                    status: 0,
+                },
+            ),
+            entry("2026-10-13T08:02:30.500Z", RunEvent::End { status: 0 }),
+        ];
+        let run = fold_run("r1", &entries).unwrap();
+        assert_eq!(run.status, RunStatus::Succeeded);
+        assert_eq!(run.duration_ms, Some(150_500));
+        assert_eq!(run.manifest_hash.as_deref(), Some("def"));
+        assert!(run.plan_hash.is_none());
+        assert_eq!((run.items_succeeded, run.items_failed), (1, 1));
+        assert_eq!(run.snapshots[0].id.as_deref(), Some("42"));
+        assert_eq!(run.phases[0].name, "updates");
+
+        // Without an end event the run stays incomplete.
+        let run = fold_run("r2", &entries[..3]).unwrap();
+        assert_eq!(run.status, RunStatus::Incomplete);
+        assert!(run.duration_ms.is_none());
+        assert!(fold_run("r3", &entries[1..]).is_none());
+    }
+
+    #[test]
+    fn journal_files_round_trip_and_prune() {
+        let tmp = TempDir::new("synsyu_runs_").unwrap();
+        let dir = tmp.path();
+        for run_id in ["20261013T080000Z-aa", "20261014T080000Z-bb"] {
+            append_event(
+                dir,
+                run_id,
+                RunEvent::Start {
+                    command: "update".into(),
+                    arguments: vec!["vim".into()],
+                    config_path: None,
+                    config_hash: None,
+                    log_path: None,
+                },
+            )
+            .unwrap();
+            append_event(dir, run_id, RunEvent::End { status: 1 }).unwrap();
+        }
+        assert!(append_event(dir, "../escape", RunEvent::End { status: 0 }).is_err());
+
+        let runs = load_runs(dir);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[1].status, RunStatus::Failed);
+        assert_eq!(
+            find_run(dir, "20261014").unwrap().run_id,
+            "20261014T080000Z-bb"
+        );
+        assert!(find_run(dir, "2026101").is_err());
+
+        assert_eq!(prune_runs(dir, 1), 1);
+        assert_eq!(load_runs(dir).len(), 1);
+    }
+}
@@ -0,0 +1,422 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::signing
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Generate the ed25519 signing key, write detached `.sig`
+    signatures for manifests and plans, and verify them
+    against trusted public keys so a plan handed between
+    machines or CI jobs cannot be altered unnoticed.
+
+  Security / Safety Notes:
+    The private key is created 0600 and refused when group
+    or world can read it. Signatures cover the exact file
+    bytes; the recorded SHA-256 only tells a modified file
+    apart from a forged signature. Verification never trusts
+    the public key carried in the signature on its own.
+============================================================*/
+
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use chrono::{SecondsFormat, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::config::SigningConfig;
+use crate::error::{Result, SynsyuError};
+
+pub const SIGNATURE_SUFFIX: &str = ".sig";
+const ALGORITHM: &str = "ed25519";
+
+/// Detached signature file contents.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DetachedSignature {
+    pub algorithm: String,
+    /// Hex public key of the signer.
+    pub public_key: String,
+    /// Hex SHA-256 of the signed file.
+    pub sha256: String,
+    /// Hex ed25519 signature over the file bytes.
+    pub signature: String,
+    pub signed_at: String,
+}
+
+/// Outcome of checking one file.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    Valid,
+    /// No `.sig` next to the file.
+    Unsigned,
+    /// The file changed after it was signed.
+    Modified,
+    /// The signature does not match the file and key.
+    BadSignature,
+    /// Signed by a key that is not trusted here.
+    UntrustedKey,
+    /// The `.sig` could not be parsed.
+    Malformed,
+}
+
+impl VerifyStatus {
+    pub fn describe(self) -> &'static str {
+        match self {
+            VerifyStatus::Valid => "valid signature",
+            VerifyStatus::Unsigned => "no signature",
+            VerifyStatus::Modified => "modified since it was signed",
+            VerifyStatus::BadSignature => "signature does not match",
+            VerifyStatus::UntrustedKey => "signed by an untrusted key",
+            VerifyStatus::Malformed => "unreadable signature file",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+    pub path: PathBuf,
+    pub status: VerifyStatus,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signed_at: Option<String>,
+}
+
+/// `<path>.sig`.
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(SIGNATURE_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Create a new key pair at `secret` and `public`; refuses to replace an
+/// existing private key unless `force`.
+pub fn generate_key(secret: &Path, public: &Path, force: bool) -> Result<VerifyingKey> {
+    if let Some(parent) = secret.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            SynsyuError::Filesystem(format!(
+                "Failed to create key directory {}: {err}",
+                parent.display()
+            ))
+        })?;
+    }
+    let mut seed = [0u8; 32];
+    fs::File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut seed))
+        .map_err(|err| SynsyuError::Runtime(format!("Failed to read /dev/urandom: {err}")))?;
+    let key = SigningKey::from_bytes(&seed);
+
+    let mut options = OpenOptions::new();
+    options.write(true).mode(0o600);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    let mut file = options.open(secret).map_err(|err| {
+        if err.kind() == std::io::ErrorKind::AlreadyExists {
+            SynsyuError::Config(format!(
+                "Signing key {} already exists; pass --force to replace it",
+                secret.display()
+            ))
+        } else {
+            SynsyuError::Filesystem(format!(
+                "Failed to create signing key {}: {err}",
+                secret.display()
+            ))
+        }
+    })?;
+    // A replaced key keeps the mode it had; tighten it again.
+    fs::set_permissions(secret, fs::Permissions::from_mode(0o600))
+        .and_then(|_| writeln!(file, "{}", to_hex(&seed)))
+        .map_err(|err| {
+            SynsyuError::Filesystem(format!(
+                "Failed to write signing key {}: {err}",
+                secret.display()
+            ))
+        })?;
+    let verifying = key.verifying_key();
+    fs::write(public, format!("{}\n", to_hex(verifying.as_bytes()))).map_err(|err| {
+        SynsyuError::Filesystem(format!(
+            "Failed to write public key {}: {err}",
+            public.display()
+        ))
+    })?;
+    Ok(verifying)
+}
+
+/// Load the private key, refusing one that group or world can read.
+pub fn load_signing_key(path: &Path) -> Result<SigningKey> {
+    let metadata = fs::metadata(path).map_err(|err| {
+        SynsyuError::Config(format!(
+            "Signing key {} unavailable ({err}); create one with `synsyu_core sign --generate-key`",
+            path.display()
+        ))
+    })?;
+    if metadata.permissions().mode() & 0o077 != 0 {
+        return Err(SynsyuError::Config(format!(
+            "Signing key {} must not be readable by group or others (chmod 600)",
+            path.display()
+        )));
+    }
+    let seed = read_key_bytes(path)?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Load a hex public key file.
+pub fn load_verifying_key(path: &Path) -> Result<VerifyingKey> {
+    let bytes = read_key_bytes(path)?;
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|err| SynsyuError::Config(format!("Invalid public key {}: {err}", path.display())))
+}
+
+fn read_key_bytes(path: &Path) -> Result<[u8; 32]> {
+    let raw = fs::read_to_string(path).map_err(|err| {
+        SynsyuError::Filesystem(format!("Failed to read key {}: {err}", path.display()))
+    })?;
+    from_hex(raw.trim())
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| {
+            SynsyuError::Config(format!(
+                "Key {} is not 32 hex-encoded bytes",
+                path.display()
+            ))
+        })
+}
+
+/// Sign `path` and write `<path>.sig`.
+pub fn sign_file(path: &Path, key: &SigningKey) -> Result<PathBuf> {
+    let data = fs::read(path).map_err(|err| {
+        SynsyuError::Filesystem(format!("Failed to read {}: {err}", path.display()))
+    })?;
+    let signature = DetachedSignature {
+        algorithm: ALGORITHM.to_string(),
+        public_key: to_hex(key.verifying_key().as_bytes()),
+        sha256: format!("{:x}", Sha256::digest(&data)),
+        signature: to_hex(&key.sign(&data).to_bytes()),
+        signed_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+    };
+    let sig_path = signature_path(path);
+    let json = serde_json::to_string_pretty(&signature)
+        .map_err(|err| SynsyuError::Serialization(err.to_string()))?;
+    fs::write(&sig_path, format!("{json}\n")).map_err(|err| {
+        SynsyuError::Filesystem(format!(
+            "Failed to write signature {}: {err}",
+            sig_path.display()
+        ))
+    })?;
+    Ok(sig_path)
+}
+
+/// Check `path` against its `.sig` and the `trusted` keys.
+pub fn verify_file(path: &Path, trusted: &[VerifyingKey]) -> Result<VerifyReport> {
+    let data = fs::read(path).map_err(|err| {
+        SynsyuError::Filesystem(format!("Failed to read {}: {err}", path.display()))
+    })?;
+    let report = |status: VerifyStatus, signature: Option<&DetachedSignature>| VerifyReport {
+        path: path.to_path_buf(),
+        status,
+        ok: status == VerifyStatus::Valid,
+        public_key: signature.map(|sig| sig.public_key.clone()),
+        signed_at: signature.map(|sig| sig.signed_at.clone()),
+    };
+
+    let raw = match fs::read_to_string(signature_path(path)) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(report(VerifyStatus::Unsigned, None))
+        }
+        Err(err) => {
+            return Err(SynsyuError::Filesystem(format!(
+                "Failed to read signature {}: {err}",
+                signature_path(path).display()
+            )))
+        }
+    };
+    let Ok(signature) = serde_json::from_str::<DetachedSignature>(&raw) else {
+        return Ok(report(VerifyStatus::Malformed, None));
+    };
+    let parsed = (signature.algorithm == ALGORITHM)
+        .then(|| {
+            let key = from_hex(&signature.public_key)?;
+            let sig = from_hex(&signature.signature)?;
+            Some((
+                <[u8; 32]>::try_from(key).ok()?,
+                Signature::from_slice(&sig).ok()?,
+            ))
+        })
+        .flatten();
+    let Some((key_bytes, sig)) = parsed else {
+        return Ok(report(VerifyStatus::Malformed, Some(&signature)));
+    };
+    let Some(key) = trusted.iter().find(|key| key.as_bytes() == &key_bytes) else {
+        return Ok(report(VerifyStatus::UntrustedKey, Some(&signature)));
+    };
+    if format!("{:x}", Sha256::digest(&data)) != signature.sha256 {
+        return Ok(report(VerifyStatus::Modified, Some(&signature)));
+    }
+    let status = if key.verify_strict(&data, &sig).is_ok() {
+        VerifyStatus::Valid
+    } else {
+        VerifyStatus::BadSignature
+    };
+    Ok(report(status, Some(&signature)))
+}
+
+/// Load every trusted public key; at least one must exist.
+pub fn load_trusted_keys(paths: &[PathBuf]) -> Result<Vec<VerifyingKey>> {
+    if paths.is_empty() {
+        return Err(SynsyuError::Config(
+            "No trusted public keys configured (signing.trusted_keys)".into(),
+        ));
+    }
+    paths.iter().map(|path| load_verifying_key(path)).collect()
+}
+
+/// Fail unless `path` carries a valid signature from a trusted key.
+pub fn require_signed(path: &Path, trusted: &[VerifyingKey]) -> Result<()> {
+    let report = verify_file(path, trusted)?;
+    if report.ok {
+        Ok(())
+    } else {
+        Err(SynsyuError::Runtime(format!(
+            "Refusing {}: {} (signing.require is set)",
+            path.display(),
+            report.status.describe()
+        )))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(&text[at..at + 2], 16).ok())
+        .collect()
+}
+
+/// Read and parse the manifest or plan (`what`) at `path`. When
+/// `signing.require` is set the file is refused first unless a trusted key
+/// signed it; every command that acts on a manifest or plan loads it here.
+pub fn load_document(signing: &SigningConfig, path: &Path, what: &str) -> Result<Value> {
+    if signing.require {
+        let trusted = load_trusted_keys(&signing.trusted_key_paths())?;
+        require_signed(path, &trusted)?;
+    }
+    let raw = fs::read_to_string(path).map_err(|err| {
+        SynsyuError::Filesystem(format!("Failed to read {what} {}: {err}", path.display()))
+    })?;
+    serde_json::from_str(&raw).map_err(|err| {
+        SynsyuError::Serialization(format!("Failed to parse {what} {}: {err}", path.display()))
+    })
+}
+
+/// `load_document` for an optional file: `None` when `path` does not exist.
+pub fn load_optional_document(
+    signing: &SigningConfig,
+    path: &Path,
+    what: &str,
+) -> Result<Option<Value>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    load_document(signing, path, what).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn detects_tampering_and_untrusted_keys() {
+        let tmp = TempDir::new("synsyu_signing_").unwrap();
+        let dir = tmp.path();
+        let (secret, public) = (dir.join("signing.key"), dir.join("signing.pub"));
+        let verifying = generate_key(&secret, &public, false).unwrap();
+        assert!(generate_key(&secret, &public, false).is_err());
+        assert_eq!(
+            fs::metadata(&secret).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(load_verifying_key(&public).unwrap(), verifying);
+
+        let plan = dir.join("plan.json");
+        fs::write(&plan, "{\"counts\":{\"pacman\":1}}").unwrap();
+        let trusted = [verifying];
+        assert_eq!(
+            verify_file(&plan, &trusted).unwrap().status,
+            VerifyStatus::Unsigned
+        );
+        sign_file(&plan, &load_signing_key(&secret).unwrap()).unwrap();
+        assert!(verify_file(&plan, &trusted).unwrap().ok);
+        assert!(require_signed(&plan, &trusted).is_ok());
+
+        let stranger = SigningKey::from_bytes(&[7u8; 32]).verifying_key();
+        assert_eq!(
+            verify_file(&plan, &[stranger]).unwrap().status,
+            VerifyStatus::UntrustedKey
+        );
+
+        fs::write(&plan, "{\"counts\":{\"pacman\":9}}").unwrap();
+        assert_eq!(
+            verify_file(&plan, &trusted).unwrap().status,
+            VerifyStatus::Modified
+        );
+        // Rewriting the recorded digest does not help a forger.
+        let sig_path = signature_path(&plan);
+        let mut sig: DetachedSignature =
+            serde_json::from_str(&fs::read_to_string(&sig_path).unwrap()).unwrap();
+        sig.sha256 = format!("{:x}", Sha256::digest(fs::read(&plan).unwrap()));
+        fs::write(&sig_path, serde_json::to_string(&sig).unwrap()).unwrap();
+        assert_eq!(
+            verify_file(&plan, &trusted).unwrap().status,
+            VerifyStatus::BadSignature
+        );
+        assert!(require_signed(&plan, &trusted).is_err());
+
+        fs::set_permissions(&secret, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(load_signing_key(&secret).is_err());
+    }
+
+    #[test]
+    fn documents_load_only_when_signed_under_require() {
+        let tmp = TempDir::new("synsyu_signing_").unwrap();
+        let dir = tmp.path();
+        let (secret, public) = (dir.join("signing.key"), dir.join("signing.pub"));
+        generate_key(&secret, &public, false).unwrap();
+        let plan = dir.join("plan.json");
+        fs::write(&plan, "{\"counts\":{\"pacman\":1}}").unwrap();
+
+        let mut signing = SigningConfig {
+            trusted_keys: vec![public.display().to_string()],
+            ..SigningConfig::default()
+        };
+        assert_eq!(
+            load_document(&signing, &plan, "plan").unwrap()["counts"]["pacman"],
+            1
+        );
+        signing.require = true;
+        assert!(load_document(&signing, &plan, "plan").is_err());
+        sign_file(&plan, &load_signing_key(&secret).unwrap()).unwrap();
+        assert!(load_document(&signing, &plan, "plan").is_ok());
+
+        let missing = dir.join("missing.json");
+        assert!(load_optional_document(&signing, &missing, "plan")
+            .unwrap()
+            .is_none());
+        assert!(load_document(&signing, &missing, "plan").is_err());
+    }
+}
@@ -4,8 +4,10 @@
   Etiquette: Synavera Script Etiquette — Rust Profile v1.1.1
   ------------------------------------------------------------
   Purpose:
-    Assess filesystem capacity and provide human-friendly
-    formatting utilities for disk usage checks.
+    Assess filesystem capacity per update phase, grouping the
+    download, build, install, and Flatpak targets by device, and
+    provide human-friendly formatting utilities for disk usage
+    checks.
 
   Security / Safety Notes:
     Uses statvfs on Unix platforms to gather free space. Paths
@@ -27,68 +29,803 @@
     - Readable byte formatting for operator feedback
 ============================================================*/
 
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
 use std::path::{Path, PathBuf};
 
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::build_history::{estimate as estimate_build, BuildSample};
 use crate::error::{Result, SynsyuError};
+use crate::package_info::VersionInfo;
+
+/// Update phase that consumes disk space.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum SpacePhase {
+    Download,
+    Build,
+    Install,
+    Flatpak,
+    Firmware,
+    Boot,
+}
+
+impl SpacePhase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpacePhase::Download => "download",
+            SpacePhase::Build => "build",
+            SpacePhase::Install => "install",
+            SpacePhase::Flatpak => "flatpak",
+            SpacePhase::Firmware => "firmware",
+            SpacePhase::Boot => "boot",
+        }
+    }
+}
 
-/// Outcome of a disk space assessment.
+/// Bytes and files one phase needs under a given path; negative when the
+/// phase frees space (removals, replacements, shrinking upgrades).
 #[derive(Debug, Clone)]
-pub struct SpaceReport {
-    pub checked_path: PathBuf,
+pub struct SpaceRequirement {
+    pub phase: SpacePhase,
+    pub path: PathBuf,
+    pub bytes: i64,
+    /// Estimated change in file count (inodes).
+    pub files: i64,
+}
+
+/// How btrfs chunk allocation feeds into the capacity check.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BtrfsMode {
+    /// Trust statvfs; do not read btrfs allocation.
+    Off,
+    /// Read allocation and report it; statvfs still decides.
+    #[default]
+    Report,
+    /// Cap free space at data headroom and require metadata headroom.
+    Enforce,
+}
+
+impl std::fmt::Display for BtrfsMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BtrfsMode::Off => write!(f, "off"),
+            BtrfsMode::Report => write!(f, "report"),
+            BtrfsMode::Enforce => write!(f, "enforce"),
+        }
+    }
+}
+
+/// Buffers and btrfs handling applied when checking each filesystem.
+#[derive(Debug, Clone, Copy)]
+pub struct SpaceLimits {
+    pub margin_bytes: u64,
+    pub inode_margin: u64,
+    pub btrfs_mode: BtrfsMode,
+}
+
+/// Metadata a btrfs install is charged per file: inode, directory, and
+/// extent items, plus small files stored inline.
+pub const BTRFS_METADATA_PER_FILE: u64 = 4096;
+
+/// Fallback bytes-per-file ratio when the local package database is unreadable.
+pub const DEFAULT_FILE_BYTES: u64 = 32 * 1024;
+
+/// Btrfs chunk allocation for one filesystem, read from
+/// `/sys/fs/btrfs/<fsid>/allocation`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BtrfsAllocation {
+    pub data_total: u64,
+    pub data_used: u64,
+    pub metadata_total: u64,
+    pub metadata_used: u64,
+    pub global_reserve: u64,
+    /// Raw bytes across all member devices.
+    pub device_bytes: u64,
+    /// Raw device bytes not yet assigned to any chunk.
+    pub unallocated: u64,
+    /// Free data chunk space plus what unallocated space can still hold.
+    pub data_headroom: u64,
+    /// Free metadata chunk space (less the global reserve) plus what
+    /// unallocated space can still hold.
+    pub metadata_headroom: u64,
+    pub metadata_required: u64,
+    pub enforced: bool,
+    pub ok: bool,
+}
+
+/// Summed requirements and capacity for one filesystem.
+#[derive(Debug, Clone, Serialize)]
+pub struct FilesystemUsage {
+    /// Grouping key: `btrfs:<fsid>` for btrfs, whose subvolumes each get their
+    /// own `st_dev` but share one pool, otherwise `dev:<st_dev>`.
+    pub filesystem: String,
+    pub mount_point: PathBuf,
+    /// Filesystem type from the mount table (`unknown` when not found).
+    pub fs_type: String,
+    pub paths: Vec<PathBuf>,
+    pub phases: BTreeMap<SpacePhase, i64>,
+    /// Sum of all phases; may be negative when the update frees space.
+    pub net_bytes: i64,
+    /// `net_bytes` clamped at zero.
+    pub required_bytes: u64,
+    pub margin_bytes: u64,
     pub available_bytes: u64,
+    /// Estimated change in file count across all phases.
+    pub net_files: i64,
+    /// `net_files` clamped at zero.
+    pub required_files: u64,
+    pub inode_margin: u64,
+    /// Free inodes; `None` when the filesystem allocates inodes dynamically.
+    pub available_inodes: Option<u64>,
+    pub btrfs: Option<BtrfsAllocation>,
+    pub space_ok: bool,
+    pub inodes_ok: bool,
+    pub ok: bool,
 }
 
-/// Determine available bytes for the most constrained filesystem among candidates.
-pub fn assess_default_paths() -> Result<SpaceReport> {
-    let candidates = [
-        Path::new("/var/cache/pacman/pkg"),
-        Path::new("/var/tmp"),
-        Path::new("/tmp"),
-        Path::new("/"),
-    ];
-
-    let mut report: Option<SpaceReport> = None;
-    for candidate in candidates {
-        if let Some(existing) = ensure_existing(candidate) {
-            match free_bytes(existing) {
-                Ok(bytes) => match &report {
-                    Some(current) if bytes >= current.available_bytes => {}
-                    _ => {
-                        report = Some(SpaceReport {
-                            checked_path: existing.to_path_buf(),
-                            available_bytes: bytes,
-                        });
-                    }
-                },
-                Err(err) => {
-                    // Propagate the last error if no valid path is found.
-                    if report.is_none() {
-                        return Err(err);
-                    }
-                }
+impl FilesystemUsage {
+    /// Report lines for this filesystem: passing checks go to `details`,
+    /// enforced shortfalls to `failures`.
+    pub fn report(&self, details: &mut Vec<String>, failures: &mut Vec<String>) {
+        let phases = self
+            .phases
+            .iter()
+            .map(|(phase, bytes)| {
+                let amount = if *bytes < 0 {
+                    format_delta(*bytes)
+                } else {
+                    format_bytes(bytes.unsigned_abs())
+                };
+                format!("{} {amount}", phase.label())
+            })
+            .collect::<Vec<_>>()
+            .join(" + ");
+        let line = format!(
+            "need ~{} ({phases} + buffer {}) on {} ({}); have {}",
+            format_bytes(self.required_bytes.saturating_add(self.margin_bytes)),
+            format_bytes(self.margin_bytes),
+            self.mount_point.display(),
+            self.fs_type,
+            format_bytes(self.available_bytes),
+        );
+        if self.space_ok {
+            details.push(format!("OK: {line}"));
+        } else {
+            failures.push(format!("Insufficient space: {line}"));
+        }
+        if let Some(free) = self.available_inodes.filter(|_| self.required_files > 0) {
+            let line = format!(
+                "need ~{} inodes (+ buffer {}) on {}; have {free}",
+                self.required_files,
+                self.inode_margin,
+                self.mount_point.display(),
+            );
+            if self.inodes_ok {
+                details.push(format!("OK: {line}"));
+            } else {
+                failures.push(format!("Insufficient inodes: {line}"));
+            }
+        }
+        if let Some(btrfs) = &self.btrfs {
+            let line = format!(
+                "btrfs {}: data headroom {} (chunks {} free + {} unallocated), metadata headroom {} for ~{} needed (global reserve {})",
+                self.mount_point.display(),
+                format_bytes(btrfs.data_headroom),
+                format_bytes(btrfs.data_total.saturating_sub(btrfs.data_used)),
+                format_bytes(btrfs.unallocated),
+                format_bytes(btrfs.metadata_headroom),
+                format_bytes(btrfs.metadata_required),
+                format_bytes(btrfs.global_reserve),
+            );
+            match (btrfs.ok, btrfs.enforced) {
+                (true, _) => details.push(line),
+                (false, true) => failures.push(format!("Insufficient btrfs headroom: {line}")),
+                (false, false) => details.push(format!(
+                    "WARN: {line}; would fail with space.btrfs_mode = \"enforce\""
+                )),
+            }
+        }
+    }
+}
+
+/// Map each requirement to its filesystem by device ID, sum per filesystem,
+/// and check bytes, inodes, and (on btrfs) chunk headroom against `limits`.
+pub fn account_by_filesystem(
+    requirements: &[SpaceRequirement],
+    limits: &SpaceLimits,
+) -> Result<Vec<FilesystemUsage>> {
+    account_with(requirements, limits, probe_filesystem)
+}
+
+/// Grouping key of the filesystem holding `path`, as used for
+/// [`FilesystemUsage::filesystem`].
+pub fn filesystem_key(path: &Path) -> Result<String> {
+    probe_filesystem(path).map(|probe| probe.key)
+}
+
+/// What grouping and checking need to know about a requirement's filesystem.
+#[derive(Debug, Clone)]
+struct FilesystemProbe {
+    key: String,
+    mount_point: PathBuf,
+    fs_type: String,
+    stats: FsStats,
+    /// `/sys/fs/btrfs/<fsid>` for btrfs filesystems.
+    btrfs_dir: Option<PathBuf>,
+}
+
+fn probe_filesystem(path: &Path) -> Result<FilesystemProbe> {
+    let existing = ensure_existing(path).unwrap_or(Path::new("/"));
+    let device = device_id(existing)?;
+    let mount_point = mount_point(existing)?;
+    let (fs_type, source) =
+        mount_source(&mount_point).unwrap_or_else(|| ("unknown".into(), String::new()));
+    let (key, btrfs_dir) = if fs_type == "btrfs" {
+        let dir = btrfs_sysfs_dir(&source);
+        let fsid = dir
+            .as_deref()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or(source);
+        (format!("btrfs:{fsid}"), dir)
+    } else {
+        (format!("dev:{device}"), None)
+    };
+    Ok(FilesystemProbe {
+        key,
+        mount_point,
+        fs_type,
+        stats: fs_stats(existing)?,
+        btrfs_dir,
+    })
+}
+
+fn account_with(
+    requirements: &[SpaceRequirement],
+    limits: &SpaceLimits,
+    probe: impl Fn(&Path) -> Result<FilesystemProbe>,
+) -> Result<Vec<FilesystemUsage>> {
+    let mut probes: HashMap<&Path, FilesystemProbe> = HashMap::new();
+    let mut rows: BTreeMap<String, FilesystemUsage> = BTreeMap::new();
+    for requirement in requirements {
+        let fs = match probes.get(requirement.path.as_path()) {
+            Some(fs) => fs.clone(),
+            None => {
+                let fs = probe(&requirement.path)?;
+                probes.insert(&requirement.path, fs.clone());
+                fs
             }
+        };
+        let row = match rows.entry(fs.key.clone()) {
+            std::collections::btree_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::btree_map::Entry::Vacant(entry) => {
+                let btrfs = match limits.btrfs_mode {
+                    BtrfsMode::Off => None,
+                    _ => fs.btrfs_dir.as_deref().and_then(btrfs_allocation),
+                };
+                entry.insert(FilesystemUsage {
+                    filesystem: fs.key,
+                    mount_point: fs.mount_point,
+                    fs_type: fs.fs_type,
+                    paths: Vec::new(),
+                    phases: BTreeMap::new(),
+                    net_bytes: 0,
+                    required_bytes: 0,
+                    margin_bytes: limits.margin_bytes,
+                    available_bytes: fs.stats.available_bytes,
+                    net_files: 0,
+                    required_files: 0,
+                    inode_margin: limits.inode_margin,
+                    available_inodes: fs.stats.available_inodes,
+                    btrfs,
+                    space_ok: true,
+                    inodes_ok: true,
+                    ok: true,
+                })
+            }
+        };
+        if !row.paths.contains(&requirement.path) {
+            row.paths.push(requirement.path.clone());
         }
+        let phase = row.phases.entry(requirement.phase).or_insert(0);
+        *phase = phase.saturating_add(requirement.bytes);
+        row.net_bytes = row.net_bytes.saturating_add(requirement.bytes);
+        row.net_files = row.net_files.saturating_add(requirement.files);
     }
 
-    report.ok_or_else(|| SynsyuError::Runtime("Unable to determine available disk space".into()))
+    let mut usage: Vec<FilesystemUsage> = rows.into_values().collect();
+    for row in &mut usage {
+        check_row(row, limits.btrfs_mode);
+    }
+    usage.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    Ok(usage)
+}
+
+fn check_row(row: &mut FilesystemUsage, btrfs_mode: BtrfsMode) {
+    row.required_bytes = row.net_bytes.max(0) as u64;
+    row.required_files = row.net_files.max(0) as u64;
+    if let Some(btrfs) = &mut row.btrfs {
+        btrfs.metadata_required = row.required_files.saturating_mul(BTRFS_METADATA_PER_FILE);
+        btrfs.enforced = btrfs_mode == BtrfsMode::Enforce;
+        btrfs.ok = btrfs.metadata_headroom >= btrfs.metadata_required
+            && btrfs.data_headroom >= row.required_bytes;
+        if btrfs.enforced {
+            row.available_bytes = row.available_bytes.min(btrfs.data_headroom);
+        }
+    }
+    row.space_ok = row.required_bytes == 0
+        || row.available_bytes >= row.required_bytes.saturating_add(row.margin_bytes);
+    if let Some(btrfs) = &row.btrfs {
+        if btrfs.enforced && btrfs.metadata_headroom < btrfs.metadata_required {
+            row.space_ok = false;
+        }
+    }
+    row.inodes_ok = match row.available_inodes {
+        Some(free) => {
+            row.required_files == 0 || free >= row.required_files.saturating_add(row.inode_margin)
+        }
+        None => true,
+    };
+    row.ok = row.space_ok && row.inodes_ok;
 }
 
-/// Determine available bytes for a specific path (or its closest existing parent).
-pub fn assess_path(target: &Path) -> Result<SpaceReport> {
-    if let Some(existing) = ensure_existing(target) {
-        let bytes = free_bytes(existing)?;
-        Ok(SpaceReport {
-            checked_path: existing.to_path_buf(),
-            available_bytes: bytes,
+/// Estimated change in file count for one package. Upgrades scale the
+/// current file count by the size change; new packages divide their size by
+/// `bytes_per_file`; removals give back their current count.
+pub fn file_delta(current: Option<LocalFiles>, new_bytes: Option<u64>, bytes_per_file: u64) -> i64 {
+    let signed = |count: u64| i64::try_from(count).unwrap_or(i64::MAX);
+    match (current, new_bytes) {
+        (Some(current), Some(new)) if current.bytes > 0 => {
+            let scaled = (current.files as f64 * new as f64 / current.bytes as f64).round();
+            signed(scaled as u64) - signed(current.files)
+        }
+        (Some(_), Some(_)) => 0,
+        (None, Some(new)) => signed(files_for_bytes(new, bytes_per_file)),
+        (Some(current), None) => -signed(current.files),
+        (None, None) => 0,
+    }
+}
+
+/// Files needed to hold `bytes` at `bytes_per_file` each, rounded up.
+pub fn files_for_bytes(bytes: u64, bytes_per_file: u64) -> u64 {
+    if bytes == 0 {
+        0
+    } else {
+        bytes.div_ceil(bytes_per_file.max(1))
+    }
+}
+
+/// File count and installed bytes of one package from the local database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocalFiles {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// File counts per installed package, read from pacman's local database
+/// (`<name>-<version>/desc` and `files`). Unreadable entries are skipped.
+pub fn local_file_counts(local_db: &Path) -> HashMap<String, LocalFiles> {
+    let mut counts = HashMap::new();
+    let Ok(entries) = fs::read_dir(local_db) else {
+        return counts;
+    };
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let (Ok(desc), Ok(files)) = (
+            fs::read_to_string(dir.join("desc")),
+            fs::read_to_string(dir.join("files")),
+        ) else {
+            continue;
+        };
+        let Some(name) = db_field(&desc, "%NAME%") else {
+            continue;
+        };
+        let bytes = db_field(&desc, "%SIZE%")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        counts.insert(
+            name.to_string(),
+            LocalFiles {
+                files: db_section(&files, "%FILES%").count() as u64,
+                bytes,
+            },
+        );
+    }
+    counts
+}
+
+/// Average installed bytes per file across `counts`, or
+/// [`DEFAULT_FILE_BYTES`] when nothing usable was read.
+pub fn average_file_bytes(counts: &HashMap<String, LocalFiles>) -> u64 {
+    let (files, bytes) = counts.values().fold((0u64, 0u64), |(files, bytes), c| {
+        (files.saturating_add(c.files), bytes.saturating_add(c.bytes))
+    });
+    if files == 0 || bytes == 0 {
+        DEFAULT_FILE_BYTES
+    } else {
+        (bytes / files).max(1)
+    }
+}
+
+/// Where the download, build, and install phases land on disk.
+#[derive(Debug, Clone)]
+pub struct PhasePaths {
+    pub download: PathBuf,
+    pub build: PathBuf,
+    pub install: PathBuf,
+}
+
+impl PhasePaths {
+    /// Fold every phase onto one location (`space --path`).
+    pub fn uniform(path: &Path) -> Self {
+        Self {
+            download: path.to_path_buf(),
+            build: path.to_path_buf(),
+            install: path.to_path_buf(),
+        }
+    }
+
+    fn path(&self, phase: SpacePhase) -> &Path {
+        match phase {
+            SpacePhase::Download => &self.download,
+            SpacePhase::Build => &self.build,
+            _ => &self.install,
+        }
+    }
+}
+
+/// Requirements plus the report lines that explain them.
+#[derive(Debug, Default)]
+pub struct RequirementSet {
+    pub requirements: Vec<SpaceRequirement>,
+    pub details: Vec<String>,
+    /// Packages and apps with no size telemetry.
+    pub unknowns: Vec<String>,
+}
+
+impl RequirementSet {
+    /// Record a requirement, skipping phases that change nothing.
+    pub fn push(&mut self, phase: SpacePhase, path: PathBuf, bytes: i64, files: i64) {
+        if bytes != 0 || files != 0 {
+            self.requirements.push(SpaceRequirement {
+                phase,
+                path,
+                bytes,
+                files,
+            });
+        }
+    }
+}
+
+/// Size sources for the pacman and AUR side of an update.
+pub struct PackageSizing<'a> {
+    pub manifest: &'a Value,
+    /// `pacman -Si` data for requested repo packages and unknown names.
+    pub repo_sizes: &'a HashMap<String, VersionInfo>,
+    /// AUR helper data for AUR packages.
+    pub aur_sizes: &'a HashMap<String, VersionInfo>,
+    /// Latest measured build per AUR package.
+    pub build_samples: &'a BTreeMap<String, BuildSample>,
+    /// File counts from the local database, for inode checks.
+    pub local_files: &'a HashMap<String, LocalFiles>,
+    pub bytes_per_file: u64,
+    pub paths: PhasePaths,
+}
+
+impl PackageSizing<'_> {
+    /// Requirements for `packages` (or the whole manifest when empty), with
+    /// replaced and removed packages credited back.
+    pub fn requirements(&self, packages: &[String], removals: &[String]) -> RequirementSet {
+        let mut out = RequirementSet::default();
+        if packages.is_empty() {
+            self.aggregate(&mut out);
+        }
+        for pkg in packages {
+            self.package(pkg, &mut out);
+        }
+        self.released(packages, removals, &mut out);
+        out
+    }
+
+    fn require(&self, out: &mut RequirementSet, phase: SpacePhase, bytes: i64, files: i64) {
+        out.push(phase, self.paths.path(phase).to_path_buf(), bytes, files);
+    }
+
+    fn packages(&self) -> Option<&serde_json::Map<String, Value>> {
+        self.manifest.get("packages").and_then(|p| p.as_object())
+    }
+
+    fn current_size(&self, pkg: &str) -> Option<u64> {
+        self.packages()
+            .and_then(|p| p.get(pkg))
+            .and_then(|e| e.get("installed_size"))
+            .and_then(|v| v.as_u64())
+    }
+
+    /// Packages missing from the local database fall back to their manifest size.
+    fn current_files(&self, pkg: &str) -> Option<LocalFiles> {
+        self.local_files.get(pkg).copied().or_else(|| {
+            self.current_size(pkg).map(|bytes| LocalFiles {
+                files: files_for_bytes(bytes, self.bytes_per_file),
+                bytes,
+            })
         })
+    }
+
+    fn files_for(&self, bytes: u64) -> i64 {
+        signed(files_for_bytes(bytes, self.bytes_per_file))
+    }
+
+    /// Aggregate requirement using manifest metadata if present. Installs are
+    /// charged net of current sizes when per-package telemetry exists.
+    fn aggregate(&self, out: &mut RequirementSet) {
+        let meta = self.manifest.get("metadata");
+        let total = |key: &str| {
+            meta.and_then(|m| m.get(key))
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0)
+        };
+        let deltas: Vec<(i64, i64)> = self
+            .packages()
+            .map(|pkgs| {
+                pkgs.iter()
+                    .filter_map(|(name, entry)| {
+                        let new = new_install_size(entry)?;
+                        let current = entry.get("installed_size").and_then(|v| v.as_u64());
+                        Some((
+                            install_delta(current, Some(new)),
+                            file_delta(self.current_files(name), Some(new), self.bytes_per_file),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let (install, install_files) = if deltas.is_empty() {
+            let install = total("install_size_total");
+            (signed(install), self.files_for(install))
+        } else {
+            deltas.iter().fold((0i64, 0i64), |(bytes, files), (b, f)| {
+                (bytes.saturating_add(*b), files.saturating_add(*f))
+            })
+        };
+        let (download, build) = (total("download_size_total"), total("build_size_total"));
+        if download == 0 && build == 0 && install == 0 {
+            // Only an undivided transient total is known; charge it to the install target.
+            let transient = total("transient_size_total");
+            self.require(
+                out,
+                SpacePhase::Install,
+                signed(transient),
+                self.files_for(transient),
+            );
+        } else {
+            let archives = download_files(download) * (deltas.len().max(1) as i64);
+            self.require(out, SpacePhase::Download, signed(download), archives);
+            self.require(out, SpacePhase::Build, signed(build), self.files_for(build));
+            self.require(out, SpacePhase::Install, install, install_files);
+        }
+    }
+
+    fn package(&self, pkg: &str, out: &mut RequirementSet) {
+        let Some(entry) = self.packages().and_then(|p| p.get(pkg)) else {
+            self.new_dependency(pkg, out);
+            return;
+        };
+        let current = self.current_size(pkg);
+        let source = entry.get("source").and_then(|s| s.as_str()).unwrap_or("");
+        let size = |key: &str| entry.get(key).and_then(|v| v.as_u64());
+        let download = size("download_size_selected")
+            .or_else(|| size("download_size_estimate"))
+            .unwrap_or(0);
+        let build = size("build_size_estimate").unwrap_or(0);
+        let new_install = new_install_size(entry);
+        let transient = size("transient_size_estimate").unwrap_or(0);
+        // Prefer repo data for pacman packages to avoid relying on installed size.
+        let is_aur = source.eq_ignore_ascii_case("AUR");
+        let info = if source.eq_ignore_ascii_case("PACMAN") {
+            self.repo_sizes.get(pkg)
+        } else if is_aur {
+            self.aur_sizes.get(pkg)
+        } else {
+            None
+        };
+        let (download, new_install, build) = match info {
+            Some(info) => (
+                info.download_size.unwrap_or(download),
+                info.installed_size.or(new_install),
+                0u64,
+            ),
+            None => (download, new_install, build),
+        };
+        // Measured build history fills AUR build estimates the manifest lacks;
+        // the observed peak already covers sources fetched into the build dir.
+        let history_estimate = (is_aur && build == 0)
+            .then(|| self.build_samples.get(pkg))
+            .flatten()
+            .map(|sample| estimate_build(sample, (download > 0).then_some(download)));
+        let (download, build) = match &history_estimate {
+            Some(estimate) => (0, estimate.build_bytes),
+            None => (download, build),
+        };
+        if let Some(estimate) = &history_estimate {
+            out.details.push(format!(
+                "Package {pkg}: build estimate {} from {} build (scale {:.2})",
+                format_bytes(estimate.build_bytes),
+                estimate.observed_version,
+                estimate.scale
+            ));
+        }
+
+        if download == 0 && build == 0 && new_install.is_none() && transient == 0 {
+            out.details.push(format!(
+                "WARN: no size telemetry for {pkg}; unable to validate disk usage"
+            ));
+            out.unknowns.push(pkg.to_string());
+            return;
+        }
+        // Only charge the difference for upgrades; an unknown new size is not a removal.
+        let install = new_install
+            .map(|new| install_delta(current, Some(new)))
+            .unwrap_or(0);
+        let install_files = new_install
+            .map(|new| file_delta(self.current_files(pkg), Some(new), self.bytes_per_file))
+            .unwrap_or(0);
+        if download == 0 && build == 0 && new_install.is_none() {
+            self.require(
+                out,
+                SpacePhase::Install,
+                signed(transient),
+                self.files_for(transient),
+            );
+        } else if is_aur {
+            // AUR sources are fetched into and built in the helper's build dir.
+            let fetched = download.saturating_add(build);
+            self.require(
+                out,
+                SpacePhase::Build,
+                signed(fetched),
+                self.files_for(fetched),
+            );
+            self.require(out, SpacePhase::Install, install, install_files);
+        } else {
+            self.require(
+                out,
+                SpacePhase::Download,
+                signed(download),
+                download_files(download),
+            );
+            self.require(out, SpacePhase::Build, signed(build), self.files_for(build));
+            self.require(out, SpacePhase::Install, install, install_files);
+        }
+        out.details.push(format!(
+            "Package {pkg}: download {} + build {} + install {} ({install_files:+} files)",
+            format_bytes(download),
+            format_bytes(build),
+            format_delta(install),
+        ));
+    }
+
+    /// Not installed yet: a newly pulled dependency costs its full size.
+    fn new_dependency(&self, pkg: &str, out: &mut RequirementSet) {
+        let Some(info) = self.repo_sizes.get(pkg) else {
+            out.details.push(format!(
+                "WARN: {pkg} not found in manifest or repos; skipping disk check"
+            ));
+            return;
+        };
+        let download = info.download_size.unwrap_or(0);
+        let install = install_delta(None, info.installed_size);
+        let install_files = file_delta(None, info.installed_size, self.bytes_per_file);
+        self.require(
+            out,
+            SpacePhase::Download,
+            signed(download),
+            download_files(download),
+        );
+        self.require(out, SpacePhase::Install, install, install_files);
+        out.details.push(format!(
+            "Package {pkg} (new): download {} + install {} ({install_files:+} files)",
+            format_bytes(download),
+            format_delta(install),
+        ));
+    }
+
+    /// Replaced and removed packages give their current installed size back.
+    fn released(&self, packages: &[String], removals: &[String], out: &mut RequirementSet) {
+        let mut released: Vec<(String, String)> = self
+            .repo_sizes
+            .iter()
+            .flat_map(|(pkg, info)| {
+                info.replaces
+                    .iter()
+                    .map(move |old| (old.clone(), format!("replaced by {pkg}")))
+            })
+            .filter(|(old, _)| !packages.contains(old))
+            .collect();
+        released.extend(
+            removals
+                .iter()
+                .map(|pkg| (pkg.clone(), "removed".to_string())),
+        );
+        released.sort();
+        released.dedup_by(|a, b| a.0 == b.0);
+        for (pkg, reason) in released {
+            match self.current_size(&pkg) {
+                Some(current) => {
+                    let install = install_delta(Some(current), None);
+                    let install_files =
+                        file_delta(self.current_files(&pkg), None, self.bytes_per_file);
+                    self.require(out, SpacePhase::Install, install, install_files);
+                    out.details.push(format!(
+                        "Package {pkg} ({reason}): install {}",
+                        format_delta(install)
+                    ));
+                }
+                None => out.details.push(format!(
+                    "WARN: {pkg} ({reason}) has no installed size in manifest; not credited"
+                )),
+            }
+        }
+    }
+}
+
+/// New installed size from a manifest entry, when known.
+fn new_install_size(entry: &Value) -> Option<u64> {
+    entry
+        .get("install_size_estimate")
+        .or_else(|| entry.get("installed_size_selected"))
+        .and_then(|v| v.as_u64())
+        .filter(|bytes| *bytes > 0)
+}
+
+/// A package archive and its detached signature.
+fn download_files(bytes: u64) -> i64 {
+    if bytes > 0 {
+        2
     } else {
-        Err(SynsyuError::Runtime(format!(
-            "Unable to assess space for {}",
-            target.display()
-        )))
+        0
     }
 }
 
+fn signed(bytes: u64) -> i64 {
+    i64::try_from(bytes).unwrap_or(i64::MAX)
+}
+
+fn db_section<'a>(contents: &'a str, header: &'a str) -> impl Iterator<Item = &'a str> {
+    contents
+        .lines()
+        .skip_while(move |line| *line != header)
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+}
+
+fn db_field<'a>(contents: &'a str, header: &'a str) -> Option<&'a str> {
+    db_section(contents, header).next()
+}
+
+/// Net installed-size change for one package: upgrades are charged the
+/// difference from the current size, new packages their full size, and
+/// removals (`new = None`) their current size as a negative delta.
+pub fn install_delta(current: Option<u64>, new: Option<u64>) -> i64 {
+    let signed = |bytes: u64| i64::try_from(bytes).unwrap_or(i64::MAX);
+    match (current, new) {
+        (Some(current), Some(new)) => signed(new) - signed(current),
+        (None, Some(new)) => signed(new),
+        (Some(current), None) => -signed(current),
+        (None, None) => 0,
+    }
+}
+
+/// Format a signed byte delta with an explicit sign.
+pub fn format_delta(bytes: i64) -> String {
+    let sign = if bytes < 0 { "-" } else { "+" };
+    format!("{sign}{}", format_bytes(bytes.unsigned_abs()))
+}
+
 /// Format bytes into a concise human-readable string (IEC units).
 pub fn format_bytes(bytes: u64) -> String {
     const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
@@ -111,32 +848,6 @@ pub fn format_bytes(bytes: u64) -> String {
     }
 }
 
-/// Validate that sufficient space exists; returns a descriptive error message on failure.
-pub fn ensure_capacity(
-    report: &SpaceReport,
-    required_bytes: u64,
-    download_bytes: u64,
-    build_bytes: u64,
-    install_bytes: u64,
-    margin_bytes: u64,
-) -> std::result::Result<(), String> {
-    if report.available_bytes < required_bytes {
-        let message = format!(
-            "Insufficient space: need ~{} (download {} + build {} + install {} + buffer {}) on {}; only {} available",
-            format_bytes(required_bytes),
-            format_bytes(download_bytes),
-            format_bytes(build_bytes),
-            format_bytes(install_bytes),
-            format_bytes(margin_bytes),
-            report.checked_path.display(),
-            format_bytes(report.available_bytes),
-        );
-        Err(message)
-    } else {
-        Ok(())
-    }
-}
-
 fn ensure_existing(path: &Path) -> Option<&Path> {
     if path.exists() {
         Some(path)
@@ -153,7 +864,44 @@ fn ensure_existing(path: &Path) -> Option<&Path> {
 }
 
 #[cfg(target_family = "unix")]
-fn free_bytes(path: &Path) -> Result<u64> {
+fn device_id(path: &Path) -> Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    std::fs::metadata(path)
+        .map(|meta| meta.dev())
+        .map_err(|err| {
+            SynsyuError::Filesystem(format!("stat failed for {}: {err}", path.display()))
+        })
+}
+
+#[cfg(not(target_family = "unix"))]
+fn device_id(_path: &Path) -> Result<u64> {
+    Ok(0)
+}
+
+/// Walk up from `path` until the parent sits on a different device.
+fn mount_point(path: &Path) -> Result<PathBuf> {
+    let mut current = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let device = device_id(&current)?;
+    while let Some(parent) = current.parent() {
+        if device_id(parent)? != device {
+            break;
+        }
+        current = parent.to_path_buf();
+    }
+    Ok(current)
+}
+
+/// Free blocks and inodes reported by statvfs.
+#[derive(Debug, Clone, Copy)]
+struct FsStats {
+    available_bytes: u64,
+    /// `None` when the filesystem reports no inode table (btrfs, ZFS).
+    available_inodes: Option<u64>,
+}
+
+#[cfg(target_family = "unix")]
+fn fs_stats(path: &Path) -> Result<FsStats> {
     use std::ffi::CString;
     use std::os::unix::ffi::OsStrExt;
 
@@ -176,19 +924,147 @@ fn free_bytes(path: &Path) -> Result<u64> {
     let available = (data.f_bavail as u128)
         .saturating_mul(data.f_frsize as u128)
         .min(u64::MAX as u128);
-    Ok(available as u64)
+    Ok(FsStats {
+        available_bytes: available as u64,
+        available_inodes: (data.f_files > 0).then_some(data.f_favail),
+    })
 }
 
 #[cfg(not(target_family = "unix"))]
-fn free_bytes(_path: &Path) -> Result<u64> {
+fn fs_stats(_path: &Path) -> Result<FsStats> {
     Err(SynsyuError::Runtime(
         "Disk space checks are not supported on this platform".into(),
     ))
 }
 
+/// Filesystem type and source device mounted at `mount_point`.
+fn mount_source(mount_point: &Path) -> Option<(String, String)> {
+    let contents = fs::read_to_string("/proc/self/mountinfo").ok()?;
+    parse_mountinfo(&contents, mount_point)
+}
+
+/// Find `mount_point` in mountinfo contents; the last entry wins so that
+/// over-mounts shadow what they cover.
+fn parse_mountinfo(contents: &str, mount_point: &Path) -> Option<(String, String)> {
+    contents.lines().rev().find_map(|line| {
+        let (left, right) = line.split_once(" - ")?;
+        let target = left.split(' ').nth(4)?;
+        let mut fields = right.split(' ');
+        let fs_type = fields.next()?;
+        let source = fields.next().unwrap_or("");
+        (Path::new(&unescape_mount_field(target)) == mount_point)
+            .then(|| (fs_type.to_string(), unescape_mount_field(source)))
+    })
+}
+
+/// Decode the octal escapes (`\040` for space) used in mount tables.
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 4 <= bytes.len() {
+            let digits = std::str::from_utf8(&bytes[i + 1..i + 4]).unwrap_or("");
+            if let Ok(value) = u8::from_str_radix(digits, 8) {
+                out.push(value);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+const BTRFS_SYSFS: &str = "/sys/fs/btrfs";
+
+/// `/sys/fs/btrfs/<fsid>` for the filesystem whose member list holds `source`.
+fn btrfs_sysfs_dir(source: &str) -> Option<PathBuf> {
+    let device = fs::canonicalize(source).unwrap_or_else(|_| PathBuf::from(source));
+    let name = device.file_name()?.to_os_string();
+    fs::read_dir(BTRFS_SYSFS)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|dir| dir.join("devices").join(&name).exists())
+}
+
+/// Chunk allocation and headroom from a btrfs sysfs directory. Raw device
+/// bytes are converted to usable bytes with each profile's on-disk ratio
+/// (2 for DUP/RAID1 metadata, 1 for single data).
+fn btrfs_allocation(fs_dir: &Path) -> Option<BtrfsAllocation> {
+    let read = |rel: &str| -> Option<u64> {
+        fs::read_to_string(fs_dir.join(rel))
+            .ok()
+            .and_then(|raw| raw.trim().parse().ok())
+    };
+    let chunk = |kind: &str| -> Option<(u64, u64, u64)> {
+        let total = read(&format!("allocation/{kind}/total_bytes"))?;
+        let used = read(&format!("allocation/{kind}/bytes_used"))?;
+        let disk_total = read(&format!("allocation/{kind}/disk_total")).unwrap_or(total);
+        Some((total, used, disk_total))
+    };
+    let (data_total, data_used, data_disk) = chunk("data")?;
+    let (metadata_total, metadata_used, metadata_disk) = chunk("metadata")?;
+    let system_disk = chunk("system").map(|(_, _, disk)| disk).unwrap_or(0);
+    let global_reserve = read("allocation/global_rsv_size").unwrap_or(0);
+    let device_bytes: u64 = fs::read_dir(fs_dir.join("devices"))
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            fs::read_to_string(entry.path().join("size"))
+                .ok()
+                .and_then(|raw| raw.trim().parse::<u64>().ok())
+        })
+        .map(|sectors| sectors.saturating_mul(512))
+        .sum();
+    let unallocated = device_bytes
+        .saturating_sub(data_disk)
+        .saturating_sub(metadata_disk)
+        .saturating_sub(system_disk);
+    let usable = |raw: u64, total: u64, disk: u64| -> u64 {
+        if total == 0 || disk <= total {
+            raw
+        } else {
+            (raw as u128 * total as u128 / disk as u128) as u64
+        }
+    };
+    Some(BtrfsAllocation {
+        data_total,
+        data_used,
+        metadata_total,
+        metadata_used,
+        global_reserve,
+        device_bytes,
+        unallocated,
+        data_headroom: data_total.saturating_sub(data_used).saturating_add(usable(
+            unallocated,
+            data_total,
+            data_disk,
+        )),
+        metadata_headroom: metadata_total
+            .saturating_sub(metadata_used)
+            .saturating_sub(global_reserve)
+            .saturating_add(usable(unallocated, metadata_total, metadata_disk)),
+        metadata_required: 0,
+        enforced: false,
+        ok: true,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn limits(margin_bytes: u64) -> SpaceLimits {
+        SpaceLimits {
+            margin_bytes,
+            inode_margin: 0,
+            btrfs_mode: BtrfsMode::Report,
+        }
+    }
 
     #[test]
     fn format_bytes_human_readable() {
@@ -199,39 +1075,332 @@ mod tests {
     }
 
     #[test]
-    fn ensure_capacity_passes_when_available() {
-        let report = SpaceReport {
-            checked_path: PathBuf::from("/"),
-            available_bytes: 8 * 1024 * 1024 * 1024,
-        };
-        assert!(
-            ensure_capacity(&report, 6 * 1024 * 1024 * 1024, 1, 1, 1, 1).is_ok(),
-            "expected capacity check to succeed"
+    fn requirements_on_one_filesystem_share_a_row() {
+        let tmp = TempDir::new("synsyu_space_").expect("tmp");
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("cache")).expect("dir");
+        let requirements = [
+            SpaceRequirement {
+                phase: SpacePhase::Download,
+                path: dir.join("cache"),
+                bytes: 100,
+                files: 2,
+            },
+            SpaceRequirement {
+                phase: SpacePhase::Build,
+                path: dir.join("build/not-yet-created"),
+                bytes: 50,
+                files: 10,
+            },
+            SpaceRequirement {
+                phase: SpacePhase::Download,
+                path: dir.join("cache"),
+                bytes: 25,
+                files: 0,
+            },
+        ];
+        let rows = account_by_filesystem(&requirements, &limits(0)).expect("rows");
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.required_bytes, 175);
+        assert_eq!(row.phases.get(&SpacePhase::Download), Some(&125));
+        assert_eq!(row.phases.get(&SpacePhase::Build), Some(&50));
+        assert_eq!(row.paths.len(), 2);
+        assert_eq!(row.required_files, 12);
+        assert!(row.ok);
+
+        let shrinking = [
+            requirements[0].clone(),
+            SpaceRequirement {
+                phase: SpacePhase::Install,
+                path: dir.join("cache"),
+                bytes: -400,
+                files: -12,
+            },
+        ];
+        let rows = account_by_filesystem(&shrinking, &limits(u64::MAX)).expect("rows");
+        assert_eq!(rows[0].net_bytes, -300);
+        assert_eq!(rows[0].required_bytes, 0);
+        assert_eq!(rows[0].required_files, 0);
+        assert!(rows[0].ok, "an update that frees space never fails");
+
+        let starved = account_by_filesystem(&requirements[..1], &limits(u64::MAX)).expect("rows");
+        assert!(!starved[0].ok);
+    }
+
+    #[test]
+    fn btrfs_subvolumes_of_one_pool_share_a_row() {
+        // `@` and `@home` have their own st_dev but one fsid; /srv is ext4.
+        let probe = |path: &Path| -> Result<FilesystemProbe> {
+            let (key, mount_point, fs_type) = if path.starts_with("/srv") {
+                ("dev:3", "/srv", "ext4")
+            } else if path.starts_with("/home") {
+                ("btrfs:0f6a-pool", "/home", "btrfs")
+            } else {
+                ("btrfs:0f6a-pool", "/", "btrfs")
+            };
+            Ok(FilesystemProbe {
+                key: key.into(),
+                mount_point: PathBuf::from(mount_point),
+                fs_type: fs_type.into(),
+                stats: FsStats {
+                    available_bytes: 1000,
+                    available_inodes: None,
+                },
+                btrfs_dir: None,
+            })
+        };
+        let requirement = |phase, path: &str, bytes| SpaceRequirement {
+            phase,
+            path: PathBuf::from(path),
+            bytes,
+            files: 0,
+        };
+        let requirements = [
+            requirement(SpacePhase::Download, "/var/cache/pacman/pkg", 300),
+            requirement(SpacePhase::Build, "/home/me/.cache/paru", 600),
+            requirement(SpacePhase::Install, "/usr", 200),
+            requirement(SpacePhase::Flatpak, "/srv/flatpak", 50),
+        ];
+        let rows = account_with(&requirements, &limits(0), probe).expect("rows");
+        assert_eq!(rows.len(), 2);
+        let pool = rows
+            .iter()
+            .find(|row| row.filesystem == "btrfs:0f6a-pool")
+            .expect("pool row");
+        assert_eq!(pool.required_bytes, 1100);
+        assert_eq!(pool.paths.len(), 3);
+        assert!(!pool.ok, "the summed need exceeds the pool's free space");
+        let srv = rows.iter().find(|row| row.filesystem == "dev:3").unwrap();
+        assert!(srv.ok);
+    }
+
+    #[test]
+    fn install_delta_nets_upgrades_and_credits_removals() {
+        assert_eq!(install_delta(Some(300), Some(200)), -100);
+        assert_eq!(install_delta(Some(200), Some(300)), 100);
+        assert_eq!(install_delta(None, Some(50)), 50);
+        assert_eq!(install_delta(Some(80), None), -80);
+        assert_eq!(format_delta(-2048), "-2 KiB");
+        assert_eq!(format_delta(1024), "+1 KiB");
+    }
+
+    #[test]
+    fn package_sizing_charges_phases_and_credits_releases() {
+        let manifest = serde_json::json!({
+            "packages": {
+                "bash": {"source": "PACMAN", "installed_size": 1000},
+                "paru": {"source": "AUR", "installed_size": 500},
+                "old": {"source": "PACMAN", "installed_size": 400},
+            }
+        });
+        let info = |download, installed, replaces: &[&str]| VersionInfo {
+            version: "1-1".into(),
+            download_size: Some(download),
+            installed_size: Some(installed),
+            replaces: replaces.iter().map(|name| name.to_string()).collect(),
+        };
+        let repo_sizes = HashMap::from([("bash".to_string(), info(300, 1200, &["sh-legacy"]))]);
+        let aur_sizes = HashMap::from([("paru".to_string(), info(200, 600, &[]))]);
+        let build_samples = BTreeMap::from([(
+            "paru".to_string(),
+            BuildSample {
+                at_epoch: 0,
+                package: "paru".into(),
+                version: "0.9-1".into(),
+                helper: None,
+                peak_build_bytes: 1000,
+                package_bytes: 100,
+                source_bytes: 50_000,
+                snapshot_bytes: 100,
+            },
+        )]);
+        let sizing = PackageSizing {
+            manifest: &manifest,
+            repo_sizes: &repo_sizes,
+            aur_sizes: &aur_sizes,
+            build_samples: &build_samples,
+            local_files: &HashMap::new(),
+            bytes_per_file: 100,
+            paths: PhasePaths {
+                download: PathBuf::from("/cache"),
+                build: PathBuf::from("/build"),
+                install: PathBuf::from("/usr"),
+            },
+        };
+        let set = sizing.requirements(&["bash".into(), "paru".into()], &["old".into()]);
+        let charged: Vec<(SpacePhase, &Path, i64)> = set
+            .requirements
+            .iter()
+            .map(|r| (r.phase, r.path.as_path(), r.bytes))
+            .collect();
+        assert_eq!(
+            charged,
+            [
+                (SpacePhase::Download, Path::new("/cache"), 300),
+                (SpacePhase::Install, Path::new("/usr"), 200),
+                // Build history scaled by the doubled snapshot size.
+                (SpacePhase::Build, Path::new("/build"), 2000),
+                (SpacePhase::Install, Path::new("/usr"), 100),
+                (SpacePhase::Install, Path::new("/usr"), -400),
+            ]
         );
+        assert!(set.unknowns.is_empty());
+        assert!(set
+            .details
+            .iter()
+            .any(|line| line.starts_with("Package paru: build estimate")));
+        assert!(set
+            .details
+            .iter()
+            .any(|line| line.starts_with("WARN: sh-legacy (replaced by bash)")));
     }
 
     #[test]
-    fn ensure_capacity_fails_with_message() {
-        let report = SpaceReport {
-            checked_path: PathBuf::from("/var"),
-            available_bytes: 512 * 1024 * 1024,
-        };
-        let err = ensure_capacity(
-            &report,
-            2 * 1024 * 1024 * 1024,
-            300 * 1024 * 1024,
-            900 * 1024 * 1024,
-            300 * 1024 * 1024,
-            500 * 1024 * 1024,
+    fn file_estimates_scale_with_size_and_read_the_local_db() {
+        let current = LocalFiles {
+            files: 100,
+            bytes: 1000,
+        };
+        assert_eq!(file_delta(Some(current), Some(1500), 10), 50);
+        assert_eq!(file_delta(Some(current), None, 10), -100);
+        assert_eq!(file_delta(None, Some(25), 10), 3);
+        assert_eq!(files_for_bytes(0, 10), 0);
+
+        let tmp = TempDir::new("synsyu_localdb_").expect("tmp");
+        let db = tmp.path();
+        let entry = db.join("bash-5.2.037-1");
+        std::fs::create_dir_all(&entry).expect("dir");
+        std::fs::write(
+            entry.join("desc"),
+            "%NAME%\nbash\n\n%VERSION%\n5.2.037-1\n\n%SIZE%\n9000\n\n",
         )
-        .expect_err("expected capacity failure");
-        assert!(
-            err.contains("Insufficient space"),
-            "error message should mention insufficiency"
+        .expect("desc");
+        std::fs::write(
+            entry.join("files"),
+            "%FILES%\nusr/\nusr/bin/\nusr/bin/bash\n\n%BACKUP%\netc/bash.bashrc\tabc\n",
+        )
+        .expect("files");
+        let counts = local_file_counts(db);
+        assert_eq!(
+            counts.get("bash"),
+            Some(&LocalFiles {
+                files: 3,
+                bytes: 9000
+            })
+        );
+        assert_eq!(average_file_bytes(&counts), 3000);
+        assert_eq!(average_file_bytes(&HashMap::new()), DEFAULT_FILE_BYTES);
+    }
+
+    #[test]
+    fn mountinfo_lookup_prefers_the_last_mount() {
+        let mountinfo = "\
+22 1 0:21 / / rw,relatime shared:1 - ext4 /dev/sda2 rw
+40 22 0:35 / /mnt/my\\040disk rw - xfs /dev/sdb1 rw
+41 22 0:36 /@home /home rw - ext4 /dev/sda3 rw
+42 22 0:37 /@home /home rw - btrfs /dev/mapper/home rw,space_cache=v2
+";
+        assert_eq!(
+            parse_mountinfo(mountinfo, Path::new("/home")),
+            Some(("btrfs".into(), "/dev/mapper/home".into()))
         );
-        assert!(
-            err.contains("download") && err.contains("build") && err.contains("buffer"),
-            "error message should enumerate components"
+        assert_eq!(
+            parse_mountinfo(mountinfo, Path::new("/mnt/my disk")),
+            Some(("xfs".into(), "/dev/sdb1".into()))
         );
+        assert_eq!(parse_mountinfo(mountinfo, Path::new("/srv")), None);
+    }
+
+    #[test]
+    fn btrfs_headroom_counts_unallocated_and_enforces_metadata() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        let tmp = TempDir::new("synsyu_btrfs_").expect("tmp");
+        let dir = tmp.path();
+        let write = |rel: &str, value: u64| {
+            let path = dir.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).expect("dir");
+            std::fs::write(path, format!("{value}\n")).expect("write");
+        };
+        write("allocation/data/total_bytes", 90 * GIB);
+        write("allocation/data/bytes_used", 89 * GIB);
+        write("allocation/data/disk_total", 90 * GIB);
+        write("allocation/metadata/total_bytes", 2 * GIB);
+        write(
+            "allocation/metadata/bytes_used",
+            2 * GIB - 600 * 1024 * 1024,
+        );
+        write("allocation/metadata/disk_total", 4 * GIB);
+        write("allocation/system/total_bytes", 32 * 1024 * 1024);
+        write("allocation/system/bytes_used", 16 * 1024);
+        write("allocation/system/disk_total", 64 * 1024 * 1024);
+        write("allocation/global_rsv_size", 512 * 1024 * 1024);
+        // 100 GiB device in 512-byte sectors.
+        write("devices/dm-0/size", 100 * GIB / 512);
+        let btrfs = btrfs_allocation(dir).expect("allocation");
+
+        let unallocated = 6 * GIB - 64 * 1024 * 1024;
+        assert_eq!(btrfs.device_bytes, 100 * GIB);
+        assert_eq!(btrfs.unallocated, unallocated);
+        assert_eq!(btrfs.data_headroom, GIB + unallocated);
+        // DUP metadata: each usable byte takes two raw bytes.
+        assert_eq!(btrfs.metadata_headroom, 88 * 1024 * 1024 + unallocated / 2);
+
+        let mut row = FilesystemUsage {
+            filesystem: "btrfs:test".into(),
+            mount_point: PathBuf::from("/"),
+            fs_type: "btrfs".into(),
+            paths: Vec::new(),
+            phases: BTreeMap::new(),
+            net_bytes: 2 * GIB as i64,
+            required_bytes: 0,
+            margin_bytes: 0,
+            available_bytes: 50 * GIB,
+            net_files: 1_000_000,
+            required_files: 0,
+            inode_margin: 0,
+            available_inodes: None,
+            btrfs: Some(btrfs),
+            space_ok: true,
+            inodes_ok: true,
+            ok: true,
+        };
+        let mut reported = row.clone();
+        check_row(&mut reported, BtrfsMode::Report);
+        assert!(reported.ok, "report mode leaves the statvfs verdict alone");
+        assert!(!reported.btrfs.as_ref().unwrap().ok);
+        assert_eq!(reported.available_bytes, 50 * GIB);
+
+        check_row(&mut row, BtrfsMode::Enforce);
+        assert!(!row.ok, "metadata for a million files exceeds the headroom");
+        assert_eq!(row.available_bytes, GIB + unallocated);
+        assert!(row.inodes_ok);
+    }
+
+    #[test]
+    fn inode_shortfall_fails_the_row() {
+        let mut row = FilesystemUsage {
+            filesystem: "btrfs:test".into(),
+            mount_point: PathBuf::from("/"),
+            fs_type: "ext4".into(),
+            paths: Vec::new(),
+            phases: BTreeMap::new(),
+            net_bytes: 10,
+            required_bytes: 0,
+            margin_bytes: 0,
+            available_bytes: 1000,
+            net_files: 500,
+            required_files: 0,
+            inode_margin: 100,
+            available_inodes: Some(550),
+            btrfs: None,
+            space_ok: true,
+            inodes_ok: true,
+            ok: true,
+        };
+        check_row(&mut row, BtrfsMode::Report);
+        assert!(row.space_ok);
+        assert!(!row.inodes_ok);
+        assert!(!row.ok);
     }
 }
@@ -0,0 +1,83 @@
+/*============================================================
+  Synavera Project: Syn-Syu
+  Module: synsyu_core::temp_dir
+  Etiquette: Synavera Script Etiquette - Rust Profile v1.1.1
+  ------------------------------------------------------------
+  Purpose:
+    Create private scratch directories with unpredictable
+    names, removed again when dropped.
+
+  Security / Safety Notes:
+    mkdtemp(3) picks the name and creates the directory with
+    mode 0700 in one step, so another user cannot pre-create
+    or swap it under /tmp.
+============================================================*/
+
+use std::ffi::{CString, OsString};
+use std::fs;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, SynsyuError};
+
+/// A private directory under the system temp dir.
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create `<tmp>/<prefix>XXXXXX`.
+    pub fn new(prefix: &str) -> Result<Self> {
+        let template = std::env::temp_dir().join(format!("{prefix}XXXXXX"));
+        let mut bytes = CString::new(template.as_os_str().as_bytes())
+            .map_err(|_| {
+                SynsyuError::Filesystem(format!("Invalid temp dir template {}", template.display()))
+            })?
+            .into_bytes_with_nul();
+        // SAFETY: `bytes` is a writable NUL-terminated buffer ending in XXXXXX,
+        // which mkdtemp rewrites in place.
+        let created = unsafe { libc::mkdtemp(bytes.as_mut_ptr().cast()) };
+        if created.is_null() {
+            return Err(SynsyuError::Filesystem(format!(
+                "Unable to create temporary directory {}: {}",
+                template.display(),
+                std::io::Error::last_os_error()
+            )));
+        }
+        bytes.pop();
+        Ok(Self {
+            path: PathBuf::from(OsString::from_vec(bytes)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn directories_are_private_unique_and_removed() {
+        let first = TempDir::new("synsyu_test_").unwrap();
+        let second = TempDir::new("synsyu_test_").unwrap();
+        assert_ne!(first.path(), second.path());
+        let mode = fs::metadata(first.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        let kept = first.path().to_path_buf();
+        fs::write(kept.join("file"), "x").unwrap();
+        drop(first);
+        assert!(!kept.exists());
+    }
+}
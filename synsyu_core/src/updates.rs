@@ -1,11 +1,8 @@
 use std::collections::HashSet;
-use std::path::PathBuf;
 
 use regex::Regex;
 use serde::Serialize;
 
-use crate::error::{Result, SynsyuError};
-
 #[derive(Debug, Serialize, Clone)]
 pub struct UpdateEntry {
     pub name: String,
@@ -15,7 +12,6 @@ pub struct UpdateEntry {
 }
 
 pub struct UpdatesFilter {
-    pub manifest: PathBuf,
     pub include: Vec<String>,
     pub exclude: Vec<String>,
     pub allow_repo: bool,
@@ -23,20 +19,7 @@ pub struct UpdatesFilter {
     pub packages: Vec<String>,
 }
 
-pub fn collect_updates(filter: UpdatesFilter) -> Result<Vec<UpdateEntry>> {
-    let file = std::fs::File::open(&filter.manifest).map_err(|err| {
-        SynsyuError::Filesystem(format!(
-            "Failed to open manifest {}: {err}",
-            filter.manifest.display()
-        ))
-    })?;
-    let manifest: serde_json::Value = serde_json::from_reader(file).map_err(|err| {
-        SynsyuError::Serialization(format!(
-            "Failed to parse manifest {}: {err}",
-            filter.manifest.display()
-        ))
-    })?;
-
+pub fn collect_updates(manifest: &serde_json::Value, filter: UpdatesFilter) -> Vec<UpdateEntry> {
     let include_res: Vec<Regex> = filter
         .include
         .iter()
@@ -119,5 +102,5 @@ pub fn collect_updates(filter: UpdatesFilter) -> Result<Vec<UpdateEntry>> {
         }
     }
 
-    Ok(updates)
+    updates
 }
@@ -0,0 +1,59 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+REPO_ROOT="$(cd "$SCRIPT_DIR/.." && pwd)"
+
+WORK_DIR="$(mktemp -d "${TMPDIR:-/tmp}/synsyu_seal_test_XXXXXX")"
+trap 'rm -rf "$WORK_DIR"' EXIT
+LOG_FILE="$WORK_DIR/session.log"
+
+# Run a session the way syn-syu does (EXIT and INT traps installed), then
+# interrupt it and let the traps finish the log.
+set +e
+(
+  # shellcheck source=../synsyu/lib/logging.sh
+  . "$REPO_ROOT/synsyu/lib/logging.sh"
+  journal_end() { :; }
+  SESSION_STAMP="test"
+  LOG_DIR="$WORK_DIR"
+  LOG_PATH="$LOG_FILE"
+  RUN_ID="seal-test"
+  log_init
+  trap 'handle_exit $?' EXIT
+  trap 'handle_interrupt' INT
+  log_info "TEST" "Session started"
+  kill -INT "$BASHPID"
+  log_info "TEST" "Not reached"
+) >/dev/null 2>&1
+status=$?
+set -e
+
+fail() {
+  printf '%s\n' "$1" >&2
+  cat "$LOG_FILE" >&2
+  exit 1
+}
+
+[ "$status" -eq 130 ] || fail "expected exit status 130 after SIGINT, got $status"
+[ "$(grep -c '\[SEAL\]' "$LOG_FILE")" -eq 1 ] || fail "expected exactly one SEAL entry"
+tail -n 1 "$LOG_FILE" | grep -q '\[SEAL\]' || fail "expected SEAL to be the last entry"
+grep -q '\[INT\]' "$LOG_FILE" || fail "expected the INT entry to be logged"
+grep -q '\[EXIT\]' "$LOG_FILE" || fail "expected the EXIT entry to be logged before the seal"
+(cd "$WORK_DIR" && sha256sum --quiet -c "$LOG_FILE.hash") || fail "sidecar hash does not match the sealed log"
+
+# Reopening a sealed log must not append after the seal.
+(
+  # shellcheck source=../synsyu/lib/logging.sh
+  . "$REPO_ROOT/synsyu/lib/logging.sh"
+  LOG_PATH="$LOG_FILE"
+  LOG_VERBOSE=0
+  LOG_LEVEL_THRESHOLD=3
+  log_warn "TEST" "After the seal"
+) >/dev/null 2>&1
+tail -n 1 "$LOG_FILE" | grep -q '\[SEAL\]' || fail "entry written after the seal"
+
+core_bin="${SYN_CORE_BIN:-$REPO_ROOT/synsyu_core/target/debug/synsyu_core}"
+if [ -x "$core_bin" ]; then
+  "$core_bin" logs --verify "$LOG_FILE" >/dev/null || fail "synsyu_core logs --verify rejected the sealed log"
+fi
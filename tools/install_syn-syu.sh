@@ -552,6 +552,7 @@ verify_installation_files() {
     "$LIB_DIR/disk.sh"
     "$LIB_DIR/apps.sh"
     "$LIB_DIR/plan.sh"
+    "$LIB_DIR/journal.sh"
     "$LIB_DIR/commands.sh"
   )
   local p
@@ -583,6 +584,7 @@ deploy_libraries() {
   install_with_policy 644 "$PROJECT_ROOT/synsyu/lib/disk.sh" "$LIB_DIR/disk.sh"
   install_with_policy 644 "$PROJECT_ROOT/synsyu/lib/apps.sh" "$LIB_DIR/apps.sh"
   install_with_policy 644 "$PROJECT_ROOT/synsyu/lib/plan.sh" "$LIB_DIR/plan.sh"
+  install_with_policy 644 "$PROJECT_ROOT/synsyu/lib/journal.sh" "$LIB_DIR/journal.sh"
   install_with_policy 644 "$PROJECT_ROOT/synsyu/lib/commands.sh" "$LIB_DIR/commands.sh"
 }
 
@@ -602,6 +604,7 @@ detect_existing() {
     "$LIB_DIR/disk.sh"
     "$LIB_DIR/apps.sh"
     "$LIB_DIR/plan.sh"
+    "$LIB_DIR/journal.sh"
     "$LIB_DIR/commands.sh"
   )
   local p